@@ -100,6 +100,7 @@ fn certify_all_assets() {
             }],
             aliased_by: vec!["/".to_string()],
             encodings: encodings.clone(),
+            options: Default::default(),
         },
         AssetConfig::Pattern {
             pattern: "**/*.js".to_string(),
@@ -109,6 +110,7 @@ fn certify_all_assets() {
                 IMMUTABLE_ASSET_CACHE_CONTROL.to_string(),
             )]),
             encodings: encodings.clone(),
+            options: Default::default(),
         },
         AssetConfig::Pattern {
             pattern: "**/*.css".to_string(),
@@ -118,6 +120,7 @@ fn certify_all_assets() {
                 IMMUTABLE_ASSET_CACHE_CONTROL.to_string(),
             )]),
             encodings,
+            options: Default::default(),
         },
         AssetConfig::Pattern {
             pattern: "**/*.ico".to_string(),
@@ -127,6 +130,7 @@ fn certify_all_assets() {
                 IMMUTABLE_ASSET_CACHE_CONTROL.to_string(),
             )]),
             encodings: vec![],
+            options: Default::default(),
         },
         AssetConfig::Redirect {
             from: "/old-url".to_string(),
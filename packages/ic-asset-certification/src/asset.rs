@@ -2,8 +2,8 @@ use std::borrow::Cow;
 
 /// An asset to be certified and served by an [AssetRouter](crate::AssetRouter).
 ///
-/// Use the [new](Asset::new) associated function to create instances of
-/// this struct.
+/// Use the [new](Asset::new) or [borrowed](Asset::borrowed) associated
+/// functions to create instances of this struct.
 ///
 /// # Examples
 ///
@@ -48,6 +48,38 @@ impl<'content, 'path> Asset<'content, 'path> {
             content: content.into(),
         }
     }
+
+    /// Creates a new asset that borrows the given content, instead of owning a copy of it.
+    ///
+    /// This is equivalent to calling [new](Asset::new) with a slice, and is intended for
+    /// large assets that are already held in memory elsewhere, such as a buffer loaded from
+    /// stable memory. The [AssetRouter](crate::AssetRouter) keeps borrowing the content for
+    /// every response that it certifies for the asset, including each chunk of an asset that
+    /// is larger than [ASSET_CHUNK_SIZE](crate::ASSET_CHUNK_SIZE), so the content is held in
+    /// memory only once.
+    ///
+    /// The borrowed content must outlive the router, which is enforced by the `'content`
+    /// lifetime of the [AssetRouter](crate::AssetRouter). Since a router is usually stored
+    /// in a `thread_local!` for the lifetime of the canister, this typically requires a
+    /// `'static` buffer, such as one created with [Vec::leak] or [include_bytes!]. Note that
+    /// a leaked buffer is never freed, even after the asset is deleted from the router, so
+    /// leaking is best suited to content that is certified once, such as during
+    /// `post_upgrade`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetRouter};
+    ///
+    /// let content: &'static [u8] = vec![0; 1024].leak();
+    /// let asset = Asset::borrowed("large.bin", content);
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router.certify_assets(vec![asset], vec![]).unwrap();
+    /// ```
+    pub fn borrowed(path: &'path str, content: &'content [u8]) -> Self {
+        Self::new(path, content)
+    }
 }
 
 fn path_to_url(path: &str) -> String {
@@ -86,4 +118,17 @@ mod tests {
         assert_eq!(asset.url, "/foo");
         assert_eq!(asset.content, content);
     }
+
+    #[rstest]
+    fn asset_borrowed() {
+        let content = [1, 2, 3].as_slice();
+
+        let asset = Asset::borrowed("foo", content);
+
+        assert_eq!(asset.path, "foo");
+        assert_eq!(asset.url, "/foo");
+        assert!(
+            matches!(asset.content, Cow::Borrowed(borrowed) if borrowed.as_ptr() == content.as_ptr())
+        );
+    }
 }
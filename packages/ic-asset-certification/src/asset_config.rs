@@ -1,6 +1,6 @@
 use crate::{Asset, AssetCertificationError};
 use globset::{Glob, GlobMatcher};
-use ic_http_certification::StatusCode;
+use ic_http_certification::{Method, StatusCode};
 use std::fmt::{Display, Formatter};
 
 /// Certification configuration for [assets](Asset). This configuration
@@ -38,6 +38,7 @@ use std::fmt::{Display, Formatter};
 ///         AssetEncoding::Brotli.default_config(),
 ///         AssetEncoding::Gzip.default_config(),
 ///     ],
+///     options: Default::default(),
 /// };
 /// ```
 ///
@@ -68,6 +69,7 @@ use std::fmt::{Display, Formatter};
 ///         AssetEncoding::Brotli.default_config(),
 ///         AssetEncoding::Gzip.default_config(),
 ///     ],
+///     options: Default::default(),
 /// };
 /// ```
 ///
@@ -123,6 +125,7 @@ use std::fmt::{Display, Formatter};
 ///         AssetEncoding::Brotli.default_config(),
 ///         AssetEncoding::Gzip.default_config(),
 ///     ],
+///     options: Default::default(),
 /// };
 /// ```
 ///
@@ -146,6 +149,7 @@ use std::fmt::{Display, Formatter};
 ///         AssetEncoding::Brotli.default_config(),
 ///         AssetEncoding::Gzip.default_config(),
 ///     ],
+///     options: Default::default(),
 /// };
 /// ```
 ///
@@ -186,6 +190,31 @@ use std::fmt::{Display, Formatter};
 ///     )],
 /// };
 /// ```
+///
+/// ## Builders
+///
+/// The [file](AssetConfig::file), [pattern](AssetConfig::pattern) and
+/// [redirect](AssetConfig::redirect) functions return builders that default every
+/// field that is not set to the most common value, so only the relevant fields need
+/// to be provided. This example builds the same config as the
+/// [index HTML example](#index-html-file-with-fallback).
+///
+/// ```
+/// use ic_http_certification::StatusCode;
+/// use ic_asset_certification::{AssetConfig, AssetFallbackConfig, AssetEncoding};
+///
+/// let config = AssetConfig::file("index.html")
+///     .with_content_type("text/html")
+///     .with_header("Cache-Control", "public, no-cache, no-store")
+///     .with_fallback_for(AssetFallbackConfig {
+///         scope: "/".to_string(),
+///         status_code: Some(StatusCode::OK),
+///     })
+///     .with_alias("/")
+///     .with_encoding(AssetEncoding::Brotli)
+///     .with_encoding(AssetEncoding::Gzip)
+///     .build();
+/// ```
 #[derive(Debug, Clone)]
 pub enum AssetConfig {
     /// Matches a specific file.
@@ -240,6 +269,10 @@ pub enum AssetConfig {
         /// If multiple fallback assets are configured, the first one found will
         /// be used. If no asset is found with any of these fallback scopes, no
         /// response will be returned.
+        ///
+        /// Every scope configured here serves the body of this asset. To serve a
+        /// different body for a scope, configure that scope as a fallback on the
+        /// asset providing the body instead, see [AssetFallbackConfig].
         fallback_for: Vec<AssetFallbackConfig>,
 
         /// A list of aliases for this asset. If a request is made for one of
@@ -281,6 +314,15 @@ pub enum AssetConfig {
         /// The asset router will return the highest priority encoding that has
         /// been certified and is supported by the client.
         encodings: Vec<(AssetEncoding, String)>,
+
+        /// The additional options of the file, such as whether it is
+        /// [addressable](AssetFileOptions::addressable) by its own path, see
+        /// [AssetFileOptions].
+        ///
+        /// Most assets should use `Default::default()`, or only set the options that
+        /// differ from the defaults, e.g.
+        /// `AssetFileOptions { addressable: false, ..Default::default() }`.
+        options: AssetFileOptions,
     },
 
     /// Matches files using a glob pattern.
@@ -360,10 +402,32 @@ pub enum AssetConfig {
         /// The asset router will return the highest priority encoding that has
         /// been certified and is supported by the client.
         encodings: Vec<(AssetEncoding, String)>,
+
+        /// The additional options of the matching files, see [AssetPatternOptions].
+        ///
+        /// Most patterns should use `Default::default()`.
+        options: AssetPatternOptions,
     },
 
     /// Redirects the request to another URL. This config type is not matched
     /// against any assets.
+    ///
+    /// Redirect responses are certified with full certification, using the following
+    /// CEL expression:
+    ///
+    /// ```
+    /// use ic_http_certification::{DefaultCelBuilder, DefaultResponseCertification};
+    ///
+    /// let cel_expr = DefaultCelBuilder::full_certification()
+    ///     .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+    ///         vec![],
+    ///     ))
+    ///     .build();
+    /// ```
+    ///
+    /// No request headers or query parameters are certified, but the status code and all
+    /// response headers are, including the `Location` header. This means that a client verifying
+    /// the response can trust both the kind of redirect and its target.
     Redirect {
         /// The URL to redirect from.
         from: String,
@@ -384,10 +448,607 @@ pub enum AssetConfig {
     },
 }
 
+impl AssetConfig {
+    /// The methods that assets are served for by default, `GET` and `HEAD`, see
+    /// [allowed_methods](AssetFileOptions::allowed_methods).
+    pub const DEFAULT_ALLOWED_METHODS: [Method; 2] = [Method::GET, Method::HEAD];
+
+    /// Creates a new [AssetFileConfigBuilder] for a [File](AssetConfig::File) config
+    /// with the given path.
+    ///
+    /// The file is [addressable](AssetFileOptions::addressable) and certified, and all
+    /// other fields are empty unless they are set on the builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetConfig;
+    ///
+    /// let config = AssetConfig::file("app.js")
+    ///     .with_content_type("text/javascript")
+    ///     .with_header("Cache-Control", "public, max-age=31536000, immutable")
+    ///     .build();
+    /// ```
+    pub fn file(path: impl Into<String>) -> AssetFileConfigBuilder {
+        AssetFileConfigBuilder {
+            path: path.into(),
+            content_type: None,
+            headers: vec![],
+            fallback_for: vec![],
+            aliased_by: vec![],
+            encodings: vec![],
+            addressable: true,
+            skip_certification: false,
+            upgrade: false,
+            certified_query_parameters: vec![],
+            allowed_methods: Self::DEFAULT_ALLOWED_METHODS.to_vec(),
+        }
+    }
+
+    /// The path that browsers request a favicon from when a page does not link to one.
+    pub const FAVICON_PATH: &'static str = "/favicon.ico";
+
+    /// Creates a new [AssetFileConfigBuilder] for a favicon with the given path.
+    ///
+    /// Browsers request [FAVICON_PATH](AssetConfig::FAVICON_PATH) for every page that
+    /// does not link to a favicon. If the file is not at that path, it is
+    /// [aliased](AssetConfig::File::aliased_by) by it, so an SVG or PNG favicon
+    /// can be served for the `.ico` path. The content type is set from the
+    /// `.ico`, `.svg` or `.png` extension of the path, and can be overridden on the
+    /// builder.
+    ///
+    /// Exact matches and aliases take precedence over fallbacks. Without a favicon,
+    /// the request is served the fallback whose
+    /// [scope](AssetFallbackConfig::scope) covers the root, such as an `index.html`
+    /// served for `/`, or the router returns a
+    /// [NoAssetMatchingRequestUrl](crate::AssetCertificationError::NoAssetMatchingRequestUrl)
+    /// error if there is no fallback, which usually ends in an uncertified 404. With a
+    /// favicon, the request resolves to the favicon and no fallback is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetConfig;
+    ///
+    /// let config = AssetConfig::favicon("favicon.svg")
+    ///     .with_header("Cache-Control", "public, max-age=86400")
+    ///     .build();
+    /// ```
+    pub fn favicon(path: impl Into<String>) -> AssetFileConfigBuilder {
+        let path = path.into();
+        let content_type = match path.rsplit_once('.') {
+            Some((_, "ico")) => Some("image/x-icon"),
+            Some((_, "svg")) => Some("image/svg+xml"),
+            Some((_, "png")) => Some("image/png"),
+            _ => None,
+        };
+        let is_favicon_path =
+            path.trim_start_matches('/') == Self::FAVICON_PATH.trim_start_matches('/');
+
+        let mut builder = Self::file(path);
+        builder.content_type = content_type.map(str::to_string);
+        if !is_favicon_path {
+            builder = builder.with_alias(Self::FAVICON_PATH);
+        }
+
+        builder
+    }
+
+    /// Creates a new [AssetPatternConfigBuilder] for a [Pattern](AssetConfig::Pattern)
+    /// config with the given glob pattern.
+    ///
+    /// All other fields are empty unless they are set on the builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{AssetConfig, AssetEncoding};
+    ///
+    /// let config = AssetConfig::pattern("**/*.css")
+    ///     .with_content_type("text/css")
+    ///     .with_encoding(AssetEncoding::Brotli)
+    ///     .build();
+    /// ```
+    pub fn pattern(pattern: impl Into<String>) -> AssetPatternConfigBuilder {
+        AssetPatternConfigBuilder {
+            pattern: pattern.into(),
+            content_type: None,
+            headers: vec![],
+            encodings: vec![],
+            certified_query_parameters: vec![],
+        }
+    }
+
+    /// Creates a new [AssetRedirectConfigBuilder] for a [Redirect](AssetConfig::Redirect)
+    /// config from and to the given URLs.
+    ///
+    /// The redirect has no additional headers unless they are set on the builder.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{AssetConfig, AssetRedirectKind};
+    ///
+    /// let config = AssetConfig::redirect("/old", "/new", AssetRedirectKind::Permanent)
+    ///     .with_header("content-type", "text/plain; charset=utf-8")
+    ///     .build();
+    /// ```
+    pub fn redirect(
+        from: impl Into<String>,
+        to: impl Into<String>,
+        kind: AssetRedirectKind,
+    ) -> AssetRedirectConfigBuilder {
+        AssetRedirectConfigBuilder {
+            from: from.into(),
+            to: to.into(),
+            kind,
+            headers: vec![],
+        }
+    }
+}
+
+/// The additional options of a [File](AssetConfig::File) config.
+///
+/// The [default](AssetFileOptions::default) options serve the file by its own path, with
+/// certification, for `GET` and `HEAD` requests. Setting only the options that differ from
+/// the defaults, with `..Default::default()` for the others, keeps configs compiling when
+/// new options are added.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::StatusCode;
+/// use ic_asset_certification::{AssetConfig, AssetFallbackConfig, AssetFileOptions};
+///
+/// let config = AssetConfig::File {
+///     path: "404.html".to_string(),
+///     content_type: Some("text/html".to_string()),
+///     headers: vec![],
+///     fallback_for: vec![AssetFallbackConfig {
+///         scope: "/".to_string(),
+///         status_code: Some(StatusCode::NOT_FOUND),
+///     }],
+///     aliased_by: vec![],
+///     encodings: vec![],
+///     options: AssetFileOptions {
+///         addressable: false,
+///         ..Default::default()
+///     },
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct AssetFileOptions {
+    /// Whether the asset can be requested directly by its own path.
+    ///
+    /// When set to `false`, the asset will not be served when a request is
+    /// made for its literal path. Such a request will instead be handled as
+    /// if the asset did not exist, i.e. by any matching
+    /// [fallback](AssetConfig::File::fallback_for). The asset will still be
+    /// served for each of its [aliases](AssetConfig::File::aliased_by) and
+    /// for each of its fallback scopes.
+    ///
+    /// For example, a `404.html` asset that is configured as a fallback for
+    /// the `/` scope with `addressable` set to `false` will not be served
+    /// for a request to `/404.html` by its exact path, but will be served as
+    /// the fallback response for that request and any other request that
+    /// does not match an asset.
+    ///
+    /// Note that if the asset's own path is included in
+    /// [aliased_by](AssetConfig::File::aliased_by), then it will be
+    /// served on that path like any other alias.
+    ///
+    /// This defaults to `true`.
+    pub addressable: bool,
+
+    /// Whether to skip certification of the asset's responses.
+    ///
+    /// **Warning:** responses for skipped assets are not certified at all. Only the
+    /// fact that certification was skipped for the asset's paths is certified, and a
+    /// malicious replica or boundary node can serve any body, headers or status code
+    /// for them without failing verification. Verifiers will report these responses
+    /// as not fully certified, see
+    /// [is_fully_certified](https://docs.rs/ic-response-verification/latest/ic_response_verification/types/struct.VerificationInfo.html#method.is_fully_certified).
+    ///
+    /// This trade-off is only appropriate for large, public, non-security-sensitive
+    /// assets, such as videos, where the cost of hashing and storing the full content
+    /// in certification outweighs the importance of its integrity. Never skip
+    /// certification for HTML, JavaScript, CSS or any other content that can affect
+    /// the behavior of the application.
+    ///
+    /// When set to `true`, the asset and all of its aliases, fallbacks and encodings are
+    /// served with the `skip` CEL expression in the
+    /// [IC-CertificateExpression](ic_http_certification::CERTIFICATE_EXPRESSION_HEADER_NAME)
+    /// header. This defaults to `false`.
+    pub skip_certification: bool,
+
+    /// Whether the HTTP Gateway should upgrade requests for this asset to an update call.
+    ///
+    /// When set to `true`, the asset and all of its aliases, fallbacks and encodings are
+    /// served with the [upgrade](ic_http_certification::HttpResponse::upgrade) flag set,
+    /// and the HTTP Gateway will re-issue the request to the canister's `http_request_update`
+    /// method instead of using the certified query response. The canister is then
+    /// responsible for handling the request in `http_request_update`.
+    ///
+    /// Responses to update calls go through consensus and are not verified using response
+    /// verification, so the certification of the query response still needs to be valid, but
+    /// the response served by `http_request_update` is not required to match it. The upgrade
+    /// flag itself is not certified.
+    ///
+    /// This defaults to `false`.
+    pub upgrade: bool,
+
+    /// Query parameters to include in the certification of the asset's responses, as
+    /// name and value pairs, e.g. `vec![("w".to_string(), "100".to_string())]`.
+    ///
+    /// When provided, the asset and its aliases are certified with full request
+    /// certification of the listed query parameter names, binding the responses to
+    /// requests with exactly these values. The asset is only served for requests that
+    /// include the listed query parameters with these values, in the same order, and
+    /// verifiers will reject the responses for requests with any other values. Query
+    /// parameters that are not listed are ignored, they are neither certified nor
+    /// considered when matching requests. Names and values are compared as they appear in
+    /// the request URL, so they must be percent-encoded where necessary.
+    ///
+    /// Several assets can be aliased by the same path with different values, for example
+    /// to serve `/img?w=100` and `/img?w=200`. Requests that do not match any of them are
+    /// served by the asset certified for the path without query parameters, if any, and
+    /// otherwise by a matching [fallback](AssetConfig::File::fallback_for). Fallback
+    /// responses never certify query parameters.
+    ///
+    /// This defaults to no query parameters.
+    pub certified_query_parameters: Vec<(String, String)>,
+
+    /// The HTTP methods that the asset is served for, e.g.
+    /// `vec![Method::GET, Method::POST]`.
+    ///
+    /// Requests for the asset's path and its aliases with any other method are served a
+    /// certified `405 Method Not Allowed` response, with a certified `Allow` header listing
+    /// these methods, so that a replica cannot serve the asset for those methods without the
+    /// response failing verification. `HEAD` requests are served the same certified response
+    /// as `GET` requests. Responses certified with
+    /// [certify_method_response](crate::AssetRouter::certify_method_response) for the same
+    /// path take precedence over the asset, and their methods are included in the `Allow`
+    /// header. Fallbacks are served for any method.
+    ///
+    /// This defaults to [DEFAULT_ALLOWED_METHODS](AssetConfig::DEFAULT_ALLOWED_METHODS),
+    /// `GET` and `HEAD`. Setting it to an empty list opts out of the `405` response, so the
+    /// asset is served for requests with any method, which lets a replica answer a `POST` or
+    /// `PUT` request with the asset's certified response.
+    pub allowed_methods: Vec<Method>,
+}
+
+impl Default for AssetFileOptions {
+    fn default() -> Self {
+        Self {
+            addressable: true,
+            skip_certification: false,
+            upgrade: false,
+            certified_query_parameters: vec![],
+            allowed_methods: AssetConfig::DEFAULT_ALLOWED_METHODS.to_vec(),
+        }
+    }
+}
+
+/// The additional options of a [Pattern](AssetConfig::Pattern) config.
+///
+/// The [default](AssetPatternOptions::default) options certify no query parameters. As with
+/// [AssetFileOptions], setting only the options that differ from the defaults keeps configs
+/// compiling when new options are added.
+///
+/// # Examples
+///
+/// ```
+/// use ic_asset_certification::{AssetConfig, AssetPatternOptions};
+///
+/// let config = AssetConfig::Pattern {
+///     pattern: "**/*.png".to_string(),
+///     content_type: Some("image/png".to_string()),
+///     headers: vec![],
+///     encodings: vec![],
+///     options: AssetPatternOptions {
+///         certified_query_parameters: vec![("w".to_string(), "100".to_string())],
+///     },
+/// };
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AssetPatternOptions {
+    /// Query parameters to include in the certification of the responses of all
+    /// matching [Assets](Asset), as name and value pairs.
+    ///
+    /// See [certified_query_parameters](AssetFileOptions::certified_query_parameters)
+    /// for more information on how query parameters are certified and matched.
+    pub certified_query_parameters: Vec<(String, String)>,
+}
+
+/// A builder for a [File](AssetConfig::File) config, created with
+/// [AssetConfig::file]. See the fields of [File](AssetConfig::File) for
+/// more information on each option.
+#[derive(Debug, Clone)]
+pub struct AssetFileConfigBuilder {
+    path: String,
+    content_type: Option<String>,
+    headers: Vec<(String, String)>,
+    fallback_for: Vec<AssetFallbackConfig>,
+    aliased_by: Vec<String>,
+    encodings: Vec<(AssetEncoding, String)>,
+    addressable: bool,
+    skip_certification: bool,
+    upgrade: bool,
+    certified_query_parameters: Vec<(String, String)>,
+    allowed_methods: Vec<Method>,
+}
+
+impl AssetFileConfigBuilder {
+    /// Sets the [content type](AssetConfig::File::content_type) of the file.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+
+        self
+    }
+
+    /// Adds a header to the [headers](AssetConfig::File::headers) of the file.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Adds a scope to the [fallbacks](AssetConfig::File::fallback_for) of the file.
+    pub fn with_fallback_for(mut self, fallback_for: AssetFallbackConfig) -> Self {
+        self.fallback_for.push(fallback_for);
+
+        self
+    }
+
+    /// Adds a path to the [aliases](AssetConfig::File::aliased_by) of the file.
+    pub fn with_alias(mut self, alias: impl Into<String>) -> Self {
+        self.aliased_by.push(alias.into());
+
+        self
+    }
+
+    /// Adds an [encoding](AssetConfig::File::encodings) of the file, using the
+    /// [default file extension](AssetEncoding::default_config) of the encoding.
+    pub fn with_encoding(mut self, encoding: AssetEncoding) -> Self {
+        self.encodings.push(encoding.default_config());
+
+        self
+    }
+
+    /// Adds an [encoding](AssetConfig::File::encodings) of the file, using a
+    /// [custom file extension](AssetEncoding::custom_config).
+    pub fn with_custom_encoding(
+        mut self,
+        encoding: AssetEncoding,
+        extension: impl Into<String>,
+    ) -> Self {
+        self.encodings
+            .push(encoding.custom_config(extension.into()));
+
+        self
+    }
+
+    /// Sets whether the file is [addressable](AssetFileOptions::addressable) by its own
+    /// path, which defaults to `true`.
+    pub fn with_addressable(mut self, addressable: bool) -> Self {
+        self.addressable = addressable;
+
+        self
+    }
+
+    /// Sets whether to [skip certification](AssetFileOptions::skip_certification) of the
+    /// file's responses, which defaults to `false`.
+    pub fn with_skip_certification(mut self, skip_certification: bool) -> Self {
+        self.skip_certification = skip_certification;
+
+        self
+    }
+
+    /// Sets whether to [upgrade](AssetFileOptions::upgrade) requests for the file to an
+    /// update call, which defaults to `false`.
+    pub fn with_upgrade(mut self, upgrade: bool) -> Self {
+        self.upgrade = upgrade;
+
+        self
+    }
+
+    /// Adds a query parameter to the
+    /// [certified query parameters](AssetFileOptions::certified_query_parameters) of the
+    /// file.
+    pub fn with_certified_query_parameter(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.certified_query_parameters
+            .push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Sets the [allowed methods](AssetFileOptions::allowed_methods) of the file, which
+    /// defaults to `GET` and `HEAD`.
+    pub fn with_allowed_methods(mut self, allowed_methods: Vec<Method>) -> Self {
+        self.allowed_methods = allowed_methods;
+
+        self
+    }
+
+    /// Builds the [File](AssetConfig::File) config.
+    pub fn build(self) -> AssetConfig {
+        AssetConfig::File {
+            path: self.path,
+            content_type: self.content_type,
+            headers: self.headers,
+            fallback_for: self.fallback_for,
+            aliased_by: self.aliased_by,
+            encodings: self.encodings,
+            options: AssetFileOptions {
+                addressable: self.addressable,
+                skip_certification: self.skip_certification,
+                upgrade: self.upgrade,
+                certified_query_parameters: self.certified_query_parameters,
+                allowed_methods: self.allowed_methods,
+            },
+        }
+    }
+}
+
+/// A builder for a [Pattern](AssetConfig::Pattern) config, created with
+/// [AssetConfig::pattern]. See the fields of [Pattern](AssetConfig::Pattern) for
+/// more information on each option.
+#[derive(Debug, Clone)]
+pub struct AssetPatternConfigBuilder {
+    pattern: String,
+    content_type: Option<String>,
+    headers: Vec<(String, String)>,
+    encodings: Vec<(AssetEncoding, String)>,
+    certified_query_parameters: Vec<(String, String)>,
+}
+
+impl AssetPatternConfigBuilder {
+    /// Sets the [content type](AssetConfig::Pattern::content_type) of the matching files.
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+
+        self
+    }
+
+    /// Adds a header to the [headers](AssetConfig::Pattern::headers) of the matching files.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Adds an [encoding](AssetConfig::Pattern::encodings) of the matching files, using the
+    /// [default file extension](AssetEncoding::default_config) of the encoding.
+    pub fn with_encoding(mut self, encoding: AssetEncoding) -> Self {
+        self.encodings.push(encoding.default_config());
+
+        self
+    }
+
+    /// Adds an [encoding](AssetConfig::Pattern::encodings) of the matching files, using a
+    /// [custom file extension](AssetEncoding::custom_config).
+    pub fn with_custom_encoding(
+        mut self,
+        encoding: AssetEncoding,
+        extension: impl Into<String>,
+    ) -> Self {
+        self.encodings
+            .push(encoding.custom_config(extension.into()));
+
+        self
+    }
+
+    /// Adds a query parameter to the
+    /// [certified query parameters](AssetPatternOptions::certified_query_parameters) of the
+    /// matching files.
+    pub fn with_certified_query_parameter(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.certified_query_parameters
+            .push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Builds the [Pattern](AssetConfig::Pattern) config.
+    pub fn build(self) -> AssetConfig {
+        AssetConfig::Pattern {
+            pattern: self.pattern,
+            content_type: self.content_type,
+            headers: self.headers,
+            encodings: self.encodings,
+            options: AssetPatternOptions {
+                certified_query_parameters: self.certified_query_parameters,
+            },
+        }
+    }
+}
+
+/// A builder for a [Redirect](AssetConfig::Redirect) config, created with
+/// [AssetConfig::redirect]. See the fields of [Redirect](AssetConfig::Redirect) for
+/// more information on each option.
+#[derive(Debug, Clone)]
+pub struct AssetRedirectConfigBuilder {
+    from: String,
+    to: String,
+    kind: AssetRedirectKind,
+    headers: Vec<(String, String)>,
+}
+
+impl AssetRedirectConfigBuilder {
+    /// Adds a header to the [headers](AssetConfig::Redirect::headers) of the redirect.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+
+        self
+    }
+
+    /// Builds the [Redirect](AssetConfig::Redirect) config.
+    pub fn build(self) -> AssetConfig {
+        AssetConfig::Redirect {
+            from: self.from,
+            to: self.to,
+            kind: self.kind,
+            headers: self.headers,
+        }
+    }
+}
+
 /// Configuration for an asset to be used as a fallback for a specific scope.
 ///
+/// A fallback always serves the body of the asset that it is configured on. To serve
+/// different bodies for different scopes, such as a JSON `404` response for `/api` and an
+/// HTML `404` response for `/`, configure each fallback on the asset providing that body.
+/// Each of these assets is certified separately, with the wildcard expression path of its
+/// scopes, and with its own headers and status code.
+///
 /// See the [fallback_for](AssetConfig::File::fallback_for) configuration
 /// of the [AssetConfig] interface for more information.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::StatusCode;
+/// use ic_asset_certification::{AssetConfig, AssetFallbackConfig, AssetFileOptions};
+///
+/// let api_not_found_config = AssetConfig::File {
+///     path: "404.json".to_string(),
+///     content_type: Some("application/json".to_string()),
+///     headers: vec![],
+///     fallback_for: vec![AssetFallbackConfig {
+///         scope: "/api".to_string(),
+///         status_code: Some(StatusCode::NOT_FOUND),
+///     }],
+///     aliased_by: vec![],
+///     encodings: vec![],
+///     options: AssetFileOptions {
+///         addressable: false,
+///         ..Default::default()
+///     },
+/// };
+///
+/// let not_found_config = AssetConfig::File {
+///     path: "404.html".to_string(),
+///     content_type: Some("text/html".to_string()),
+///     headers: vec![],
+///     fallback_for: vec![AssetFallbackConfig {
+///         scope: "/".to_string(),
+///         status_code: Some(StatusCode::NOT_FOUND),
+///     }],
+///     aliased_by: vec![],
+///     encodings: vec![],
+///     options: AssetFileOptions {
+///         addressable: false,
+///         ..Default::default()
+///     },
+/// };
+/// ```
 #[derive(Debug, Clone)]
 pub struct AssetFallbackConfig {
     /// The scope to use this asset as a fallback for.
@@ -431,6 +1092,390 @@ pub enum AssetRedirectKind {
     Temporary,
 }
 
+/// The kind of `ETag` header that the [AssetRouter](crate::AssetRouter) will
+/// generate for assets. ETags are enabled using the
+/// [with_etags](crate::AssetRouter::with_etags) method.
+///
+/// Regardless of the kind of ETag that is generated, a `304 Not Modified`
+/// response is only served for requests with an `If-None-Match` header that
+/// is exactly the asset's ETag, as sent back by browsers, since the header is
+/// certified as part of the request. Other forms that match using the weak
+/// comparison function described in
+/// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-8.8.3.2), such as
+/// lists of entity tags or `*`, are served the asset itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetETagKind {
+    /// A strong ETag, e.g. `"<hash>"`.
+    ///
+    /// The ETag is calculated from the content of each encoding of an asset,
+    /// so each encoding will be served with a different ETag.
+    Strong,
+
+    /// A weak ETag, e.g. `W/"<hash>"`.
+    ///
+    /// The ETag is calculated from the identity encoding of an asset, so all
+    /// encodings of the same asset will be served with the same ETag. This is
+    /// useful when encoded variants of an asset are semantically equivalent.
+    Weak,
+}
+
+/// A helper for building the `Content-Security-Policy` and
+/// `Content-Security-Policy-Report-Only` headers of an asset.
+///
+/// Browsers enforce the policy of the `Content-Security-Policy` header, and only report
+/// violations of the policy of the `Content-Security-Policy-Report-Only` header, without
+/// blocking anything. Serving both allows a stricter policy to be observed while the current
+/// policy is still enforced, before the stricter policy is rolled out.
+///
+/// The [headers](AssetContentSecurityPolicy::headers) are added to the `headers` of an
+/// [AssetConfig], so they are certified along with the rest of the asset's headers.
+///
+/// Violations are reported to the endpoints configured in the policy itself, for example
+/// with the `report-to` or `report-uri` directives. Such endpoints are not provided by the
+/// [AssetRouter](crate::AssetRouter) and need to be served separately.
+///
+/// # Examples
+///
+/// ```
+/// use ic_asset_certification::{AssetConfig, AssetContentSecurityPolicy};
+///
+/// let csp = AssetContentSecurityPolicy::new()
+///     .with_enforced("default-src 'self'")
+///     .with_report_only("default-src 'self'; script-src 'none'; report-uri /csp-reports");
+///
+/// let config = AssetConfig::File {
+///     path: "index.html".to_string(),
+///     content_type: Some("text/html".to_string()),
+///     headers: csp.headers(),
+///     fallback_for: vec![],
+///     aliased_by: vec![],
+///     encodings: vec![],
+///     options: Default::default(),
+/// };
+///
+/// assert_eq!(
+///     csp.headers(),
+///     vec![
+///         (
+///             "Content-Security-Policy".to_string(),
+///             "default-src 'self'".to_string(),
+///         ),
+///         (
+///             "Content-Security-Policy-Report-Only".to_string(),
+///             "default-src 'self'; script-src 'none'; report-uri /csp-reports".to_string(),
+///         ),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetContentSecurityPolicy {
+    enforced: Option<String>,
+    report_only: Option<String>,
+}
+
+impl AssetContentSecurityPolicy {
+    /// Creates a new [AssetContentSecurityPolicy] without any policies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the policy that is enforced using the `Content-Security-Policy` header.
+    pub fn with_enforced(mut self, policy: impl Into<String>) -> Self {
+        self.enforced = Some(policy.into());
+
+        self
+    }
+
+    /// Sets the policy that is only reported on using the
+    /// `Content-Security-Policy-Report-Only` header.
+    pub fn with_report_only(mut self, policy: impl Into<String>) -> Self {
+        self.report_only = Some(policy.into());
+
+        self
+    }
+
+    /// Returns the headers for the configured policies, to be included in the `headers`
+    /// of an [AssetConfig]. Policies that have not been set are omitted.
+    pub fn headers(&self) -> Vec<(String, String)> {
+        [
+            (CONTENT_SECURITY_POLICY_HEADER_NAME, &self.enforced),
+            (
+                CONTENT_SECURITY_POLICY_REPORT_ONLY_HEADER_NAME,
+                &self.report_only,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, policy)| {
+            policy
+                .as_ref()
+                .map(|policy| (name.to_string(), policy.clone()))
+        })
+        .collect()
+    }
+}
+
+const CONTENT_SECURITY_POLICY_HEADER_NAME: &str = "Content-Security-Policy";
+const CONTENT_SECURITY_POLICY_REPORT_ONLY_HEADER_NAME: &str = "Content-Security-Policy-Report-Only";
+
+/// The Cross-Origin Resource Sharing (CORS) configuration of an
+/// [AssetRouter](crate::AssetRouter), see
+/// [with_cors](crate::AssetRouter::with_cors).
+///
+/// The configuration consists of:
+///
+/// - The allowed origin, served in the `Access-Control-Allow-Origin` header of every response,
+///   e.g. `https://app.example.com`, or `*` to allow any origin. Responses are certified ahead
+///   of time, so they cannot echo the `Origin` header of a request, and only a single origin,
+///   or `*`, can be allowed.
+/// - The allowed methods, served in the `Access-Control-Allow-Methods` header of preflight
+///   responses. This defaults to `GET` and `HEAD`.
+/// - The allowed request headers, served in the `Access-Control-Allow-Headers` header of
+///   preflight responses. This defaults to no headers, so the header is omitted.
+/// - The maximum age, in seconds, for which browsers may cache a preflight response, served in
+///   the `Access-Control-Max-Age` header of preflight responses. This defaults to no maximum
+///   age, so the header is omitted and browsers use their own default.
+///
+/// # Examples
+///
+/// ```
+/// use ic_asset_certification::AssetCorsConfig;
+/// use ic_http_certification::Method;
+///
+/// let cors = AssetCorsConfig::new("https://app.example.com")
+///     .with_allowed_methods(vec![Method::GET, Method::POST])
+///     .with_allowed_headers(vec!["Content-Type".to_string()])
+///     .with_max_age(86_400);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetCorsConfig {
+    allowed_origin: String,
+    allowed_methods: Vec<Method>,
+    allowed_headers: Vec<String>,
+    max_age: Option<u64>,
+}
+
+impl AssetCorsConfig {
+    /// Creates a new [AssetCorsConfig] that allows the given single origin, or any origin if
+    /// `*` is provided.
+    pub fn new(allowed_origin: impl Into<String>) -> Self {
+        Self {
+            allowed_origin: allowed_origin.into(),
+            allowed_methods: AssetConfig::DEFAULT_ALLOWED_METHODS.to_vec(),
+            allowed_headers: vec![],
+            max_age: None,
+        }
+    }
+
+    /// Sets the methods that are allowed for cross-origin requests.
+    pub fn with_allowed_methods(mut self, allowed_methods: Vec<Method>) -> Self {
+        self.allowed_methods = allowed_methods;
+
+        self
+    }
+
+    /// Sets the request headers that are allowed for cross-origin requests.
+    pub fn with_allowed_headers(mut self, allowed_headers: Vec<String>) -> Self {
+        self.allowed_headers = allowed_headers;
+
+        self
+    }
+
+    /// Sets the maximum age for which browsers may cache preflight responses, in seconds.
+    pub fn with_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+
+        self
+    }
+
+    /// Returns the CORS headers of responses to actual requests.
+    pub(crate) fn response_headers(&self) -> Vec<(String, String)> {
+        vec![(
+            http::header::ACCESS_CONTROL_ALLOW_ORIGIN.to_string(),
+            self.allowed_origin.clone(),
+        )]
+    }
+
+    /// Returns the CORS headers of responses to preflight requests.
+    pub(crate) fn preflight_headers(&self) -> Vec<(String, String)> {
+        let mut headers = self.response_headers();
+
+        let allowed_methods = self
+            .allowed_methods
+            .iter()
+            .map(Method::as_str)
+            .collect::<Vec<_>>();
+        headers.push((
+            http::header::ACCESS_CONTROL_ALLOW_METHODS.to_string(),
+            allowed_methods.join(", "),
+        ));
+
+        if !self.allowed_headers.is_empty() {
+            headers.push((
+                http::header::ACCESS_CONTROL_ALLOW_HEADERS.to_string(),
+                self.allowed_headers.join(", "),
+            ));
+        }
+
+        if let Some(max_age) = self.max_age {
+            headers.push((
+                http::header::ACCESS_CONTROL_MAX_AGE.to_string(),
+                max_age.to_string(),
+            ));
+        }
+
+        headers
+    }
+}
+
+/// The configuration for validating the content hashes that are embedded in the filenames
+/// of fingerprinted assets, such as `app.3f2a1b4c.js`, see
+/// [with_filename_hash_validation](crate::AssetRouter::with_filename_hash_validation).
+///
+/// The configuration consists of:
+///
+/// - The glob pattern of the paths of the assets whose filenames contain a hash, e.g.
+///   `assets/*.js`. Encoded assets are certified with their own content, so the pattern
+///   should only match the unencoded assets.
+/// - The separator that precedes the hash in the filename. This defaults to `.`, for
+///   filenames such as `app.3f2a1b4c.js`, and can be set to `-` for filenames such as
+///   `app-3f2a1b4c.js`.
+/// - The minimum length of the hash. This defaults to 8 characters, so that short segments
+///   of the filename are not mistaken for a hash.
+///
+/// The hash is the segment of the filename, without its extension, that follows the last
+/// separator. For `assets/app.3f2a1b4c.js` with the default separator, the filename without
+/// its extension is `app.3f2a1b4c`, and the hash is `3f2a1b4c`. Only segments that consist
+/// entirely of hex digits are treated as hashes, so filenames such as `jquery.validate.js` or
+/// `vendor.polyfill.js` have no hash, and matching them with the pattern is an error. The hash
+/// must be a prefix of the hex encoded SHA-256 hash of the asset's content, ignoring case.
+///
+/// Bundlers usually fingerprint files with other hashing algorithms and encodings, so their
+/// default filenames can't be validated. Vite names files such as `index-BfV3aMf0.js`, with a
+/// base64url encoded hash that isn't hex, and webpack's `[contenthash]` is an xxhash64 hash
+/// by default. The build pipeline must be configured to embed a hex encoded SHA-256 hash, such
+/// as with webpack's `output.hashFunction: 'sha256'`, or with a custom naming function for
+/// Vite's `build.rollupOptions.output` options.
+///
+/// # Examples
+///
+/// ```
+/// use ic_asset_certification::AssetFilenameHashConfig;
+///
+/// let filename_hash = AssetFilenameHashConfig::new("assets/*.js")
+///     .with_separator('-')
+///     .with_min_hash_length(16);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetFilenameHashConfig {
+    pattern: String,
+    separator: char,
+    min_hash_length: usize,
+}
+
+impl AssetFilenameHashConfig {
+    /// Creates a new [AssetFilenameHashConfig] for the assets matching the given glob pattern.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            separator: '.',
+            min_hash_length: 8,
+        }
+    }
+
+    /// Sets the separator that precedes the hash in the filename.
+    pub fn with_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+
+        self
+    }
+
+    /// Sets the minimum length of the hash.
+    pub fn with_min_hash_length(mut self, min_hash_length: usize) -> Self {
+        self.min_hash_length = min_hash_length;
+
+        self
+    }
+
+    /// Returns the glob pattern of the assets whose filenames contain a hash.
+    pub(crate) fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the hash in the filename of the given path, if it has a hex segment that is at
+    /// least as long as the minimum hash length.
+    pub(crate) fn filename_hash<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let filename = path.rsplit('/').next().unwrap_or(path);
+        let (stem, _extension) = filename.rsplit_once('.')?;
+        let (_name, hash) = stem.rsplit_once(self.separator)?;
+
+        (hash.len() >= self.min_hash_length && hash.chars().all(|c| c.is_ascii_hexdigit()))
+            .then_some(hash)
+    }
+}
+
+/// The client hints configuration of an [AssetRouter](crate::AssetRouter), see
+/// [with_client_hints](crate::AssetRouter::with_client_hints).
+///
+/// The configuration consists of:
+///
+/// - The client hints that the router asks browsers to send, served in the `Accept-CH` and
+///   `Vary` headers of every response, e.g. `Sec-CH-Prefers-Color-Scheme` or `Sec-CH-DPR`.
+/// - The critical client hints, served in the `Critical-CH` header of every response. A
+///   browser that receives a response without a critical hint that it supports retries the
+///   request with the hint, so that the first response is already adapted to it. The
+///   critical hints should also be listed in the client hints. This defaults to no hints,
+///   so the header is omitted.
+///
+/// # Examples
+///
+/// ```
+/// use ic_asset_certification::AssetClientHintsConfig;
+///
+/// let client_hints = AssetClientHintsConfig::new(vec![
+///     "Sec-CH-Prefers-Color-Scheme".to_string(),
+///     "Sec-CH-DPR".to_string(),
+/// ])
+/// .with_critical_hints(vec!["Sec-CH-Prefers-Color-Scheme".to_string()]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetClientHintsConfig {
+    hints: Vec<String>,
+    critical_hints: Vec<String>,
+}
+
+impl AssetClientHintsConfig {
+    /// Creates a new [AssetClientHintsConfig] that asks browsers to send the given client
+    /// hints.
+    pub fn new(hints: Vec<String>) -> Self {
+        Self {
+            hints,
+            critical_hints: vec![],
+        }
+    }
+
+    /// Sets the client hints that browsers should retry requests with, if they are missing.
+    pub fn with_critical_hints(mut self, critical_hints: Vec<String>) -> Self {
+        self.critical_hints = critical_hints;
+
+        self
+    }
+
+    /// Returns the client hints headers of responses.
+    pub(crate) fn response_headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![];
+
+        if !self.hints.is_empty() {
+            headers.push(("accept-ch".to_string(), self.hints.join(", ")));
+        }
+
+        if !self.critical_hints.is_empty() {
+            headers.push(("critical-ch".to_string(), self.critical_hints.join(", ")));
+        }
+
+        headers
+    }
+}
+
 /// The encoding of an asset.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AssetEncoding {
@@ -447,6 +1492,13 @@ pub enum AssetEncoding {
     Gzip,
 
     /// The asset is encoded with the Deflate algorithm.
+    ///
+    /// The `deflate` content encoding is defined by
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-deflate-coding) as DEFLATE data
+    /// wrapped in the zlib format, so the encoded asset should be zlib-wrapped, as produced by
+    /// e.g. `pigz -z`. The encoded asset is certified and served as is, so raw DEFLATE data
+    /// without the zlib wrapper is also accepted, and is decoded by response verification as
+    /// a fallback, but may not be decoded by all clients.
     Deflate,
 }
 
@@ -540,12 +1592,18 @@ pub(crate) enum NormalizedAssetConfig {
         fallback_for: Vec<AssetFallbackConfig>,
         aliased_by: Vec<String>,
         encodings: Vec<(AssetEncoding, String)>,
+        addressable: bool,
+        skip_certification: bool,
+        upgrade: bool,
+        certified_query_parameters: Vec<(String, String)>,
+        allowed_methods: Vec<Method>,
     },
     Pattern {
-        pattern: GlobMatcher,
+        pattern: AssetPatternMatcher,
         content_type: Option<String>,
         headers: Vec<(String, String)>,
         encodings: Vec<(AssetEncoding, String)>,
+        certified_query_parameters: Vec<(String, String)>,
     },
     Redirect {
         from: String,
@@ -567,6 +1625,14 @@ impl TryFrom<AssetConfig> for NormalizedAssetConfig {
                 fallback_for,
                 aliased_by,
                 encodings,
+                options:
+                    AssetFileOptions {
+                        addressable,
+                        skip_certification,
+                        upgrade,
+                        certified_query_parameters,
+                        allowed_methods,
+                    },
             } => Ok(NormalizedAssetConfig::File {
                 path,
                 content_type,
@@ -574,17 +1640,28 @@ impl TryFrom<AssetConfig> for NormalizedAssetConfig {
                 fallback_for,
                 aliased_by,
                 encodings,
+                addressable,
+                skip_certification,
+                upgrade,
+                certified_query_parameters,
+                allowed_methods,
             }),
             AssetConfig::Pattern {
                 pattern,
                 content_type,
                 headers,
                 encodings,
+                options:
+                    AssetPatternOptions {
+                        certified_query_parameters,
+                    },
             } => Ok(NormalizedAssetConfig::Pattern {
-                pattern: Glob::new(&pattern)?.compile_matcher(),
+                pattern: AssetPatternMatcher::new(&pattern)
+                    .map_err(|source| AssetCertificationError::InvalidGlob { pattern, source })?,
                 content_type,
                 headers,
                 encodings,
+                certified_query_parameters,
             }),
             AssetConfig::Redirect {
                 from,
@@ -601,6 +1678,39 @@ impl TryFrom<AssetConfig> for NormalizedAssetConfig {
     }
 }
 
+/// Matches asset paths against the glob pattern of a [Pattern](AssetConfig::Pattern) config.
+///
+/// Directory-prefix patterns such as `assets/**` are common and match every path
+/// starting with the directory, so they are detected when the config is normalized and
+/// matched with a plain prefix comparison instead of evaluating the glob.
+#[derive(Debug, Clone)]
+pub(crate) enum AssetPatternMatcher {
+    /// The directory prefix of the pattern, including the trailing `/`.
+    Prefix(String),
+    /// Any other pattern, evaluated as a glob.
+    Glob(GlobMatcher),
+}
+
+const GLOB_META_CHARS: &[char] = &['*', '?', '[', ']', '{', '}', '\\'];
+
+impl AssetPatternMatcher {
+    pub(crate) fn new(pattern: &str) -> Result<Self, globset::Error> {
+        match pattern.strip_suffix("/**") {
+            Some(prefix) if !prefix.is_empty() && !prefix.contains(GLOB_META_CHARS) => {
+                Ok(Self::Prefix(format!("{prefix}/")))
+            }
+            _ => Ok(Self::Glob(Glob::new(pattern)?.compile_matcher())),
+        }
+    }
+
+    pub(crate) fn is_match(&self, path: &str) -> bool {
+        match self {
+            Self::Prefix(prefix) => path.starts_with(prefix.as_str()),
+            Self::Glob(matcher) => matcher.is_match(path),
+        }
+    }
+}
+
 impl NormalizedAssetConfig {
     pub(crate) fn matches_asset(&self, asset: &Asset) -> bool {
         match self {
@@ -617,6 +1727,37 @@ mod tests {
     use crate::Asset;
     use rstest::*;
 
+    #[rstest]
+    #[case(AssetContentSecurityPolicy::new(), vec![])]
+    #[case(
+        AssetContentSecurityPolicy::new().with_enforced("default-src 'self'"),
+        vec![("Content-Security-Policy", "default-src 'self'")],
+    )]
+    #[case(
+        AssetContentSecurityPolicy::new().with_report_only("default-src 'none'"),
+        vec![("Content-Security-Policy-Report-Only", "default-src 'none'")],
+    )]
+    #[case(
+        AssetContentSecurityPolicy::new()
+            .with_report_only("default-src 'none'")
+            .with_enforced("default-src 'self'"),
+        vec![
+            ("Content-Security-Policy", "default-src 'self'"),
+            ("Content-Security-Policy-Report-Only", "default-src 'none'"),
+        ],
+    )]
+    fn content_security_policy_headers(
+        #[case] csp: AssetContentSecurityPolicy,
+        #[case] expected_headers: Vec<(&str, &str)>,
+    ) {
+        let expected_headers: Vec<_> = expected_headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        assert_eq!(csp.headers(), expected_headers);
+    }
+
     #[rstest]
     #[case("index.html", "index.html", true)]
     #[case("app.js", "app.js", true)]
@@ -635,6 +1776,7 @@ mod tests {
             fallback_for: vec![],
             aliased_by: vec![],
             encodings: vec![],
+            options: Default::default(),
         }
         .try_into()
         .unwrap();
@@ -722,6 +1864,18 @@ mod tests {
     #[case("assets/app.js", "assets/**.{js,html}", true)]
     #[case("assets/app.js", "assets/**/*.{js,html}", true)]
     #[case("assets/app.js", "assets/**/**.{js,html}", true)]
+    // assets/**
+    #[case("assets/app.js", "assets/**", true)]
+    #[case("assets/js/app.js", "assets/**", true)]
+    #[case("app.js", "assets/**", false)]
+    #[case("assets", "assets/**", false)]
+    #[case("assets.js", "assets/**", false)]
+    #[case("assets-old/app.js", "assets/**", false)]
+    #[case("static/assets/app.js", "assets/**", false)]
+    // assets/js/**
+    #[case("assets/js/app.js", "assets/js/**", true)]
+    #[case("assets/js/vendor/lib.js", "assets/js/**", true)]
+    #[case("assets/css/app.css", "assets/js/**", false)]
     fn matches_asset_pattern(
         #[case] asset_path: &str,
         #[case] config_pattern: &str,
@@ -733,6 +1887,7 @@ mod tests {
             content_type: None,
             headers: vec![],
             encodings: vec![],
+            options: Default::default(),
         }
         .try_into()
         .unwrap();
@@ -740,6 +1895,43 @@ mod tests {
         assert_eq!(config.matches_asset(&asset), expected);
     }
 
+    #[rstest]
+    #[case("assets/**", true)]
+    #[case("assets/js/**", true)]
+    #[case("**", false)]
+    #[case("**/**", false)]
+    #[case("assets/*/**", false)]
+    #[case("assets/{js,css}/**", false)]
+    #[case("assets/**/*.js", false)]
+    #[case("assets/*", false)]
+    fn asset_pattern_matcher_detects_prefix_patterns(
+        #[case] pattern: &str,
+        #[case] expected_prefix: bool,
+    ) {
+        let matcher = AssetPatternMatcher::new(pattern).unwrap();
+
+        assert_eq!(
+            matches!(matcher, AssetPatternMatcher::Prefix(_)),
+            expected_prefix
+        );
+    }
+
+    #[rstest]
+    #[case("assets/app.js")]
+    #[case("assets/js/app.js")]
+    #[case("assets")]
+    #[case("app.js")]
+    #[case("assets-old/app.js")]
+    fn asset_pattern_matcher_prefix_matches_glob(#[case] asset_path: &str) {
+        let prefix_matcher = AssetPatternMatcher::new("assets/**").unwrap();
+        let glob_matcher = Glob::new("assets/**").unwrap().compile_matcher();
+
+        assert_eq!(
+            prefix_matcher.is_match(asset_path),
+            glob_matcher.is_match(asset_path)
+        );
+    }
+
     #[rstest]
     #[case("index.html")]
     #[case("app.js")]
@@ -770,4 +1962,295 @@ mod tests {
         assert_eq!(AssetEncoding::Deflate.to_string(), "deflate");
         assert_eq!(AssetEncoding::Identity.to_string(), "identity");
     }
+
+    #[test]
+    fn file_builder_defaults() {
+        let config = AssetConfig::file("index.html").build();
+
+        assert!(matches!(
+            config,
+            AssetConfig::File {
+                path,
+                content_type: None,
+                headers,
+                fallback_for,
+                aliased_by,
+                encodings,
+                options:
+                    AssetFileOptions {
+                        addressable: true,
+                        skip_certification: false,
+                        upgrade: false,
+                        certified_query_parameters,
+                        allowed_methods,
+                    },
+            } if path == "index.html"
+                && headers.is_empty()
+                && fallback_for.is_empty()
+                && aliased_by.is_empty()
+                && encodings.is_empty()
+                && certified_query_parameters.is_empty()
+                && allowed_methods == &AssetConfig::DEFAULT_ALLOWED_METHODS
+        ));
+    }
+
+    #[test]
+    fn file_builder() {
+        let config = AssetConfig::file("index.html")
+            .with_content_type("text/html")
+            .with_header("Cache-Control", "public, no-cache, no-store")
+            .with_fallback_for(AssetFallbackConfig {
+                scope: "/".to_string(),
+                status_code: Some(StatusCode::OK),
+            })
+            .with_alias("/")
+            .with_alias("/index")
+            .with_encoding(AssetEncoding::Brotli)
+            .with_custom_encoding(AssetEncoding::Gzip, "gzip")
+            .with_addressable(false)
+            .with_skip_certification(true)
+            .with_upgrade(true)
+            .with_certified_query_parameter("lang", "en")
+            .with_allowed_methods(vec![Method::GET])
+            .build();
+
+        let AssetConfig::File {
+            path,
+            content_type,
+            headers,
+            fallback_for,
+            aliased_by,
+            encodings,
+            options:
+                AssetFileOptions {
+                    addressable,
+                    skip_certification,
+                    upgrade,
+                    certified_query_parameters,
+                    allowed_methods,
+                },
+        } = config
+        else {
+            panic!("Expected a file config");
+        };
+
+        assert_eq!(path, "index.html");
+        assert_eq!(content_type, Some("text/html".to_string()));
+        assert_eq!(
+            headers,
+            vec![(
+                "Cache-Control".to_string(),
+                "public, no-cache, no-store".to_string()
+            )]
+        );
+        assert!(matches!(
+            fallback_for.as_slice(),
+            [AssetFallbackConfig {
+                scope,
+                status_code: Some(StatusCode::OK),
+            }] if scope == "/"
+        ));
+        assert_eq!(aliased_by, vec!["/".to_string(), "/index".to_string()]);
+        assert_eq!(
+            encodings,
+            vec![
+                (AssetEncoding::Brotli, ".br".to_string()),
+                (AssetEncoding::Gzip, "gzip".to_string()),
+            ]
+        );
+        assert!(!addressable);
+        assert!(skip_certification);
+        assert!(upgrade);
+        assert_eq!(
+            certified_query_parameters,
+            vec![("lang".to_string(), "en".to_string())]
+        );
+        assert_eq!(allowed_methods, vec![Method::GET]);
+    }
+
+    #[test]
+    fn pattern_builder() {
+        let config = AssetConfig::pattern("**/*.js")
+            .with_content_type("text/javascript")
+            .with_header("Cache-Control", "public, max-age=31536000, immutable")
+            .with_encoding(AssetEncoding::Gzip)
+            .with_custom_encoding(AssetEncoding::Brotli, "brotli")
+            .with_certified_query_parameter("v", "1")
+            .build();
+
+        let AssetConfig::Pattern {
+            pattern,
+            content_type,
+            headers,
+            encodings,
+            options: AssetPatternOptions {
+                certified_query_parameters,
+            },
+        } = config
+        else {
+            panic!("Expected a pattern config");
+        };
+
+        assert_eq!(pattern, "**/*.js");
+        assert_eq!(content_type, Some("text/javascript".to_string()));
+        assert_eq!(
+            headers,
+            vec![(
+                "Cache-Control".to_string(),
+                "public, max-age=31536000, immutable".to_string()
+            )]
+        );
+        assert_eq!(
+            encodings,
+            vec![
+                (AssetEncoding::Gzip, ".gz".to_string()),
+                (AssetEncoding::Brotli, "brotli".to_string()),
+            ]
+        );
+        assert_eq!(
+            certified_query_parameters,
+            vec![("v".to_string(), "1".to_string())]
+        );
+    }
+
+    #[test]
+    fn redirect_builder() {
+        let config = AssetConfig::redirect("/old", "/new", AssetRedirectKind::Temporary)
+            .with_header("content-type", "text/plain; charset=utf-8")
+            .build();
+
+        assert!(matches!(
+            config,
+            AssetConfig::Redirect {
+                from,
+                to,
+                kind: AssetRedirectKind::Temporary,
+                headers,
+            } if from == "/old"
+                && to == "/new"
+                && headers == vec![(
+                    "content-type".to_string(),
+                    "text/plain; charset=utf-8".to_string(),
+                )]
+        ));
+    }
+
+    #[rstest]
+    #[case("favicon.ico", Some("image/x-icon"), vec![])]
+    #[case("/favicon.ico", Some("image/x-icon"), vec![])]
+    #[case("favicon.svg", Some("image/svg+xml"), vec!["/favicon.ico"])]
+    #[case("icons/favicon.png", Some("image/png"), vec!["/favicon.ico"])]
+    #[case("favicon", None, vec!["/favicon.ico"])]
+    fn favicon_builder(
+        #[case] favicon_path: &str,
+        #[case] expected_content_type: Option<&str>,
+        #[case] expected_aliased_by: Vec<&str>,
+    ) {
+        let config = AssetConfig::favicon(favicon_path).build();
+
+        let AssetConfig::File {
+            path,
+            content_type,
+            aliased_by,
+            options:
+                AssetFileOptions {
+                    addressable,
+                    skip_certification,
+                    ..
+                },
+            ..
+        } = config
+        else {
+            panic!("Expected a file config");
+        };
+
+        assert_eq!(path, favicon_path);
+        assert_eq!(content_type.as_deref(), expected_content_type);
+        assert_eq!(aliased_by, expected_aliased_by);
+        assert!(addressable);
+        assert!(!skip_certification);
+    }
+
+    #[rstest]
+    #[case(
+        AssetFilenameHashConfig::new("**"),
+        "app.3f2a1b4c.js",
+        Some("3f2a1b4c")
+    )]
+    #[case(
+        AssetFilenameHashConfig::new("**"),
+        "assets/app.3f2a1b4c.js",
+        Some("3f2a1b4c")
+    )]
+    #[case(
+        AssetFilenameHashConfig::new("**"),
+        "assets/app.min.3f2a1b4c.js",
+        Some("3f2a1b4c")
+    )]
+    #[case(AssetFilenameHashConfig::new("**"), "assets.v1/app.js", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "app.3f2a1b4.js", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "app.js", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "jquery.validate.js", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "vendor.polyfill.js", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "app.3f2a1b4g.js", None)]
+    #[case(
+        AssetFilenameHashConfig::new("**").with_separator('-'),
+        "index-BfV3aMf0.js",
+        None
+    )]
+    #[case(
+        AssetFilenameHashConfig::new("**"),
+        "app.3F2A1B4C.js",
+        Some("3F2A1B4C")
+    )]
+    #[case(AssetFilenameHashConfig::new("**"), "3f2a1b4c", None)]
+    #[case(AssetFilenameHashConfig::new("**"), "app-3f2a1b4c.js", None)]
+    #[case(
+        AssetFilenameHashConfig::new("**").with_separator('-'),
+        "app-3f2a1b4c.js",
+        Some("3f2a1b4c")
+    )]
+    #[case(
+        AssetFilenameHashConfig::new("**").with_min_hash_length(4),
+        "app.3f2a.js",
+        Some("3f2a")
+    )]
+    fn filename_hash(
+        #[case] config: AssetFilenameHashConfig,
+        #[case] path: &str,
+        #[case] expected_hash: Option<&str>,
+    ) {
+        assert_eq!(config.filename_hash(path), expected_hash);
+    }
+
+    #[rstest]
+    #[case(AssetClientHintsConfig::new(vec![]), vec![])]
+    #[case(
+        AssetClientHintsConfig::new(vec!["Sec-CH-Prefers-Color-Scheme".to_string()]),
+        vec![
+            ("accept-ch", "Sec-CH-Prefers-Color-Scheme"),
+        ]
+    )]
+    #[case(
+        AssetClientHintsConfig::new(vec![
+            "Sec-CH-Prefers-Color-Scheme".to_string(),
+            "Sec-CH-DPR".to_string(),
+        ])
+        .with_critical_hints(vec!["Sec-CH-Prefers-Color-Scheme".to_string()]),
+        vec![
+            ("accept-ch", "Sec-CH-Prefers-Color-Scheme, Sec-CH-DPR"),
+            ("critical-ch", "Sec-CH-Prefers-Color-Scheme"),
+        ]
+    )]
+    fn client_hints_response_headers(
+        #[case] config: AssetClientHintsConfig,
+        #[case] expected_headers: Vec<(&str, &str)>,
+    ) {
+        let expected_headers = expected_headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(config.response_headers(), expected_headers);
+    }
 }
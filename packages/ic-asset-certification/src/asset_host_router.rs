@@ -0,0 +1,398 @@
+use crate::{Asset, AssetCertificationError, AssetCertificationResult, AssetConfig, AssetRouter};
+use ic_http_certification::{Hash, HttpCertificationTree, HttpRequest, HttpResponse};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// A router for certifying and serving separate sets of [Assets](Asset) per host,
+/// for canisters that serve multiple domains.
+///
+/// Each host is served by its own [AssetRouter], and the router for a request is
+/// selected using the request's [host](HttpRequest::host). Hosts are matched
+/// case-insensitively, and any port is ignored, so `Example.com:443` will be served by
+/// the assets certified for `example.com`.
+///
+/// If there are no assets certified for the request's host, or the request does not have a
+/// host, then the request is served by the [default host](AssetHostRouter::with_default_host),
+/// if one is configured. Otherwise,
+/// [NoAssetsMatchingRequestHost](AssetCertificationError::NoAssetsMatchingRequestHost)
+/// is returned, which canisters would typically translate into a `404 Not Found` response.
+///
+/// All hosts share the same
+/// [HttpCertificationTree](ic_http_certification::HttpCertificationTree), so a single
+/// [root hash](AssetHostRouter::root_hash) needs to be set as the canister's certified data.
+/// Every response is certified together with the `Host` request header of its host, so a
+/// response certified for one host will not pass verification when served for another host.
+/// Since the header is certified exactly, responses only pass verification for requests with the
+/// lowercase host, without a port, as it is sent by browsers for the default HTTPS port.
+/// This also applies to requests served by the default host, so assets need to be certified for
+/// every host that they should pass verification for.
+///
+/// The exceptions are `405 Method Not Allowed` responses, which are certified with response-only
+/// certification and therefore pass verification for any host. Since the tree is shared, the routers
+/// of the hosts don't support [maintenance mode](AssetRouter::enter_maintenance). Fallback scopes
+/// are also shared between hosts, since the certification tree is keyed by request path, so a
+/// fallback certified for a more specific scope on one host will prevent less specific fallbacks
+/// from passing verification on other hosts, for requests within that scope. Hosts that certify
+/// fallbacks should therefore certify fallbacks for the same scopes.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::HttpRequest;
+/// use ic_asset_certification::{Asset, AssetConfig, AssetHostRouter};
+///
+/// let mut asset_host_router = AssetHostRouter::default().with_default_host("example.com");
+///
+/// let asset_config = AssetConfig::File {
+///     path: "index.html".to_string(),
+///     content_type: Some("text/html".to_string()),
+///     headers: vec![],
+///     fallback_for: vec![],
+///     aliased_by: vec!["/".to_string()],
+///     encodings: vec![],
+///     options: Default::default(),
+/// };
+///
+/// asset_host_router
+///     .certify_assets(
+///         "example.com",
+///         vec![Asset::new("index.html", b"<h1>Example</h1>".as_slice())],
+///         vec![asset_config.clone()],
+///     )
+///     .unwrap();
+/// asset_host_router
+///     .certify_assets(
+///         "example.org",
+///         vec![Asset::new("index.html", b"<h1>Another example</h1>".as_slice())],
+///         vec![asset_config],
+///     )
+///     .unwrap();
+///
+/// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+/// let data_certificate = vec![1, 2, 3];
+///
+/// let request = HttpRequest::get("/")
+///     .with_headers(vec![("Host".to_string(), "example.org".to_string())])
+///     .build();
+/// let response = asset_host_router
+///     .serve_asset(&data_certificate, &request)
+///     .unwrap();
+/// assert_eq!(response.body(), b"<h1>Another example</h1>");
+///
+/// let request = HttpRequest::get("/")
+///     .with_headers(vec![("Host".to_string(), "unknown.com".to_string())])
+///     .build();
+/// let response = asset_host_router
+///     .serve_asset(&data_certificate, &request)
+///     .unwrap();
+/// assert_eq!(response.body(), b"<h1>Example</h1>");
+/// ```
+#[derive(Debug)]
+pub struct AssetHostRouter<'content> {
+    tree: Rc<RefCell<HttpCertificationTree>>,
+    routers: HashMap<String, AssetRouter<'content>>,
+    default_host: Option<String>,
+}
+
+impl<'content> AssetHostRouter<'content> {
+    /// Creates a new [AssetHostRouter].
+    pub fn new() -> Self {
+        Self::with_tree(Default::default())
+    }
+
+    /// Creates a new [AssetHostRouter] using the provided
+    /// [HttpCertificationTree](ic_http_certification::HttpCertificationTree)
+    /// for certifying assets of all hosts.
+    pub fn with_tree(tree: Rc<RefCell<HttpCertificationTree>>) -> Self {
+        Self {
+            tree,
+            routers: HashMap::new(),
+            default_host: None,
+        }
+    }
+
+    /// Sets the host whose assets are served for requests without a host,
+    /// or requests for a host that has no certified assets.
+    ///
+    /// By default, there is no default host and such requests will return
+    /// [NoAssetsMatchingRequestHost](AssetCertificationError::NoAssetsMatchingRequestHost).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetHostRouter;
+    ///
+    /// let asset_host_router = AssetHostRouter::default().with_default_host("example.com");
+    /// ```
+    pub fn with_default_host(mut self, host: impl AsRef<str>) -> Self {
+        self.default_host = Some(Self::normalize_host(host.as_ref()));
+
+        self
+    }
+
+    /// Returns the [AssetRouter] for the given host, if any assets have been
+    /// certified for that host.
+    pub fn get_router(&self, host: &str) -> Option<&AssetRouter<'content>> {
+        self.routers.get(&Self::normalize_host(host))
+    }
+
+    /// Certifies multiple assets and inserts them into the router for the given host,
+    /// to be served later by the [serve_asset](AssetHostRouter::serve_asset) function.
+    ///
+    /// See [certify_assets](AssetRouter::certify_assets) for more information on how
+    /// assets are certified.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetHostRouter::root_hash) of the tree.
+    pub fn certify_assets<'path>(
+        &mut self,
+        host: &str,
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+    ) -> AssetCertificationResult {
+        let tree = self.tree.clone();
+
+        self.routers
+            .entry(Self::normalize_host(host))
+            .or_insert_with_key(|host| AssetRouter::with_tree(tree).with_certified_host(host))
+            .certify_assets(assets, asset_configs)
+    }
+
+    /// Deletes multiple assets from the router for the given host, including any
+    /// certification for those assets.
+    ///
+    /// See [delete_assets](AssetRouter::delete_assets) for more information on how
+    /// assets are deleted. Certifications that are shared with other hosts, such as
+    /// identical `405 Method Not Allowed` responses at the same path, remain in the tree
+    /// for those hosts.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetHostRouter::root_hash) of the tree.
+    pub fn delete_assets<'path>(
+        &mut self,
+        host: &str,
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+    ) -> AssetCertificationResult {
+        let host = Self::normalize_host(host);
+        let Some(router) = self.routers.get_mut(&host) else {
+            return Ok(());
+        };
+
+        router.delete_assets(assets, asset_configs)?;
+
+        for (_, router) in self.routers.iter().filter(|(name, _)| **name != host) {
+            router.insert_tree_entries();
+        }
+
+        Ok(())
+    }
+
+    /// Serves an asset for the given request, using the router of the request's host.
+    ///
+    /// See [serve_asset](AssetRouter::serve_asset) for more information on how assets
+    /// are served, and [AssetHostRouter] for more information on how the host's router is
+    /// selected.
+    pub fn serve_asset(
+        &self,
+        data_certificate: &[u8],
+        request: &HttpRequest,
+    ) -> AssetCertificationResult<HttpResponse<'content>> {
+        let host = request.host().map(Self::normalize_host);
+
+        let router = host
+            .as_ref()
+            .and_then(|host| self.routers.get(host))
+            .or_else(|| {
+                self.default_host
+                    .as_ref()
+                    .and_then(|default_host| self.routers.get(default_host))
+            })
+            .ok_or(AssetCertificationError::NoAssetsMatchingRequestHost { host })?;
+
+        router.serve_asset(data_certificate, request)
+    }
+
+    /// Returns the root hash of the underlying
+    /// [HttpCertificationTree](ic_http_certification::HttpCertificationTree).
+    pub fn root_hash(&self) -> Hash {
+        self.tree.borrow().root_hash()
+    }
+
+    fn normalize_host(host: &str) -> String {
+        let host = host.trim();
+        let host = match host.rsplit_once(':') {
+            Some((name, port))
+                if port.bytes().all(|byte| byte.is_ascii_digit())
+                    && (name.ends_with(']') || !name.contains(':')) =>
+            {
+                name
+            }
+            _ => host,
+        };
+
+        host.trim_end_matches('.').to_ascii_lowercase()
+    }
+}
+
+impl Default for AssetHostRouter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use ic_response_verification::{verify_request_response_pair, ResponseVerificationError};
+    use ic_response_verification_test_utils::{
+        create_v2_certificate_fixture, get_current_timestamp, V2CertificateFixture,
+    };
+    use rstest::*;
+
+    const DATA_CERTIFICATE: &[u8] = &[1, 2, 3];
+    const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
+    const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
+
+    #[rstest]
+    #[case("example.com", "example.com")]
+    #[case("Example.COM", "example.com")]
+    #[case("example.com:8080", "example.com")]
+    #[case("example.com.", "example.com")]
+    #[case("[::1]:8080", "[::1]")]
+    #[case("[::1]", "[::1]")]
+    fn test_normalize_host(#[case] host: &str, #[case] expected: &str) {
+        assert_eq!(AssetHostRouter::normalize_host(host), expected);
+    }
+
+    #[rstest]
+    #[case("example.com", b"<h1>Example</h1>".as_slice())]
+    #[case("EXAMPLE.com:443", b"<h1>Example</h1>".as_slice())]
+    #[case("example.org", b"<h1>Another example</h1>".as_slice())]
+    fn test_serve_asset_for_host(#[case] host: &str, #[case] expected_body: &[u8]) {
+        let asset_host_router = asset_host_router();
+
+        let response = asset_host_router
+            .serve_asset(DATA_CERTIFICATE, &request_for_host(Some(host)))
+            .unwrap();
+
+        assert_eq!(response.body(), expected_body);
+    }
+
+    #[rstest]
+    #[case(Some("unknown.com"))]
+    #[case(None)]
+    fn test_serve_asset_for_unknown_host(#[case] host: Option<&str>) {
+        let asset_host_router = asset_host_router();
+
+        let result = asset_host_router.serve_asset(DATA_CERTIFICATE, &request_for_host(host));
+
+        assert!(matches!(
+            result,
+            Err(AssetCertificationError::NoAssetsMatchingRequestHost { host: actual_host })
+                if actual_host.as_deref() == host
+        ));
+
+        let asset_host_router = asset_host_router.with_default_host("Example.com");
+        let response = asset_host_router
+            .serve_asset(DATA_CERTIFICATE, &request_for_host(host))
+            .unwrap();
+
+        assert_eq!(response.body(), b"<h1>Example</h1>");
+    }
+
+    #[test]
+    fn test_delete_assets_keeps_other_hosts_certification() {
+        let mut asset_host_router = AssetHostRouter::default();
+        for host in ["example.com", "example.org"] {
+            asset_host_router
+                .certify_assets(host, vec![index_html("<h1>Example</h1>")], vec![])
+                .unwrap();
+        }
+
+        let mut expected_router = AssetRouter::default().with_certified_host("example.org");
+        expected_router
+            .certify_assets(vec![index_html("<h1>Example</h1>")], vec![])
+            .unwrap();
+
+        asset_host_router
+            .delete_assets("example.com", vec![index_html("<h1>Example</h1>")], vec![])
+            .unwrap();
+
+        assert_eq!(asset_host_router.root_hash(), expected_router.root_hash());
+        assert!(asset_host_router
+            .serve_asset(DATA_CERTIFICATE, &request_for_host(Some("example.com")))
+            .is_err());
+        assert!(asset_host_router
+            .serve_asset(DATA_CERTIFICATE, &request_for_host(Some("example.org")))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_serve_asset_for_other_host_fails_verification() {
+        let asset_host_router = asset_host_router();
+
+        let current_time = get_current_timestamp();
+        let V2CertificateFixture {
+            root_key,
+            certificate_cbor,
+            canister_id,
+        } = create_v2_certificate_fixture(&asset_host_router.root_hash(), &current_time);
+        let verify = |request: HttpRequest, response: HttpResponse| {
+            verify_request_response_pair(
+                request,
+                response,
+                canister_id.as_ref(),
+                current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            )
+        };
+
+        let request = request_for_host(Some("example.com"));
+        let response = asset_host_router
+            .serve_asset(&certificate_cbor, &request)
+            .unwrap();
+        assert_eq!(response.body(), b"<h1>Example</h1>");
+
+        let verification_info = verify(request, response.clone()).unwrap();
+        assert!(verification_info.is_fully_certified());
+
+        let result = verify(request_for_host(Some("example.org")), response);
+        assert_matches!(
+            result,
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+    }
+
+    fn asset_host_router() -> AssetHostRouter<'static> {
+        let mut asset_host_router = AssetHostRouter::default();
+        asset_host_router
+            .certify_assets("example.com", vec![index_html("<h1>Example</h1>")], vec![])
+            .unwrap();
+        asset_host_router
+            .certify_assets(
+                "example.org",
+                vec![index_html("<h1>Another example</h1>")],
+                vec![],
+            )
+            .unwrap();
+
+        asset_host_router
+    }
+
+    fn index_html(body: &'static str) -> Asset<'static, 'static> {
+        Asset::new("index.html", body.as_bytes())
+    }
+
+    fn request_for_host(host: Option<&str>) -> HttpRequest<'static> {
+        let headers = host
+            .map(|host| vec![("Host".to_string(), host.to_string())])
+            .unwrap_or_default();
+
+        HttpRequest::get("/index.html")
+            .with_headers(headers)
+            .build()
+    }
+}
@@ -1,14 +1,23 @@
 use crate::{
-    Asset, AssetCertificationError, AssetCertificationResult, AssetConfig, AssetEncoding,
-    AssetFallbackConfig, AssetMap, AssetRedirectKind, CertifiedAssetResponse,
+    Asset, AssetCertificationError, AssetCertificationResult, AssetClientHintsConfig, AssetConfig,
+    AssetCorsConfig, AssetETagKind, AssetEncoding, AssetFallbackConfig, AssetFilenameHashConfig,
+    AssetMap, AssetPatternMatcher, AssetRedirectKind, CertifiedAssetResponse,
     NormalizedAssetConfig, RequestKey,
 };
 use ic_http_certification::{
-    utils::add_v2_certificate_header, DefaultCelBuilder, DefaultResponseCertification, Hash,
-    HttpCertification, HttpCertificationPath, HttpCertificationTree, HttpCertificationTreeEntry,
-    HttpRequest, HttpResponse, StatusCode, CERTIFICATE_EXPRESSION_HEADER_NAME,
+    utils::{add_v2_certificate_header, WILDCARD_PATH_TERMINATOR},
+    DefaultCelBuilder, DefaultResponseCertification, Hash, HttpCertification,
+    HttpCertificationPath, HttpCertificationTree, HttpCertificationTreeEntry, HttpRequest,
+    HttpResponse, Method, StatusCode, CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
+};
+use ic_representation_independent_hash::hash;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    cmp,
+    collections::{HashMap, HashSet},
+    rc::Rc,
 };
-use std::{borrow::Cow, cell::RefCell, cmp, collections::HashMap, rc::Rc};
 
 /// A router for certifying and serving static [Assets](Asset).
 ///
@@ -49,6 +58,7 @@ use std::{borrow::Cow, cell::RefCell, cmp, collections::HashMap, rc::Rc};
 ///             AssetEncoding::Brotli.default_config(),
 ///             AssetEncoding::Gzip.default_config(),
 ///         ],
+///         options: Default::default(),
 ///     },
 ///     AssetConfig::Pattern {
 ///         pattern: "**/*.js".to_string(),
@@ -61,6 +71,7 @@ use std::{borrow::Cow, cell::RefCell, cmp, collections::HashMap, rc::Rc};
 ///             AssetEncoding::Brotli.default_config(),
 ///             AssetEncoding::Gzip.default_config(),
 ///         ],
+///         options: Default::default(),
 ///     },
 ///     AssetConfig::Pattern {
 ///         pattern: "**/*.css".to_string(),
@@ -73,6 +84,7 @@ use std::{borrow::Cow, cell::RefCell, cmp, collections::HashMap, rc::Rc};
 ///             AssetEncoding::Brotli.default_config(),
 ///             AssetEncoding::Gzip.default_config(),
 ///         ],
+///         options: Default::default(),
 ///     },
 ///     AssetConfig::Redirect {
 ///         from: "/old-url".to_string(),
@@ -124,65 +136,162 @@ pub struct AssetRouter<'content> {
     tree: Rc<RefCell<HttpCertificationTree>>,
     responses: HashMap<RequestKey, CertifiedAssetResponse<'content>>,
     fallback_responses: HashMap<RequestKey, CertifiedAssetResponse<'content>>,
+    method_responses: HashMap<String, HashMap<Method, CertifiedAssetResponse<'content>>>,
+    method_not_allowed_responses: HashMap<String, CertifiedAssetResponse<'content>>,
+    asset_allowed_methods: HashMap<String, Vec<Method>>,
+    fallback_max_depth: Option<usize>,
+    etag_kind: Option<AssetETagKind>,
+    accept_ranges: bool,
+    pending_certification: Option<PendingCertification<'content>>,
+    certified_query_parameter_names: HashMap<String, Vec<Vec<String>>>,
+    certificate_header_name: Option<String>,
+    build_id: Option<String>,
+    cors: Option<AssetCorsConfig>,
+    client_hints: Option<AssetClientHintsConfig>,
+    allow_conflicting_routes: bool,
+    alias_owners: HashMap<String, String>,
+    fallback_scope_owners: HashMap<String, String>,
+    cors_preflight_responses: HashMap<String, CertifiedAssetResponse<'content>>,
+    maintenance: Option<AssetMaintenance<'content>>,
+    variant_responses: HashMap<String, AssetVariants<'content>>,
+    media_type_fallback_responses: HashMap<String, Vec<(String, CertifiedAssetResponse<'content>)>>,
+    vary_accept_fallbacks: HashSet<RequestKey>,
+    trusted_forwarded_hosts: Vec<String>,
+    encoding_priority: Vec<AssetEncoding>,
+    any_encoding_when_accept_encoding_missing: bool,
+    path_rewrites: Vec<(String, String)>,
+    max_cache_age_secs: Option<u64>,
+    collapse_slashes: bool,
+    filename_hash_validation: Option<AssetFilenameHashConfig>,
+    forwarded_redirect_responses:
+        HashMap<String, HashMap<(String, String), CertifiedAssetResponse<'content>>>,
+    certified_host: Option<String>,
+}
+
+/// The request header that selects the host of a redirect certified for
+/// [trusted forwarded hosts](AssetRouter::with_trusted_forwarded_hosts).
+const X_FORWARDED_HOST_HEADER_NAME: &str = "X-Forwarded-Host";
+
+/// The request header that selects the protocol of a redirect certified for
+/// [trusted forwarded hosts](AssetRouter::with_trusted_forwarded_hosts).
+const X_FORWARDED_PROTO_HEADER_NAME: &str = "X-Forwarded-Proto";
+
+/// The protocols that a redirect is certified for, for each trusted forwarded host.
+const FORWARDED_PROTOS: [&str; 2] = ["https", "http"];
+
+/// The responses certified for a path with [certify_variants](AssetRouter::certify_variants),
+/// keyed by the value of the request header that selects them, along with the default response
+/// for requests without that header.
+#[derive(Debug)]
+struct AssetVariants<'content> {
+    header_name: String,
+    default: CertifiedAssetResponse<'content>,
+    responses: HashMap<String, CertifiedAssetResponse<'content>>,
+}
+
+/// A chunked certification started with [begin_certification](AssetRouter::begin_certification).
+///
+/// The chunks are certified into a separate router with its own tree, which is merged into the
+/// router that started the certification when it is [finalized](AssetRouter::finalize).
+#[derive(Debug)]
+struct PendingCertification<'content> {
+    asset_configs: Vec<NormalizedAssetConfig>,
+    router: Box<AssetRouter<'content>>,
 }
 
-#[derive(Debug, PartialEq)]
-struct RangeRequestValues {
-    pub range_begin: usize,
-    #[allow(unused)]
-    pub range_end: Option<usize>,
+/// The certified `503 Service Unavailable` response that is served while the router is in
+/// maintenance mode, along with the separate tree that it is certified in.
+#[derive(Debug)]
+struct AssetMaintenance<'content> {
+    tree: HttpCertificationTree,
+    response: CertifiedAssetResponse<'content>,
 }
 
 /// The chunk size that will be used when splitting assets larger than 2mb down into smaller chunks.
 pub const ASSET_CHUNK_SIZE: usize = 2_000_000;
 
+/// The name of the header that carries the build ID set with
+/// [with_build_id](AssetRouter::with_build_id).
+pub const BUILD_ID_HEADER_NAME: &str = "X-Build-Id";
+
+/// The names of the headers that are echoed from an asset's response in its
+/// `304 Not Modified` response, as listed in
+/// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-304-not-modified).
+///
+/// CORS response headers, with names starting with `Access-Control-`, are
+/// echoed as well.
+pub const NOT_MODIFIED_HEADER_NAMES: [&str; 6] = [
+    "cache-control",
+    "content-location",
+    "date",
+    "etag",
+    "expires",
+    "vary",
+];
+
+/// The encodings that are acceptable to a client sending an `Accept-Encoding: *` header.
+const WILDCARD_ENCODINGS: [&str; 4] = ["br", "zstd", "gzip", "deflate"];
+
+/// The default order of preference of encodings, see
+/// [with_encoding_priority](AssetRouter::with_encoding_priority).
+pub const DEFAULT_ENCODING_PRIORITY: [AssetEncoding; 4] = [
+    AssetEncoding::Brotli,
+    AssetEncoding::Zstd,
+    AssetEncoding::Gzip,
+    AssetEncoding::Deflate,
+];
+
 fn encoding_str(maybe_encoding: Option<AssetEncoding>) -> Option<String> {
     maybe_encoding.map(|enc| enc.to_string())
 }
 
-fn parse_range_header_str(range_str: &str) -> Result<RangeRequestValues, String> {
-    // expected format: `bytes=<range-begin>-[<range-end>]`
-    let str_value = range_str.trim();
-    if !str_value.starts_with("bytes=") {
-        return Err(format!("Invalid Range header '{}'", range_str).to_string());
-    }
-    let str_value = str_value.trim_start_matches("bytes=");
-    let range_header_parts = str_value.split('-').collect::<Vec<_>>();
-    if range_header_parts.is_empty() || range_header_parts.len() != 2 {
-        return Err(format!("Invalid Range header '{}'", range_str).to_string());
+/// Returns whether the given `Vary` header value lists the given header name, or `*`.
+fn vary_lists(vary: &str, header_name: &str) -> bool {
+    vary.split(',')
+        .map(str::trim)
+        .any(|token| token == "*" || token.eq_ignore_ascii_case(header_name))
+}
+
+/// Adds the given header name to the `Vary` header of the given headers, adding a `Vary`
+/// header if there is none, unless it is already listed.
+fn with_vary_token(mut headers: Vec<(String, String)>, header_name: &str) -> Vec<(String, String)> {
+    match headers
+        .iter_mut()
+        .find(|(name, _)| name.eq_ignore_ascii_case(http::header::VARY.as_str()))
+    {
+        Some((_, vary)) if vary_lists(vary, header_name) => {}
+        Some((_, vary)) => *vary = format!("{vary}, {header_name}"),
+        None => headers.push((http::header::VARY.to_string(), header_name.to_string())),
     }
 
-    let range_begin = range_header_parts[0].parse::<usize>().map_err(|e| {
-        format!(
-            "Malformed range_begin in Range header '{}': {}",
-            range_str, e
-        )
-    })?;
-    let range_end =
-        match range_header_parts[1] {
-            "" => None,
-            _ => Some(range_header_parts[1].parse::<usize>().map_err(|e| {
-                format!("Malformed range_end in Range header '{}': {}", range_str, e)
-            })?),
-        };
+    headers
+}
 
-    if range_begin > range_end.unwrap_or(usize::MAX) {
-        return Err(format!("Invalid values in Range header '{}'", range_str).to_string());
-    }
-    Ok(RangeRequestValues {
-        range_begin,
-        range_end,
-    })
+/// Removes the given header name from the `Vary` header of the given headers, removing the
+/// `Vary` header if no other header names are listed.
+fn without_vary_token(headers: Vec<(String, String)>, header_name: &str) -> Vec<(String, String)> {
+    headers
+        .into_iter()
+        .filter_map(|(name, value)| {
+            if !name.eq_ignore_ascii_case(http::header::VARY.as_str()) {
+                return Some((name, value));
+            }
+
+            let value = value
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.eq_ignore_ascii_case(header_name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            (!value.is_empty()).then_some((name, value))
+        })
+        .collect()
 }
 
 impl<'content> AssetRouter<'content> {
     /// Creates a new [AssetRouter].
     pub fn new() -> Self {
-        AssetRouter {
-            tree: Default::default(),
-            responses: HashMap::new(),
-            fallback_responses: HashMap::new(),
-        }
+        Self::with_tree(Rc::new(RefCell::new(HttpCertificationTree::default())))
     }
 
     /// Creates a new [AssetRouter] using the provided
@@ -193,1790 +302,7861 @@ impl<'content> AssetRouter<'content> {
             tree,
             responses: HashMap::new(),
             fallback_responses: HashMap::new(),
+            method_responses: HashMap::new(),
+            method_not_allowed_responses: HashMap::new(),
+            asset_allowed_methods: HashMap::new(),
+            fallback_max_depth: None,
+            etag_kind: None,
+            accept_ranges: false,
+            pending_certification: None,
+            certified_query_parameter_names: HashMap::new(),
+            certificate_header_name: None,
+            build_id: None,
+            cors: None,
+            client_hints: None,
+            allow_conflicting_routes: false,
+            alias_owners: HashMap::new(),
+            fallback_scope_owners: HashMap::new(),
+            cors_preflight_responses: HashMap::new(),
+            maintenance: None,
+            variant_responses: HashMap::new(),
+            media_type_fallback_responses: HashMap::new(),
+            vary_accept_fallbacks: HashSet::new(),
+            trusted_forwarded_hosts: vec![],
+            encoding_priority: DEFAULT_ENCODING_PRIORITY.to_vec(),
+            any_encoding_when_accept_encoding_missing: true,
+            path_rewrites: vec![],
+            max_cache_age_secs: None,
+            collapse_slashes: false,
+            filename_hash_validation: None,
+            forwarded_redirect_responses: HashMap::new(),
+            certified_host: None,
         }
     }
 
-    fn maybe_get_range_begin(request: &HttpRequest) -> AssetCertificationResult<Option<usize>> {
-        if let Some(range_str) = Self::get_range_header(request) {
-            parse_range_header_str(range_str)
-                .map(|e| Some(e.range_begin))
-                .map_err(AssetCertificationError::RequestError)
-        } else {
-            Ok(None)
-        }
-    }
-
-    /// Returns the corresponding
-    /// [HttpResponse](ic_http_certification::HttpResponse) for the provided
-    /// [HttpRequest](ic_http_certification::HttpRequest) if it is found
-    /// in the router.
+    /// Limits the number of scopes that will be searched when resolving a
+    /// fallback for a request that does not exactly match any asset.
     ///
-    /// # Arguments
+    /// Scopes are searched starting from the directory containing the
+    /// requested path and moving up towards the root. A `max_depth` of `1`
+    /// will only consider fallbacks scoped to the requested path's own
+    /// directory, a `max_depth` of `2` will also consider its parent
+    /// directory, and so on. A `max_depth` of `0` disables fallbacks entirely.
     ///
-    /// * `data_certificate` - A byte slice representing the data certificate used for asset certification.
-    ///     This should be retrieved using `ic_cdk::api::data_certificate()`.
-    /// * `request` - A reference to an [HttpRequest](ic_http_certification::HttpRequest) object representing the incoming HTTP request.
+    /// If no fallback is found within the limit, then
+    /// [serve_asset](AssetRouter::serve_asset) will return
+    /// [NoAssetMatchingRequestUrl](AssetCertificationError::NoAssetMatchingRequestUrl).
     ///
-    /// If an exact match is not found, then a fallback will
-    /// be searched for. See the
-    /// [fallback_for](AssetConfig::File::fallback_for) configuration
-    /// option for more information on fallbacks.
+    /// By default, there is no limit.
     ///
-    /// Returns [None] if no suitable
-    /// [HttpResponse](ic_http_certification::HttpResponse) is found for the
-    /// given [HttpRequest](ic_http_certification::HttpRequest).
-    pub fn serve_asset(
-        &self,
-        data_certificate: &[u8],
-        request: &HttpRequest,
-    ) -> AssetCertificationResult<HttpResponse<'content>> {
-        let preferred_encodings = self.get_preferred_encodings(request);
-        let request_url = request.get_path()?;
-        let maybe_range_begin = Self::maybe_get_range_begin(request)?;
-        let mut cert_response = self
-            .get_asset_for_request(&request_url, preferred_encodings, maybe_range_begin)
-            .cloned()?;
-        let witness = self
-            .tree
-            .borrow()
-            .witness(&cert_response.tree_entry, &request_url)?;
-        let expr_path = cert_response.tree_entry.path.to_expr_path();
-        add_v2_certificate_header(
-            data_certificate,
-            &mut cert_response.response,
-            &witness,
-            &expr_path,
-        );
-        Ok(cert_response.response.clone())
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let asset_router = AssetRouter::default().with_fallback_max_depth(2);
+    /// ```
+    pub fn with_fallback_max_depth(mut self, max_depth: usize) -> Self {
+        self.fallback_max_depth = Some(max_depth);
+
+        self
     }
 
-    /// Returns all standard assets stored in the router.
+    /// Enables generation of an `ETag` header for assets, and serving of
+    /// `304 Not Modified` responses for conditional requests.
     ///
-    /// See the [get_fallback_assets()](AssetRouter::get_fallback_assets)
-    /// function for fallback assets.
+    /// Each asset will be certified and served with an `ETag` header of the
+    /// given [kind](AssetETagKind). In addition, a `304 Not Modified` response is
+    /// certified for each asset that is not split into multiple chunks. This
+    /// response is served instead of the asset when a request includes an
+    /// `If-None-Match` header that is exactly the asset's ETag, see
+    /// [AssetETagKind] for more details. The `If-None-Match` header is certified
+    /// as part of the request, so the `304 Not Modified` response only passes
+    /// verification for such requests, and can't be served in place of the asset
+    /// for an unconditional request.
+    /// The `304 Not Modified` response only echoes the asset's headers that are
+    /// listed in [NOT_MODIFIED_HEADER_NAMES], along with its CORS headers, and can
+    /// also be served directly with
+    /// [serve_not_modified](AssetRouter::serve_not_modified).
     ///
-    /// See the [AssetMap] struct for more information on the returned type.
-    pub fn get_assets(&self) -> &impl AssetMap<'content> {
-        &self.responses
+    /// This option must be set before any assets are certified, and must not be
+    /// changed before certified assets are deleted with
+    /// [delete_assets](AssetRouter::delete_assets).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{AssetETagKind, AssetRouter};
+    ///
+    /// let asset_router = AssetRouter::default().with_etags(AssetETagKind::Weak);
+    /// ```
+    pub fn with_etags(mut self, etag_kind: AssetETagKind) -> Self {
+        self.etag_kind = Some(etag_kind);
+
+        self
     }
 
-    /// Returns all fallback assets stored in the router.
+    /// Sets whether requests without an `Accept-Encoding` header are treated as accepting any
+    /// encoding, as if they had sent `Accept-Encoding: *`, rather than only the identity
+    /// encoding.
     ///
-    /// See the [get_assets()](AssetRouter::get_assets)
-    /// function for standard assets.
+    /// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-accept-encoding) distinguishes
+    /// a missing `Accept-Encoding` header, which means that any encoding is acceptable, from
+    /// an empty one, which means that only the identity encoding is acceptable. By default,
+    /// the highest priority certified encoding is served to requests without the header,
+    /// while requests with an empty header are served the identity variant. Some clients
+    /// omit the header even though they cannot decode compressed bodies, disabling this
+    /// option serves the identity variant to them instead.
     ///
-    /// See the [AssetMap] struct for more information on the returned type.
-    pub fn get_fallback_assets(&self) -> &impl AssetMap<'content> {
-        &self.fallback_responses
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let asset_router =
+    ///     AssetRouter::default().with_any_encoding_when_accept_encoding_missing(false);
+    /// ```
+    pub fn with_any_encoding_when_accept_encoding_missing(
+        mut self,
+        any_encoding_when_accept_encoding_missing: bool,
+    ) -> Self {
+        self.any_encoding_when_accept_encoding_missing = any_encoding_when_accept_encoding_missing;
+
+        self
     }
 
-    /// Certifies multiple assets and inserts them into the router, to be served
-    /// later by the [serve_asset](AssetRouter::serve_asset) method.
+    /// Enables or disables advertising support for range requests with a
+    /// certified `Accept-Ranges` header.
     ///
-    /// The asset certification is configured using the provided [AssetConfig]
-    /// enum.
+    /// When enabled, assets that are larger than [ASSET_CHUNK_SIZE], and are
+    /// therefore served in chunks with range requests, will be certified and
+    /// served with an `Accept-Ranges: bytes` header. All other assets, including
+    /// fallbacks, will be certified and served with an `Accept-Ranges: none`
+    /// header.
     ///
-    /// If no configuration matches an individual asset, the asset will be
-    /// served and certified as-is, without headers.
+    /// The header can be configured per asset by including an `Accept-Ranges`
+    /// header in the [headers](crate::AssetConfig::File::headers) of the asset's
+    /// config, in which case the configured header is used instead.
     ///
-    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
-    /// to the new [root hash](AssetRouter::root_hash) of the tree.
-    pub fn certify_assets<'path>(
-        &mut self,
-        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
-        asset_configs: impl IntoIterator<Item = AssetConfig>,
-    ) -> AssetCertificationResult {
-        let asset_configs: Vec<NormalizedAssetConfig> = asset_configs
-            .into_iter()
-            .map(TryInto::try_into)
-            .collect::<AssetCertificationResult<_>>()?;
-
-        let asset_map = assets
-            .into_iter()
-            .map(|asset| (asset.path.clone(), asset))
-            .collect::<HashMap<_, _>>();
-
-        for asset in asset_map.values() {
-            let asset_config = asset_configs.iter().find(|e| e.matches_asset(asset));
-            for (encoding, postfix) in asset_config
-                .map(|e| match e {
-                    NormalizedAssetConfig::File { encodings, .. } => encodings.clone(),
-                    NormalizedAssetConfig::Pattern { encodings, .. } => encodings.clone(),
-                    _ => vec![],
-                })
-                .unwrap_or_default()
-            {
-                let encoded_asset_path = format!("{}{}", asset.path, postfix);
-                let encoded_asset = asset_map.get(encoded_asset_path.as_str()).cloned();
-                if let Some(mut encoded_asset) = encoded_asset {
-                    encoded_asset.url.clone_from(&asset.url);
+    /// This option must be set before any assets are certified, and must not be
+    /// changed before certified assets are deleted with
+    /// [delete_assets](AssetRouter::delete_assets).
+    ///
+    /// By default, no `Accept-Ranges` header is added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let asset_router = AssetRouter::default().with_accept_ranges(true);
+    /// ```
+    pub fn with_accept_ranges(mut self, accept_ranges: bool) -> Self {
+        self.accept_ranges = accept_ranges;
 
-                    self.certify_asset_impl(encoded_asset, asset_config, Some(encoding))?;
-                }
-            }
+        self
+    }
 
-            self.certify_asset_impl(asset.clone(), asset_config, None)?;
-        }
+    /// Sets the name of the header that carries the certificate in served responses,
+    /// instead of [CERTIFICATE_HEADER_NAME](ic_http_certification::CERTIFICATE_HEADER_NAME).
+    ///
+    /// The certificate header is not part of certification, so this option can be changed
+    /// at any time. The `IC-CertificateExpression` header is part of certification and is
+    /// not affected.
+    ///
+    /// **Warning:** standard HTTP Gateways only accept the `IC-Certificate` header, so
+    /// responses served with a different header name will fail verification with them. This
+    /// option is only intended for interoperability testing with verifiers that are configured
+    /// with the same header name, such as `ResponseVerifier::with_certificate_header_name`
+    /// in the `ic-response-verification` crate.
+    ///
+    /// By default, the `IC-Certificate` header is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let asset_router = AssetRouter::default().with_certificate_header_name("X-IC-Certificate");
+    /// ```
+    pub fn with_certificate_header_name(
+        mut self,
+        certificate_header_name: impl Into<String>,
+    ) -> Self {
+        self.certificate_header_name = Some(certificate_header_name.into());
+
+        self
+    }
 
-        for asset_config in asset_configs {
-            if let NormalizedAssetConfig::Redirect {
-                from,
-                to,
-                kind,
-                headers,
-            } = asset_config
-            {
-                self.insert_redirect(from, to, kind, headers)?;
-            }
-        }
+    /// Sets a build ID, such as a version number or a commit hash, that is
+    /// certified and served with a [BUILD_ID_HEADER_NAME] header on every
+    /// asset, fallback and redirect response. This allows clients to discover
+    /// which build of the frontend they are being served.
+    ///
+    /// The header can be configured per asset by including a [BUILD_ID_HEADER_NAME]
+    /// header in the [headers](crate::AssetConfig::File::headers) of the asset's
+    /// config, in which case the configured header is used instead.
+    ///
+    /// The build ID is part of the certification of every response, so changing
+    /// it re-certifies everything: all certified assets must be deleted with
+    /// [delete_assets](AssetRouter::delete_assets) or [clear](AssetRouter::clear)
+    /// before the build ID is changed, and certified again afterwards. This option
+    /// must therefore be set before any assets are certified.
+    ///
+    /// By default, no build ID header is added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let asset_router = AssetRouter::default().with_build_id("1.2.3");
+    /// ```
+    pub fn with_build_id(mut self, build_id: impl Into<String>) -> Self {
+        self.build_id = Some(build_id.into());
 
-        Ok(())
+        self
     }
 
-    /// Deletes multiple assets from the router, including any certification for those assets.
+    /// Bounds the `max-age` and `s-maxage` directives of the `Cache-Control` header of
+    /// certified assets by the maximum certificate time offset that clients accept.
     ///
-    /// Depending on the configuration provided to the [certify_assets](AssetRouter::certify_assets) function,
-    /// multiple responses may be generated for the same asset. To ensure that all generated responses are deleted,
-    /// this function accepts the same configuration.
+    /// A response is verified against the certificate that it was served with, and
+    /// verification fails if the time of that certificate is more than
+    /// `max_cert_time_offset_ns` nanoseconds away from the current time, see
+    /// [verify_request_response_pair](https://docs.rs/ic-response-verification/latest/ic_response_verification/fn.verify_request_response_pair.html).
+    /// The certificate time is close to the time that the response was served, so a
+    /// response that is cached for longer than this offset and verified afterwards, such as
+    /// by a service worker that verifies responses from its cache, will fail verification.
+    /// The given offset should therefore match the one used by clients, which is 5 minutes
+    /// for the HTTP gateways.
     ///
-    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
-    /// to the new [root hash](AssetRouter::root_hash) of the tree.
-    pub fn delete_assets<'path>(
-        &mut self,
-        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
-        asset_configs: impl IntoIterator<Item = AssetConfig>,
-    ) -> AssetCertificationResult {
-        let asset_configs: Vec<NormalizedAssetConfig> = asset_configs
-            .into_iter()
-            .map(TryInto::try_into)
-            .collect::<AssetCertificationResult<_>>()?;
+    /// Any `max-age` or `s-maxage` directive above the offset, rounded down to whole
+    /// seconds, is lowered to it. The bound is applied when assets are certified, so the
+    /// bounded `Cache-Control` header is certified, and this option must be set before any
+    /// assets are certified. Assets without a `Cache-Control` header are not affected, and
+    /// neither are redirects.
+    ///
+    /// By default, the `Cache-Control` header is certified as it is configured.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpRequest;
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default()
+    ///     .with_max_cert_time_offset(300_000_000_000);
+    ///
+    /// let asset = Asset::new("app.js", b"console.log('Hello, World!');".as_slice());
+    /// let asset_config = AssetConfig::file("app.js")
+    ///     .with_content_type("text/javascript")
+    ///     .with_header("Cache-Control", "public, max-age=31536000, immutable")
+    ///     .build();
+    ///
+    /// asset_router.certify_assets(vec![asset], vec![asset_config]).unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/app.js").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert!(response.headers().contains(&(
+    ///     "Cache-Control".to_string(),
+    ///     "public, max-age=300, immutable".to_string()
+    /// )));
+    /// assert_eq!(asset_router.bounded_max_age(31536000), 300);
+    /// ```
+    pub fn with_max_cert_time_offset(mut self, max_cert_time_offset_ns: u128) -> Self {
+        let max_cache_age_secs = max_cert_time_offset_ns / 1_000_000_000;
+        self.max_cache_age_secs = Some(u64::try_from(max_cache_age_secs).unwrap_or(u64::MAX));
+
+        self
+    }
 
-        let asset_map = assets
-            .into_iter()
-            .map(|asset| (asset.path.clone(), asset))
-            .collect::<HashMap<_, _>>();
+    /// Returns the given `max-age`, in seconds, bounded by the maximum certificate time
+    /// offset configured with [with_max_cert_time_offset](AssetRouter::with_max_cert_time_offset).
+    ///
+    /// This can be used to compute the `Cache-Control` header of responses that are
+    /// certified outside of the router, such as with
+    /// [certify_method_response](AssetRouter::certify_method_response). If no offset is
+    /// configured, the `max-age` is returned as is.
+    pub fn bounded_max_age(&self, max_age_secs: u64) -> u64 {
+        self.max_cache_age_secs
+            .map_or(max_age_secs, |max_cache_age_secs| {
+                max_age_secs.min(max_cache_age_secs)
+            })
+    }
 
-        for asset in asset_map.values() {
-            let asset_config = asset_configs.iter().find(|e| e.matches_asset(asset));
+    /// Certifies redirects with a relative target, such as `/new-page`, a second time for
+    /// each of the given hosts, with an absolute `Location` that is built from the
+    /// `X-Forwarded-Proto` and `X-Forwarded-Host` headers of the request, such as
+    /// `https://example.com/new-page`. This allows a canister that is served behind a custom
+    /// domain, through a proxy that sets these headers, to redirect to its external host.
+    ///
+    /// The absolute redirect is only served for `GET` requests with an `X-Forwarded-Host`
+    /// header that exactly matches one of the given hosts, and an `X-Forwarded-Proto` header
+    /// of exactly `https` or `http`. Any other request, including one without these headers,
+    /// is served the redirect with its relative `Location`, as it is configured. Redirects
+    /// with an absolute target are always served as they are configured.
+    ///
+    /// Forwarded headers can be set by any client, not just by a trusted proxy, so their
+    /// values are never used to build a `Location` directly. Instead, an absolute redirect is
+    /// certified in advance for every trusted host and protocol, with full certification
+    /// that includes both forwarded request headers, so that a client verifying the redirect
+    /// can check that it was served for the forwarded headers of its request. A client that
+    /// sets these headers itself can only be redirected to one of the trusted hosts, which
+    /// prevents open redirects. Note that each redirect is certified once for every trusted
+    /// host and protocol, so the certification tree grows with the number of trusted hosts.
+    ///
+    /// This option must be set before any redirects are certified. By default, no hosts are
+    /// trusted and redirects are always served with their configured `Location`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpRequest;
+    /// use ic_asset_certification::{AssetConfig, AssetRedirectKind, AssetRouter};
+    ///
+    /// let mut asset_router =
+    ///     AssetRouter::default().with_trusted_forwarded_hosts(vec!["example.com".to_string()]);
+    /// asset_router
+    ///     .certify_assets(
+    ///         vec![],
+    ///         vec![AssetConfig::Redirect {
+    ///             from: "/old".to_string(),
+    ///             to: "/new".to_string(),
+    ///             kind: AssetRedirectKind::Permanent,
+    ///             headers: vec![],
+    ///         }],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/old")
+    ///     .with_headers(vec![
+    ///         ("X-Forwarded-Host".to_string(), "example.com".to_string()),
+    ///         ("X-Forwarded-Proto".to_string(), "https".to_string()),
+    ///     ])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert!(response
+    ///     .headers()
+    ///     .contains(&("location".to_string(), "https://example.com/new".to_string())));
+    /// ```
+    pub fn with_trusted_forwarded_hosts(mut self, trusted_forwarded_hosts: Vec<String>) -> Self {
+        self.trusted_forwarded_hosts = trusted_forwarded_hosts;
+
+        self
+    }
 
-            for (encoding, postfix) in asset_config
-                .map(|e| match e {
-                    NormalizedAssetConfig::File { encodings, .. } => encodings.clone(),
-                    NormalizedAssetConfig::Pattern { encodings, .. } => encodings.clone(),
-                    _ => vec![],
-                })
-                .unwrap_or_default()
+    /// Sets the order of preference in which acceptable encodings are served, replacing the
+    /// [default order](DEFAULT_ENCODING_PRIORITY) of `br`, `zstd`, `gzip` and `deflate`.
+    ///
+    /// The `Accept-Encoding` header of a request only determines which encodings are
+    /// acceptable to the client, and the certified encoding of an asset that comes first in
+    /// this order is served. The default order minimizes the size of responses, but
+    /// preferring `gzip`, for example, reduces the cost of decoding on low-end clients.
+    /// Encodings that are not listed keep their default relative order, after all of the
+    /// listed encodings. The identity variant of an asset is always served last, as described
+    /// in [serve_asset](AssetRouter::serve_asset), so it cannot be listed.
+    ///
+    /// Returns an
+    /// [InvalidEncodingPriority](AssetCertificationError::InvalidEncodingPriority) error if
+    /// the [Identity](AssetEncoding::Identity) encoding is listed, or if an encoding is listed
+    /// more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpRequest;
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetEncoding, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default()
+    ///     .with_encoding_priority(vec![AssetEncoding::Gzip])
+    ///     .unwrap();
+    /// asset_router
+    ///     .certify_assets(
+    ///         vec![
+    ///             Asset::new("app.js", b"console.log('Hello World!');".as_slice()),
+    ///             Asset::new("app.js.br", b"brotli".as_slice()),
+    ///             Asset::new("app.js.gz", b"gzip".as_slice()),
+    ///         ],
+    ///         vec![AssetConfig::File {
+    ///             path: "app.js".to_string(),
+    ///             content_type: Some("text/javascript".to_string()),
+    ///             headers: vec![],
+    ///             fallback_for: vec![],
+    ///             aliased_by: vec![],
+    ///             encodings: vec![
+    ///                 AssetEncoding::Brotli.default_config(),
+    ///                 AssetEncoding::Gzip.default_config(),
+    ///             ],
+    ///             options: Default::default(),
+    ///         }],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/app.js")
+    ///     .with_headers(vec![("Accept-Encoding".to_string(), "br, gzip".to_string())])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"gzip");
+    /// ```
+    pub fn with_encoding_priority(
+        mut self,
+        encoding_priority: Vec<AssetEncoding>,
+    ) -> AssetCertificationResult<Self> {
+        for (index, encoding) in encoding_priority.iter().enumerate() {
+            if *encoding == AssetEncoding::Identity || encoding_priority[..index].contains(encoding)
             {
-                let encoded_asset_path = format!("{}{}", asset.path, postfix);
-                let encoded_asset = asset_map.get(encoded_asset_path.as_str()).cloned();
-
-                if let Some(mut encoded_asset) = encoded_asset {
-                    encoded_asset.url.clone_from(&asset.url);
-
-                    self.delete_asset_impl(encoded_asset, asset_config, Some(encoding))?;
-                }
+                return Err(AssetCertificationError::InvalidEncodingPriority {
+                    encoding: *encoding,
+                });
             }
-
-            self.delete_asset_impl(asset.clone(), asset_config, None)?;
         }
 
-        for asset_config in asset_configs {
-            if let NormalizedAssetConfig::Redirect {
-                from,
-                to,
-                kind,
-                headers,
-            } = asset_config
-            {
-                self.delete_redirect(from, to, kind, headers)?;
-            }
-        }
+        self.encoding_priority = encoding_priority
+            .iter()
+            .chain(
+                DEFAULT_ENCODING_PRIORITY
+                    .iter()
+                    .filter(|encoding| !encoding_priority.contains(encoding)),
+            )
+            .copied()
+            .collect();
 
-        Ok(())
+        Ok(self)
     }
 
-    /// Deletes multiple assets from the router by path, including any certification for those assets.
+    /// Rewrites request paths that start with one prefix onto paths that start with another
+    /// prefix, before they are matched against assets and fallbacks. Each rewrite is given as
+    /// a `(from, to)` pair of prefixes, such as `("/v1/", "/")`, and both prefixes must start
+    /// and end with a `/`.
     ///
-    /// Depending on the configuration provided to the [certify_assets](AssetRouter::certify_assets) function,
-    /// multiple responses may be generated for the same asset. These assets may exist on different paths,
-    /// for example if the `alias` configuration is used. If `alias` paths are not passed to this function,
-    /// they will not be deleted.
+    /// With the rewrite above, a request for `/v1/app.js` is served the `/app.js` asset, and a
+    /// request for `/v1/unknown` is served the fallback for the scope that `/unknown` falls
+    /// into. Rewrites happen server side and are invisible to clients, no redirect is served and
+    /// the URL in the client stays the same. If multiple rewrites match a request path, only the
+    /// first one is applied.
     ///
-    /// If multiple encodings exist for a path, all encodings will be deleted.
+    /// Since a response must be certified for the path that the client requested, every
+    /// certification for a path that a rewrite maps onto is also added to the tree for the path
+    /// that it is rewritten from. This covers assets, aliases, fallbacks, redirects,
+    /// [method responses](AssetRouter::certify_method_response),
+    /// [variants](AssetRouter::certify_variants),
+    /// [media type fallbacks](AssetRouter::certify_media_type_fallback) and CORS preflights. The
+    /// responses themselves, including their bodies, are only stored once. Request paths that
+    /// match a rewrite are always rewritten, so assets certified for such paths are never served.
     ///
-    /// Fallbacks are also not deleted, to delete them, use the
-    /// [delete_fallback_assets_by_path](AssetRouter::delete_fallback_assets_by_path) function.
+    /// This option must be set before any assets are certified, and must not be changed before
+    /// certified assets are deleted with [delete_assets](AssetRouter::delete_assets).
     ///
-    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
-    /// to the new [root hash](AssetRouter::root_hash) of the tree.
-    pub fn delete_assets_by_path<'path>(
-        &mut self,
-        asset_paths: impl IntoIterator<Item = &'path str>,
-    ) {
-        for asset_path in asset_paths {
-            self.responses
-                .remove(&RequestKey::new(asset_path, None, None));
-            self.tree
-                .borrow_mut()
-                .delete_by_path(&HttpCertificationPath::exact(asset_path));
+    /// Returns [InvalidPathRewrite](AssetCertificationError::InvalidPathRewrite) if a prefix
+    /// does not start and end with a `/`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpRequest;
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default()
+    ///     .with_path_rewrites(vec![("/v1/".to_string(), "/".to_string())])
+    ///     .unwrap();
+    /// asset_router
+    ///     .certify_assets(
+    ///         vec![Asset::new("app.js", b"console.log('Hello World!');".as_slice())],
+    ///         vec![AssetConfig::File {
+    ///             path: "app.js".to_string(),
+    ///             content_type: Some("text/javascript".to_string()),
+    ///             headers: vec![],
+    ///             fallback_for: vec![],
+    ///             aliased_by: vec![],
+    ///             encodings: vec![],
+    ///             options: Default::default(),
+    ///         }],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/v1/app.js").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"console.log('Hello World!');");
+    /// ```
+    pub fn with_path_rewrites(
+        mut self,
+        path_rewrites: Vec<(String, String)>,
+    ) -> AssetCertificationResult<Self> {
+        for prefix in path_rewrites.iter().flat_map(|(from, to)| [from, to]) {
+            if !prefix.starts_with('/') || !prefix.ends_with('/') {
+                return Err(AssetCertificationError::InvalidPathRewrite {
+                    prefix: prefix.clone(),
+                });
+            }
         }
+
+        self.path_rewrites = path_rewrites;
+
+        Ok(self)
     }
 
-    /// Deletes multiple fallback assets from the router by path, including certification for those assets.
+    /// Collapses consecutive slashes in the path of requests before they are matched
+    /// against assets, so that requests such as `/assets//app.js` or `//index.html`,
+    /// which are usually the result of sloppy link construction, are served the
+    /// `/assets/app.js` or `/index.html` assets.
     ///
-    /// This function will only delete fallbacks, to delete standard assets, use the
-    /// [delete_assets_by_path](AssetRouter::delete_assets_by_path) function.
+    /// Slashes are collapsed in the percent-decoded path, so `/assets/%2Fapp.js` is
+    /// collapsed as well. The response is certified for the collapsed path, which
+    /// verifies for the original request since empty path segments are ignored when
+    /// the expression path of a request is computed, see
+    /// [exact_expr_path](ic_http_certification::utils::exact_expr_path).
     ///
-    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
-    /// to the new [root hash](AssetRouter::root_hash) of the tree.
-    pub fn delete_fallback_assets_by_path<'path>(
-        &mut self,
-        asset_paths: impl IntoIterator<Item = &'path str>,
-    ) {
-        for asset_path in asset_paths {
-            self.fallback_responses
-                .remove(&RequestKey::new(asset_path, None, None));
-            self.tree
-                .borrow_mut()
-                .delete_by_path(&HttpCertificationPath::wildcard(asset_path));
-        }
+    /// Fallback scopes are matched against the collapsed path too. Without this option,
+    /// the scopes derived from a path with consecutive slashes contain those slashes, so
+    /// `/assets//missing.js` is matched against the `/assets//` scope before the
+    /// `/assets/` scope, but an exact match for `/assets/app.js` is never found for
+    /// `/assets//app.js`, which is served a fallback instead.
+    ///
+    /// The asset is served directly instead of a `301 Moved Permanently` redirect to
+    /// the collapsed path, since such a redirect would be certified for the collapsed
+    /// path as well and could then be served for it, resulting in a redirect loop.
+    ///
+    /// By default, slashes are not collapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpRequest;
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default().with_collapsed_slashes(true);
+    ///
+    /// let asset = Asset::new("assets/app.js", b"console.log('Hello World!');".as_slice());
+    /// let asset_config = AssetConfig::file("assets/app.js")
+    ///     .with_content_type("text/javascript")
+    ///     .build();
+    ///
+    /// asset_router.certify_assets(vec![asset], vec![asset_config]).unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/assets//app.js").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"console.log('Hello World!');");
+    /// ```
+    pub fn with_collapsed_slashes(mut self, collapse_slashes: bool) -> Self {
+        self.collapse_slashes = collapse_slashes;
+
+        self
     }
 
-    /// Deletes all assets from the router, including any certification for those assets.
+    /// Validates that the hash in the filename of every fingerprinted asset matches the
+    /// hash of its content when it is certified, using the provided
+    /// [AssetFilenameHashConfig]. This catches build pipelines that fingerprint a file with a
+    /// stale hash, so that clients do not indefinitely cache an outdated file under its
+    /// immutable URL.
     ///
-    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
-    /// to the new [root hash](AssetRouter::root_hash) of the tree.
-    pub fn delete_all_assets(&mut self) {
-        self.responses.clear();
-        self.fallback_responses.clear();
-        self.tree.borrow_mut().clear();
+    /// [certify_assets](AssetRouter::certify_assets) and
+    /// [certify_chunk](AssetRouter::certify_chunk) return
+    /// [MissingFilenameHash](AssetCertificationError::MissingFilenameHash) if the filename of
+    /// an asset that matches the pattern of the config does not contain a hash, and
+    /// [FilenameHashMismatch](AssetCertificationError::FilenameHashMismatch) if the hash does
+    /// not match the content of the asset. No assets are certified if any asset fails
+    /// validation. See [AssetFilenameHashConfig] for how the hash is found in the filename and
+    /// compared to the content.
+    ///
+    /// By default, filename hashes are not validated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{
+    ///     Asset, AssetCertificationError, AssetFilenameHashConfig, AssetRouter,
+    /// };
+    ///
+    /// let mut asset_router = AssetRouter::default()
+    ///     .with_filename_hash_validation(AssetFilenameHashConfig::new("assets/*.js"));
+    ///
+    /// let result = asset_router.certify_assets(
+    ///     vec![Asset::new("assets/app.00000000.js", b"console.log('Hello World!');".as_slice())],
+    ///     vec![],
+    /// );
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(AssetCertificationError::FilenameHashMismatch { filename_hash, .. })
+    ///         if filename_hash == "00000000"
+    /// ));
+    /// ```
+    pub fn with_filename_hash_validation(mut self, filename_hash: AssetFilenameHashConfig) -> Self {
+        self.filename_hash_validation = Some(filename_hash);
+
+        self
     }
 
-    /// Returns the root hash of the underlying
-    /// [HttpCertificationTree](ic_http_certification::HttpCertificationTree).
-    pub fn root_hash(&self) -> Hash {
-        self.tree.borrow().root_hash()
+    /// Enables Cross-Origin Resource Sharing (CORS) for all assets, using the provided
+    /// [AssetCorsConfig].
+    ///
+    /// Only a single origin, or `*` for any origin, can be allowed. Responses are certified
+    /// ahead of time, so they can't echo the `Origin` header of a request, and a canister that
+    /// serves several origins must allow `*`.
+    ///
+    /// The `Access-Control-Allow-Origin` header is certified and served on every asset,
+    /// fallback and redirect response, unless the asset's config already includes the header
+    /// in its [headers](crate::AssetConfig::File::headers). For the path of every asset and
+    /// alias, a `204 No Content` response is also certified and served for `OPTIONS`
+    /// requests, to answer CORS preflight requests, with the `Access-Control-Allow-Origin`,
+    /// `Access-Control-Allow-Methods`, `Access-Control-Allow-Headers` and
+    /// `Access-Control-Max-Age` headers as configured. The preflight responses are certified
+    /// with the request method, so they only pass verification for `OPTIONS` requests.
+    /// Responses certified with
+    /// [certify_method_response](AssetRouter::certify_method_response) for `OPTIONS` take
+    /// precedence over the generated preflight responses.
+    ///
+    /// The CORS headers are part of the certification of every response, so this option
+    /// must be set before any assets are certified, in the same way as
+    /// [with_build_id](AssetRouter::with_build_id).
+    ///
+    /// By default, no CORS headers or preflight responses are served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetCorsConfig, AssetRouter};
+    /// use ic_http_certification::{HttpRequest, Method, StatusCode};
+    ///
+    /// let mut asset_router = AssetRouter::default().with_cors(
+    ///     AssetCorsConfig::new("https://app.example.com")
+    ///         .with_allowed_headers(vec!["Content-Type".to_string()])
+    ///         .with_max_age(600),
+    /// );
+    /// asset_router
+    ///     .certify_assets(vec![Asset::new("data.json", b"{}".as_slice())], vec![])
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::builder()
+    ///     .with_method(Method::OPTIONS)
+    ///     .with_url("/data.json")
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+    /// assert!(response.headers().contains(&(
+    ///     "access-control-allow-methods".to_string(),
+    ///     "GET, HEAD".to_string()
+    /// )));
+    /// ```
+    pub fn with_cors(mut self, cors: AssetCorsConfig) -> Self {
+        self.cors = Some(cors);
+
+        self
     }
 
-    fn get_asset_for_request<'a>(
+    /// Asks browsers to send client hints, such as `Sec-CH-Prefers-Color-Scheme` or
+    /// `Sec-CH-DPR`, using the provided [AssetClientHintsConfig], so that responses can be
+    /// adapted to them.
+    ///
+    /// The `Accept-CH` and `Critical-CH` headers are certified and served on every asset,
+    /// fallback, redirect and [variant](AssetRouter::certify_variants) response, as
+    /// configured, unless the asset's config already includes the header in its
+    /// [headers](crate::AssetConfig::File::headers). Browsers only send client hints after
+    /// they have received an `Accept-CH` header from the origin, or, for critical hints, retry
+    /// the request to include them.
+    ///
+    /// Client hints are only listed in the `Vary` header of the responses that are adapted to
+    /// them, so that caches don't keep separate copies of responses that are the same for
+    /// every client.
+    ///
+    /// The router does not adapt responses to the client hints by itself. Responses adapted
+    /// to a client hint can be certified as [variants](AssetRouter::certify_variants)
+    /// selected by the hint, in which case the hint is certified as part of the request, and
+    /// is added to the variants' `Vary` header. Browsers that support a hint send it on every
+    /// request, so the default response should also be certified for the values of the hint
+    /// that have no variant, with
+    /// [certify_variants_with_selector](AssetRouter::certify_variants_with_selector).
+    ///
+    /// The client hints headers are part of the certification of every response, so this
+    /// option must be set before any assets are certified, in the same way as
+    /// [with_build_id](AssetRouter::with_build_id).
+    ///
+    /// By default, no client hints headers are served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{AssetClientHintsConfig, AssetRouter};
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    ///
+    /// let mut asset_router = AssetRouter::default().with_client_hints(
+    ///     AssetClientHintsConfig::new(vec!["Sec-CH-Prefers-Color-Scheme".to_string()])
+    ///         .with_critical_hints(vec!["Sec-CH-Prefers-Color-Scheme".to_string()]),
+    /// );
+    /// asset_router
+    ///     .certify_variants_with_selector(
+    ///         "/logo.svg",
+    ///         "Sec-CH-Prefers-Color-Scheme",
+    ///         vec![r#""light""#.to_string(), r#""dark""#.to_string()],
+    ///         |color_scheme| (color_scheme == r#""dark""#).then_some(r#""dark""#),
+    ///         HttpResponse::ok(b"<svg></svg>".as_slice(), vec![]).build(),
+    ///         vec![(
+    ///             r#""dark""#.to_string(),
+    ///             HttpResponse::ok(b"<svg class=\"dark\"></svg>".as_slice(), vec![]).build(),
+    ///         )],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/logo.svg").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"<svg></svg>");
+    /// assert!(response.headers().contains(&(
+    ///     "accept-ch".to_string(),
+    ///     "Sec-CH-Prefers-Color-Scheme".to_string()
+    /// )));
+    ///
+    /// let request = HttpRequest::get("/logo.svg")
+    ///     .with_headers(vec![(
+    ///         "Sec-CH-Prefers-Color-Scheme".to_string(),
+    ///         r#""light""#.to_string(),
+    ///     )])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"<svg></svg>");
+    ///
+    /// let request = HttpRequest::get("/logo.svg")
+    ///     .with_headers(vec![(
+    ///         "Sec-CH-Prefers-Color-Scheme".to_string(),
+    ///         r#""dark""#.to_string(),
+    ///     )])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"<svg class=\"dark\"></svg>");
+    /// ```
+    pub fn with_client_hints(mut self, client_hints: AssetClientHintsConfig) -> Self {
+        self.client_hints = Some(client_hints);
+
+        self
+    }
+
+    /// Allows the same alias, or the same fallback scope, to be listed by multiple
+    /// [File](AssetConfig::File) configs.
+    ///
+    /// By default, [certify_assets](AssetRouter::certify_assets),
+    /// [begin_certification](AssetRouter::begin_certification) and
+    /// [delete_assets](AssetRouter::delete_assets) return a
+    /// [DuplicateAlias](AssetCertificationError::DuplicateAlias) or
+    /// [ConflictingFallbackScope](AssetCertificationError::ConflictingFallbackScope) error
+    /// when an alias in [aliased_by](AssetConfig::File::aliased_by), or a scope in
+    /// [fallback_for](AssetConfig::File::fallback_for), is listed by more than one of the
+    /// provided configs, or was already certified for another file.
+    ///
+    /// When conflicting routes are allowed, the config that comes last in the list of configs
+    /// wins, and the alias or fallback scope is ignored for all configs before it. This makes
+    /// it possible to assemble configs from multiple sources, and override the routes of
+    /// earlier sources by appending the configs of later ones.
+    ///
+    /// Conflicts are also detected with the routes that were certified by an earlier call for
+    /// another file, which are only replaced when conflicting routes are allowed. Routes stop
+    /// conflicting once the asset that they were certified for is deleted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetRouter};
+    /// use ic_http_certification::HttpRequest;
+    ///
+    /// let config = |path: &str| AssetConfig::File {
+    ///     path: path.to_string(),
+    ///     content_type: Some("text/html".to_string()),
+    ///     headers: vec![],
+    ///     fallback_for: vec![],
+    ///     aliased_by: vec!["/".to_string()],
+    ///     encodings: vec![],
+    ///     options: Default::default(),
+    /// };
+    /// let assets = vec![
+    ///     Asset::new("index.html", b"<h1>Index</h1>".as_slice()),
+    ///     Asset::new("home.html", b"<h1>Home</h1>".as_slice()),
+    /// ];
+    ///
+    /// let mut asset_router = AssetRouter::default().with_conflicting_routes_allowed(true);
+    /// asset_router
+    ///     .certify_assets(assets, vec![config("index.html"), config("home.html")])
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"<h1>Home</h1>");
+    /// ```
+    pub fn with_conflicting_routes_allowed(mut self, allow_conflicting_routes: bool) -> Self {
+        self.allow_conflicting_routes = allow_conflicting_routes;
+
+        self
+    }
+
+    /// Binds every certification to the given host, by certifying the `Host` request header
+    /// as part of the request. Used by the [AssetHostRouter](crate::AssetHostRouter), so that
+    /// a response certified for one host does not pass verification for another host.
+    pub(crate) fn with_certified_host(mut self, host: impl Into<String>) -> Self {
+        self.certified_host = Some(host.into());
+
+        self
+    }
+
+    /// Returns the beginning of the byte range requested by the request's `Range` header, as
+    /// parsed by [HttpRequest::range] against the length of the asset that would otherwise be
+    /// served for the request. Fallbacks are never served in chunks, so the header is ignored
+    /// if no asset matches the request exactly.
+    fn maybe_get_range_begin(
         &self,
-        req_path: &'a str,
-        preferred_encodings: Vec<&'a str>,
-        maybe_range_begin: Option<usize>,
-    ) -> AssetCertificationResult<&CertifiedAssetResponse<'content>> {
-        if let Some(response) =
-            self.get_encoded_asset(&preferred_encodings, req_path, maybe_range_begin)
-        {
-            return Ok(response);
-        }
+        request: &HttpRequest,
+        req_path: &str,
+        certified_queries: &[String],
+        preferred_encodings: &[&str],
+    ) -> AssetCertificationResult<Option<usize>> {
+        let queries = certified_queries
+            .iter()
+            .map(|query| Some(query.clone()))
+            .chain(std::iter::once(None));
+        let encodings = preferred_encodings
+            .iter()
+            .map(|encoding| Some(encoding.to_string()))
+            .chain(std::iter::once(None))
+            .collect::<Vec<_>>();
+        let asset_len = queries
+            .flat_map(|query| {
+                encodings.iter().map(move |encoding| {
+                    RequestKey::new(req_path, encoding.clone(), None).with_query(query.clone())
+                })
+            })
+            .find_map(|request_key| self.responses.get(&request_key))
+            .map(|cert_response| cert_response.response.body().len());
+        let Some(asset_len) = asset_len else {
+            return Ok(None);
+        };
 
-        if let Some(response) =
-            self.responses
-                .get(&RequestKey::new(req_path, None, maybe_range_begin))
-        {
-            if response.response.body().len() > ASSET_CHUNK_SIZE {
-                if let Some(first_chunk_response) =
-                    self.responses
-                        .get(&RequestKey::new(req_path, None, Some(0)))
-                {
-                    return Ok(first_chunk_response);
-                }
-            } else {
-                return Ok(response);
-            }
+        match request.range(asset_len as u64) {
+            None => Ok(None),
+            Some(Ok(ranges)) => match ranges.as_slice() {
+                [(range_begin, _)] => Ok(Some(*range_begin as usize)),
+                _ => Err(AssetCertificationError::RequestError(
+                    "Range headers with multiple ranges are not supported".to_string(),
+                )),
+            },
+            Some(Err(err)) => Err(AssetCertificationError::RequestError(err.to_string())),
         }
+    }
 
-        let mut url_scopes = req_path.split('/').collect::<Vec<_>>();
-        url_scopes.pop();
+    /// Returns the corresponding
+    /// [HttpResponse](ic_http_certification::HttpResponse) for the provided
+    /// [HttpRequest](ic_http_certification::HttpRequest) if it is found
+    /// in the router.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_certificate` - A byte slice representing the data certificate used for asset certification.
+    ///     This should be retrieved using `ic_cdk::api::data_certificate()`.
+    /// * `request` - A reference to an [HttpRequest](ic_http_certification::HttpRequest) object representing the incoming HTTP request.
+    ///
+    /// If an exact match is not found, then a fallback will
+    /// be searched for. See the
+    /// [fallback_for](AssetConfig::File::fallback_for) configuration
+    /// option for more information on fallbacks.
+    ///
+    /// Encoded variants of an asset are served according to the request's
+    /// `Accept-Encoding` header. Acceptable encodings are tried in the order
+    /// of the quality values that the client assigns to them (e.g. `br;q=0.5`,
+    /// `1` when omitted), and encodings of equal quality are tried in the
+    /// server's order of preference (`br`, `zstd`, `gzip`, `deflate` by default,
+    /// see [with_encoding_priority](AssetRouter::with_encoding_priority)).
+    /// Encodings with a quality of zero (e.g. `br;q=0`) are never served.
+    /// A `*` wildcard with a non-zero quality makes every encoding that is not
+    /// explicitly listed in the header acceptable, so `*` alone selects the
+    /// highest priority certified encoding, and `br;q=0, *` selects the
+    /// highest priority certified encoding other than `br`. A `*;q=0` wildcard
+    /// does not add any encodings, leaving only the explicitly listed ones.
+    /// A missing header is treated like `*`, unless the router is configured
+    /// not to [accept any encoding](AssetRouter::with_any_encoding_when_accept_encoding_missing)
+    /// in that case. The identity variant of an asset is always certified and
+    /// is served when the header is empty, when it only lists `identity`, when
+    /// `identity` has a higher quality than the available encodings, or when
+    /// none of the acceptable encodings are available. The identity variant
+    /// is served even if the client marks it as unacceptable, rather than
+    /// responding with `406 Not Acceptable`.
+    ///
+    /// Requests with a path that contains a `..` segment, either literally or
+    /// percent-encoded, are rejected before any asset or fallback is matched,
+    /// see [get_validated_path](ic_http_certification::HttpRequest::get_validated_path).
+    /// In this case a
+    /// [PathTraversal](ic_http_certification::HttpCertificationError::PathTraversal)
+    /// error is returned, and the canister should respond with
+    /// `400 Bad Request`.
+    ///
+    /// Consecutive slashes in the request path are only collapsed if
+    /// [with_collapsed_slashes](AssetRouter::with_collapsed_slashes) is enabled.
+    ///
+    /// While the router is in [maintenance mode](AssetRouter::enter_maintenance), the certified
+    /// `503 Service Unavailable` response is served for every request instead.
+    ///
+    /// Returns [None] if no suitable
+    /// [HttpResponse](ic_http_certification::HttpResponse) is found for the
+    /// given [HttpRequest](ic_http_certification::HttpRequest).
+    pub fn serve_asset(
+        &self,
+        data_certificate: &[u8],
+        request: &HttpRequest,
+    ) -> AssetCertificationResult<HttpResponse<'content>> {
+        let request_url = self.get_request_path(request)?;
+        if let Some(maintenance) = &self.maintenance {
+            return self.with_certificate_header(
+                data_certificate,
+                &request_url,
+                &maintenance.tree,
+                maintenance.response.clone(),
+            );
+        }
 
-        while !url_scopes.is_empty() {
-            let mut scope = url_scopes.join("/");
-            scope.push('/');
+        let path_rewrite = self.get_path_rewrite(&request_url);
+        let lookup_url = path_rewrite
+            .as_ref()
+            .map_or(request_url.as_str(), |(rewritten_path, _)| rewritten_path);
+        let mut cert_response = match self
+            .get_method_response_for_request(lookup_url, request)
+            .or_else(|| self.get_forwarded_redirect_response_for_request(lookup_url, request))
+            .or_else(|| self.get_variant_response_for_request(lookup_url, request))
+        {
+            Some(cert_response) => cert_response.clone(),
+            None => {
+                let preferred_encodings = self.get_preferred_encodings(request);
+                let certified_queries = self.get_certified_queries(lookup_url, request)?;
+                let maybe_range_begin = self.maybe_get_range_begin(
+                    request,
+                    lookup_url,
+                    &certified_queries,
+                    &preferred_encodings,
+                )?;
+                let mut cert_response = self
+                    .get_asset_for_request(
+                        request,
+                        lookup_url,
+                        &certified_queries,
+                        preferred_encodings,
+                        maybe_range_begin,
+                    )
+                    .cloned()?;
+                if let Some(not_modified) = cert_response.not_modified.take() {
+                    if Self::is_not_modified(request, &not_modified.response) {
+                        cert_response = *not_modified;
+                    }
+                }
 
-            if let Some(response) = self.get_encoded_fallback_asset(&preferred_encodings, &scope) {
-                return Ok(response);
+                cert_response
             }
+        };
+        if let Some((_, path_rewrite)) = path_rewrite {
+            cert_response.tree_entry =
+                Self::rewritten_tree_entry(&cert_response.tree_entry, path_rewrite);
+        }
 
-            if let Some(response) = self
-                .fallback_responses
-                .get(&RequestKey::new(&scope, None, None))
-            {
-                return Ok(response);
-            }
+        self.with_certificate_header(
+            data_certificate,
+            &request_url,
+            &self.tree.borrow(),
+            cert_response,
+        )
+    }
 
-            scope.pop();
+    /// Serves the certified `304 Not Modified` response for the asset that
+    /// matches the given request, regardless of the request's `If-None-Match`
+    /// header.
+    ///
+    /// This is useful for canisters that evaluate other conditional request
+    /// headers themselves. [serve_asset](AssetRouter::serve_asset) already
+    /// serves this response when the request's `If-None-Match` header is
+    /// exactly the asset's ETag.
+    ///
+    /// The response carries the asset's `ETag` header along with the other
+    /// headers from the asset's response that are listed in
+    /// [NOT_MODIFIED_HEADER_NAMES], and is certified for the asset's path and
+    /// for requests with an `If-None-Match` header that is exactly the asset's
+    /// ETag. It does not pass verification for requests without that header,
+    /// such as unconditional requests.
+    ///
+    /// Returns `None` if [ETags](AssetRouter::with_etags) are not enabled, if
+    /// the router is in maintenance mode, or if the matched asset is split into
+    /// multiple chunks. Returns an error if no asset matches the request.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, StatusCode};
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetETagKind, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default().with_etags(AssetETagKind::Strong);
+    ///
+    /// let assets = vec![Asset::new("index.html", b"<h1>Home</h1>".as_slice())];
+    /// let asset_configs = vec![AssetConfig::File {
+    ///     path: "index.html".to_string(),
+    ///     content_type: Some("text/html".to_string()),
+    ///     headers: vec![("Cache-Control".to_string(), "no-cache".to_string())],
+    ///     fallback_for: vec![],
+    ///     aliased_by: vec![],
+    ///     encodings: vec![],
+    ///     options: Default::default(),
+    /// }];
+    /// asset_router.certify_assets(assets, asset_configs).unwrap();
+    ///
+    /// let request = HttpRequest::get("/index.html").build();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let response = asset_router
+    ///     .serve_not_modified(&data_certificate, &request)
+    ///     .unwrap()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+    /// assert!(response.body().is_empty());
+    /// assert!(response.headers().iter().any(|(name, _)| name == "etag"));
+    /// assert!(response.headers().iter().any(|(name, _)| name == "Cache-Control"));
+    /// ```
+    pub fn serve_not_modified(
+        &self,
+        data_certificate: &[u8],
+        request: &HttpRequest,
+    ) -> AssetCertificationResult<Option<HttpResponse<'content>>> {
+        let request_url = self.get_request_path(request)?;
+        if self.etag_kind.is_none() || self.maintenance.is_some() {
+            return Ok(None);
+        }
 
-            if let Some(response) = self.get_encoded_fallback_asset(&preferred_encodings, &scope) {
-                return Ok(response);
-            }
+        let path_rewrite = self.get_path_rewrite(&request_url);
+        let lookup_url = path_rewrite
+            .as_ref()
+            .map_or(request_url.as_str(), |(rewritten_path, _)| rewritten_path);
+        let preferred_encodings = self.get_preferred_encodings(request);
+        let certified_queries = self.get_certified_queries(lookup_url, request)?;
+        let cert_response = self.get_asset_for_request(
+            request,
+            lookup_url,
+            &certified_queries,
+            preferred_encodings,
+            None,
+        )?;
+        let Some(not_modified) = &cert_response.not_modified else {
+            return Ok(None);
+        };
+        let mut not_modified = *not_modified.clone();
+        if let Some((_, path_rewrite)) = path_rewrite {
+            not_modified.tree_entry =
+                Self::rewritten_tree_entry(&not_modified.tree_entry, path_rewrite);
+        }
 
-            if let Some(response) = self
-                .fallback_responses
-                .get(&RequestKey::new(&scope, None, None))
-            {
-                return Ok(response);
-            }
+        self.with_certificate_header(
+            data_certificate,
+            &request_url,
+            &self.tree.borrow(),
+            not_modified,
+        )
+        .map(Some)
+    }
 
-            url_scopes.pop();
+    fn get_request_path(&self, request: &HttpRequest) -> AssetCertificationResult<String> {
+        let request_path = request.get_validated_path()?;
+        if !self.collapse_slashes {
+            return Ok(request_path);
         }
-        Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-            request_url: req_path.to_string(),
-        })
-    }
 
-    fn certify_asset_impl<'path>(
-        &mut self,
-        asset: Asset<'content, 'path>,
-        asset_config: Option<&NormalizedAssetConfig>,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult {
-        match asset_config {
-            Some(NormalizedAssetConfig::Pattern {
-                content_type,
-                headers,
-                ..
-            }) => {
-                self.insert_static_asset(asset, content_type.clone(), headers.clone(), encoding)?;
+        let mut collapsed_path = String::with_capacity(request_path.len());
+        for c in request_path.chars() {
+            if c != '/' || !collapsed_path.ends_with('/') {
+                collapsed_path.push(c);
             }
-            Some(NormalizedAssetConfig::File {
-                content_type,
-                headers,
-                fallback_for,
-                aliased_by,
-                ..
-            }) => {
-                self.insert_static_asset(
-                    asset.clone(),
-                    content_type.clone(),
-                    headers.clone(),
-                    encoding,
-                )?;
-
-                for fallback_for in fallback_for.iter() {
-                    self.insert_fallback_asset(
-                        asset.clone(),
-                        content_type.clone(),
-                        headers.clone(),
-                        fallback_for.clone(),
-                        encoding,
-                    )?;
-                }
+        }
 
-                for aliased_by in aliased_by.iter() {
-                    let mut aliased_asset = asset.clone();
-                    aliased_asset.url = Cow::Owned(aliased_by.clone());
+        Ok(collapsed_path)
+    }
 
-                    self.insert_static_asset(
-                        aliased_asset,
-                        content_type.clone(),
-                        headers.clone(),
-                        encoding,
-                    )?;
+    fn with_certificate_header(
+        &self,
+        data_certificate: &[u8],
+        request_url: &str,
+        tree: &HttpCertificationTree,
+        mut cert_response: CertifiedAssetResponse<'content>,
+    ) -> AssetCertificationResult<HttpResponse<'content>> {
+        let witness = tree.witness(&cert_response.tree_entry, request_url)?;
+        let expr_path = cert_response.tree_entry.path.to_expr_path();
+        add_v2_certificate_header(
+            data_certificate,
+            &mut cert_response.response,
+            &witness,
+            &expr_path,
+        );
+        if let Some(certificate_header_name) = &self.certificate_header_name {
+            for (name, _) in cert_response.response.headers_mut() {
+                if name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME) {
+                    name.clone_from(certificate_header_name);
                 }
             }
-            _ => {
-                self.insert_static_asset(asset, None, vec![], encoding)?;
-            }
         }
+        Ok(cert_response.response)
+    }
 
-        Ok(())
+    /// Returns all standard assets stored in the router.
+    ///
+    /// See the [get_fallback_assets()](AssetRouter::get_fallback_assets)
+    /// function for fallback assets.
+    ///
+    /// See the [AssetMap] struct for more information on the returned type.
+    pub fn get_assets(&self) -> &impl AssetMap<'content> {
+        &self.responses
     }
 
-    fn delete_asset_impl<'path>(
-        &mut self,
-        asset: Asset<'content, 'path>,
-        asset_config: Option<&NormalizedAssetConfig>,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult {
-        match asset_config {
-            Some(NormalizedAssetConfig::Pattern {
-                content_type,
-                headers,
-                ..
-            }) => {
-                self.delete_static_asset(asset, content_type.clone(), headers.clone(), encoding)?;
-            }
-            Some(NormalizedAssetConfig::File {
-                content_type,
-                headers,
-                fallback_for,
-                aliased_by,
-                ..
-            }) => {
-                self.delete_static_asset(
-                    asset.clone(),
-                    content_type.clone(),
-                    headers.clone(),
-                    encoding,
-                )?;
-
-                for fallback_for in fallback_for.iter() {
-                    self.delete_fallback_asset(
-                        asset.clone(),
-                        content_type.clone(),
-                        headers.clone(),
-                        fallback_for.clone(),
-                        encoding,
-                    )?;
-                }
-
-                for aliased_by in aliased_by.iter() {
-                    let mut aliased_asset = asset.clone();
-                    aliased_asset.url = Cow::Owned(aliased_by.clone());
+    /// Returns all fallback assets stored in the router.
+    ///
+    /// See the [get_assets()](AssetRouter::get_assets)
+    /// function for standard assets.
+    ///
+    /// See the [AssetMap] struct for more information on the returned type.
+    pub fn get_fallback_assets(&self) -> &impl AssetMap<'content> {
+        &self.fallback_responses
+    }
 
-                    self.delete_static_asset(
-                        aliased_asset,
-                        content_type.clone(),
-                        headers.clone(),
-                        encoding,
-                    )?;
-                }
-            }
-            _ => {
-                self.delete_static_asset(asset, None, vec![], encoding)?;
-            }
+    /// Returns the certified body stored in the router for the standard asset with the given
+    /// path and encoding, such as `/index.html` and [Gzip](AssetEncoding::Gzip), or [None] if
+    /// no such asset is certified. The path can also be one of the asset's
+    /// [aliases](AssetConfig::File::aliased_by).
+    ///
+    /// This returns the complete body as it is stored and certified, even for assets that are
+    /// served in [chunks](crate::ASSET_CHUNK_SIZE), rather than a response negotiated for a
+    /// request, so it can be compared against a source of truth, for example to audit or export
+    /// the certified content. Use [Identity](AssetEncoding::Identity) for the unencoded body.
+    /// Assets configured with [skip_certification](crate::AssetFileOptions::skip_certification) have
+    /// no certified body, so [None] is returned for them. Fallbacks are not included, see
+    /// [get_fallback_assets](AssetRouter::get_fallback_assets).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetEncoding, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_assets(vec![Asset::new("index.html", b"<h1>Hello</h1>".as_slice())], vec![])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     asset_router.get_certified_body("/index.html", AssetEncoding::Identity),
+    ///     Some(b"<h1>Hello</h1>".as_slice())
+    /// );
+    /// assert_eq!(
+    ///     asset_router.get_certified_body("/index.html", AssetEncoding::Gzip),
+    ///     None
+    /// );
+    /// ```
+    pub fn get_certified_body(&self, path: &str, encoding: AssetEncoding) -> Option<&[u8]> {
+        let encoding = match encoding {
+            AssetEncoding::Identity => None,
+            encoding => Some(encoding.to_string()),
+        };
+        let cert_response = self.responses.get(&RequestKey::new(path, encoding, None))?;
+        if *cert_response.tree_entry.certification == HttpCertification::skip() {
+            return None;
         }
 
-        Ok(())
+        Some(cert_response.response.body())
     }
 
-    fn insert_static_asset<'path>(
+    /// Certifies multiple assets and inserts them into the router, to be served
+    /// later by the [serve_asset](AssetRouter::serve_asset) method.
+    ///
+    /// The asset certification is configured using the provided [AssetConfig]
+    /// enum.
+    ///
+    /// If no configuration matches an individual asset, the asset will be
+    /// served and certified as-is, without headers.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn certify_assets<'path>(
         &mut self,
-        asset: Asset<'content, 'path>,
-        content_type: Option<String>,
-        additional_headers: Vec<(String, String)>,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult<()> {
-        let asset_url = asset.url.to_string();
-        let total_length = asset.content.len();
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+    ) -> AssetCertificationResult {
+        let asset_configs =
+            Self::normalize_asset_configs(asset_configs, self.allow_conflicting_routes)?;
+        self.check_certified_routes(&asset_configs)?;
 
-        if total_length > ASSET_CHUNK_SIZE {
-            let mut range_begin = 0;
-            while range_begin < asset.content.len() {
-                let response = Self::prepare_static_asset(
-                    asset.clone(),
-                    content_type.clone(),
-                    additional_headers.clone(),
-                    encoding,
-                    Some(range_begin),
-                )?;
-                self.tree.borrow_mut().insert(&response.tree_entry);
-                self.responses.insert(
-                    RequestKey::new(&asset_url, encoding_str(encoding), Some(range_begin)),
-                    response,
-                );
-                range_begin += ASSET_CHUNK_SIZE;
+        self.certify_assets_with_configs(assets, &asset_configs)?;
+        self.insert_redirects(asset_configs)
+    }
+
+    /// Checks that the provided assets and [AssetConfig]s are consistent with each other,
+    /// as a development aid when wiring up a complex asset configuration.
+    ///
+    /// [certify_assets](AssetRouter::certify_assets) is lenient, since it is commonly called
+    /// with the full list of configs and only some of the assets, so configs that do not match
+    /// any asset and encoded assets that are missing are silently ignored, and only the last of
+    /// multiple assets with the same path is certified. This function
+    /// instead expects the complete set of assets and returns an error for the first problem
+    /// that it finds:
+    ///
+    /// - [InvalidGlob](AssetCertificationError::InvalidGlob) if the pattern of a
+    ///   [Pattern](AssetConfig::Pattern) config is not a valid glob.
+    /// - [DuplicatePath](AssetCertificationError::DuplicatePath) if multiple assets, or
+    ///   multiple [File](AssetConfig::File) configs, have the same path.
+    /// - [ConfigAssetMismatch](AssetCertificationError::ConfigAssetMismatch) if a
+    ///   [File](AssetConfig::File) config does not match any asset.
+    /// - [MissingEncodingAsset](AssetCertificationError::MissingEncodingAsset) if a
+    ///   [File](AssetConfig::File) config lists an encoding, but the encoded asset is missing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{
+    ///     Asset, AssetCertificationError, AssetConfig, AssetEncoding, AssetRouter,
+    /// };
+    ///
+    /// let assets = vec![Asset::new("index.html", b"<html></html>".as_slice())];
+    /// let asset_configs = vec![AssetConfig::File {
+    ///     path: "index.html".to_string(),
+    ///     content_type: Some("text/html".to_string()),
+    ///     headers: vec![],
+    ///     fallback_for: vec![],
+    ///     aliased_by: vec![],
+    ///     encodings: vec![AssetEncoding::Gzip.default_config()],
+    ///     options: Default::default(),
+    /// }];
+    ///
+    /// let result = AssetRouter::validate_assets(&assets, &asset_configs);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(AssetCertificationError::MissingEncodingAsset { encoded_path, .. })
+    ///         if encoded_path == "index.html.gz"
+    /// ));
+    /// ```
+    pub fn validate_assets(
+        assets: &[Asset<'content, '_>],
+        asset_configs: &[AssetConfig],
+    ) -> AssetCertificationResult {
+        let asset_configs = Self::normalize_asset_configs(asset_configs.iter().cloned(), false)?;
+
+        let mut asset_paths = HashSet::new();
+        for asset in assets {
+            if !asset_paths.insert(asset.path.as_ref()) {
+                return Err(AssetCertificationError::DuplicatePath {
+                    path: asset.path.to_string(),
+                });
             }
         }
 
-        let response =
-            Self::prepare_static_asset(asset, content_type, additional_headers, encoding, None)?;
-
-        self.tree.borrow_mut().insert(&response.tree_entry);
-        self.responses.insert(
-            RequestKey::new(&asset_url, encoding_str(encoding), None),
-            response,
-        );
-        Ok(())
-    }
+        let mut config_paths = HashSet::new();
+        for asset_config in &asset_configs {
+            let NormalizedAssetConfig::File {
+                path, encodings, ..
+            } = asset_config
+            else {
+                continue;
+            };
 
-    fn delete_static_asset<'path>(
-        &mut self,
-        asset: Asset<'content, 'path>,
-        content_type: Option<String>,
-        additional_headers: Vec<(String, String)>,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult<()> {
-        let asset_url = asset.url.to_string();
-        let response =
-            Self::prepare_static_asset(asset, content_type, additional_headers, encoding, None)?;
+            if !config_paths.insert(path.as_str()) {
+                return Err(AssetCertificationError::DuplicatePath { path: path.clone() });
+            }
 
-        self.tree.borrow_mut().delete(&response.tree_entry);
-        self.responses
-            .remove(&RequestKey::new(&asset_url, encoding_str(encoding), None));
+            if !asset_paths.contains(path.as_str()) {
+                return Err(AssetCertificationError::ConfigAssetMismatch {
+                    config_path: path.clone(),
+                });
+            }
 
-        if response.response.body().len() > ASSET_CHUNK_SIZE {
-            // Delete also chunks.
-            let mut range_begin: usize = 0;
-            while range_begin < response.response.body().len() {
-                self.responses.remove(&RequestKey::new(
-                    &asset_url,
-                    encoding_str(encoding),
-                    Some(range_begin),
-                ));
-                range_begin += ASSET_CHUNK_SIZE;
+            for (encoding, postfix) in encodings {
+                let encoded_path = format!("{path}{postfix}");
+                if !asset_paths.contains(encoded_path.as_str()) {
+                    return Err(AssetCertificationError::MissingEncodingAsset {
+                        path: path.clone(),
+                        encoding: *encoding,
+                        encoded_path,
+                    });
+                }
             }
         }
 
         Ok(())
     }
 
-    fn prepare_static_asset<'path>(
-        asset: Asset<'content, 'path>,
-        content_type: Option<String>,
-        additional_headers: Vec<(String, String)>,
-        encoding: Option<AssetEncoding>,
-        range_begin: Option<usize>,
-    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
-        let asset_url = asset.url.to_string();
+    /// Starts certifying assets in chunks, for sites with too many assets to certify in a
+    /// single message without exceeding the instruction limit.
+    ///
+    /// The provided [AssetConfig]s are kept by the router and applied to every chunk of assets
+    /// that is certified with [certify_chunk](AssetRouter::certify_chunk), across as many
+    /// messages as needed. The certification is completed with
+    /// [finalize](AssetRouter::finalize), which also certifies any
+    /// [redirects](AssetConfig::Redirect).
+    ///
+    /// Any assets that have already been certified are kept, so use [clear](AssetRouter::clear)
+    /// beforehand to rebuild the router from scratch.
+    ///
+    /// Returns [CertificationAlreadyInProgress](AssetCertificationError::CertificationAlreadyInProgress)
+    /// if a chunked certification has been started and not yet finalized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetConfig, AssetEncoding, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    ///
+    /// // e.g. in `init`
+    /// asset_router
+    ///     .begin_certification(vec![AssetConfig::Pattern {
+    ///         pattern: "**/*.js".to_string(),
+    ///         content_type: Some("text/javascript".to_string()),
+    ///         headers: vec![],
+    ///         encodings: vec![AssetEncoding::Gzip.default_config()],
+    ///         options: Default::default(),
+    ///     }])
+    ///     .unwrap();
+    ///
+    /// // e.g. in one update call per chunk
+    /// asset_router
+    ///     .certify_chunk(vec![
+    ///         Asset::new("app.js", b"console.log('app');".as_slice()),
+    ///         Asset::new("app.js.gz", b"<gzip encoded app.js>".as_slice()),
+    ///     ])
+    ///     .unwrap();
+    /// asset_router
+    ///     .certify_chunk(vec![Asset::new("vendor.js", b"console.log('vendor');".as_slice())])
+    ///     .unwrap();
+    ///
+    /// // in the last update call
+    /// let root_hash = asset_router.finalize().unwrap();
+    /// // ic_cdk::api::set_certified_data(&root_hash);
+    /// # assert_eq!(root_hash, asset_router.root_hash());
+    /// ```
+    pub fn begin_certification(
+        &mut self,
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+    ) -> AssetCertificationResult {
+        if self.pending_certification.is_some() {
+            return Err(AssetCertificationError::CertificationAlreadyInProgress);
+        }
 
-        let (response, certification) = Self::prepare_asset_response_and_certification(
-            asset,
-            additional_headers,
-            content_type,
-            encoding,
-            range_begin,
-            None,
-        )?;
+        let asset_configs =
+            Self::normalize_asset_configs(asset_configs, self.allow_conflicting_routes)?;
+        self.check_certified_routes(&asset_configs)?;
 
-        let tree_entry =
-            HttpCertificationTreeEntry::new(HttpCertificationPath::exact(asset_url), certification);
+        self.pending_certification = Some(PendingCertification {
+            asset_configs,
+            router: Box::new(self.staging_router()),
+        });
 
-        Ok(CertifiedAssetResponse {
-            response,
-            tree_entry,
-        })
+        Ok(())
     }
 
-    fn insert_fallback_asset<'path>(
+    /// Certifies a chunk of assets using the [AssetConfig]s provided to
+    /// [begin_certification](AssetRouter::begin_certification).
+    ///
+    /// Each chunk is certified in the same way as [certify_assets](AssetRouter::certify_assets),
+    /// so the encoded variants of an asset, such as `app.js.gz` for `app.js`, must be part of the
+    /// same chunk as the asset itself.
+    ///
+    /// The chunks are certified into a separate tree, so neither the responses served by the
+    /// router nor its [root hash](AssetRouter::root_hash) change until the certification has
+    /// been [finalized](AssetRouter::finalize). Until then, the router keeps serving the
+    /// previously certified assets, which keep passing verification against the canister's
+    /// certified data.
+    ///
+    /// Returns [NoCertificationInProgress](AssetCertificationError::NoCertificationInProgress)
+    /// if no chunked certification has been started.
+    pub fn certify_chunk<'path>(
         &mut self,
-        asset: Asset<'content, 'path>,
-        content_type: Option<String>,
-        additional_headers: Vec<(String, String)>,
-        fallback_for: AssetFallbackConfig,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult<()> {
-        let response = Self::prepare_fallback_asset(
-            asset,
-            additional_headers,
-            content_type,
-            fallback_for.clone(),
-            encoding,
-        )?;
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+    ) -> AssetCertificationResult {
+        let pending = self
+            .pending_certification
+            .as_mut()
+            .ok_or(AssetCertificationError::NoCertificationInProgress)?;
+
+        pending
+            .router
+            .certify_assets_with_configs(assets, &pending.asset_configs)
+    }
 
-        self.tree.borrow_mut().insert(&response.tree_entry);
-        self.fallback_responses.insert(
-            RequestKey::new(&fallback_for.scope, encoding_str(encoding), None),
-            response,
-        );
-        Ok(())
+    /// Completes a chunked certification that was started with
+    /// [begin_certification](AssetRouter::begin_certification), certifying any
+    /// [redirects](AssetConfig::Redirect) and returning the new [root hash](AssetRouter::root_hash)
+    /// of the tree.
+    ///
+    /// The assets certified by every [chunk](AssetRouter::certify_chunk) are added to the router
+    /// and its tree at once, so the router starts serving them from this point on.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the returned root hash.
+    ///
+    /// Returns [NoCertificationInProgress](AssetCertificationError::NoCertificationInProgress)
+    /// if no chunked certification has been started.
+    pub fn finalize(&mut self) -> AssetCertificationResult<Hash> {
+        let PendingCertification {
+            asset_configs,
+            router,
+        } = self
+            .pending_certification
+            .take()
+            .ok_or(AssetCertificationError::NoCertificationInProgress)?;
+
+        let mut router = *router;
+        router.insert_redirects(asset_configs)?;
+        self.merge_certification(router)?;
+
+        Ok(self.root_hash())
     }
 
-    fn delete_fallback_asset<'path>(
+    /// Creates an empty router with the same options as this one, and its own tree, that a
+    /// chunked certification is certified into.
+    fn staging_router(&self) -> AssetRouter<'content> {
+        AssetRouter {
+            fallback_max_depth: self.fallback_max_depth,
+            etag_kind: self.etag_kind,
+            accept_ranges: self.accept_ranges,
+            certificate_header_name: self.certificate_header_name.clone(),
+            build_id: self.build_id.clone(),
+            cors: self.cors.clone(),
+            client_hints: self.client_hints.clone(),
+            allow_conflicting_routes: self.allow_conflicting_routes,
+            trusted_forwarded_hosts: self.trusted_forwarded_hosts.clone(),
+            encoding_priority: self.encoding_priority.clone(),
+            any_encoding_when_accept_encoding_missing: self
+                .any_encoding_when_accept_encoding_missing,
+            path_rewrites: self.path_rewrites.clone(),
+            max_cache_age_secs: self.max_cache_age_secs,
+            collapse_slashes: self.collapse_slashes,
+            filename_hash_validation: self.filename_hash_validation.clone(),
+            certified_host: self.certified_host.clone(),
+            ..AssetRouter::new()
+        }
+    }
+
+    /// Moves the responses certified by a [staging router](AssetRouter::staging_router) into
+    /// this router, and their entries into this router's tree.
+    fn merge_certification(
         &mut self,
-        asset: Asset<'content, 'path>,
-        content_type: Option<String>,
-        additional_headers: Vec<(String, String)>,
-        fallback_for: AssetFallbackConfig,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult<()> {
-        let response = Self::prepare_fallback_asset(
-            asset,
-            additional_headers,
-            content_type,
-            fallback_for.clone(),
-            encoding,
-        )?;
+        mut router: AssetRouter<'content>,
+    ) -> AssetCertificationResult {
+        // the `405 Method Not Allowed` responses are recertified below, with the allowed methods
+        // of both routers
+        router.method_not_allowed_responses.clear();
+
+        // responses that are certified again replace the previously certified ones, which must
+        // stop passing verification
+        for request_key in router
+            .responses
+            .keys()
+            .filter(|request_key| request_key.range_begin.is_none())
+        {
+            self.delete_replaced_response(request_key);
+        }
+        for request_key in router.fallback_responses.keys() {
+            if let Some(cert_response) = self.fallback_responses.remove(request_key) {
+                self.delete_response_tree_entries(&cert_response);
+            }
+            self.vary_accept_fallbacks.remove(request_key);
+        }
+        for path in router.cors_preflight_responses.keys() {
+            if let Some(cert_response) = self.cors_preflight_responses.remove(path) {
+                self.delete_tree_entry(&cert_response.tree_entry);
+            }
+        }
+        for path in router.forwarded_redirect_responses.keys() {
+            self.delete_replaced_forwarded_redirects(path);
+        }
 
-        self.tree.borrow_mut().delete(&response.tree_entry);
-        self.fallback_responses.remove(&RequestKey::new(
-            &fallback_for.scope,
-            encoding_str(encoding),
-            None,
-        ));
-        Ok(())
-    }
+        router.tree = Rc::clone(&self.tree);
+        router.insert_tree_entries();
+
+        self.responses.extend(router.responses);
+        self.fallback_responses.extend(router.fallback_responses);
+        self.cors_preflight_responses
+            .extend(router.cors_preflight_responses);
+        self.forwarded_redirect_responses
+            .extend(router.forwarded_redirect_responses);
+        self.alias_owners.extend(router.alias_owners);
+        self.fallback_scope_owners
+            .extend(router.fallback_scope_owners);
+
+        let media_type_fallback_scopes = self
+            .media_type_fallback_responses
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        for scope in media_type_fallback_scopes {
+            self.set_fallbacks_vary_accept(&scope, true)?;
+        }
 
-    fn prepare_fallback_asset<'path>(
-        asset: Asset<'content, 'path>,
-        additional_headers: Vec<(String, String)>,
-        content_type: Option<String>,
-        fallback_for: AssetFallbackConfig,
-        encoding: Option<AssetEncoding>,
-    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
-        let (response, certification) = Self::prepare_asset_response_and_certification(
-            asset,
-            additional_headers,
-            content_type,
-            encoding,
-            None,
-            fallback_for.status_code,
-        )?;
+        for (path, query_parameter_names) in router.certified_query_parameter_names {
+            let path_query_parameter_names = self
+                .certified_query_parameter_names
+                .entry(path)
+                .or_default();
 
-        let tree_entry = HttpCertificationTreeEntry::new(
-            HttpCertificationPath::wildcard(fallback_for.scope.clone()),
-            certification,
-        );
+            for names in query_parameter_names {
+                if !path_query_parameter_names.contains(&names) {
+                    path_query_parameter_names.push(names);
+                }
+            }
+        }
 
-        Ok(CertifiedAssetResponse {
-            response,
-            tree_entry,
-        })
-    }
+        for (path, allowed_methods) in router.asset_allowed_methods {
+            self.asset_allowed_methods
+                .insert(path.clone(), allowed_methods);
+            self.update_method_not_allowed_response(&path)?;
+        }
 
-    fn insert_redirect(
-        &mut self,
-        from: String,
-        to: String,
-        kind: AssetRedirectKind,
-        additional_headers: Vec<(String, String)>,
-    ) -> AssetCertificationResult<()> {
-        let response = Self::prepare_redirect(from.clone(), to, kind, additional_headers)?;
+        Ok(())
+    }
 
-        self.tree.borrow_mut().insert(&response.tree_entry);
+    fn normalize_asset_configs(
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+        allow_conflicting_routes: bool,
+    ) -> AssetCertificationResult<Vec<NormalizedAssetConfig>> {
+        let mut asset_configs = asset_configs
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<AssetCertificationResult<Vec<_>>>()?;
 
-        self.responses
-            .insert(RequestKey::new(&from, None, None), response);
+        Self::resolve_conflicting_routes(&mut asset_configs, allow_conflicting_routes)?;
 
-        Ok(())
+        Ok(asset_configs)
     }
 
-    fn delete_redirect(
-        &mut self,
-        from: String,
-        to: String,
-        kind: AssetRedirectKind,
-        addtional_headers: Vec<(String, String)>,
-    ) -> AssetCertificationResult<()> {
-        let response = Self::prepare_redirect(from.clone(), to, kind, addtional_headers)?;
-
-        self.tree.borrow_mut().delete(&response.tree_entry);
-        self.responses.remove(&RequestKey::new(&from, None, None));
-
-        Ok(())
-    }
-
-    fn prepare_redirect(
-        from: String,
-        to: String,
-        kind: AssetRedirectKind,
-        addtional_headers: Vec<(String, String)>,
-    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
-        let status_code = match kind {
-            AssetRedirectKind::Permanent => StatusCode::MOVED_PERMANENTLY,
-            AssetRedirectKind::Temporary => StatusCode::TEMPORARY_REDIRECT,
-        };
-
-        let mut headers = vec![("location".to_string(), to)];
-        headers.extend(addtional_headers);
-
-        let (response, certification) = Self::prepare_response_and_certification(
-            from.clone(),
-            status_code,
-            Cow::Owned(vec![]),
-            headers,
-            vec![],
-        )?;
-
-        Ok(CertifiedAssetResponse {
-            response,
-            tree_entry: HttpCertificationTreeEntry::new(
-                HttpCertificationPath::exact(from),
-                certification,
-            ),
-        })
-    }
-
-    fn prepare_asset_response_and_certification<'path>(
-        asset: Asset<'content, 'path>,
-        additional_headers: Vec<(String, String)>,
-        content_type: Option<String>,
-        encoding: Option<AssetEncoding>,
-        range_begin: Option<usize>,
-        status_code: Option<StatusCode>,
-    ) -> AssetCertificationResult<(HttpResponse<'content>, HttpCertification)> {
-        let mut content = asset.content;
-        let mut status_code = status_code.unwrap_or(StatusCode::OK);
-        let mut headers = vec![];
-        headers.extend(additional_headers);
-
-        if let Some(content_type) = content_type {
-            headers.push(("content-type".to_string(), content_type));
-        }
-
-        if let Some(encoding) = encoding {
-            headers.push(("content-encoding".to_string(), encoding.to_string()));
-        }
-
-        let mut request_headers = vec![];
-        if let Some(range_begin) = range_begin {
-            let total_length = content.len();
-            let range_end = cmp::min(range_begin + ASSET_CHUNK_SIZE, total_length) - 1;
-            content = content[range_begin..(range_end + 1)].to_owned().into();
-            status_code = StatusCode::PARTIAL_CONTENT;
-            headers.push((
-                http::header::CONTENT_RANGE.to_string(),
-                format!("bytes {range_begin}-{range_end}/{total_length}"),
-            ));
+    /// Checks that each alias and fallback scope is only listed by a single file config,
+    /// or if conflicting routes are allowed, removes them from all but the last config that
+    /// lists them. Configs with the same path are reported as duplicate paths elsewhere.
+    fn resolve_conflicting_routes(
+        asset_configs: &mut [NormalizedAssetConfig],
+        allow_conflicting_routes: bool,
+    ) -> AssetCertificationResult {
+        let mut alias_owners = HashMap::new();
+        let mut scope_owners = HashMap::new();
 
-            // The `Range` request header will not be sent with the first request,
-            // so we don't include it in certification for the first chunk.
-            if range_begin != 0 {
-                request_headers.push((
-                    http::header::RANGE.to_string(),
-                    format!("bytes={range_begin}-"),
-                ));
+        for (index, asset_config) in asset_configs.iter().enumerate() {
+            let NormalizedAssetConfig::File {
+                path,
+                aliased_by,
+                fallback_for,
+                ..
+            } = asset_config
+            else {
+                continue;
+            };
+
+            for alias in aliased_by {
+                match alias_owners.get(alias) {
+                    Some((_, other_path)) if other_path == path => continue,
+                    Some((_, other_path)) if !allow_conflicting_routes => {
+                        return Err(AssetCertificationError::DuplicateAlias {
+                            alias: alias.clone(),
+                            path: other_path.clone(),
+                            other_path: path.clone(),
+                        });
+                    }
+                    _ => {
+                        alias_owners.insert(alias.clone(), (index, path.clone()));
+                    }
+                }
             }
-        };
-
-        Self::prepare_response_and_certification(
-            asset.url.to_string(),
-            status_code,
-            content,
-            headers,
-            request_headers,
-        )
-    }
-
-    fn prepare_response_and_certification(
-        url: String,
-        status_code: StatusCode,
-        body: Cow<'content, [u8]>,
-        additional_response_headers: Vec<(String, String)>,
-        certified_request_headers: Vec<(String, String)>,
-    ) -> AssetCertificationResult<(HttpResponse<'content>, HttpCertification)> {
-        let mut headers = vec![("content-length".to_string(), body.len().to_string())];
-
-        headers.extend(additional_response_headers);
-        let cel_expr = DefaultCelBuilder::full_certification()
-            .with_request_headers(
-                certified_request_headers
-                    .iter()
-                    .map(|(s, _)| s.as_str())
-                    .collect::<Vec<&str>>(),
-            )
-            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
-                vec![],
-            ))
-            .build();
-        let cel_expr_str = cel_expr.to_string();
-        headers.push((CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(), cel_expr_str));
-
-        let request = HttpRequest::get(url)
-            .with_headers(certified_request_headers.clone())
-            .build();
-
-        let response = HttpResponse::builder()
-            .with_status_code(status_code)
-            .with_body(body)
-            .with_headers(headers)
-            .build();
-
-        let certification = HttpCertification::full(&cel_expr, &request, &response, None)?;
 
-        Ok((response, certification))
-    }
-
-    fn get_encoded_asset(
-        &self,
-        preferred_encodings: &[&str],
-        url: &str,
-        maybe_range_begin: Option<usize>,
-    ) -> Option<&CertifiedAssetResponse<'content>> {
-        for encoding in preferred_encodings {
-            if let Some(response) = self.responses.get(&RequestKey::new(
-                url,
-                Some(encoding.to_string()),
-                maybe_range_begin,
-            )) {
-                if response.response.body().len() > ASSET_CHUNK_SIZE {
-                    if let Some(first_chunk_response) = self.responses.get(&RequestKey::new(
-                        url,
-                        Some(encoding.to_string()),
-                        Some(0),
-                    )) {
-                        return Some(first_chunk_response);
-                    } else {
-                        return None;
+            for AssetFallbackConfig { scope, .. } in fallback_for {
+                match scope_owners.get(scope) {
+                    Some((_, other_path)) if other_path == path => continue,
+                    Some((_, other_path)) if !allow_conflicting_routes => {
+                        return Err(AssetCertificationError::ConflictingFallbackScope {
+                            scope: scope.clone(),
+                            path: other_path.clone(),
+                            other_path: path.clone(),
+                        });
+                    }
+                    _ => {
+                        scope_owners.insert(scope.clone(), (index, path.clone()));
                     }
-                } else {
-                    return Some(response);
                 }
             }
         }
 
-        None
+        for (index, asset_config) in asset_configs.iter_mut().enumerate() {
+            if let NormalizedAssetConfig::File {
+                aliased_by,
+                fallback_for,
+                ..
+            } = asset_config
+            {
+                aliased_by.retain(|alias| {
+                    alias_owners
+                        .get(alias)
+                        .is_some_and(|(owner, _)| *owner == index)
+                });
+                fallback_for.retain(|fallback| {
+                    scope_owners
+                        .get(&fallback.scope)
+                        .is_some_and(|(owner, _)| *owner == index)
+                });
+            }
+        }
+
+        Ok(())
     }
 
-    fn get_encoded_fallback_asset(
+    /// Checks that no alias or fallback scope of the given configs was already certified for
+    /// another file, unless conflicting routes are allowed.
+    fn check_certified_routes(
         &self,
-        preferred_encodings: &[&str],
-        scope: &str,
-    ) -> Option<&CertifiedAssetResponse<'content>> {
-        for encoding in preferred_encodings {
-            if let Some(response) = self.fallback_responses.get(&RequestKey::new(
-                scope,
-                Some(encoding.to_string()),
-                None,
-            )) {
-                return Some(response);
-            }
+        asset_configs: &[NormalizedAssetConfig],
+    ) -> AssetCertificationResult {
+        if self.allow_conflicting_routes {
+            return Ok(());
         }
 
-        None
-    }
+        for asset_config in asset_configs {
+            let NormalizedAssetConfig::File {
+                path,
+                aliased_by,
+                fallback_for,
+                ..
+            } = asset_config
+            else {
+                continue;
+            };
 
-    fn get_range_header<'a>(request: &'a HttpRequest) -> Option<&'a str> {
-        for (name, value) in request.headers().iter() {
-            if name.to_lowercase().eq(&http::header::RANGE.as_str()) {
-                return Some(value);
+            for alias in aliased_by {
+                if let Some(other_path) =
+                    self.alias_owners.get(alias).filter(|owner| *owner != path)
+                {
+                    return Err(AssetCertificationError::DuplicateAlias {
+                        alias: alias.clone(),
+                        path: other_path.clone(),
+                        other_path: path.clone(),
+                    });
+                }
             }
-        }
-        None
-    }
 
-    fn get_preferred_encodings<'a>(&self, request: &'a HttpRequest) -> Vec<&'a str> {
-        for (name, value) in request.headers().iter() {
-            if name.to_lowercase() == "accept-encoding" {
-                return Self::prioritized_encodings(value)
-                    .iter()
-                    .map(|(encoding, _quality)| *encoding)
-                    .collect();
+            for AssetFallbackConfig { scope, .. } in fallback_for {
+                if let Some(other_path) = self
+                    .fallback_scope_owners
+                    .get(scope)
+                    .filter(|owner| *owner != path)
+                {
+                    return Err(AssetCertificationError::ConflictingFallbackScope {
+                        scope: scope.clone(),
+                        path: other_path.clone(),
+                        other_path: path.clone(),
+                    });
+                }
             }
         }
 
-        vec![]
-    }
-
-    fn prioritized_encodings(encodings: &str) -> Vec<(&str, f32)> {
-        let mut encodings = encodings
-            .split(',')
-            .filter_map(|encoding| {
-                encoding
-                    .split(';')
-                    .collect::<Vec<_>>()
-                    .first()
-                    .map(|s| s.trim())
-                    .map(|s| (s, Self::default_encoding_quality(s)))
-            })
-            .collect::<Vec<_>>();
-
-        // this `unwrap()` call is safe as long as the values returned by
-        // `default_encoding_quality` are comparable (not NaN)
-        encodings.sort_unstable_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
-
-        encodings
+        Ok(())
     }
 
-    fn default_encoding_quality(encoding: &str) -> f32 {
-        if encoding.eq_ignore_ascii_case("br") {
-            return 1.0;
-        }
+    fn certify_assets_with_configs<'path>(
+        &mut self,
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+        asset_configs: &[NormalizedAssetConfig],
+    ) -> AssetCertificationResult {
+        let asset_map = assets
+            .into_iter()
+            .map(|asset| (asset.path.clone(), asset))
+            .collect::<HashMap<_, _>>();
 
-        if encoding.eq_ignore_ascii_case("zstd") {
-            return 0.9;
+        if let Some(filename_hash_validation) = &self.filename_hash_validation {
+            let assets = asset_map.values().collect::<Vec<_>>();
+            Self::validate_filename_hashes(&assets, filename_hash_validation)?;
         }
 
-        if encoding.eq_ignore_ascii_case("gzip") {
-            return 0.8;
-        }
+        for asset in asset_map.values() {
+            let asset_config = asset_configs.iter().find(|e| e.matches_asset(asset));
+            for (encoding, postfix) in asset_config
+                .map(|e| match e {
+                    NormalizedAssetConfig::File { encodings, .. } => encodings.clone(),
+                    NormalizedAssetConfig::Pattern { encodings, .. } => encodings.clone(),
+                    _ => vec![],
+                })
+                .unwrap_or_default()
+            {
+                let encoded_asset_path = format!("{}{}", asset.path, postfix);
+                let encoded_asset = asset_map.get(encoded_asset_path.as_str()).cloned();
+                if let Some(mut encoded_asset) = encoded_asset {
+                    encoded_asset.url.clone_from(&asset.url);
+                    let etag = self.asset_etag(&asset.content, &encoded_asset.content);
 
-        if encoding.eq_ignore_ascii_case("deflate") {
-            return 0.7;
-        }
+                    self.certify_asset_impl(encoded_asset, asset_config, Some(encoding), etag)?;
+                }
+            }
 
-        if encoding.eq_ignore_ascii_case("identity") {
-            return 0.5;
+            let etag = self.asset_etag(&asset.content, &asset.content);
+            self.certify_asset_impl(asset.clone(), asset_config, None, etag)?;
         }
 
-        0.6
+        Ok(())
     }
-}
 
-impl Default for AssetRouter<'_> {
-    fn default() -> Self {
-        Self::new()
+    fn insert_redirects(
+        &mut self,
+        asset_configs: Vec<NormalizedAssetConfig>,
+    ) -> AssetCertificationResult {
+        for asset_config in asset_configs {
+            if let NormalizedAssetConfig::Redirect {
+                from,
+                to,
+                kind,
+                headers,
+            } = asset_config
+            {
+                self.insert_redirect(from, to, kind, headers)?;
+            }
+        }
+
+        Ok(())
     }
-}
+
+    /// Deletes multiple assets from the router, including any certification for those assets.
+    ///
+    /// Depending on the configuration provided to the [certify_assets](AssetRouter::certify_assets) function,
+    /// multiple responses may be generated for the same asset. To ensure that all generated responses are deleted,
+    /// this function accepts the same configuration.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_assets<'path>(
+        &mut self,
+        assets: impl IntoIterator<Item = Asset<'content, 'path>>,
+        asset_configs: impl IntoIterator<Item = AssetConfig>,
+    ) -> AssetCertificationResult {
+        let asset_configs =
+            Self::normalize_asset_configs(asset_configs, self.allow_conflicting_routes)?;
+
+        let asset_map = assets
+            .into_iter()
+            .map(|asset| (asset.path.clone(), asset))
+            .collect::<HashMap<_, _>>();
+
+        for asset in asset_map.values() {
+            let asset_config = asset_configs.iter().find(|e| e.matches_asset(asset));
+
+            for (encoding, postfix) in asset_config
+                .map(|e| match e {
+                    NormalizedAssetConfig::File { encodings, .. } => encodings.clone(),
+                    NormalizedAssetConfig::Pattern { encodings, .. } => encodings.clone(),
+                    _ => vec![],
+                })
+                .unwrap_or_default()
+            {
+                let encoded_asset_path = format!("{}{}", asset.path, postfix);
+                let encoded_asset = asset_map.get(encoded_asset_path.as_str()).cloned();
+
+                if let Some(mut encoded_asset) = encoded_asset {
+                    encoded_asset.url.clone_from(&asset.url);
+                    let etag = self.asset_etag(&asset.content, &encoded_asset.content);
+
+                    self.delete_asset_impl(encoded_asset, asset_config, Some(encoding), etag)?;
+                }
+            }
+
+            let etag = self.asset_etag(&asset.content, &asset.content);
+            self.delete_asset_impl(asset.clone(), asset_config, None, etag)?;
+        }
+
+        for asset_config in asset_configs {
+            if let NormalizedAssetConfig::Redirect {
+                from,
+                to,
+                kind,
+                headers,
+            } = asset_config
+            {
+                self.delete_redirect(from, to, kind, headers)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes multiple assets from the router by path, including any certification for those assets.
+    ///
+    /// Depending on the configuration provided to the [certify_assets](AssetRouter::certify_assets) function,
+    /// multiple responses may be generated for the same asset. These assets may exist on different paths,
+    /// for example if the `alias` configuration is used. If `alias` paths are not passed to this function,
+    /// they will not be deleted.
+    ///
+    /// If multiple encodings exist for a path, all encodings will be deleted.
+    ///
+    /// Everything else that is certified for the exact path is deleted as well, including
+    /// [method responses](AssetRouter::certify_method_response), the `405 Method Not Allowed`
+    /// response and [variants](AssetRouter::certify_variants), since their certification is
+    /// removed along with the asset's.
+    ///
+    /// Fallbacks are also not deleted, to delete them, use the
+    /// [delete_fallback_assets_by_path](AssetRouter::delete_fallback_assets_by_path) function.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_assets_by_path<'path>(
+        &mut self,
+        asset_paths: impl IntoIterator<Item = &'path str>,
+    ) {
+        for asset_path in asset_paths {
+            self.responses.retain(|key, _| key.path != asset_path);
+            self.method_responses.remove(asset_path);
+            self.method_not_allowed_responses.remove(asset_path);
+            self.asset_allowed_methods.remove(asset_path);
+            self.certified_query_parameter_names.remove(asset_path);
+            self.variant_responses.remove(asset_path);
+            self.forwarded_redirect_responses.remove(asset_path);
+            self.cors_preflight_responses.remove(asset_path);
+            self.alias_owners.remove(asset_path);
+            for path in self.with_rewritten_paths(asset_path) {
+                self.tree
+                    .borrow_mut()
+                    .delete_by_path(&HttpCertificationPath::exact(path));
+            }
+        }
+    }
+
+    /// Deletes multiple fallback assets from the router by path, including certification for those assets.
+    ///
+    /// This function will only delete fallbacks, to delete standard assets, use the
+    /// [delete_assets_by_path](AssetRouter::delete_assets_by_path) function.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_fallback_assets_by_path<'path>(
+        &mut self,
+        asset_paths: impl IntoIterator<Item = &'path str>,
+    ) {
+        for asset_path in asset_paths {
+            self.fallback_responses
+                .remove(&RequestKey::new(asset_path, None, None));
+            self.fallback_scope_owners.remove(asset_path);
+            for path in self.with_rewritten_paths(asset_path) {
+                self.tree
+                    .borrow_mut()
+                    .delete_by_path(&HttpCertificationPath::wildcard(path));
+            }
+        }
+    }
+
+    /// Deletes all assets from the router, including any certification for those assets.
+    ///
+    /// This is equivalent to [clear](AssetRouter::clear).
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_all_assets(&mut self) {
+        self.clear();
+    }
+
+    /// Removes everything that has been certified with this router and resets the underlying
+    /// [HttpCertificationTree](ic_http_certification::HttpCertificationTree) to an empty tree.
+    ///
+    /// This removes all assets, including their aliases and encodings, all fallbacks and all
+    /// [method responses](AssetRouter::certify_method_response), so a canister can rebuild the
+    /// router from scratch, for example in `post_upgrade` or after a frontend redeploy. Any
+    /// [chunked certification](AssetRouter::begin_certification) in progress is discarded and
+    /// the router exits [maintenance mode](AssetRouter::enter_maintenance). Options that were
+    /// set when creating the router, such as [ETags](AssetRouter::with_etags), are kept.
+    ///
+    /// If the tree is shared with other routers, through [with_tree](AssetRouter::with_tree),
+    /// their certifications are removed from the tree as well.
+    ///
+    /// After performing this operation, the assets must be certified again using
+    /// [certify_assets](AssetRouter::certify_assets), and one must set the canister's certified data
+    /// (`ic_cdk::api::set_certified_data()`) to the new [root hash](AssetRouter::root_hash) of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_asset_certification::{Asset, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_assets(vec![Asset::new("index.html", b"<h1>Hello</h1>".as_slice())], vec![])
+    ///     .unwrap();
+    ///
+    /// asset_router.clear();
+    ///
+    /// assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    /// ```
+    pub fn clear(&mut self) {
+        self.responses.clear();
+        self.fallback_responses.clear();
+        self.method_responses.clear();
+        self.method_not_allowed_responses.clear();
+        self.asset_allowed_methods.clear();
+        self.cors_preflight_responses.clear();
+        self.variant_responses.clear();
+        self.media_type_fallback_responses.clear();
+        self.vary_accept_fallbacks.clear();
+        self.forwarded_redirect_responses.clear();
+        self.certified_query_parameter_names.clear();
+        self.alias_owners.clear();
+        self.fallback_scope_owners.clear();
+        self.pending_certification = None;
+        self.maintenance = None;
+        self.tree.borrow_mut().clear();
+    }
+
+    /// Certifies a response for requests with the given method to the given path, to be
+    /// served later by the [serve_asset](AssetRouter::serve_asset) method.
+    ///
+    /// The response is certified with full certification, so the request's method is
+    /// included in certification, along with the request's body. Note that this means the
+    /// response will only pass verification for requests with an empty body.
+    ///
+    /// Once a response has been certified for a method on a path, requests to that path with
+    /// any other method are served a certified `405 Method Not Allowed` response, with an
+    /// `Allow` header listing the certified methods, instead of any asset certified for the
+    /// path. Certifying another response for the same method and path replaces the previous one.
+    ///
+    /// The [allowed methods](crate::AssetFileOptions::allowed_methods) of an asset certified for
+    /// the path, or one of its aliases, which default to `GET` and `HEAD`, keep serving the asset
+    /// and are included in the `Allow` header. An asset whose config allows no methods is no
+    /// longer served for `GET` requests once a response is certified for another method,
+    /// although its certification remains in the tree until the asset is deleted.
+    ///
+    /// The status code, headers and body of the response are entirely under the caller's
+    /// control and are all certified, so this method can also be used with [Method::GET] to
+    /// serve responses that are not backed by a file. For example, a `200 OK` response
+    /// with a `Refresh` header or an HTML `<meta http-equiv="refresh">` tag can be used to
+    /// redirect clients that do not follow `3xx` redirects, see the second example below.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse, Method, StatusCode};
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_method_response(
+    ///         "/submit",
+    ///         Method::POST,
+    ///         HttpResponse::ok(b"Submitted", vec![]).with_upgrade(true).build(),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::post("/submit").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.status_code(), StatusCode::OK);
+    /// assert_eq!(response.upgrade(), Some(true));
+    ///
+    /// let request = HttpRequest::get("/submit").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+    /// ```
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse, Method, StatusCode};
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_method_response(
+    ///         "/old-page",
+    ///         Method::GET,
+    ///         HttpResponse::ok(
+    ///             br#"<meta http-equiv="refresh" content="0; url=/new-page">"#,
+    ///             vec![
+    ///                 ("Content-Type".to_string(), "text/html".to_string()),
+    ///                 ("Refresh".to_string(), "0; url=/new-page".to_string()),
+    ///             ],
+    ///         )
+    ///         .build(),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/old-page").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.status_code(), StatusCode::OK);
+    /// ```
+    pub fn certify_method_response(
+        &mut self,
+        path: &str,
+        method: Method,
+        response: HttpResponse<'content>,
+    ) -> AssetCertificationResult {
+        let cert_response = self.prepare_method_response(path, &method, response)?;
+
+        let path_responses = self.method_responses.entry(path.to_string()).or_default();
+        if let Some(previous_response) = path_responses.remove(&method) {
+            self.delete_tree_entry(&previous_response.tree_entry);
+        }
+        self.insert_tree_entry(&cert_response.tree_entry);
+        path_responses.insert(method, cert_response);
+
+        self.update_method_not_allowed_response(path)
+    }
+
+    /// Deletes the response certified for requests with the given method to the given path
+    /// with [certify_method_response](AssetRouter::certify_method_response), including its
+    /// certification.
+    ///
+    /// Once the responses for all methods on a path have been deleted, requests to that path
+    /// are served any asset certified for the path again.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_method_response(
+        &mut self,
+        path: &str,
+        method: &Method,
+    ) -> AssetCertificationResult {
+        let Some(cert_response) = self
+            .method_responses
+            .get_mut(path)
+            .and_then(|method_responses| method_responses.remove(method))
+        else {
+            return Ok(());
+        };
+        self.delete_tree_entry(&cert_response.tree_entry);
+
+        self.update_method_not_allowed_response(path)
+    }
+
+    /// Certifies multiple variants of the response for `GET` requests to the given path, such
+    /// as locale-specific bodies or A/B test content, to be served later by the
+    /// [serve_asset](AssetRouter::serve_asset) method. Each variant is selected by the value of
+    /// the request header with the given name, and is served for `GET` requests with exactly
+    /// that header value, while the default response is served for `GET` requests without the
+    /// header. Certifying variants for a path replaces any variants that were previously
+    /// certified for it.
+    ///
+    /// Each variant, and the default response, is certified with full certification,
+    /// including the selecting request header, so that the choice of variant is bound to the
+    /// request. A variant only passes verification for requests with the header value that it
+    /// was certified for, and the default response only passes verification for requests
+    /// without the header, so that a replica cannot downgrade a request that selects a variant
+    /// to the default response. As with
+    /// [certify_method_response](AssetRouter::certify_method_response), the request's
+    /// method and body are also certified, so variants only pass verification for `GET`
+    /// requests with an empty body.
+    ///
+    /// Requests with a header value that does not match any variant, or with any other method,
+    /// are served as if no variants were certified for the path, for example by an asset
+    /// certified for the path, and otherwise [serve_asset](AssetRouter::serve_asset) returns
+    /// an error. Such an asset is certified independently of the request's headers, so a
+    /// replica could serve it in place of any variant. To keep the choice of variant bound to
+    /// the request, no asset should be certified for a path with variants.
+    ///
+    /// Since the header value is matched exactly, the header should carry the variant key
+    /// itself, such as a custom `X-Variant` header set by the client. Variants selected by a
+    /// header that carries other data as well, such as the `Cookie` header, can be certified
+    /// with [certify_variants_with_selector](AssetRouter::certify_variants_with_selector).
+    ///
+    /// Client hints, such as `Sec-CH-Prefers-Color-Scheme`, also carry the variant key
+    /// itself, so variants can be selected by them, adapting the response to the client as
+    /// described in [with_client_hints](AssetRouter::with_client_hints). Like any other
+    /// selecting header, the client hint is certified, so a variant only passes verification
+    /// for requests with the hint value that it was certified for. Some client hints are sent
+    /// as quoted strings, such as `"dark"` for `Sec-CH-Prefers-Color-Scheme`, and the quotes
+    /// are part of the value that is matched. Browsers send the hint on every request once
+    /// they have been asked to, so the default response is only served to browsers that
+    /// don't support the hint. The default response can be certified for the other values of
+    /// the hint with [certify_variants_with_selector](AssetRouter::certify_variants_with_selector).
+    ///
+    /// Every variant, and the default response, is certified and served with the selecting
+    /// header listed in its `Vary` header, which is merged into the response's own `Vary`
+    /// header if it has one, so that caches keep variants apart. The
+    /// [build ID](AssetRouter::with_build_id), [CORS](AssetRouter::with_cors) and client hints
+    /// headers are certified and served on every variant as well, in the same way as for
+    /// assets.
+    ///
+    /// Every variant is certified separately, and stores a full copy of its body, so the
+    /// memory and certification tree size grow linearly with the number of variants. Only one
+    /// header can select variants for a path, so selecting variants by multiple dimensions,
+    /// such as locale and experiment group, requires a variant for every combination of them,
+    /// and the number of variants grows with the product of the number of options for each
+    /// dimension.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_variants(
+    ///         "/home",
+    ///         "X-Locale",
+    ///         HttpResponse::ok(b"Hello".as_slice(), vec![]).build(),
+    ///         vec![
+    ///             ("fr".to_string(), HttpResponse::ok(b"Bonjour".as_slice(), vec![]).build()),
+    ///             ("de".to_string(), HttpResponse::ok(b"Hallo".as_slice(), vec![]).build()),
+    ///         ],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/home")
+    ///     .with_headers(vec![("X-Locale".to_string(), "fr".to_string())])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"Bonjour");
+    ///
+    /// let request = HttpRequest::get("/home").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"Hello");
+    /// ```
+    pub fn certify_variants(
+        &mut self,
+        path: &str,
+        header_name: &str,
+        default: HttpResponse<'content>,
+        variants: impl IntoIterator<Item = (String, HttpResponse<'content>)>,
+    ) -> AssetCertificationResult {
+        let variants = variants.into_iter().collect::<HashMap<_, _>>();
+        let header_values = variants.keys().cloned().collect::<Vec<_>>();
+
+        self.certify_variants_with_selector(
+            path,
+            header_name,
+            header_values,
+            |header_value| Some(header_value),
+            default,
+            variants,
+        )
+    }
+
+    /// Certifies multiple variants of the response for `GET` requests to the given path, in
+    /// the same way as [certify_variants](AssetRouter::certify_variants), with each variant
+    /// selected by a function over the value of the request header with the given name.
+    ///
+    /// The selector returns the key of the variant to serve for a header value, or [None] for
+    /// header values that do not select any variant. This allows a variant to be selected by
+    /// part of a header value, such as a single cookie of the `Cookie` header.
+    ///
+    /// Responses are certified ahead of time, and a variant is bound to the exact header value
+    /// that it is certified for, so a variant can't be certified for every header value that
+    /// the selector picks it for. Instead, the selector is called for each of the given header
+    /// values, and the variant that it selects is certified for that header value, so
+    /// the given header values must list every value that clients are expected to send.
+    /// Requests with any other header value are served as described in
+    /// [certify_variants](AssetRouter::certify_variants) for header values that do not match
+    /// any variant. The default response is certified for header values that the selector
+    /// returns [None] for, so it is served for them, while header values for which the
+    /// selector returns the key of a variant that was not provided are not certified.
+    ///
+    /// A variant is certified once for every header value that selects it, and each
+    /// certification stores its own copy of the variant's response, so the memory and
+    /// certification tree size grow linearly with the number of header values, rather than
+    /// the number of variants. Selecting variants by a cookie requires a header value for
+    /// every combination of cookies that clients are expected to send, so this works best
+    /// for cookies that are set by the canister itself.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_asset_certification::AssetRouter;
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_variants_with_selector(
+    ///         "/home",
+    ///         "Cookie",
+    ///         vec![
+    ///             "group=a".to_string(),
+    ///             "group=b".to_string(),
+    ///             "consent=yes; group=b".to_string(),
+    ///         ],
+    ///         |cookies| {
+    ///             cookies
+    ///                 .split("; ")
+    ///                 .find_map(|cookie| cookie.strip_prefix("group="))
+    ///         },
+    ///         HttpResponse::ok(b"Hello".as_slice(), vec![]).build(),
+    ///         vec![
+    ///             ("a".to_string(), HttpResponse::ok(b"Hello A".as_slice(), vec![]).build()),
+    ///             ("b".to_string(), HttpResponse::ok(b"Hello B".as_slice(), vec![]).build()),
+    ///         ],
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/home")
+    ///     .with_headers(vec![("Cookie".to_string(), "consent=yes; group=b".to_string())])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"Hello B");
+    /// ```
+    pub fn certify_variants_with_selector(
+        &mut self,
+        path: &str,
+        header_name: &str,
+        header_values: impl IntoIterator<Item = String>,
+        selector: impl Fn(&str) -> Option<&str>,
+        default: HttpResponse<'content>,
+        variants: impl IntoIterator<Item = (String, HttpResponse<'content>)>,
+    ) -> AssetCertificationResult {
+        let variants = variants.into_iter().collect::<HashMap<_, _>>();
+
+        let default_response = default;
+        let default =
+            self.prepare_variant_response(path, header_name, None, default_response.clone())?;
+        let mut responses = HashMap::new();
+        for header_value in header_values {
+            let response = match selector(&header_value) {
+                Some(key) => match variants.get(key) {
+                    Some(response) => response.clone(),
+                    None => continue,
+                },
+                None => default_response.clone(),
+            };
+
+            let cert_response =
+                self.prepare_variant_response(path, header_name, Some(&header_value), response)?;
+            responses.insert(header_value, cert_response);
+        }
+
+        self.delete_variants(path);
+
+        self.insert_tree_entry(&default.tree_entry);
+        for cert_response in responses.values() {
+            self.insert_tree_entry(&cert_response.tree_entry);
+        }
+
+        self.variant_responses.insert(
+            path.to_string(),
+            AssetVariants {
+                header_name: header_name.to_string(),
+                default,
+                responses,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Deletes all variants certified for the given path with
+    /// [certify_variants](AssetRouter::certify_variants), including their certification.
+    ///
+    /// Requests to the path are served as if no variants were certified for it, for example by
+    /// an asset certified for the path, regardless of their headers.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_variants(&mut self, path: &str) {
+        let Some(variants) = self.variant_responses.remove(path) else {
+            return;
+        };
+
+        self.delete_tree_entry(&variants.default.tree_entry);
+        for cert_response in variants.responses.values() {
+            self.delete_tree_entry(&cert_response.tree_entry);
+        }
+    }
+
+    fn get_variant_response_for_request(
+        &self,
+        req_path: &str,
+        request: &HttpRequest,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        if request.method() != Method::GET {
+            return None;
+        }
+
+        let variants = self.variant_responses.get(req_path)?;
+        let header_value = request
+            .headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(&variants.header_name));
+
+        match header_value {
+            Some((_, header_value)) => variants.responses.get(header_value),
+            None => Some(&variants.default),
+        }
+    }
+
+    /// Prepares the response of a [variant](AssetRouter::certify_variants) for requests with
+    /// the given value of the selecting header, or the default response for requests without
+    /// that header if no value is given.
+    fn prepare_variant_response(
+        &self,
+        path: &str,
+        header_name: &str,
+        header_value: Option<&str>,
+        response: HttpResponse<'content>,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let (status_code, additional_headers, body, upgrade) = response.into_parts();
+
+        let mut headers = vec![("content-length".to_string(), body.len().to_string())];
+        headers.extend(self.with_variant_headers(header_name, additional_headers));
+        let certified_request_header_names = std::iter::once(header_name.to_string())
+            .chain(
+                self.with_certified_host_header(vec![])
+                    .into_iter()
+                    .map(|(name, _)| name),
+            )
+            .collect::<Vec<_>>();
+        let request_headers = self.with_certified_host_header(
+            header_value
+                .map(|header_value| (header_name.to_string(), header_value.to_string()))
+                .into_iter()
+                .collect(),
+        );
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(
+                certified_request_header_names
+                    .iter()
+                    .map(String::as_str)
+                    .collect::<Vec<&str>>(),
+            )
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+        headers.push((
+            CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+            cel_expr.to_string(),
+        ));
+
+        let request = HttpRequest::get(path)
+            .with_headers(request_headers.clone())
+            .build();
+        let response = HttpResponse::from_parts(status_code, headers, body, upgrade);
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None)?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::exact(path.to_string()),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    /// Certifies a fallback response for requests within the given scope that prefer the given
+    /// media type, such as a JSON `404 Not Found` response for an API, to be served later by
+    /// the [serve_asset](AssetRouter::serve_asset) method. Certifying a fallback for a scope
+    /// and media type replaces any fallback that was previously certified for them.
+    ///
+    /// The fallback is certified with response-only certification for every path within the
+    /// scope, in the same way as fallbacks configured with
+    /// [AssetFallbackConfig]. A `Content-Type` header with the given media type is added to
+    /// the response, unless it already has one.
+    ///
+    /// Scopes are searched in the same order as for other fallbacks, starting from the
+    /// closest scope to the request path. Within a scope, the fallback to serve is chosen by the
+    /// request's `Accept` header, as described in
+    /// [preferred_media_type](HttpRequest::preferred_media_type), with the following
+    /// precedence:
+    ///
+    /// - The generic fallback for the scope, configured with [AssetFallbackConfig], is
+    ///   preferred over the media type specific fallbacks whenever the client accepts its
+    ///   `Content-Type` at least as much as theirs. This includes requests accepting `*/*`,
+    ///   and requests without an `Accept` header.
+    /// - Otherwise, the media type specific fallback that is most preferred by the client is
+    ///   served. If multiple of them are preferred equally, the one that was certified first
+    ///   is served.
+    /// - If the client accepts none of the media type specific fallbacks, the generic fallback
+    ///   is served, regardless of its `Content-Type`. If the scope has no generic fallback,
+    ///   the media type specific fallback that was certified first is served, rather than
+    ///   responding with `406 Not Acceptable`. The search does not continue with the next
+    ///   scope, since a fallback for a less specific scope would not pass verification.
+    ///
+    /// A generic fallback without a `Content-Type` header is only served if none of the media
+    /// type specific fallbacks are acceptable.
+    ///
+    /// Media type specific fallbacks are served as they were certified, regardless of the
+    /// request's `Accept-Encoding` header.
+    ///
+    /// Since the response depends on the request's `Accept` header, `Accept` is added to the
+    /// `Vary` header of the media type specific fallbacks, and of the generic fallback for the
+    /// scope, unless it is already listed, so that caches keep them apart. The generic
+    /// fallback is recertified with the `Vary` header while the scope has media type specific
+    /// fallbacks, including when it is certified afterwards, and is certified without it again
+    /// once the last of them is [deleted](AssetRouter::delete_media_type_fallback).
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse, StatusCode};
+    /// use ic_asset_certification::{
+    ///     Asset, AssetConfig, AssetFallbackConfig, AssetFileOptions, AssetRouter,
+    /// };
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    /// asset_router
+    ///     .certify_assets(
+    ///         vec![Asset::new("404.html", b"<h1>Not found</h1>".as_slice())],
+    ///         vec![AssetConfig::File {
+    ///             path: "404.html".to_string(),
+    ///             content_type: Some("text/html".to_string()),
+    ///             fallback_for: vec![AssetFallbackConfig {
+    ///                 scope: "/api".to_string(),
+    ///                 status_code: Some(StatusCode::NOT_FOUND),
+    ///             }],
+    ///             headers: vec![],
+    ///             aliased_by: vec![],
+    ///             encodings: vec![],
+    ///             options: AssetFileOptions {
+    ///                 addressable: false,
+    ///                 ..Default::default()
+    ///             },
+    ///         }],
+    ///     )
+    ///     .unwrap();
+    /// asset_router
+    ///     .certify_media_type_fallback(
+    ///         "/api",
+    ///         "application/json",
+    ///         HttpResponse::not_found(br#"{"error":"not found"}"#.as_slice(), vec![]).build(),
+    ///     )
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/api/missing")
+    ///     .with_headers(vec![("Accept".to_string(), "application/json".to_string())])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), br#"{"error":"not found"}"#);
+    /// assert!(response
+    ///     .headers()
+    ///     .contains(&("vary".to_string(), "Accept".to_string())));
+    ///
+    /// let request = HttpRequest::get("/api/missing")
+    ///     .with_headers(vec![("Accept".to_string(), "text/html".to_string())])
+    ///     .build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.body(), b"<h1>Not found</h1>");
+    /// assert!(response
+    ///     .headers()
+    ///     .contains(&("vary".to_string(), "Accept".to_string())));
+    /// ```
+    pub fn certify_media_type_fallback(
+        &mut self,
+        scope: &str,
+        media_type: &str,
+        response: HttpResponse<'content>,
+    ) -> AssetCertificationResult {
+        let cert_response = self.prepare_media_type_fallback(scope, media_type, response)?;
+
+        self.delete_media_type_fallback(scope, media_type)?;
+        self.insert_tree_entry(&cert_response.tree_entry);
+        self.media_type_fallback_responses
+            .entry(scope.to_string())
+            .or_default()
+            .push((media_type.to_string(), cert_response));
+
+        self.set_fallbacks_vary_accept(scope, true)
+    }
+
+    /// Deletes the fallback certified for the given scope and media type with
+    /// [certify_media_type_fallback](AssetRouter::certify_media_type_fallback), including
+    /// its certification.
+    ///
+    /// Once the last media type specific fallback for the scope is deleted, the generic
+    /// fallback for the scope is certified without `Accept` in its `Vary` header again.
+    ///
+    /// After performing this operation, one must set the canister's certified data (`ic_cdk::api::set_certified_data()`)
+    /// to the new [root hash](AssetRouter::root_hash) of the tree.
+    pub fn delete_media_type_fallback(
+        &mut self,
+        scope: &str,
+        media_type: &str,
+    ) -> AssetCertificationResult {
+        let Some(fallbacks) = self.media_type_fallback_responses.get_mut(scope) else {
+            return Ok(());
+        };
+
+        if let Some(index) = fallbacks
+            .iter()
+            .position(|(fallback_media_type, _)| fallback_media_type == media_type)
+        {
+            let (_, cert_response) = fallbacks.remove(index);
+            self.delete_tree_entry(&cert_response.tree_entry);
+        }
+        if fallbacks.is_empty() {
+            self.media_type_fallback_responses.remove(scope);
+            self.set_fallbacks_vary_accept(scope, false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Recertifies the generic fallbacks for the given scope, in every encoding, with `Accept`
+    /// added to their `Vary` header, or removed again if it was added by the router.
+    fn set_fallbacks_vary_accept(
+        &mut self,
+        scope: &str,
+        vary_accept: bool,
+    ) -> AssetCertificationResult {
+        let request_keys = self
+            .fallback_responses
+            .keys()
+            .filter(|request_key| request_key.path == scope)
+            .filter(|request_key| vary_accept != self.vary_accept_fallbacks.contains(request_key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for request_key in request_keys {
+            let Some(cert_response) = self.fallback_responses.get(&request_key) else {
+                continue;
+            };
+            let has_vary_accept = cert_response
+                .response
+                .headers()
+                .iter()
+                .any(|(name, value)| {
+                    name.eq_ignore_ascii_case(http::header::VARY.as_str())
+                        && vary_lists(value, http::header::ACCEPT.as_str())
+                });
+            if vary_accept && has_vary_accept {
+                continue;
+            }
+
+            let recertified_response =
+                self.recertify_fallback_vary_accept(scope, cert_response, vary_accept)?;
+
+            self.delete_tree_entry(&cert_response.tree_entry);
+            if let Some(not_modified) = &cert_response.not_modified {
+                self.delete_tree_entry(&not_modified.tree_entry);
+            }
+            self.insert_tree_entry(&recertified_response.tree_entry);
+            if let Some(not_modified) = &recertified_response.not_modified {
+                self.insert_tree_entry(&not_modified.tree_entry);
+            }
+
+            if vary_accept {
+                self.vary_accept_fallbacks.insert(request_key.clone());
+            } else {
+                self.vary_accept_fallbacks.remove(&request_key);
+            }
+            self.fallback_responses
+                .insert(request_key, recertified_response);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the given generic fallback for the given scope, certified with `Accept` added
+    /// to, or removed from, its `Vary` header.
+    fn recertify_fallback_vary_accept(
+        &self,
+        scope: &str,
+        cert_response: &CertifiedAssetResponse<'content>,
+        vary_accept: bool,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let (status_code, headers, body, upgrade) = cert_response.response.clone().into_parts();
+        let skip_certification_expr = DefaultCelBuilder::skip_certification().to_string();
+        let skip_certification = headers.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case(CERTIFICATE_EXPRESSION_HEADER_NAME)
+                && *value == skip_certification_expr
+        });
+
+        // the `Content-Length` and certificate expression headers are added again when the
+        // response is certified
+        let headers = headers
+            .into_iter()
+            .filter(|(name, _)| {
+                !name.eq_ignore_ascii_case(http::header::CONTENT_LENGTH.as_str())
+                    && !name.eq_ignore_ascii_case(CERTIFICATE_EXPRESSION_HEADER_NAME)
+            })
+            .collect::<Vec<_>>();
+        let headers = if vary_accept {
+            with_vary_token(headers, http::header::ACCEPT.as_str())
+        } else {
+            without_vary_token(headers, http::header::ACCEPT.as_str())
+        };
+
+        let path = HttpCertificationPath::wildcard(scope.to_string());
+        let not_modified = match &cert_response.not_modified {
+            Some(_) => Some(Box::new(self.prepare_not_modified(
+                scope.to_string(),
+                path.clone(),
+                headers.clone(),
+                skip_certification,
+                &[],
+            )?)),
+            None => None,
+        };
+
+        let (response, certification) = self.prepare_response_and_certification(
+            scope.to_string(),
+            status_code,
+            body,
+            headers,
+            vec![],
+            &[],
+            skip_certification,
+        )?;
+
+        Ok(Self::upgrade_response(
+            CertifiedAssetResponse {
+                response,
+                tree_entry: HttpCertificationTreeEntry::new(path, certification),
+                not_modified,
+            },
+            upgrade.unwrap_or_default(),
+        ))
+    }
+
+    fn prepare_media_type_fallback(
+        &self,
+        scope: &str,
+        media_type: &str,
+        response: HttpResponse<'content>,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let (status_code, additional_headers, body, upgrade) = response.into_parts();
+
+        let mut headers = vec![("content-length".to_string(), body.len().to_string())];
+        if !additional_headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(http::header::CONTENT_TYPE.as_str()))
+        {
+            headers.push((
+                http::header::CONTENT_TYPE.to_string(),
+                media_type.to_string(),
+            ));
+        }
+        headers.extend(additional_headers);
+        let mut headers = with_vary_token(headers, http::header::ACCEPT.as_str());
+
+        // the request is only certified when the router is bound to a host,
+        // otherwise this response is valid for any method
+        let request_headers = self.with_certified_host_header(vec![]);
+        let full_cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(
+                request_headers
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+        let response_only_cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+        let cel_expr_str = if self.certified_host.is_some() {
+            full_cel_expr.to_string()
+        } else {
+            response_only_cel_expr.to_string()
+        };
+        headers.push((CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(), cel_expr_str));
+
+        let response = HttpResponse::from_parts(status_code, headers, body, upgrade);
+        let certification = if self.certified_host.is_some() {
+            let request = HttpRequest::get(scope)
+                .with_headers(request_headers.clone())
+                .build();
+            HttpCertification::full(&full_cel_expr, &request, &response, None)?
+        } else {
+            HttpCertification::response_only(&response_only_cel_expr, &response, None)?
+        };
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::wildcard(scope.to_string()),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    /// Puts the router into maintenance mode, so that a certified `503 Service Unavailable`
+    /// response, with a `Retry-After` header set to the given number of seconds, is served
+    /// for every request until [exit_maintenance](AssetRouter::exit_maintenance) is called.
+    ///
+    /// This allows assets to be certified, for example with
+    /// [chunked certification](AssetRouter::begin_certification) spread over multiple update
+    /// calls, without serving partially certified or uncertified content in the meantime.
+    /// Assets can be certified and deleted as usual while the router is in maintenance mode.
+    ///
+    /// The `503` response is itself certified, with response-only certification for every
+    /// path and method. It is certified in a separate tree, so that it is not affected by any
+    /// changes made to the router's tree during maintenance. This function returns the root
+    /// hash of that tree, which is also returned by [root_hash](AssetRouter::root_hash) until
+    /// the router exits maintenance mode, and one must set the canister's certified data
+    /// (`ic_cdk::api::set_certified_data()`) to it, for the `503` response to pass verification.
+    /// Calling this function again while already in maintenance mode replaces the `503`
+    /// response.
+    ///
+    /// Since the canister's certified data can only be set to a single root hash, maintenance
+    /// mode is not supported for routers that share their tree with other routers or with the
+    /// canister, such as routers created with [with_tree](AssetRouter::with_tree) or by an
+    /// [AssetHostRouter](crate::AssetHostRouter), while the tree is still shared. In this case
+    /// [MaintenanceWithSharedTree](AssetCertificationError::MaintenanceWithSharedTree) is
+    /// returned, and the router stays out of maintenance mode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, StatusCode};
+    /// use ic_asset_certification::{Asset, AssetRouter};
+    ///
+    /// let mut asset_router = AssetRouter::default();
+    ///
+    /// let maintenance_root_hash = asset_router.enter_maintenance(120).unwrap();
+    /// // ic_cdk::api::set_certified_data(&maintenance_root_hash);
+    ///
+    /// asset_router
+    ///     .certify_assets(vec![Asset::new("index.html", b"<h1>Hello</h1>".as_slice())], vec![])
+    ///     .unwrap();
+    ///
+    /// // this should normally be retrieved using `ic_cdk::api::data_certificate()`.
+    /// let data_certificate = vec![1, 2, 3];
+    ///
+    /// let request = HttpRequest::get("/index.html").build();
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+    /// assert!(response
+    ///     .headers()
+    ///     .contains(&("retry-after".to_string(), "120".to_string())));
+    ///
+    /// let root_hash = asset_router.exit_maintenance();
+    /// // ic_cdk::api::set_certified_data(&root_hash);
+    ///
+    /// let response = asset_router.serve_asset(&data_certificate, &request).unwrap();
+    /// assert_eq!(response.status_code(), StatusCode::OK);
+    /// ```
+    pub fn enter_maintenance(&mut self, retry_after_secs: u64) -> AssetCertificationResult<Hash> {
+        // the root hash of the separate tree would replace the certified data
+        // of every other router that uses the same tree
+        if Rc::strong_count(&self.tree) > 1 {
+            return Err(AssetCertificationError::MaintenanceWithSharedTree);
+        }
+
+        let response = self.prepare_maintenance(retry_after_secs)?;
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&response.tree_entry);
+        let root_hash = tree.root_hash();
+
+        self.maintenance = Some(AssetMaintenance { tree, response });
+
+        Ok(root_hash)
+    }
+
+    /// Takes the router out of [maintenance mode](AssetRouter::enter_maintenance), so that
+    /// assets are served as usual again, and returns the [root hash](AssetRouter::root_hash)
+    /// of the router's tree.
+    ///
+    /// After performing this operation, one must set the canister's certified data
+    /// (`ic_cdk::api::set_certified_data()`) to the returned root hash.
+    pub fn exit_maintenance(&mut self) -> Hash {
+        self.maintenance = None;
+
+        self.root_hash()
+    }
+
+    /// Returns `true` if the router is in [maintenance mode](AssetRouter::enter_maintenance).
+    pub fn is_in_maintenance(&self) -> bool {
+        self.maintenance.is_some()
+    }
+
+    fn prepare_maintenance(
+        &self,
+        retry_after_secs: u64,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        // the request is not certified, so that this response is valid for any method
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+
+        let headers = self.with_cors_headers(self.with_build_id_header(vec![
+            ("content-length".to_string(), "0".to_string()),
+            (
+                http::header::RETRY_AFTER.to_string(),
+                retry_after_secs.to_string(),
+            ),
+            (
+                CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+                cel_expr.to_string(),
+            ),
+        ]));
+        let response = HttpResponse::builder()
+            .with_status_code(StatusCode::SERVICE_UNAVAILABLE)
+            .with_headers(headers)
+            .build();
+        let certification = HttpCertification::response_only(&cel_expr, &response, None)?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::wildcard("/"),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    /// Returns the root hash of the underlying
+    /// [HttpCertificationTree](ic_http_certification::HttpCertificationTree).
+    ///
+    /// While the router is in [maintenance mode](AssetRouter::enter_maintenance), this is the
+    /// root hash of the separate tree that the `503` response is certified in, since that is
+    /// the tree that served responses are verified against.
+    pub fn root_hash(&self) -> Hash {
+        if let Some(maintenance) = &self.maintenance {
+            return maintenance.tree.root_hash();
+        }
+
+        self.tree.borrow().root_hash()
+    }
+
+    pub(crate) fn insert_tree_entries(&self) {
+        for response in self
+            .responses
+            .values()
+            .chain(self.fallback_responses.values())
+            .chain(self.method_responses.values().flat_map(HashMap::values))
+            .chain(self.method_not_allowed_responses.values())
+            .chain(self.cors_preflight_responses.values())
+            .chain(self.variant_responses.values().flat_map(|variants| {
+                variants
+                    .responses
+                    .values()
+                    .chain(std::iter::once(&variants.default))
+            }))
+            .chain(
+                self.media_type_fallback_responses
+                    .values()
+                    .flat_map(|fallbacks| fallbacks.iter().map(|(_, response)| response)),
+            )
+            .chain(
+                self.forwarded_redirect_responses
+                    .values()
+                    .flat_map(HashMap::values),
+            )
+        {
+            self.insert_tree_entry(&response.tree_entry);
+
+            if let Some(not_modified) = &response.not_modified {
+                self.insert_tree_entry(&not_modified.tree_entry);
+            }
+        }
+    }
+
+    fn get_method_response_for_request(
+        &self,
+        req_path: &str,
+        request: &HttpRequest,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        let method_response = self
+            .method_responses
+            .get(req_path)
+            .and_then(|method_responses| method_responses.get(request.method()));
+        if method_response.is_some() {
+            return method_response;
+        }
+
+        if request.method() == Method::OPTIONS {
+            let cors_preflight_response = self.cors_preflight_responses.get(req_path);
+            if cors_preflight_response.is_some() {
+                return cors_preflight_response;
+            }
+        }
+
+        let is_allowed_for_asset = self
+            .asset_allowed_methods
+            .get(req_path)
+            .is_some_and(|allowed_methods| allowed_methods.contains(request.method()));
+        if is_allowed_for_asset {
+            return None;
+        }
+
+        self.method_not_allowed_responses.get(req_path)
+    }
+
+    fn update_method_not_allowed_response(&mut self, path: &str) -> AssetCertificationResult {
+        if let Some(cert_response) = self.method_not_allowed_responses.remove(path) {
+            self.delete_tree_entry(&cert_response.tree_entry);
+        }
+
+        if self
+            .method_responses
+            .get(path)
+            .is_some_and(HashMap::is_empty)
+        {
+            self.method_responses.remove(path);
+        }
+
+        let mut allowed_methods = self
+            .method_responses
+            .get(path)
+            .into_iter()
+            .flat_map(HashMap::keys)
+            .chain(self.asset_allowed_methods.get(path).into_iter().flatten())
+            .cloned()
+            .collect::<Vec<_>>();
+        if allowed_methods.is_empty() {
+            return Ok(());
+        }
+        allowed_methods.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+        allowed_methods.dedup();
+
+        let cert_response = Self::prepare_method_not_allowed(path, &allowed_methods)?;
+        self.insert_tree_entry(&cert_response.tree_entry);
+        self.method_not_allowed_responses
+            .insert(path.to_string(), cert_response);
+
+        Ok(())
+    }
+
+    fn prepare_method_response(
+        &self,
+        path: &str,
+        method: &Method,
+        response: HttpResponse<'content>,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let (status_code, additional_headers, body, upgrade) = response.into_parts();
+
+        let mut headers = vec![("content-length".to_string(), body.len().to_string())];
+        headers.extend(additional_headers);
+        let request_headers = self.with_certified_host_header(vec![]);
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(
+                request_headers
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+        headers.push((
+            CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+            cel_expr.to_string(),
+        ));
+
+        let request = HttpRequest::builder()
+            .with_method(method.clone())
+            .with_url(path)
+            .with_headers(request_headers.clone())
+            .build();
+        let response = HttpResponse::from_parts(status_code, headers, body, upgrade);
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None)?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::exact(path.to_string()),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    fn prepare_method_not_allowed(
+        path: &str,
+        allowed_methods: &[Method],
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        // the request is not certified, so that this response is valid for any method
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+
+        let response = HttpResponse::method_not_allowed_with_allow(
+            allowed_methods,
+            vec![],
+            vec![
+                ("content-length".to_string(), "0".to_string()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+                    cel_expr.to_string(),
+                ),
+            ],
+        )
+        .build();
+        let certification = HttpCertification::response_only(&cel_expr, &response, None)?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::exact(path.to_string()),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    fn get_asset_for_request<'a>(
+        &self,
+        request: &HttpRequest,
+        req_path: &'a str,
+        certified_queries: &[String],
+        preferred_encodings: Vec<&'a str>,
+        maybe_range_begin: Option<usize>,
+    ) -> AssetCertificationResult<&CertifiedAssetResponse<'content>> {
+        let queries = certified_queries
+            .iter()
+            .map(|query| Some(query.clone()))
+            .chain(std::iter::once(None));
+
+        for query in queries {
+            if let Some(response) = self.get_encoded_asset(
+                &preferred_encodings,
+                req_path,
+                query.clone(),
+                maybe_range_begin,
+            ) {
+                return Ok(response);
+            }
+
+            if let Some(response) = self
+                .responses
+                .get(&RequestKey::new(req_path, None, maybe_range_begin).with_query(query.clone()))
+            {
+                if response.response.body().len() > ASSET_CHUNK_SIZE {
+                    if let Some(first_chunk_response) = self
+                        .responses
+                        .get(&RequestKey::new(req_path, None, Some(0)).with_query(query))
+                    {
+                        return Ok(first_chunk_response);
+                    }
+                } else {
+                    return Ok(response);
+                }
+            }
+        }
+
+        let mut url_scopes = req_path.split('/').collect::<Vec<_>>();
+        url_scopes.pop();
+
+        let min_scopes_len = self
+            .fallback_max_depth
+            .map(|max_depth| url_scopes.len().saturating_sub(max_depth))
+            .unwrap_or_default();
+
+        while url_scopes.len() > min_scopes_len {
+            let mut scope = url_scopes.join("/");
+            scope.push('/');
+
+            if let Some(response) =
+                self.get_fallback_for_scope(request, &preferred_encodings, &scope)
+            {
+                return Ok(response);
+            }
+
+            scope.pop();
+
+            if let Some(response) =
+                self.get_fallback_for_scope(request, &preferred_encodings, &scope)
+            {
+                return Ok(response);
+            }
+
+            url_scopes.pop();
+        }
+        Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+            request_url: req_path.to_string(),
+        })
+    }
+
+    fn certify_asset_impl<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        asset_config: Option<&NormalizedAssetConfig>,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+    ) -> AssetCertificationResult {
+        match asset_config {
+            Some(NormalizedAssetConfig::Pattern {
+                content_type,
+                headers,
+                certified_query_parameters,
+                ..
+            }) => {
+                self.insert_static_asset(
+                    asset,
+                    content_type.clone(),
+                    headers.clone(),
+                    encoding,
+                    etag.clone(),
+                    false,
+                    false,
+                    certified_query_parameters.clone(),
+                )?;
+            }
+            Some(NormalizedAssetConfig::File {
+                content_type,
+                headers,
+                fallback_for,
+                aliased_by,
+                addressable,
+                skip_certification,
+                upgrade,
+                certified_query_parameters,
+                allowed_methods,
+                ..
+            }) => {
+                if encoding.is_none() && !allowed_methods.is_empty() {
+                    let asset_urls = addressable
+                        .then_some(asset.url.as_ref())
+                        .into_iter()
+                        .chain(aliased_by.iter().map(String::as_str));
+                    for asset_url in asset_urls {
+                        self.asset_allowed_methods
+                            .insert(asset_url.to_string(), allowed_methods.clone());
+                        self.update_method_not_allowed_response(asset_url)?;
+                    }
+                }
+
+                if encoding.is_none() {
+                    for alias in aliased_by {
+                        self.alias_owners
+                            .insert(alias.clone(), asset.path.to_string());
+                    }
+                    for AssetFallbackConfig { scope, .. } in fallback_for {
+                        self.fallback_scope_owners
+                            .insert(scope.clone(), asset.path.to_string());
+                    }
+                }
+
+                if *addressable {
+                    self.insert_static_asset(
+                        asset.clone(),
+                        content_type.clone(),
+                        headers.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                        *upgrade,
+                        certified_query_parameters.clone(),
+                    )?;
+                }
+
+                for fallback_for in fallback_for {
+                    self.insert_fallback_asset(
+                        asset.clone(),
+                        content_type.clone(),
+                        headers.clone(),
+                        fallback_for.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                        *upgrade,
+                    )?;
+                }
+
+                for aliased_by in aliased_by.iter() {
+                    let mut aliased_asset = asset.clone();
+                    aliased_asset.url = Cow::Owned(aliased_by.clone());
+
+                    self.insert_static_asset(
+                        aliased_asset,
+                        content_type.clone(),
+                        headers.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                        *upgrade,
+                        certified_query_parameters.clone(),
+                    )?;
+                }
+            }
+            _ => {
+                self.insert_static_asset(
+                    asset,
+                    None,
+                    vec![],
+                    encoding,
+                    etag.clone(),
+                    false,
+                    false,
+                    vec![],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete_asset_impl<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        asset_config: Option<&NormalizedAssetConfig>,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+    ) -> AssetCertificationResult {
+        match asset_config {
+            Some(NormalizedAssetConfig::Pattern {
+                content_type,
+                headers,
+                certified_query_parameters,
+                ..
+            }) => {
+                self.delete_static_asset(
+                    asset,
+                    content_type.clone(),
+                    headers.clone(),
+                    encoding,
+                    etag.clone(),
+                    false,
+                    certified_query_parameters.clone(),
+                )?;
+            }
+            Some(NormalizedAssetConfig::File {
+                content_type,
+                headers,
+                fallback_for,
+                aliased_by,
+                addressable,
+                skip_certification,
+                certified_query_parameters,
+                allowed_methods,
+                ..
+            }) => {
+                if encoding.is_none() && !allowed_methods.is_empty() {
+                    let asset_urls = addressable
+                        .then_some(asset.url.as_ref())
+                        .into_iter()
+                        .chain(aliased_by.iter().map(String::as_str));
+                    for asset_url in asset_urls {
+                        self.asset_allowed_methods.remove(asset_url);
+                        self.update_method_not_allowed_response(asset_url)?;
+                    }
+                }
+
+                if encoding.is_none() {
+                    for alias in aliased_by {
+                        if self
+                            .alias_owners
+                            .get(alias)
+                            .is_some_and(|owner| *owner == asset.path)
+                        {
+                            self.alias_owners.remove(alias);
+                        }
+                    }
+                    for AssetFallbackConfig { scope, .. } in fallback_for {
+                        if self
+                            .fallback_scope_owners
+                            .get(scope)
+                            .is_some_and(|owner| *owner == asset.path)
+                        {
+                            self.fallback_scope_owners.remove(scope);
+                        }
+                    }
+                }
+
+                if *addressable {
+                    self.delete_static_asset(
+                        asset.clone(),
+                        content_type.clone(),
+                        headers.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                        certified_query_parameters.clone(),
+                    )?;
+                }
+
+                for fallback_for in fallback_for {
+                    self.delete_fallback_asset(
+                        asset.clone(),
+                        content_type.clone(),
+                        headers.clone(),
+                        fallback_for.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                    )?;
+                }
+
+                for aliased_by in aliased_by.iter() {
+                    let mut aliased_asset = asset.clone();
+                    aliased_asset.url = Cow::Owned(aliased_by.clone());
+
+                    self.delete_static_asset(
+                        aliased_asset,
+                        content_type.clone(),
+                        headers.clone(),
+                        encoding,
+                        etag.clone(),
+                        *skip_certification,
+                        certified_query_parameters.clone(),
+                    )?;
+                }
+            }
+            _ => {
+                self.delete_static_asset(
+                    asset,
+                    None,
+                    vec![],
+                    encoding,
+                    etag.clone(),
+                    false,
+                    vec![],
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_static_asset<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        content_type: Option<String>,
+        additional_headers: Vec<(String, String)>,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+        skip_certification: bool,
+        upgrade: bool,
+        certified_query_parameters: Vec<(String, String)>,
+    ) -> AssetCertificationResult<()> {
+        let additional_headers =
+            self.with_router_headers(additional_headers, asset.content.len() > ASSET_CHUNK_SIZE);
+        let asset_url = asset.url.to_string();
+        let total_length = asset.content.len();
+        let query = Self::certified_query(&certified_query_parameters);
+
+        if !certified_query_parameters.is_empty() {
+            let names = certified_query_parameters
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            let path_names = self
+                .certified_query_parameter_names
+                .entry(asset_url.clone())
+                .or_default();
+            if !path_names.contains(&names) {
+                path_names.push(names);
+            }
+        }
+
+        self.delete_replaced_response(
+            &RequestKey::new(&asset_url, encoding_str(encoding), None).with_query(query.clone()),
+        );
+
+        if total_length > ASSET_CHUNK_SIZE {
+            let mut range_begin = 0;
+            while range_begin < asset.content.len() {
+                let response = self.prepare_static_asset(
+                    asset.clone(),
+                    content_type.clone(),
+                    additional_headers.clone(),
+                    encoding,
+                    Some(range_begin),
+                    etag.clone(),
+                    skip_certification,
+                    &certified_query_parameters,
+                )?;
+                let response = Self::upgrade_response(response, upgrade);
+                self.insert_tree_entry(&response.tree_entry);
+                self.responses.insert(
+                    RequestKey::new(&asset_url, encoding_str(encoding), Some(range_begin))
+                        .with_query(query.clone()),
+                    response,
+                );
+                range_begin += ASSET_CHUNK_SIZE;
+            }
+        }
+
+        let response = self.prepare_static_asset(
+            asset,
+            content_type,
+            additional_headers,
+            encoding,
+            None,
+            etag,
+            skip_certification,
+            &certified_query_parameters,
+        )?;
+        let response = Self::upgrade_response(response, upgrade);
+
+        self.insert_tree_entry(&response.tree_entry);
+        if let Some(not_modified) = &response.not_modified {
+            self.insert_tree_entry(&not_modified.tree_entry);
+        }
+        self.responses.insert(
+            RequestKey::new(&asset_url, encoding_str(encoding), None).with_query(query),
+            response,
+        );
+        self.insert_cors_preflight_response(&asset_url)?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn delete_static_asset<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        content_type: Option<String>,
+        additional_headers: Vec<(String, String)>,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+        skip_certification: bool,
+        certified_query_parameters: Vec<(String, String)>,
+    ) -> AssetCertificationResult<()> {
+        let additional_headers =
+            self.with_router_headers(additional_headers, asset.content.len() > ASSET_CHUNK_SIZE);
+        let asset_url = asset.url.to_string();
+        let query = Self::certified_query(&certified_query_parameters);
+        let response = self.prepare_static_asset(
+            asset,
+            content_type,
+            additional_headers,
+            encoding,
+            None,
+            etag,
+            skip_certification,
+            &certified_query_parameters,
+        )?;
+
+        self.delete_tree_entry(&response.tree_entry);
+        if let Some(not_modified) = &response.not_modified {
+            self.delete_tree_entry(&not_modified.tree_entry);
+        }
+        self.responses.remove(
+            &RequestKey::new(&asset_url, encoding_str(encoding), None).with_query(query.clone()),
+        );
+
+        if response.response.body().len() > ASSET_CHUNK_SIZE {
+            // Delete also chunks.
+            let mut range_begin: usize = 0;
+            while range_begin < response.response.body().len() {
+                self.responses.remove(
+                    &RequestKey::new(&asset_url, encoding_str(encoding), Some(range_begin))
+                        .with_query(query.clone()),
+                );
+                range_begin += ASSET_CHUNK_SIZE;
+            }
+        }
+
+        if !certified_query_parameters.is_empty() {
+            self.prune_certified_query_parameter_names(&asset_url, &certified_query_parameters);
+        }
+
+        if encoding.is_none() {
+            if let Some(cors_preflight_response) = self.cors_preflight_responses.remove(&asset_url)
+            {
+                self.delete_tree_entry(&cors_preflight_response.tree_entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stops matching the names of the given certified query parameters against requests to
+    /// the given path, once no remaining response on that path is certified for them.
+    fn prune_certified_query_parameter_names(
+        &mut self,
+        path: &str,
+        certified_query_parameters: &[(String, String)],
+    ) {
+        let names = certified_query_parameters
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        let is_still_certified = self.responses.keys().any(|key| {
+            key.path == path
+                && key.query.as_deref().is_some_and(|query| {
+                    query
+                        .split('&')
+                        .map(|fragment| fragment.split('=').next().unwrap_or_default())
+                        .eq(names.iter().copied())
+                })
+        });
+        if is_still_certified {
+            return;
+        }
+
+        if let Some(path_names) = self.certified_query_parameter_names.get_mut(path) {
+            path_names.retain(|path_names| !path_names.iter().eq(names.iter()));
+
+            if path_names.is_empty() {
+                self.certified_query_parameter_names.remove(path);
+            }
+        }
+    }
+
+    fn insert_cors_preflight_response(&mut self, path: &str) -> AssetCertificationResult {
+        let Some(cors) = &self.cors else {
+            return Ok(());
+        };
+        if self.cors_preflight_responses.contains_key(path) {
+            return Ok(());
+        }
+
+        let headers = self.with_build_id_header(cors.preflight_headers());
+        let response = HttpResponse::no_content(headers).build();
+        let cert_response = self.prepare_method_response(path, &Method::OPTIONS, response)?;
+
+        self.insert_tree_entry(&cert_response.tree_entry);
+        self.cors_preflight_responses
+            .insert(path.to_string(), cert_response);
+
+        Ok(())
+    }
+
+    /// Removes the response for the given request key, along with the ranges of its body, and
+    /// deletes their certification, so that a response that is certified again does not keep
+    /// passing verification with its previous content.
+    fn delete_replaced_response(&mut self, request_key: &RequestKey) {
+        let Some(cert_response) = self.responses.remove(request_key) else {
+            return;
+        };
+        self.delete_response_tree_entries(&cert_response);
+
+        let body_length = cert_response.response.body().len();
+        if body_length > ASSET_CHUNK_SIZE {
+            let mut range_begin = 0;
+            while range_begin < body_length {
+                let mut range_request_key = request_key.clone();
+                range_request_key.range_begin = Some(range_begin);
+                if let Some(range_response) = self.responses.remove(&range_request_key) {
+                    self.delete_response_tree_entries(&range_response);
+                }
+                range_begin += ASSET_CHUNK_SIZE;
+            }
+        }
+    }
+
+    /// Removes the [forwarded redirects](AssetRouter::with_trusted_forwarded_hosts) from the
+    /// given path, and deletes their certification.
+    fn delete_replaced_forwarded_redirects(&mut self, from: &str) {
+        if let Some(forwarded_responses) = self.forwarded_redirect_responses.remove(from) {
+            for forwarded_response in forwarded_responses.values() {
+                self.delete_tree_entry(&forwarded_response.tree_entry);
+            }
+        }
+    }
+
+    fn delete_response_tree_entries(&self, cert_response: &CertifiedAssetResponse<'content>) {
+        self.delete_tree_entry(&cert_response.tree_entry);
+        if let Some(not_modified) = &cert_response.not_modified {
+            self.delete_tree_entry(&not_modified.tree_entry);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_static_asset<'path>(
+        &self,
+        asset: Asset<'content, 'path>,
+        content_type: Option<String>,
+        mut additional_headers: Vec<(String, String)>,
+        encoding: Option<AssetEncoding>,
+        range_begin: Option<usize>,
+        etag: Option<String>,
+        skip_certification: bool,
+        certified_query_parameters: &[(String, String)],
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let asset_url = asset.url.to_string();
+
+        if let Some(etag) = etag.clone() {
+            additional_headers.push((http::header::ETAG.to_string(), etag));
+        }
+
+        let not_modified = match etag {
+            Some(_) if range_begin.is_none() && asset.content.len() <= ASSET_CHUNK_SIZE => {
+                Some(Box::new(self.prepare_not_modified(
+                    asset_url.clone(),
+                    HttpCertificationPath::exact(asset_url.clone()),
+                    additional_headers.clone(),
+                    skip_certification,
+                    certified_query_parameters,
+                )?))
+            }
+            _ => None,
+        };
+
+        let (response, certification) = self.prepare_asset_response_and_certification(
+            asset,
+            additional_headers,
+            content_type,
+            encoding,
+            range_begin,
+            None,
+            skip_certification,
+            certified_query_parameters,
+        )?;
+
+        let tree_entry =
+            HttpCertificationTreeEntry::new(HttpCertificationPath::exact(asset_url), certification);
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry,
+            not_modified,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn insert_fallback_asset<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        content_type: Option<String>,
+        additional_headers: Vec<(String, String)>,
+        fallback_for: AssetFallbackConfig,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+        skip_certification: bool,
+        upgrade: bool,
+    ) -> AssetCertificationResult<()> {
+        // fallbacks are never served in chunks
+        let additional_headers = self.with_router_headers(additional_headers, false);
+        let response = self.prepare_fallback_asset(
+            asset,
+            additional_headers,
+            content_type,
+            fallback_for.clone(),
+            encoding,
+            etag,
+            skip_certification,
+        )?;
+        let response = Self::upgrade_response(response, upgrade);
+
+        let request_key = RequestKey::new(&fallback_for.scope, encoding_str(encoding), None);
+        if let Some(previous_response) = self.fallback_responses.remove(&request_key) {
+            self.delete_response_tree_entries(&previous_response);
+        }
+        self.insert_tree_entry(&response.tree_entry);
+        if let Some(not_modified) = &response.not_modified {
+            self.insert_tree_entry(&not_modified.tree_entry);
+        }
+        self.vary_accept_fallbacks.remove(&request_key);
+        self.fallback_responses.insert(request_key, response);
+
+        if self
+            .media_type_fallback_responses
+            .contains_key(&fallback_for.scope)
+        {
+            self.set_fallbacks_vary_accept(&fallback_for.scope, true)?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn delete_fallback_asset<'path>(
+        &mut self,
+        asset: Asset<'content, 'path>,
+        content_type: Option<String>,
+        additional_headers: Vec<(String, String)>,
+        fallback_for: AssetFallbackConfig,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+        skip_certification: bool,
+    ) -> AssetCertificationResult<()> {
+        // fallbacks are never served in chunks
+        let additional_headers = self.with_router_headers(additional_headers, false);
+        let response = self.prepare_fallback_asset(
+            asset,
+            additional_headers,
+            content_type,
+            fallback_for.clone(),
+            encoding,
+            etag,
+            skip_certification,
+        )?;
+
+        // a fallback that was recertified with `Accept` in its `Vary` header is deleted as it
+        // was recertified
+        let request_key = RequestKey::new(&fallback_for.scope, encoding_str(encoding), None);
+        let response = if self.vary_accept_fallbacks.remove(&request_key) {
+            self.fallback_responses
+                .remove(&request_key)
+                .unwrap_or(response)
+        } else {
+            response
+        };
+
+        self.delete_tree_entry(&response.tree_entry);
+        if let Some(not_modified) = &response.not_modified {
+            self.delete_tree_entry(&not_modified.tree_entry);
+        }
+        self.fallback_responses.remove(&request_key);
+        Ok(())
+    }
+
+    fn prepare_fallback_asset<'path>(
+        &self,
+        asset: Asset<'content, 'path>,
+        mut additional_headers: Vec<(String, String)>,
+        content_type: Option<String>,
+        fallback_for: AssetFallbackConfig,
+        encoding: Option<AssetEncoding>,
+        etag: Option<String>,
+        skip_certification: bool,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        if let Some(etag) = etag.clone() {
+            additional_headers.push((http::header::ETAG.to_string(), etag));
+        }
+
+        let not_modified = match etag {
+            Some(_) if asset.content.len() <= ASSET_CHUNK_SIZE => {
+                Some(Box::new(self.prepare_not_modified(
+                    asset.url.to_string(),
+                    HttpCertificationPath::wildcard(fallback_for.scope.clone()),
+                    additional_headers.clone(),
+                    skip_certification,
+                    &[],
+                )?))
+            }
+            _ => None,
+        };
+
+        let (response, certification) = self.prepare_asset_response_and_certification(
+            asset,
+            additional_headers,
+            content_type,
+            encoding,
+            None,
+            fallback_for.status_code,
+            skip_certification,
+            &[],
+        )?;
+
+        let tree_entry = HttpCertificationTreeEntry::new(
+            HttpCertificationPath::wildcard(fallback_for.scope.clone()),
+            certification,
+        );
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry,
+            not_modified,
+        })
+    }
+
+    fn prepare_not_modified(
+        &self,
+        url: String,
+        path: HttpCertificationPath<'content>,
+        additional_headers: Vec<(String, String)>,
+        skip_certification: bool,
+        certified_query_parameters: &[(String, String)],
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        // the `If-None-Match` header that selects this response is certified, so that it can't
+        // be served in place of the asset for an unconditional request
+        let certified_request_headers = additional_headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(http::header::ETAG.as_str()))
+            .map(|(_, etag)| (http::header::IF_NONE_MATCH.to_string(), etag.clone()))
+            .into_iter()
+            .collect();
+        let (response, certification) = self.prepare_response_and_certification(
+            url,
+            StatusCode::NOT_MODIFIED,
+            Cow::Owned(vec![]),
+            Self::not_modified_headers(additional_headers),
+            certified_request_headers,
+            certified_query_parameters,
+            skip_certification,
+        )?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(path, certification),
+            not_modified: None,
+        })
+    }
+
+    fn not_modified_headers(headers: Vec<(String, String)>) -> Vec<(String, String)> {
+        headers
+            .into_iter()
+            .filter(|(name, _)| {
+                NOT_MODIFIED_HEADER_NAMES
+                    .iter()
+                    .any(|header_name| name.eq_ignore_ascii_case(header_name))
+                    || name
+                        .get(..15)
+                        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("access-control-"))
+            })
+            .collect()
+    }
+
+    fn insert_redirect(
+        &mut self,
+        from: String,
+        to: String,
+        kind: AssetRedirectKind,
+        additional_headers: Vec<(String, String)>,
+    ) -> AssetCertificationResult<()> {
+        let additional_headers =
+            self.with_cors_headers(self.with_build_id_header(additional_headers));
+        let forwarded_responses =
+            self.prepare_forwarded_redirects(&from, &to, &kind, &additional_headers)?;
+        let response = self.prepare_redirect(from.clone(), to, kind, additional_headers)?;
+
+        let request_key = RequestKey::new(&from, None, None);
+        self.delete_replaced_response(&request_key);
+        self.delete_replaced_forwarded_redirects(&from);
+        self.insert_tree_entry(&response.tree_entry);
+
+        self.responses.insert(request_key, response);
+
+        if !forwarded_responses.is_empty() {
+            for forwarded_response in forwarded_responses.values() {
+                self.insert_tree_entry(&forwarded_response.tree_entry);
+            }
+            self.forwarded_redirect_responses
+                .insert(from, forwarded_responses);
+        }
+
+        Ok(())
+    }
+
+    fn delete_redirect(
+        &mut self,
+        from: String,
+        to: String,
+        kind: AssetRedirectKind,
+        addtional_headers: Vec<(String, String)>,
+    ) -> AssetCertificationResult<()> {
+        let addtional_headers =
+            self.with_cors_headers(self.with_build_id_header(addtional_headers));
+        let response = self.prepare_redirect(from.clone(), to, kind, addtional_headers)?;
+
+        self.delete_tree_entry(&response.tree_entry);
+        self.responses.remove(&RequestKey::new(&from, None, None));
+
+        if let Some(forwarded_responses) = self.forwarded_redirect_responses.remove(&from) {
+            for forwarded_response in forwarded_responses.values() {
+                self.delete_tree_entry(&forwarded_response.tree_entry);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prepares the redirects with an absolute `Location` for each of the
+    /// [trusted forwarded hosts](AssetRouter::with_trusted_forwarded_hosts), keyed by the
+    /// forwarded protocol and host that they are served for.
+    fn prepare_forwarded_redirects(
+        &self,
+        from: &str,
+        to: &str,
+        kind: &AssetRedirectKind,
+        additional_headers: &[(String, String)],
+    ) -> AssetCertificationResult<HashMap<(String, String), CertifiedAssetResponse<'content>>> {
+        let mut responses = HashMap::new();
+        if !to.starts_with('/') || to.starts_with("//") {
+            return Ok(responses);
+        }
+
+        for host in &self.trusted_forwarded_hosts {
+            for proto in FORWARDED_PROTOS {
+                let mut headers = vec![("location".to_string(), format!("{proto}://{host}{to}"))];
+                headers.extend(additional_headers.iter().cloned());
+
+                let (response, certification) = self.prepare_response_and_certification(
+                    from.to_string(),
+                    Self::redirect_status_code(kind),
+                    Cow::Owned(vec![]),
+                    headers,
+                    vec![
+                        (X_FORWARDED_HOST_HEADER_NAME.to_string(), host.clone()),
+                        (X_FORWARDED_PROTO_HEADER_NAME.to_string(), proto.to_string()),
+                    ],
+                    &[],
+                    false,
+                )?;
+
+                responses.insert(
+                    (proto.to_string(), host.clone()),
+                    CertifiedAssetResponse {
+                        response,
+                        tree_entry: HttpCertificationTreeEntry::new(
+                            HttpCertificationPath::exact(from.to_string()),
+                            certification,
+                        ),
+                        not_modified: None,
+                    },
+                );
+            }
+        }
+
+        Ok(responses)
+    }
+
+    fn get_forwarded_redirect_response_for_request(
+        &self,
+        req_path: &str,
+        request: &HttpRequest,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        if request.method() != Method::GET {
+            return None;
+        }
+
+        let forwarded_responses = self.forwarded_redirect_responses.get(req_path)?;
+        let header_value = |header_name: &str| {
+            request
+                .headers()
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header_name))
+                .map(|(_, value)| value.clone())
+        };
+        let proto = header_value(X_FORWARDED_PROTO_HEADER_NAME)?;
+        let host = header_value(X_FORWARDED_HOST_HEADER_NAME)?;
+
+        forwarded_responses.get(&(proto, host))
+    }
+
+    fn prepare_redirect(
+        &self,
+        from: String,
+        to: String,
+        kind: AssetRedirectKind,
+        addtional_headers: Vec<(String, String)>,
+    ) -> AssetCertificationResult<CertifiedAssetResponse<'content>> {
+        let mut headers = vec![("location".to_string(), to)];
+        headers.extend(addtional_headers);
+
+        let (response, certification) = self.prepare_response_and_certification(
+            from.clone(),
+            Self::redirect_status_code(&kind),
+            Cow::Owned(vec![]),
+            headers,
+            vec![],
+            &[],
+            false,
+        )?;
+
+        Ok(CertifiedAssetResponse {
+            response,
+            tree_entry: HttpCertificationTreeEntry::new(
+                HttpCertificationPath::exact(from),
+                certification,
+            ),
+            not_modified: None,
+        })
+    }
+
+    fn redirect_status_code(kind: &AssetRedirectKind) -> StatusCode {
+        match kind {
+            AssetRedirectKind::Permanent => StatusCode::MOVED_PERMANENTLY,
+            AssetRedirectKind::Temporary => StatusCode::TEMPORARY_REDIRECT,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_asset_response_and_certification<'path>(
+        &self,
+        asset: Asset<'content, 'path>,
+        additional_headers: Vec<(String, String)>,
+        content_type: Option<String>,
+        encoding: Option<AssetEncoding>,
+        range_begin: Option<usize>,
+        status_code: Option<StatusCode>,
+        skip_certification: bool,
+        certified_query_parameters: &[(String, String)],
+    ) -> AssetCertificationResult<(HttpResponse<'content>, HttpCertification)> {
+        let mut content = asset.content;
+        let mut status_code = status_code.unwrap_or(StatusCode::OK);
+        let mut headers = vec![];
+        headers.extend(additional_headers);
+
+        if let Some(content_type) = content_type {
+            headers.push(("content-type".to_string(), content_type));
+        }
+
+        if let Some(encoding) = encoding {
+            headers.push(("content-encoding".to_string(), encoding.to_string()));
+        }
+
+        let mut request_headers = vec![];
+        if let Some(range_begin) = range_begin {
+            let total_length = content.len();
+            let range_end = cmp::min(range_begin + ASSET_CHUNK_SIZE, total_length) - 1;
+            content = match content {
+                Cow::Borrowed(content) => Cow::Borrowed(&content[range_begin..(range_end + 1)]),
+                Cow::Owned(content) => Cow::Owned(content[range_begin..(range_end + 1)].to_vec()),
+            };
+            status_code = StatusCode::PARTIAL_CONTENT;
+            headers.push((
+                http::header::CONTENT_RANGE.to_string(),
+                format!("bytes {range_begin}-{range_end}/{total_length}"),
+            ));
+
+            // The `Range` request header will not be sent with the first request,
+            // so we don't include it in certification for the first chunk.
+            if range_begin != 0 {
+                request_headers.push((
+                    http::header::RANGE.to_string(),
+                    format!("bytes={range_begin}-"),
+                ));
+            }
+        };
+
+        self.prepare_response_and_certification(
+            asset.url.to_string(),
+            status_code,
+            content,
+            headers,
+            request_headers,
+            certified_query_parameters,
+            skip_certification,
+        )
+    }
+
+    fn prepare_response_and_certification(
+        &self,
+        url: String,
+        status_code: StatusCode,
+        body: Cow<'content, [u8]>,
+        additional_response_headers: Vec<(String, String)>,
+        certified_request_headers: Vec<(String, String)>,
+        certified_query_parameters: &[(String, String)],
+        skip_certification: bool,
+    ) -> AssetCertificationResult<(HttpResponse<'content>, HttpCertification)> {
+        // A `304 Not Modified` response must not include a `Content-Length` header
+        // unless it matches the length of the content that would have been sent
+        // with a `200 OK` response.
+        let mut headers = if status_code == StatusCode::NOT_MODIFIED {
+            vec![]
+        } else {
+            vec![("content-length".to_string(), body.len().to_string())]
+        };
+
+        headers.extend(additional_response_headers);
+        let certified_request_headers = self.with_certified_host_header(certified_request_headers);
+
+        if skip_certification {
+            headers.push((
+                CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+                DefaultCelBuilder::skip_certification().to_string(),
+            ));
+
+            let response = HttpResponse::builder()
+                .with_status_code(status_code)
+                .with_body(body)
+                .with_headers(headers)
+                .build();
+
+            return Ok((response, HttpCertification::skip()));
+        }
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(
+                certified_request_headers
+                    .iter()
+                    .map(|(s, _)| s.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .with_request_query_parameters(
+                certified_query_parameters
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<&str>>(),
+            )
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+        let cel_expr_str = cel_expr.to_string();
+        headers.push((CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(), cel_expr_str));
+
+        let url = match Self::certified_query(certified_query_parameters) {
+            Some(query) => format!("{url}?{query}"),
+            None => url,
+        };
+        let request = HttpRequest::get(url)
+            .with_headers(certified_request_headers.clone())
+            .build();
+
+        let response = HttpResponse::builder()
+            .with_status_code(status_code)
+            .with_body(body)
+            .with_headers(headers)
+            .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None)?;
+
+        Ok((response, certification))
+    }
+
+    fn get_encoded_asset(
+        &self,
+        preferred_encodings: &[&str],
+        url: &str,
+        query: Option<String>,
+        maybe_range_begin: Option<usize>,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        for encoding in preferred_encodings {
+            if let Some(response) = self.responses.get(
+                &RequestKey::new(url, Some(encoding.to_string()), maybe_range_begin)
+                    .with_query(query.clone()),
+            ) {
+                if response.response.body().len() > ASSET_CHUNK_SIZE {
+                    if let Some(first_chunk_response) = self.responses.get(
+                        &RequestKey::new(url, Some(encoding.to_string()), Some(0))
+                            .with_query(query.clone()),
+                    ) {
+                        return Some(first_chunk_response);
+                    } else {
+                        return None;
+                    }
+                } else {
+                    return Some(response);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the fallback for the given scope, choosing between the generic fallback and
+    /// any fallbacks certified with
+    /// [certify_media_type_fallback](AssetRouter::certify_media_type_fallback)
+    /// according to the request's `Accept` header.
+    fn get_fallback_for_scope(
+        &self,
+        request: &HttpRequest,
+        preferred_encodings: &[&str],
+        scope: &str,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        let generic_fallback = self
+            .get_encoded_fallback_asset(preferred_encodings, scope)
+            .or_else(|| {
+                self.fallback_responses
+                    .get(&RequestKey::new(scope, None, None))
+            });
+        let Some(media_type_fallbacks) = self.media_type_fallback_responses.get(scope) else {
+            return generic_fallback;
+        };
+
+        let generic_media_type = generic_fallback.and_then(|generic_fallback| {
+            generic_fallback
+                .response
+                .headers()
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(http::header::CONTENT_TYPE.as_str()))
+                .map(|(_, value)| value.as_str())
+        });
+        let offered = generic_media_type
+            .into_iter()
+            .chain(
+                media_type_fallbacks
+                    .iter()
+                    .map(|(media_type, _)| media_type.as_str()),
+            )
+            .collect::<Vec<_>>();
+        let skipped = offered.len() - media_type_fallbacks.len();
+
+        let preferred_index = request
+            .preferred_media_type(&offered)
+            .and_then(|preferred| offered.iter().position(|offered| *offered == preferred));
+        match preferred_index {
+            Some(index) if index >= skipped => Some(&media_type_fallbacks[index - skipped].1),
+            _ => generic_fallback
+                .or_else(|| media_type_fallbacks.first().map(|(_, fallback)| fallback)),
+        }
+    }
+
+    fn get_encoded_fallback_asset(
+        &self,
+        preferred_encodings: &[&str],
+        scope: &str,
+    ) -> Option<&CertifiedAssetResponse<'content>> {
+        for encoding in preferred_encodings {
+            if let Some(response) = self.fallback_responses.get(&RequestKey::new(
+                scope,
+                Some(encoding.to_string()),
+                None,
+            )) {
+                return Some(response);
+            }
+        }
+
+        None
+    }
+
+    /// Returns the request's query parameters that are certified for any asset on the given
+    /// path, once for each set of certified parameter names, filtered and joined in the same
+    /// way as they are included in the request hash.
+    fn get_certified_queries(
+        &self,
+        path: &str,
+        request: &HttpRequest,
+    ) -> AssetCertificationResult<Vec<String>> {
+        let Some(names) = self.certified_query_parameter_names.get(path) else {
+            return Ok(vec![]);
+        };
+        let Some(query) = request.get_query()? else {
+            return Ok(vec![]);
+        };
+
+        Ok(names
+            .iter()
+            .map(|names| {
+                query
+                    .split('&')
+                    .filter(|fragment| {
+                        let name = fragment.split('=').next().unwrap_or_default();
+                        names.iter().any(|n| n.eq_ignore_ascii_case(name))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("&")
+            })
+            .filter(|query| !query.is_empty())
+            .collect())
+    }
+
+    fn certified_query(certified_query_parameters: &[(String, String)]) -> Option<String> {
+        if certified_query_parameters.is_empty() {
+            return None;
+        }
+
+        Some(
+            certified_query_parameters
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect::<Vec<_>>()
+                .join("&"),
+        )
+    }
+
+    fn get_if_none_match_header<'a>(request: &'a HttpRequest) -> Option<&'a str> {
+        for (name, value) in request.headers().iter() {
+            if name.eq_ignore_ascii_case(http::header::IF_NONE_MATCH.as_str()) {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn is_not_modified(request: &HttpRequest, not_modified_response: &HttpResponse) -> bool {
+        let Some(if_none_match) = Self::get_if_none_match_header(request) else {
+            return false;
+        };
+
+        let Some((_, etag)) = not_modified_response
+            .headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(http::header::ETAG.as_str()))
+        else {
+            return false;
+        };
+
+        // the `If-None-Match` header is certified with the exact value of the ETag, so the
+        // `304 Not Modified` response would not pass verification for any other value
+        if_none_match == etag
+    }
+
+    fn upgrade_response(
+        mut cert_response: CertifiedAssetResponse<'content>,
+        upgrade: bool,
+    ) -> CertifiedAssetResponse<'content> {
+        // the upgrade flag is not certified, so it can be set after certification
+        if upgrade {
+            let (status_code, headers, body, _) = cert_response.response.into_parts();
+            cert_response.response =
+                HttpResponse::from_parts(status_code, headers, body, Some(true));
+            cert_response.not_modified = cert_response
+                .not_modified
+                .map(|not_modified| Box::new(Self::upgrade_response(*not_modified, upgrade)));
+        }
+
+        cert_response
+    }
+
+    fn with_router_headers(
+        &self,
+        additional_headers: Vec<(String, String)>,
+        range_eligible: bool,
+    ) -> Vec<(String, String)> {
+        let additional_headers = self.with_accept_ranges_header(additional_headers, range_eligible);
+        let additional_headers = self.with_build_id_header(additional_headers);
+        let additional_headers = self.with_bounded_cache_control_header(additional_headers);
+        let additional_headers = self.with_client_hints_headers(additional_headers);
+
+        self.with_cors_headers(additional_headers)
+    }
+
+    /// Returns the given headers of a [variant](AssetRouter::certify_variants) response
+    /// selected by the given header, with the selecting header added to the `Vary` header, and
+    /// the build ID, client hints and CORS headers added.
+    fn with_variant_headers(
+        &self,
+        header_name: &str,
+        additional_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        let additional_headers = with_vary_token(additional_headers, header_name);
+        let additional_headers = self.with_build_id_header(additional_headers);
+        let additional_headers = self.with_client_hints_headers(additional_headers);
+
+        self.with_cors_headers(additional_headers)
+    }
+
+    fn with_client_hints_headers(
+        &self,
+        mut additional_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        let Some(client_hints) = &self.client_hints else {
+            return additional_headers;
+        };
+
+        for (name, value) in client_hints.response_headers() {
+            let has_header = additional_headers
+                .iter()
+                .any(|(additional_name, _)| additional_name.eq_ignore_ascii_case(&name));
+
+            if !has_header {
+                additional_headers.push((name, value));
+            }
+        }
+
+        additional_headers
+    }
+
+    fn with_bounded_cache_control_header(
+        &self,
+        additional_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        if self.max_cache_age_secs.is_none() {
+            return additional_headers;
+        }
+
+        additional_headers
+            .into_iter()
+            .map(|(name, value)| {
+                if name.eq_ignore_ascii_case(http::header::CACHE_CONTROL.as_str()) {
+                    let value = self.bounded_cache_control(&value).unwrap_or(value);
+
+                    (name, value)
+                } else {
+                    (name, value)
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the given `Cache-Control` value with its `max-age` and `s-maxage` directives
+    /// [bounded](AssetRouter::bounded_max_age), or `None` if no directive had to be bounded.
+    fn bounded_cache_control(&self, cache_control: &str) -> Option<String> {
+        let mut is_bounded = false;
+
+        let directives = cache_control
+            .split(',')
+            .map(|directive| {
+                let directive = directive.trim();
+                let Some((name, max_age)) = directive.split_once('=') else {
+                    return directive.to_string();
+                };
+                let is_max_age = name.trim().eq_ignore_ascii_case("max-age")
+                    || name.trim().eq_ignore_ascii_case("s-maxage");
+                let Some(max_age) = max_age.trim().parse::<u64>().ok().filter(|_| is_max_age)
+                else {
+                    return directive.to_string();
+                };
+
+                let bounded_max_age = self.bounded_max_age(max_age);
+                if bounded_max_age == max_age {
+                    return directive.to_string();
+                }
+
+                is_bounded = true;
+                format!("{}={bounded_max_age}", name.trim())
+            })
+            .collect::<Vec<_>>();
+
+        is_bounded.then(|| directives.join(", "))
+    }
+
+    fn with_cors_headers(
+        &self,
+        mut additional_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        let Some(cors) = &self.cors else {
+            return additional_headers;
+        };
+
+        for (name, value) in cors.response_headers() {
+            let has_header = additional_headers
+                .iter()
+                .any(|(additional_name, _)| additional_name.eq_ignore_ascii_case(&name));
+
+            if !has_header {
+                additional_headers.push((name, value));
+            }
+        }
+
+        additional_headers
+    }
+
+    /// Returns the given path, along with the paths that are rewritten onto it by the
+    /// configured [path rewrites](AssetRouter::with_path_rewrites). A path is only returned if
+    /// the rewrite that maps it onto the given path is the first rewrite that matches it.
+    fn with_rewritten_paths(&self, path: &str) -> Vec<String> {
+        let rewritten_paths =
+            self.path_rewrites
+                .iter()
+                .enumerate()
+                .filter_map(|(index, (from, to))| {
+                    let rewritten_path = format!("{from}{}", path.strip_prefix(to.as_str())?);
+                    let first_rewrite = self
+                        .path_rewrites
+                        .iter()
+                        .position(|(from, _)| rewritten_path.starts_with(from.as_str()));
+
+                    (first_rewrite == Some(index) && rewritten_path != path)
+                        .then_some(rewritten_path)
+                });
+
+        std::iter::once(path.to_string())
+            .chain(rewritten_paths)
+            .collect()
+    }
+
+    /// Returns the path that the given request path is rewritten onto by the first matching
+    /// [path rewrite](AssetRouter::with_path_rewrites), along with that rewrite.
+    fn get_path_rewrite(&self, path: &str) -> Option<(String, &(String, String))> {
+        self.path_rewrites.iter().find_map(|path_rewrite| {
+            let (from, to) = path_rewrite;
+            let rewritten_path = format!("{to}{}", path.strip_prefix(from.as_str())?);
+
+            Some((rewritten_path, path_rewrite))
+        })
+    }
+
+    /// Returns the path of the given tree entry, along with whether it is a wildcard path.
+    fn get_tree_entry_path(tree_entry: &HttpCertificationTreeEntry) -> Option<(String, bool)> {
+        let expr_path = tree_entry.path.to_expr_path();
+        let [_, segments @ .., terminator] = expr_path.as_slice() else {
+            return None;
+        };
+        let path = if segments.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", segments.join("/"))
+        };
+
+        Some((path, terminator == WILDCARD_PATH_TERMINATOR))
+    }
+
+    fn with_tree_entry_path<'a>(
+        tree_entry: &HttpCertificationTreeEntry<'a>,
+        path: String,
+        is_wildcard: bool,
+    ) -> HttpCertificationTreeEntry<'a> {
+        let path = if is_wildcard {
+            HttpCertificationPath::wildcard(path)
+        } else {
+            HttpCertificationPath::exact(path)
+        };
+
+        HttpCertificationTreeEntry::new(path, tree_entry.certification.clone())
+    }
+
+    /// Returns the given tree entry, along with copies of it for the paths that are rewritten
+    /// onto its path by the configured [path rewrites](AssetRouter::with_path_rewrites).
+    fn with_rewritten_tree_entries<'a>(
+        &self,
+        tree_entry: &HttpCertificationTreeEntry<'a>,
+    ) -> Vec<HttpCertificationTreeEntry<'a>> {
+        let mut tree_entries = vec![tree_entry.clone()];
+        if self.path_rewrites.is_empty() {
+            return tree_entries;
+        }
+
+        if let Some((path, is_wildcard)) = Self::get_tree_entry_path(tree_entry) {
+            tree_entries.extend(
+                self.with_rewritten_paths(&path)
+                    .into_iter()
+                    .skip(1)
+                    .map(|path| Self::with_tree_entry_path(tree_entry, path, is_wildcard)),
+            );
+        }
+
+        tree_entries
+    }
+
+    /// Returns the tree entry that certifies the given response for a request whose path was
+    /// rewritten with the given [path rewrite](AssetRouter::with_path_rewrites).
+    fn rewritten_tree_entry<'a>(
+        tree_entry: &HttpCertificationTreeEntry<'a>,
+        (from, to): &(String, String),
+    ) -> HttpCertificationTreeEntry<'a> {
+        match Self::get_tree_entry_path(tree_entry) {
+            Some((path, is_wildcard)) => match path.strip_prefix(to.as_str()) {
+                Some(rest) => {
+                    Self::with_tree_entry_path(tree_entry, format!("{from}{rest}"), is_wildcard)
+                }
+                // a wildcard outside of the rewritten prefix also covers the requested path
+                None => tree_entry.clone(),
+            },
+            None => tree_entry.clone(),
+        }
+    }
+
+    fn insert_tree_entry(&self, tree_entry: &HttpCertificationTreeEntry) {
+        let mut tree = self.tree.borrow_mut();
+        for tree_entry in self.with_rewritten_tree_entries(tree_entry) {
+            tree.insert(&tree_entry);
+        }
+    }
+
+    fn delete_tree_entry(&self, tree_entry: &HttpCertificationTreeEntry) {
+        let mut tree = self.tree.borrow_mut();
+        for tree_entry in self.with_rewritten_tree_entries(tree_entry) {
+            tree.delete(&tree_entry);
+        }
+    }
+
+    fn with_certified_host_header(
+        &self,
+        mut certified_request_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        if let Some(host) = &self.certified_host {
+            certified_request_headers.push((http::header::HOST.to_string(), host.clone()));
+        }
+
+        certified_request_headers
+    }
+
+    fn with_build_id_header(
+        &self,
+        mut additional_headers: Vec<(String, String)>,
+    ) -> Vec<(String, String)> {
+        let has_build_id_header = additional_headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(BUILD_ID_HEADER_NAME));
+
+        if let Some(build_id) = self.build_id.as_ref().filter(|_| !has_build_id_header) {
+            additional_headers.push((BUILD_ID_HEADER_NAME.to_string(), build_id.clone()));
+        }
+
+        additional_headers
+    }
+
+    fn with_accept_ranges_header(
+        &self,
+        mut additional_headers: Vec<(String, String)>,
+        range_eligible: bool,
+    ) -> Vec<(String, String)> {
+        let has_accept_ranges_header = additional_headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(http::header::ACCEPT_RANGES.as_str()));
+
+        if self.accept_ranges && !has_accept_ranges_header {
+            let accept_ranges = if range_eligible { "bytes" } else { "none" };
+            additional_headers.push((
+                http::header::ACCEPT_RANGES.to_string(),
+                accept_ranges.to_string(),
+            ));
+        }
+
+        additional_headers
+    }
+
+    fn validate_filename_hashes(
+        assets: &[&Asset],
+        filename_hash_validation: &AssetFilenameHashConfig,
+    ) -> AssetCertificationResult {
+        let pattern = filename_hash_validation.pattern();
+        let matcher = AssetPatternMatcher::new(pattern).map_err(|source| {
+            AssetCertificationError::InvalidGlob {
+                pattern: pattern.to_string(),
+                source,
+            }
+        })?;
+
+        for asset in assets {
+            if !matcher.is_match(&asset.path) {
+                continue;
+            }
+
+            let Some(filename_hash) = filename_hash_validation.filename_hash(&asset.path) else {
+                return Err(AssetCertificationError::MissingFilenameHash {
+                    path: asset.path.to_string(),
+                });
+            };
+            let content_hash = hash(&asset.content)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            if !content_hash.starts_with(&filename_hash.to_ascii_lowercase()) {
+                return Err(AssetCertificationError::FilenameHashMismatch {
+                    path: asset.path.to_string(),
+                    filename_hash: filename_hash.to_string(),
+                    content_hash,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn asset_etag(&self, identity_content: &[u8], content: &[u8]) -> Option<String> {
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+
+        self.etag_kind.map(|etag_kind| match etag_kind {
+            AssetETagKind::Strong => format!(r#""{}""#, hex(&hash(content))),
+            AssetETagKind::Weak => format!(r#"W/"{}""#, hex(&hash(identity_content))),
+        })
+    }
+
+    fn get_preferred_encodings<'a>(&self, request: &'a HttpRequest) -> Vec<&'a str> {
+        let accept_encoding = request
+            .headers()
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("accept-encoding"))
+            .map(|(_, value)| value.as_str());
+        let accept_encoding = match accept_encoding {
+            // an empty header only accepts the identity encoding
+            Some(value) if value.trim().is_empty() => return vec![],
+            Some(value) => value,
+            None if self.any_encoding_when_accept_encoding_missing => "*",
+            None => return vec![],
+        };
+
+        // the identity variant is served when no encoding that the client
+        // prefers at least as much as `identity` has been certified
+        self.prioritized_encodings(accept_encoding)
+            .iter()
+            .map(|(encoding, _quality)| *encoding)
+            .take_while(|encoding| !encoding.eq_ignore_ascii_case("identity"))
+            .collect()
+    }
+
+    fn prioritized_encodings<'a>(&self, encodings: &'a str) -> Vec<(&'a str, f32)> {
+        let listed_encodings = encodings
+            .split(',')
+            .filter_map(|encoding| {
+                let mut params = encoding.split(';').map(|s| s.trim());
+                let encoding = params.next().filter(|encoding| !encoding.is_empty())?;
+
+                Some((
+                    encoding,
+                    params.find_map(Self::parse_quality).unwrap_or(1.0),
+                ))
+            })
+            .collect::<Vec<_>>();
+
+        // encodings with a quality of zero are explicitly not acceptable to the client
+        let mut encodings = listed_encodings
+            .iter()
+            .filter(|(encoding, quality)| *encoding != "*" && *quality > 0.0)
+            .copied()
+            .collect::<Vec<_>>();
+
+        // the wildcard matches any encoding that is not explicitly listed
+        let wildcard_quality = listed_encodings
+            .iter()
+            .find(|(encoding, _)| *encoding == "*")
+            .map(|(_, quality)| *quality)
+            .filter(|quality| *quality > 0.0);
+        if let Some(wildcard_quality) = wildcard_quality {
+            encodings.extend(
+                WILDCARD_ENCODINGS
+                    .iter()
+                    .filter(|wildcard_encoding| {
+                        !listed_encodings
+                            .iter()
+                            .any(|(encoding, _)| encoding.eq_ignore_ascii_case(wildcard_encoding))
+                    })
+                    .map(|encoding| (*encoding, wildcard_quality)),
+            );
+        }
+
+        // encodings are ordered by the client's quality values first, and by the server's
+        // order of preference for encodings of equal quality. These `unwrap()` calls are safe
+        // as long as the quality values are comparable (not NaN)
+        encodings.sort_by(|(a_encoding, a_quality), (b_encoding, b_quality)| {
+            b_quality.partial_cmp(a_quality).unwrap().then_with(|| {
+                self.encoding_quality(b_encoding)
+                    .partial_cmp(&self.encoding_quality(a_encoding))
+                    .unwrap()
+            })
+        });
+
+        encodings
+    }
+
+    fn parse_quality(param: &str) -> Option<f32> {
+        let (name, value) = param.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("q") {
+            return None;
+        }
+
+        value
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|quality| !quality.is_nan())
+            .map(|quality| quality.clamp(0.0, 1.0))
+    }
+
+    fn encoding_quality(&self, encoding: &str) -> f32 {
+        if let Some(index) = self
+            .encoding_priority
+            .iter()
+            .position(|prioritized| encoding.eq_ignore_ascii_case(&prioritized.to_string()))
+        {
+            return 1.0 - 0.1 * index as f32;
+        }
+
+        if encoding.eq_ignore_ascii_case("identity") {
+            return 0.5;
+        }
+
+        0.6
+    }
+}
+
+impl Default for AssetRouter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::AssetFallbackConfig;
+    use crate::{AssetContentSecurityPolicy, AssetFallbackConfig, AssetFileOptions};
     use assert_matches::assert_matches;
     use ic_certification::{hash_tree::SubtreeLookupResult, HashTree};
     use ic_http_certification::{
-        cel::DefaultFullCelExpressionBuilder, HeaderField, CERTIFICATE_HEADER_NAME,
+        cel::DefaultFullCelExpressionBuilder, HeaderField, HttpCertificationError,
+        CERTIFICATE_HEADER_NAME,
+    };
+    use ic_response_verification::{
+        types::VerificationInfo, verify_request_response_pair, CertificateHeader,
+        ResponseVerificationError, ResponseVerificationResult, ResponseVerifier,
+    };
+    use ic_response_verification_test_utils::{
+        base64_decode, create_v2_certificate_fixture, get_current_timestamp, hash,
+        V2CertificateFixture,
     };
-    use ic_response_verification::CertificateHeader;
-    use ic_response_verification_test_utils::{base64_decode, hash};
     use rand_chacha::rand_core::{RngCore, SeedableRng};
     use rand_chacha::ChaCha20Rng;
     use rstest::*;
     use std::vec;
 
-    const ONE_CHUNK_ASSET_LEN: usize = ASSET_CHUNK_SIZE;
-    const TWO_CHUNKS_ASSET_LEN: usize = ASSET_CHUNK_SIZE + 1;
-    const SIX_CHUNKS_ASSET_LEN: usize = 5 * ASSET_CHUNK_SIZE + 12;
-    const TEN_CHUNKS_ASSET_LEN: usize = 10 * ASSET_CHUNK_SIZE;
+    const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
+    const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
+
+    const ONE_CHUNK_ASSET_LEN: usize = ASSET_CHUNK_SIZE;
+    const TWO_CHUNKS_ASSET_LEN: usize = ASSET_CHUNK_SIZE + 1;
+    const SIX_CHUNKS_ASSET_LEN: usize = 5 * ASSET_CHUNK_SIZE + 12;
+    const TEN_CHUNKS_ASSET_LEN: usize = 10 * ASSET_CHUNK_SIZE;
+
+    const ONE_CHUNK_ASSET_NAME: &str = "long_asset_one_chunk";
+    const TWO_CHUNKS_ASSET_NAME: &str = "long_asset_two_chunks";
+    const SIX_CHUNKS_ASSET_NAME: &str = "long_asset_six_chunks";
+    const TEN_CHUNKS_ASSET_NAME: &str = "long_asset_ten_chunks";
+
+    #[rstest]
+    #[case("/")]
+    #[case("https://internetcomputer.org/")]
+    fn test_index_html(mut asset_router: AssetRouter, #[case] req_url: &str) {
+        let request = HttpRequest::get(req_url).build();
+
+        let mut expected_response = expected_index_html_response();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", "", "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == request.get_path().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_one_chunk_long_asset_served_in_full() {
+        let asset_name = ONE_CHUNK_ASSET_NAME;
+        let long_asset_router =
+            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
+        let req_url = format!("/{asset_name}");
+        let asset_body = long_asset_body(asset_name);
+        // Request the entire "one-chunk"-asset, should obtain it in full.
+        let request = HttpRequest::get(&req_url).build();
+        let mut expected_response = build_200_response(
+            asset_body,
+            asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+            ],
+        );
+
+        let response = long_asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+    }
+
+    #[rstest]
+    #[case(TWO_CHUNKS_ASSET_NAME)]
+    #[case(SIX_CHUNKS_ASSET_NAME)]
+    #[case(TEN_CHUNKS_ASSET_NAME)]
+    fn test_long_asset_served_in_chunks(#[case] asset_name: &str) {
+        let long_asset_router =
+            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
+        let req_url = format!("/{asset_name}");
+        let asset_body = long_asset_body(asset_name);
+        let asset_len = asset_body.len();
+        // Request the entire asset, should obtain the first chunk.
+        let request = HttpRequest::get(&req_url).build();
+        let mut expected_response = build_206_response(
+            asset_body[0..ASSET_CHUNK_SIZE].to_vec(),
+            asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                (
+                    "content-range".to_string(),
+                    format!("bytes 0-{}/{}", ASSET_CHUNK_SIZE - 1, asset_len),
+                ),
+            ],
+        );
+
+        let response = long_asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        // Request the subsequent chunks, should obtain them.
+        let expected_number_of_chunks =
+            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
+        let mut asset_len_so_far = response.body().len();
+        let mut number_of_chunks_so_far = 1;
+        while asset_len_so_far < asset_len {
+            let chunk_request = HttpRequest::get(&req_url)
+                .with_headers(vec![(
+                    "range".to_string(),
+                    format!("bytes={}-", asset_len_so_far),
+                )])
+                .build();
+            let expected_range_end = cmp::min(asset_len_so_far + ASSET_CHUNK_SIZE, asset_len) - 1;
+            let mut expected_response = build_206_response(
+                asset_body[asset_len_so_far..=expected_range_end].to_vec(),
+                asset_range_chunk_cel_expr(),
+                vec![
+                    (
+                        "cache-control".to_string(),
+                        "public, no-cache, no-store".to_string(),
+                    ),
+                    ("content-type".to_string(), "text/html".to_string()),
+                    (
+                        "content-range".to_string(),
+                        format!(
+                            "bytes {}-{}/{}",
+                            asset_len_so_far, expected_range_end, asset_len
+                        ),
+                    ),
+                ],
+            );
+            let response = long_asset_router
+                .serve_asset(&data_certificate(), &chunk_request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+            add_v2_certificate_header(
+                &data_certificate(),
+                &mut expected_response,
+                &witness,
+                &expr_path,
+            );
+            assert_eq!(response, expected_response);
+            asset_len_so_far += response.body().len();
+            number_of_chunks_so_far += 1;
+        }
+        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks)
+    }
+
+    #[rstest]
+    fn test_borrowed_long_asset_chunks_borrow_content() {
+        let asset_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::borrowed(TWO_CHUNKS_ASSET_NAME, &asset_body)],
+                vec![],
+            )
+            .unwrap();
+
+        let req_url = format!("/{TWO_CHUNKS_ASSET_NAME}");
+        for range_begin in [0, ASSET_CHUNK_SIZE] {
+            let request = HttpRequest::get(&req_url)
+                .with_headers(vec![("range".to_string(), format!("bytes={range_begin}-"))])
+                .build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+
+            assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+            assert_eq!(response.body().as_ptr(), asset_body[range_begin..].as_ptr());
+        }
+    }
+
+    #[rstest]
+    #[case("bytes=0-", Some(0))]
+    #[case("bytes=0-99", Some(0))]
+    #[case("bytes=2000000-", Some(ASSET_CHUNK_SIZE))]
+    #[case("bytes=-1", Some(ASSET_CHUNK_SIZE))]
+    #[case("BYTES = 2000000-2000000", Some(ASSET_CHUNK_SIZE))]
+    #[case("items=0-", None)]
+    fn test_long_asset_range_header(
+        #[case] range: &str,
+        #[case] expected_range_begin: Option<usize>,
+    ) {
+        let asset_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new(TWO_CHUNKS_ASSET_NAME, asset_body.as_slice())],
+                vec![],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get(format!("/{TWO_CHUNKS_ASSET_NAME}"))
+            .with_headers(vec![("Range".to_string(), range.to_string())])
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+
+        assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+        let range_begin = expected_range_begin.unwrap_or_default();
+        assert_eq!(
+            response.body(),
+            &asset_body[range_begin..cmp::min(range_begin + ASSET_CHUNK_SIZE, asset_body.len())]
+        );
+    }
+
+    #[rstest]
+    #[case("bytes=10-")]
+    #[case("bytes=0-0, 2000000-")]
+    #[case("bytes=3000000-")]
+    #[case("bytes=100-20")]
+    #[case("bytes=dead-beef")]
+    #[case("bytes 0-")]
+    fn test_long_asset_invalid_range_header(#[case] range: &str) {
+        let asset_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new(TWO_CHUNKS_ASSET_NAME, asset_body.as_slice())],
+                vec![],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get(format!("/{TWO_CHUNKS_ASSET_NAME}"))
+            .with_headers(vec![("Range".to_string(), range.to_string())])
+            .build();
+
+        assert_matches!(
+            asset_router.serve_asset(&data_certificate(), &request),
+            Err(AssetCertificationError::RequestError(_)
+                | AssetCertificationError::NoAssetMatchingRequestUrl { .. })
+        );
+    }
+
+    #[rstest]
+    #[case(TWO_CHUNKS_ASSET_NAME)]
+    #[case(SIX_CHUNKS_ASSET_NAME)]
+    #[case(TEN_CHUNKS_ASSET_NAME)]
+    fn test_long_asset_deletion_removes_chunks(#[case] asset_name: &str) {
+        let mut long_asset_router =
+            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
+        let req_url = format!("/{asset_name}");
+        let asset_body = long_asset_body(asset_name);
+        let asset_len = asset_body.len();
+        let mut all_requests = vec![];
+        // Request the entire asset and the chunks, all should succeed.
+        // First the asset...
+        let request = HttpRequest::get(&req_url).build();
+        let response = long_asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+
+        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+        all_requests.push(request);
+
+        // ... then the subsequent chunks.
+        let expected_number_of_chunks =
+            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
+        let mut asset_len_so_far = response.body().len();
+        let mut number_of_chunks_so_far = 1;
+        while asset_len_so_far < asset_len {
+            let chunk_request = HttpRequest::get(&req_url)
+                .with_headers(vec![(
+                    "range".to_string(),
+                    format!("bytes={}-", asset_len_so_far),
+                )])
+                .build();
+            let response = long_asset_router
+                .serve_asset(&data_certificate(), &chunk_request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+            assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+            asset_len_so_far += response.body().len();
+            number_of_chunks_so_far += 1;
+            all_requests.push(chunk_request);
+        }
+        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks);
+        assert_eq!(all_requests.len(), expected_number_of_chunks);
+
+        // Delete the asset.
+        long_asset_router
+            .delete_assets(
+                vec![Asset::new(&req_url, asset_body)],
+                vec![long_asset_config(asset_name)],
+            )
+            .expect("Asset deletion failed");
+
+        // Re-request the asset and the chunks, all should fail.
+        for request in all_requests {
+            let result = long_asset_router.serve_asset(&data_certificate(), &request);
+            assert_matches!(
+                result,
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                    request_url,
+                 }) if request_url == request.get_path().unwrap()
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(SIX_CHUNKS_ASSET_NAME, "deflate", AssetEncoding::Deflate)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "deflate, identity", AssetEncoding::Deflate)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "gzip", AssetEncoding::Gzip)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, identity", AssetEncoding::Gzip)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, deflate", AssetEncoding::Gzip)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, deflate, identity", AssetEncoding::Gzip)]
+    #[case(SIX_CHUNKS_ASSET_NAME, "br", AssetEncoding::Brotli)]
+    #[case(
+        SIX_CHUNKS_ASSET_NAME,
+        "br, gzip, deflate, identity",
+        AssetEncoding::Brotli
+    )]
+    #[case(
+        SIX_CHUNKS_ASSET_NAME,
+        "gzip, deflate, identity, br",
+        AssetEncoding::Brotli
+    )]
+    fn test_encoded_long_asset_served_in_encoded_chunks(
+        #[case] asset_name: &str,
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: AssetEncoding,
+    ) {
+        let (_, expected_encoding_suffix) = expected_encoding.default_config();
+        let long_asset_router = long_asset_router_with_params(
+            &[asset_name],
+            &[AssetEncoding::Identity, expected_encoding],
+        );
+        let req_url = format!("/{asset_name}");
+        let encoded_asset_name = format!("{asset_name}{expected_encoding_suffix}");
+        let asset_body = long_asset_body(&encoded_asset_name);
+        let asset_len = asset_body.len();
+
+        let request = HttpRequest::get(&req_url)
+            .with_headers(vec![(
+                "accept-encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
+        let mut expected_response = build_206_response(
+            asset_body[0..ASSET_CHUNK_SIZE].to_vec(),
+            asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                (
+                    "content-encoding".to_string(),
+                    expected_encoding.to_string(),
+                ),
+                (
+                    "content-range".to_string(),
+                    format!("bytes 0-{}/{}", ASSET_CHUNK_SIZE - 1, asset_len),
+                ),
+            ],
+        );
+        let response = long_asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(
+            expr_path,
+            HttpCertificationPath::exact(req_url.clone()).to_expr_path()
+        );
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        // Request the subsequent chunks, should obtain them.
+        let expected_number_of_chunks =
+            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
+        let mut asset_len_so_far = response.body().len();
+        let mut number_of_chunks_so_far = 1;
+        while asset_len_so_far < asset_len {
+            let chunk_request = HttpRequest::get(&req_url)
+                .with_headers(vec![
+                    ("range".to_string(), format!("bytes={}-", asset_len_so_far)),
+                    ("accept-encoding".to_string(), accept_encoding.to_string()),
+                ])
+                .build();
+            let expected_range_end = cmp::min(asset_len_so_far + ASSET_CHUNK_SIZE, asset_len) - 1;
+            let mut expected_response = build_206_response(
+                asset_body[asset_len_so_far..=expected_range_end].to_vec(),
+                encoded_range_chunk_asset_cel_expr(),
+                vec![
+                    (
+                        "cache-control".to_string(),
+                        "public, no-cache, no-store".to_string(),
+                    ),
+                    ("content-type".to_string(), "text/html".to_string()),
+                    (
+                        "content-encoding".to_string(),
+                        expected_encoding.to_string(),
+                    ),
+                    (
+                        "content-range".to_string(),
+                        format!(
+                            "bytes {}-{}/{}",
+                            asset_len_so_far, expected_range_end, asset_len
+                        ),
+                    ),
+                ],
+            );
+            let response = long_asset_router
+                .serve_asset(&data_certificate(), &chunk_request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+            add_v2_certificate_header(
+                &data_certificate(),
+                &mut expected_response,
+                &witness,
+                &expr_path,
+            );
+            assert_eq!(response, expected_response);
+            asset_len_so_far += response.body().len();
+            number_of_chunks_so_far += 1;
+        }
+        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks)
+    }
+
+    #[rstest]
+    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Brotli)]
+    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Gzip)]
+    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Deflate)]
+    fn test_encoded_long_asset_deletion_removes_encoded_chunks(
+        #[case] asset_name: &str,
+        #[case] encoding: AssetEncoding,
+    ) {
+        let (_, encoding_suffix) = encoding.default_config();
+        let mut long_asset_router =
+            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity, encoding]);
+        let req_url = format!("/{asset_name}");
+        let encoded_asset_name = format!("{asset_name}{encoding_suffix}");
+        let encoded_asset_body = long_asset_body(&encoded_asset_name);
+        let asset_len = encoded_asset_body.len();
+        let mut all_requests = vec![];
+        // Request the entire asset and the chunks, all should succeed.
+        // First the asset...
+        let request = HttpRequest::get(&req_url)
+            .with_headers(vec![("accept-encoding".to_string(), encoding.to_string())])
+            .build();
+        let response = long_asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+
+        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+        all_requests.push(request);
+
+        // ... then the subsequent chunks.
+        let expected_number_of_chunks =
+            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
+        let mut asset_len_so_far = response.body().len();
+        let mut number_of_chunks_so_far = 1;
+        while asset_len_so_far < asset_len {
+            let chunk_request = HttpRequest::get(&req_url)
+                .with_headers(vec![
+                    ("range".to_string(), format!("bytes={asset_len_so_far}-")),
+                    ("accept-encoding".to_string(), encoding.to_string()),
+                ])
+                .build();
+            let response = long_asset_router
+                .serve_asset(&data_certificate(), &chunk_request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+            assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
+            asset_len_so_far += response.body().len();
+            number_of_chunks_so_far += 1;
+            all_requests.push(chunk_request);
+        }
+        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks);
+        assert_eq!(all_requests.len(), expected_number_of_chunks);
+
+        // Delete the asset.
+        long_asset_router
+            .delete_assets(
+                vec![
+                    Asset::new(&req_url, long_asset_body(asset_name)),
+                    Asset::new(&format!("/{encoded_asset_name}"), encoded_asset_body),
+                ],
+                vec![long_asset_config(&req_url)],
+            )
+            .expect("Asset deletion failed");
+
+        // Re-request the asset and the chunks, all should fail.
+        for request in all_requests {
+            let result = long_asset_router.serve_asset(&data_certificate(), &request);
+            assert_matches!(
+                result,
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                    request_url,
+                 }) if request_url == request.get_path().unwrap()
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(index_html_zz_body(), "/", "deflate", "deflate")]
+    #[case(index_html_zz_body(), "/", "deflate, identity", "deflate")]
+    #[case(index_html_gz_body(), "/", "gzip", "gzip")]
+    #[case(index_html_gz_body(), "/", "gzip, identity", "gzip")]
+    #[case(index_html_gz_body(), "/", "gzip, deflate", "gzip")]
+    #[case(index_html_gz_body(), "/", "gzip, deflate, identity", "gzip")]
+    #[case(index_html_br_body(), "/", "br", "br")]
+    #[case(index_html_br_body(), "/", "br, gzip, deflate, identity", "br")]
+    #[case(index_html_br_body(), "/", "gzip, deflate, identity, br", "br")]
+    #[case(index_html_zz_body(), "/index.html", "deflate", "deflate")]
+    #[case(index_html_zz_body(), "/index.html", "deflate, identity", "deflate")]
+    #[case(index_html_gz_body(), "/index.html", "gzip", "gzip")]
+    #[case(index_html_gz_body(), "/index.html", "gzip, identity", "gzip")]
+    #[case(index_html_gz_body(), "/index.html", "gzip, deflate", "gzip")]
+    #[case(index_html_gz_body(), "/index.html", "gzip, deflate, identity", "gzip")]
+    #[case(index_html_br_body(), "/index.html", "br", "br")]
+    #[case(
+        index_html_br_body(),
+        "/index.html",
+        "br, gzip, deflate, identity",
+        "br"
+    )]
+    #[case(
+        index_html_br_body(),
+        "/index.html",
+        "gzip, deflate, identity, br",
+        "br"
+    )]
+    fn test_encoded_index_html(
+        #[case] expected_body: Vec<u8>,
+        #[case] req_url: &str,
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: &str,
+        mut asset_router: AssetRouter,
+    ) {
+        let request = HttpRequest::get(req_url)
+            .with_headers(vec![(
+                "accept-encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
+        let mut expected_response = build_200_response(
+            expected_body,
+            encoded_asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                (
+                    "content-encoding".to_string(),
+                    expected_encoding.to_string(),
+                ),
+            ],
+        );
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(
+            expr_path,
+            HttpCertificationPath::exact(req_url).to_expr_path()
+        );
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == req_url
+        );
+    }
+
+    #[rstest]
+    fn test_get_certified_body(index_html_config: AssetConfig) {
+        let long_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                    Asset::new(TWO_CHUNKS_ASSET_NAME, long_body.as_slice()),
+                    Asset::new("skipped.txt", b"skipped".as_slice()),
+                ],
+                vec![
+                    index_html_config,
+                    AssetConfig::File {
+                        path: "skipped.txt".to_string(),
+                        content_type: None,
+                        headers: vec![],
+                        fallback_for: vec![],
+                        aliased_by: vec![],
+                        encodings: vec![],
+                        options: AssetFileOptions {
+                            skip_certification: true,
+                            ..Default::default()
+                        },
+                    },
+                ],
+            )
+            .unwrap();
+
+        let index_html_body = index_html_body();
+        let index_html_gz_body = index_html_gz_body();
+        let long_asset_path = format!("/{TWO_CHUNKS_ASSET_NAME}");
+        for (path, encoding, expected_body) in [
+            (
+                "/index.html",
+                AssetEncoding::Identity,
+                Some(index_html_body.as_slice()),
+            ),
+            (
+                "/",
+                AssetEncoding::Identity,
+                Some(index_html_body.as_slice()),
+            ),
+            (
+                "/index.html",
+                AssetEncoding::Gzip,
+                Some(index_html_gz_body.as_slice()),
+            ),
+            ("/index.html", AssetEncoding::Zstd, None),
+            (
+                long_asset_path.as_str(),
+                AssetEncoding::Identity,
+                Some(long_body.as_slice()),
+            ),
+            ("/skipped.txt", AssetEncoding::Identity, None),
+            ("/not-found", AssetEncoding::Identity, None),
+        ] {
+            assert_eq!(
+                asset_router.get_certified_body(path, encoding),
+                expected_body
+            );
+        }
+    }
+
+    #[rstest]
+    #[case(vec![], "br, zstd, gzip, deflate", "br")]
+    #[case(vec![AssetEncoding::Gzip], "br, gzip, deflate", "gzip")]
+    #[case(vec![AssetEncoding::Gzip], "br, deflate", "br")]
+    #[case(vec![AssetEncoding::Gzip], "*", "gzip")]
+    #[case(vec![AssetEncoding::Gzip], "gzip;q=0, *", "br")]
+    #[case(vec![AssetEncoding::Deflate, AssetEncoding::Gzip], "br, gzip, deflate", "deflate")]
+    #[case(vec![AssetEncoding::Deflate, AssetEncoding::Gzip], "br, gzip", "gzip")]
+    fn test_encoding_priority(
+        index_html_config: AssetConfig,
+        #[case] encoding_priority: Vec<AssetEncoding>,
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: &str,
+    ) {
+        let mut asset_router = AssetRouter::default()
+            .with_encoding_priority(encoding_priority)
+            .unwrap();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![(
+                "accept-encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+
+        assert_eq!(
+            get_header(&response, "content-encoding").as_deref(),
+            Some(expected_encoding)
+        );
+    }
+
+    #[rstest]
+    #[case(vec![AssetEncoding::Identity], AssetEncoding::Identity)]
+    #[case(vec![AssetEncoding::Gzip, AssetEncoding::Brotli, AssetEncoding::Gzip], AssetEncoding::Gzip)]
+    fn test_invalid_encoding_priority(
+        #[case] encoding_priority: Vec<AssetEncoding>,
+        #[case] expected_encoding: AssetEncoding,
+    ) {
+        let result = AssetRouter::default().with_encoding_priority(encoding_priority);
+
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::InvalidEncodingPriority { encoding })
+                if encoding == expected_encoding
+        );
+    }
+
+    #[rstest]
+    fn test_collapsed_slashes(
+        index_html_config: AssetConfig,
+        js_config: AssetConfig,
+        not_found_html_config: AssetConfig,
+    ) {
+        let assets = vec![
+            Asset::new("index.html", index_html_body()),
+            Asset::new("js/app.js", app_js_body()),
+            Asset::new("not-found.html", not_found_html_body()),
+        ];
+        let asset_configs = vec![index_html_config, js_config, not_found_html_config];
+
+        let mut asset_router = AssetRouter::default().with_collapsed_slashes(true);
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
+            .unwrap();
+        let mut uncollapsed_asset_router = AssetRouter::default();
+        uncollapsed_asset_router
+            .certify_assets(assets, asset_configs)
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+        for (path, expected_body, expected_uncollapsed_body) in [
+            ("/js//app.js", app_js_body(), not_found_html_body()),
+            ("//js/app.js", app_js_body(), index_html_body()),
+            ("/js///app.js", app_js_body(), not_found_html_body()),
+            ("/js/%2Fapp.js", app_js_body(), not_found_html_body()),
+            ("//index.html", index_html_body(), index_html_body()),
+            ("//", index_html_body(), index_html_body()),
+            (
+                "/js//missing.js",
+                not_found_html_body(),
+                not_found_html_body(),
+            ),
+            ("/js/app.js", app_js_body(), app_js_body()),
+        ] {
+            let request = HttpRequest::get(path).build();
+            let response = fixture.serve(&asset_router, &request);
+
+            assert_eq!(response.body(), expected_body.as_slice(), "{path}");
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+
+            let response = fixture.serve(&uncollapsed_asset_router, &request);
+            assert_eq!(
+                response.body(),
+                expected_uncollapsed_body.as_slice(),
+                "{path}"
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_favicon(index_html_config: AssetConfig) {
+        let favicon_body = b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>".to_vec();
+
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("favicon.svg", favicon_body.clone()),
+                ],
+                vec![
+                    index_html_config,
+                    AssetConfig::favicon("favicon.svg").build(),
+                ],
+            )
+            .unwrap();
+
+        for path in ["/favicon.ico", "/favicon.svg"] {
+            let verification_info = verify_served(&asset_router, &HttpRequest::get(path).build());
+            assert!(verification_info.is_fully_certified());
+
+            let verified_response = verification_info.response.unwrap();
+            assert_eq!(verified_response.status_code, Some(200), "{path}");
+            assert_eq!(verified_response.body, favicon_body, "{path}");
+            assert!(
+                verified_response
+                    .headers
+                    .contains(&("content-type".to_string(), "image/svg+xml".to_string())),
+                "{path}"
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_path_rewrites(
+        index_html_config: AssetConfig,
+        js_config: AssetConfig,
+        not_found_html_config: AssetConfig,
+    ) {
+        let mut asset_router = AssetRouter::default()
+            .with_path_rewrites(vec![
+                ("/v1/".to_string(), "/".to_string()),
+                // shadowed by the first rewrite
+                ("/v1/legacy/".to_string(), "/js/".to_string()),
+            ])
+            .unwrap();
+        let asset_configs = vec![index_html_config, js_config, not_found_html_config];
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("js/app.js", app_js_body()),
+                    Asset::new("not-found.html", not_found_html_body()),
+                ],
+                asset_configs.clone(),
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+        for (path, expected_body) in [
+            ("/v1/index.html", index_html_body()),
+            ("/v1/", index_html_body()),
+            ("/v1/js/app.js", app_js_body()),
+            ("/v1/js/missing.js", not_found_html_body()),
+            ("/v1/unknown", index_html_body()),
+            ("/v1/legacy/app.js", index_html_body()),
+            ("/js/app.js", app_js_body()),
+        ] {
+            let request = HttpRequest::get(path).build();
+            let response = fixture.serve(&asset_router, &request);
+
+            assert_eq!(response.body(), expected_body.as_slice(), "{path}");
+            assert!(get_header(&response, "location").is_none());
+            fixture.verify(&request, response).unwrap();
+        }
+
+        asset_router
+            .delete_assets(vec![Asset::new("js/app.js", app_js_body())], asset_configs)
+            .unwrap();
+
+        let response = asset_router
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/v1/js/app.js").build(),
+            )
+            .unwrap();
+        assert_eq!(response.body(), not_found_html_body().as_slice());
+    }
+
+    #[test]
+    fn test_path_rewrites_certify_all_responses() {
+        let assets = vec![
+            Asset::new("index.html", index_html_body()),
+            Asset::new("404.html", not_found_html_body()),
+        ];
+        let asset_configs = vec![
+            AssetConfig::File {
+                path: "index.html".to_string(),
+                content_type: Some("text/html".to_string()),
+                headers: vec![],
+                fallback_for: vec![],
+                aliased_by: vec![],
+                encodings: vec![],
+                options: Default::default(),
+            },
+            AssetConfig::File {
+                path: "404.html".to_string(),
+                content_type: Some("text/html".to_string()),
+                headers: vec![],
+                fallback_for: vec![AssetFallbackConfig {
+                    scope: "/api".to_string(),
+                    status_code: Some(StatusCode::NOT_FOUND),
+                }],
+                aliased_by: vec![],
+                encodings: vec![],
+                options: AssetFileOptions {
+                    addressable: false,
+                    ..Default::default()
+                },
+            },
+            AssetConfig::Redirect {
+                from: "/home".to_string(),
+                to: "/index.html".to_string(),
+                kind: AssetRedirectKind::Permanent,
+                headers: vec![],
+            },
+        ];
+        let not_found_json_body = br#"{"error":"not found"}"#.as_slice();
+
+        let mut asset_router = AssetRouter::default()
+            .with_path_rewrites(vec![("/v1/".to_string(), "/".to_string())])
+            .unwrap()
+            .with_cors(AssetCorsConfig::new("https://app.example.com"));
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
+            .unwrap();
+        asset_router
+            .certify_method_response(
+                "/index.html",
+                Method::POST,
+                HttpResponse::ok(b"Submitted".to_vec(), vec![]).build(),
+            )
+            .unwrap();
+        asset_router
+            .certify_variants(
+                "/greeting",
+                "X-Locale",
+                HttpResponse::ok(b"Hello".as_slice(), vec![]).build(),
+                vec![(
+                    "fr".to_string(),
+                    HttpResponse::ok(b"Bonjour".as_slice(), vec![]).build(),
+                )],
+            )
+            .unwrap();
+        asset_router
+            .certify_media_type_fallback(
+                "/api",
+                "application/json",
+                HttpResponse::not_found(not_found_json_body, vec![]).build(),
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+        for (method, path, header, expected_status_code, expected_body) in [
+            (
+                Method::GET,
+                "/v1/home",
+                None,
+                StatusCode::MOVED_PERMANENTLY,
+                vec![],
+            ),
+            (
+                Method::POST,
+                "/v1/index.html",
+                None,
+                StatusCode::OK,
+                b"Submitted".to_vec(),
+            ),
+            (
+                Method::OPTIONS,
+                "/v1/index.html",
+                None,
+                StatusCode::NO_CONTENT,
+                vec![],
+            ),
+            (
+                Method::GET,
+                "/v1/greeting",
+                Some(("x-locale", "fr")),
+                StatusCode::OK,
+                b"Bonjour".to_vec(),
+            ),
+            (
+                Method::GET,
+                "/v1/greeting",
+                None,
+                StatusCode::OK,
+                b"Hello".to_vec(),
+            ),
+            (
+                Method::GET,
+                "/v1/api/missing",
+                Some(("accept", "application/json")),
+                StatusCode::NOT_FOUND,
+                not_found_json_body.to_vec(),
+            ),
+            (
+                Method::GET,
+                "/v1/api/missing",
+                None,
+                StatusCode::NOT_FOUND,
+                not_found_html_body(),
+            ),
+        ] {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url(path)
+                .with_headers(
+                    std::iter::once(("origin", "https://app.example.com"))
+                        .chain(header)
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                )
+                .build();
+            let response = fixture.serve(&asset_router, &request);
+
+            assert_eq!(response.status_code(), expected_status_code, "{path}");
+            assert_eq!(response.body(), expected_body.as_slice(), "{path}");
+            fixture.verify(&request, response).unwrap();
+        }
+
+        assert!(asset_router
+            .get_certified_body("/index.html", AssetEncoding::Identity)
+            .is_some());
+        assert!(asset_router
+            .get_certified_body("/v1/index.html", AssetEncoding::Identity)
+            .is_none());
+
+        asset_router.delete_assets(assets, asset_configs).unwrap();
+        asset_router
+            .delete_method_response("/index.html", &Method::POST)
+            .unwrap();
+        asset_router.delete_variants("/greeting");
+        asset_router
+            .delete_media_type_fallback("/api", "application/json")
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    }
+
+    #[rstest]
+    #[case(vec![("v1/".to_string(), "/".to_string())], "v1/")]
+    #[case(vec![("/v1".to_string(), "/".to_string())], "/v1")]
+    #[case(vec![("/v1/".to_string(), "/".to_string()), ("/v2/".to_string(), "".to_string())], "")]
+    fn test_invalid_path_rewrites(
+        #[case] path_rewrites: Vec<(String, String)>,
+        #[case] expected_prefix: &str,
+    ) {
+        let result = AssetRouter::default().with_path_rewrites(path_rewrites);
+
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::InvalidPathRewrite { prefix })
+                if prefix == expected_prefix
+        );
+    }
+
+    #[rstest]
+    #[case(None, None, Some("br"))]
+    #[case(Some(false), None, None)]
+    #[case(Some(true), None, Some("br"))]
+    #[case(None, Some(""), None)]
+    #[case(Some(false), Some(""), None)]
+    #[case(Some(true), Some(""), None)]
+    #[case(Some(true), Some("  "), None)]
+    #[case(Some(true), Some("gzip"), Some("gzip"))]
+    fn test_empty_and_missing_accept_encoding(
+        index_html_config: AssetConfig,
+        #[case] any_encoding_when_accept_encoding_missing: Option<bool>,
+        #[case] accept_encoding: Option<&str>,
+        #[case] expected_encoding: Option<&str>,
+    ) {
+        let mut asset_router = AssetRouter::default();
+        if let Some(any_encoding_when_accept_encoding_missing) =
+            any_encoding_when_accept_encoding_missing
+        {
+            asset_router = asset_router.with_any_encoding_when_accept_encoding_missing(
+                any_encoding_when_accept_encoding_missing,
+            );
+        }
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config],
+            )
+            .unwrap();
+
+        let headers = accept_encoding
+            .map(|accept_encoding| {
+                vec![("accept-encoding".to_string(), accept_encoding.to_string())]
+            })
+            .unwrap_or_default();
+        let request = HttpRequest::get("/index.html")
+            .with_headers(headers)
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+
+        assert_eq!(
+            get_header(&response, "content-encoding").as_deref(),
+            expected_encoding
+        );
+        if expected_encoding.is_none() {
+            assert_eq!(response.body(), index_html_body().as_slice());
+        }
+    }
+
+    #[rstest]
+    #[case(index_html_zz_body(), "/something", "deflate", "deflate")]
+    #[case(index_html_zz_body(), "/something", "deflate, identity", "deflate")]
+    #[case(index_html_gz_body(), "/something", "gzip", "gzip")]
+    #[case(index_html_gz_body(), "/something", "gzip, identity", "gzip")]
+    #[case(index_html_gz_body(), "/something", "gzip, deflate", "gzip")]
+    #[case(index_html_gz_body(), "/something", "gzip, deflate, identity", "gzip")]
+    #[case(index_html_br_body(), "/something", "br", "br")]
+    #[case(
+        index_html_br_body(),
+        "/something",
+        "br, gzip, deflate, identity",
+        "br"
+    )]
+    #[case(
+        index_html_br_body(),
+        "/something",
+        "gzip, deflate, identity, br",
+        "br"
+    )]
+    #[case(index_html_zz_body(), "/assets/css/app.css", "deflate", "deflate")]
+    #[case(
+        index_html_zz_body(),
+        "/assets/css/app.css",
+        "deflate, identity",
+        "deflate"
+    )]
+    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip", "gzip")]
+    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip, identity", "gzip")]
+    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip, deflate", "gzip")]
+    #[case(
+        index_html_gz_body(),
+        "/assets/css/app.css",
+        "gzip, deflate, identity",
+        "gzip"
+    )]
+    #[case(index_html_br_body(), "/assets/css/app.css", "br", "br")]
+    #[case(
+        index_html_br_body(),
+        "/assets/css/app.css",
+        "br, gzip, deflate, identity",
+        "br"
+    )]
+    #[case(
+        index_html_br_body(),
+        "/assets/css/app.css",
+        "gzip, deflate, identity, br",
+        "br"
+    )]
+    fn test_encoded_index_html_fallback(
+        #[case] expected_body: Vec<u8>,
+        #[case] req_url: &str,
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: &str,
+        mut asset_router: AssetRouter,
+    ) {
+        let request = HttpRequest::get(req_url)
+            .with_headers(vec![(
+                "accept-encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
+        let mut expected_response = build_200_response(
+            expected_body,
+            encoded_asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                (
+                    "content-encoding".to_string(),
+                    expected_encoding.to_string(),
+                ),
+            ],
+        );
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        let requested_expr_path = HttpCertificationPath::exact(req_url).to_expr_path();
+        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_matches!(
+            witness.lookup_subtree(&requested_expr_path),
+            SubtreeLookupResult::Absent
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == req_url
+        );
+    }
+
+    #[rstest]
+    #[case("/something", "/something")]
+    #[case("https://internetcomputer.org/something", "/something")]
+    fn test_index_html_root_fallback(
+        mut asset_router: AssetRouter,
+        #[case] req_url: &str,
+        #[case] req_path: &str,
+    ) {
+        let mut expected_response = expected_index_html_response();
+
+        let request = HttpRequest::get(req_url).build();
+        let requested_expr_path = HttpCertificationPath::exact(req_path).to_expr_path();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_matches!(
+            witness.lookup_subtree(&requested_expr_path),
+            SubtreeLookupResult::Absent
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == req_path
+        );
+    }
+
+    #[rstest]
+    #[case("/assets/css/app.css", "/assets/css/app.css")]
+    #[case(
+        "https://internetcomputer.org/assets/css/app.css",
+        "/assets/css/app.css"
+    )]
+    fn test_index_html_nested_fallback(
+        mut asset_router: AssetRouter,
+        #[case] req_url: &str,
+        #[case] req_path: &str,
+    ) {
+        let mut expected_response = expected_index_html_response();
+
+        let request = HttpRequest::get(req_url).build();
+        let requested_expr_path = HttpCertificationPath::exact(req_path).to_expr_path();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_matches!(
+            witness.lookup_subtree(&requested_expr_path),
+            SubtreeLookupResult::Absent
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == req_path
+        );
+    }
+
+    #[rstest]
+    #[case("/assets/css/app.css", 0)]
+    #[case("/assets/css/app.css", 1)]
+    #[case("/assets/css/app.css", 2)]
+    #[case("/css/core-8d4jhgy2.js", 0)]
+    fn test_fallback_max_depth_exceeded(
+        asset_router: AssetRouter,
+        #[case] req_path: &str,
+        #[case] max_depth: usize,
+    ) {
+        let asset_router = asset_router.with_fallback_max_depth(max_depth);
+        let request = HttpRequest::get(req_path).build();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == req_path
+        );
+    }
+
+    #[rstest]
+    #[case("/assets/css/app.css", 3, vec!["http_expr", "", "<*>"])]
+    #[case("/assets/css/app.css", 10, vec!["http_expr", "", "<*>"])]
+    #[case("/css/core-8d4jhgy2.js", 1, vec!["http_expr", "css", "<*>"])]
+    #[case("/css/core-8d4jhgy2.js", 2, vec!["http_expr", "css", "<*>"])]
+    fn test_fallback_max_depth_within_limit(
+        asset_router: AssetRouter,
+        #[case] req_path: &str,
+        #[case] max_depth: usize,
+        #[case] expected_expr_path: Vec<&str>,
+    ) {
+        let asset_router = asset_router.with_fallback_max_depth(max_depth);
+        let request = HttpRequest::get(req_path).build();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+
+        assert_eq!(expr_path, expected_expr_path);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+    }
+
+    #[test]
+    fn test_not_addressable_asset() {
+        let mut asset_router = AssetRouter::default();
+        let not_found_html_config = match not_found_html_config() {
+            AssetConfig::File {
+                path,
+                content_type,
+                headers,
+                fallback_for,
+                aliased_by,
+                encodings,
+                ..
+            } => AssetConfig::File {
+                path,
+                content_type,
+                headers,
+                fallback_for,
+                aliased_by,
+                encodings,
+                options: AssetFileOptions {
+                    addressable: false,
+                    ..Default::default()
+                },
+            },
+            _ => unreachable!(),
+        };
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("not-found.html", not_found_html_body()),
+                ],
+                vec![index_html_config(), not_found_html_config],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get("/not-found.html").build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (_, expr_path) = extract_witness_expr_path(&response);
+        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_eq!(response.body(), index_html_body());
+
+        let request = HttpRequest::get("/404").build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (_, expr_path) = extract_witness_expr_path(&response);
+        assert_eq!(expr_path, vec!["http_expr", "404", "<$>"]);
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), not_found_html_body());
+
+        let request = HttpRequest::get("/css/app.css").build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (_, expr_path) = extract_witness_expr_path(&response);
+        assert_eq!(expr_path, vec!["http_expr", "css", "<*>"]);
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(response.body(), not_found_html_body());
+    }
+
+    #[rstest]
+    #[case("/api/users", br#"{"error":"not found"}"#.as_slice(), "application/json")]
+    #[case("/api/", br#"{"error":"not found"}"#.as_slice(), "application/json")]
+    #[case("/users", b"<h1>Not found</h1>".as_slice(), "text/html")]
+    #[case("/apis/users", b"<h1>Not found</h1>".as_slice(), "text/html")]
+    fn test_fallback_body_per_scope(
+        #[case] req_path: &str,
+        #[case] expected_body: &[u8],
+        #[case] expected_content_type: &str,
+    ) {
+        let not_found_config = |path: &str, content_type: &str, scope: &str| AssetConfig::File {
+            path: path.to_string(),
+            content_type: Some(content_type.to_string()),
+            headers: vec![],
+            fallback_for: vec![AssetFallbackConfig {
+                scope: scope.to_string(),
+                status_code: Some(StatusCode::NOT_FOUND),
+            }],
+            aliased_by: vec![],
+            encodings: vec![],
+            options: AssetFileOptions {
+                addressable: false,
+                ..Default::default()
+            },
+        };
+
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("404.json", br#"{"error":"not found"}"#.as_slice()),
+                    Asset::new("404.html", b"<h1>Not found</h1>".as_slice()),
+                ],
+                vec![
+                    not_found_config("404.json", "application/json", "/api"),
+                    not_found_config("404.html", "text/html", "/"),
+                ],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get(req_path).build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+        assert_eq!(response.body(), expected_body);
+        assert!(response
+            .headers()
+            .iter()
+            .any(|(name, value)| name == "content-type" && value == expected_content_type));
+    }
+
+    #[test]
+    fn test_skip_certification() {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("video.mp4", vec![0; 1024])],
+                vec![AssetConfig::File {
+                    path: "video.mp4".to_string(),
+                    content_type: Some("video/mp4".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec!["/intro".to_string()],
+                    encodings: vec![],
+                    options: AssetFileOptions {
+                        skip_certification: true,
+                        allowed_methods: vec![],
+                        ..Default::default()
+                    },
+                }],
+            )
+            .unwrap();
+
+        let mut expected_tree = HttpCertificationTree::default();
+        for path in ["/video.mp4", "/intro"] {
+            expected_tree.insert(&HttpCertificationTreeEntry::new(
+                HttpCertificationPath::exact(path),
+                HttpCertification::skip(),
+            ));
+        }
+        assert_eq!(asset_router.root_hash(), expected_tree.root_hash());
 
-    const ONE_CHUNK_ASSET_NAME: &str = "long_asset_one_chunk";
-    const TWO_CHUNKS_ASSET_NAME: &str = "long_asset_two_chunks";
-    const SIX_CHUNKS_ASSET_NAME: &str = "long_asset_six_chunks";
-    const TEN_CHUNKS_ASSET_NAME: &str = "long_asset_ten_chunks";
+        for req_path in ["/video.mp4", "/intro"] {
+            let request = HttpRequest::get(req_path).build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
 
-    #[rstest]
-    #[case(0, None)]
-    #[case(ASSET_CHUNK_SIZE, None)]
-    #[case(ASSET_CHUNK_SIZE*4, None)]
-    #[case(0, Some(0))]
-    #[case(100, Some(2000))]
-    #[case(10_000, Some(300_000))]
-    #[case(ASSET_CHUNK_SIZE, Some(2 * ASSET_CHUNK_SIZE - 1))]
-    fn should_parse_range_header_str(#[case] range_begin: usize, #[case] range_end: Option<usize>) {
-        let input = if let Some(range_end) = range_end {
-            format!("bytes={}-{}", range_begin, range_end)
-        } else {
-            format!("bytes={}-", range_begin)
-        };
-        let result = parse_range_header_str(&input);
-        let output = result.unwrap_or_else(|e| panic!("failed parsing '{input}': {:?}", e));
-        assert_eq!(
-            RangeRequestValues {
-                range_begin,
-                range_end
-            },
-            output
-        );
+            assert_eq!(
+                expr_path,
+                HttpCertificationPath::exact(req_path).to_expr_path()
+            );
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), vec![0; 1024]);
+            assert!(response.headers().iter().any(|(name, value)| {
+                name == CERTIFICATE_EXPRESSION_HEADER_NAME
+                    && *value == DefaultCelBuilder::skip_certification().to_string()
+            }));
+        }
     }
 
     #[rstest]
-    #[case("")]
-    #[case("byte=1-2")]
-    #[case("bites=2-4")]
-    #[case("bytes 7-11")]
-    #[case("bytes=12345")]
-    #[case("something else")]
-    #[case("bytes=-5-19")]
-    fn should_fail_parse_range_header_str_on_invalid_input(#[case] malformed_input: &str) {
-        let result = parse_range_header_str(malformed_input);
-        assert_matches!(result, Err(e) if e.to_string().contains("Invalid Range header"));
-    }
+    #[case(true, Some(true))]
+    #[case(false, None)]
+    fn test_upgrade(#[case] upgrade: bool, #[case] expected_upgrade: Option<bool>) {
+        let asset_config = AssetConfig::File {
+            path: "submit".to_string(),
+            content_type: Some("text/plain".to_string()),
+            headers: vec![],
+            fallback_for: vec![AssetFallbackConfig {
+                scope: "/submit".to_string(),
+                status_code: None,
+            }],
+            aliased_by: vec!["/submit/".to_string()],
+            encodings: vec![],
+            options: AssetFileOptions {
+                upgrade,
+                ..Default::default()
+            },
+        };
 
-    #[rstest]
-    #[case("bytes=100-end")]
-    #[case("bytes=dead-beef")]
-    fn should_fail_parse_range_header_str_on_malformed_input(#[case] malformed_input: &str) {
-        let result = parse_range_header_str(malformed_input);
-        assert_matches!(result, Err(e) if e.to_string().contains("Malformed range_"));
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("submit", b"Submitted".as_slice())],
+                vec![asset_config],
+            )
+            .unwrap();
+
+        for req_path in ["/submit", "/submit/", "/submit/form"] {
+            let request = HttpRequest::get(req_path).build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+
+            assert_eq!(response.upgrade(), expected_upgrade);
+            assert_eq!(response.body(), b"Submitted");
+        }
     }
 
-    #[rstest]
-    #[case("bytes=100-20")]
-    #[case("bytes=20-19")]
-    fn should_fail_parse_range_header_str_on_invalid_values(#[case] malformed_input: &str) {
-        let result = parse_range_header_str(malformed_input);
-        assert_matches!(result, Err(e) if e.to_string().contains("Invalid values in Range header"));
+    #[test]
+    fn test_accept_ranges() {
+        let assets = vec![
+            Asset::new("index.html", index_html_body()),
+            Asset::new("video.mp4", vec![0; TWO_CHUNKS_ASSET_LEN]),
+            Asset::new("data.bin", vec![0; 1024]),
+        ];
+        let asset_configs = vec![AssetConfig::File {
+            path: "data.bin".to_string(),
+            content_type: None,
+            headers: vec![("Accept-Ranges".to_string(), "bytes".to_string())],
+            fallback_for: vec![],
+            aliased_by: vec![],
+            encodings: vec![],
+            options: Default::default(),
+        }];
+
+        let mut asset_router = AssetRouter::default().with_accept_ranges(true);
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
+            .unwrap();
+
+        let accept_ranges = |req_path: &str| {
+            let request = HttpRequest::get(req_path).build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+
+            response
+                .headers()
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case("accept-ranges"))
+                .map(|(_, value)| value.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(accept_ranges("/index.html"), vec!["none"]);
+        assert_eq!(accept_ranges("/video.mp4"), vec!["bytes"]);
+        assert_eq!(accept_ranges("/data.bin"), vec!["bytes"]);
+
+        let mut expected_router = AssetRouter::default().with_accept_ranges(true);
+        expected_router
+            .certify_assets(vec![assets[1].clone()], vec![])
+            .unwrap();
+
+        asset_router
+            .delete_assets(vec![assets[0].clone(), assets[2].clone()], asset_configs)
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), expected_router.root_hash());
     }
 
-    #[rstest]
-    #[case("/")]
-    #[case("https://internetcomputer.org/")]
-    fn test_index_html(mut asset_router: AssetRouter, #[case] req_url: &str) {
-        let request = HttpRequest::get(req_url).build();
+    #[test]
+    fn test_content_security_policy() {
+        let csp_router = |report_only_policy: &str| {
+            let csp = AssetContentSecurityPolicy::new()
+                .with_enforced("default-src 'self'")
+                .with_report_only(report_only_policy);
 
-        let mut expected_response = expected_index_html_response();
+            let mut asset_router = AssetRouter::default();
+            asset_router
+                .certify_assets(
+                    vec![Asset::new("index.html", index_html_body())],
+                    vec![AssetConfig::File {
+                        path: "index.html".to_string(),
+                        content_type: Some("text/html".to_string()),
+                        headers: csp.headers(),
+                        fallback_for: vec![],
+                        aliased_by: vec![],
+                        encodings: vec![],
+                        options: Default::default(),
+                    }],
+                )
+                .unwrap();
 
+            asset_router
+        };
+
+        let asset_router = csp_router("default-src 'none'");
         let response = asset_router
-            .serve_asset(&data_certificate(), &request)
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/index.html").build(),
+            )
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
+        let header_value = |header_name: &str| {
+            response
+                .headers()
+                .iter()
+                .find(|(name, _)| name == header_name)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(
+            header_value("Content-Security-Policy"),
+            Some("default-src 'self'")
+        );
+        assert_eq!(
+            header_value("Content-Security-Policy-Report-Only"),
+            Some("default-src 'none'")
         );
 
-        assert_eq!(expr_path, vec!["http_expr", "", "<$>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+        // the report-only policy is certified, so changing it changes the root hash
+        assert_ne!(
+            asset_router.root_hash(),
+            csp_router("default-src 'self'; script-src 'none'").root_hash()
         );
-        assert_eq!(response, expected_response);
+    }
 
+    #[test]
+    fn test_method_responses() {
+        let mut asset_router = AssetRouter::default();
         asset_router
-            .delete_assets(
-                vec![Asset::new("index.html", index_html_body())],
-                vec![index_html_config()],
+            .certify_assets(
+                vec![Asset::new("submit", b"Submit form".as_slice())],
+                vec![],
+            )
+            .unwrap();
+        asset_router
+            .certify_method_response(
+                "/submit",
+                Method::POST,
+                HttpResponse::ok(b"Submitted".as_slice(), vec![]).build(),
             )
             .unwrap();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
-        assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == request.get_path().unwrap()
-        );
+        let serve = |asset_router: &AssetRouter, method: Method| {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url("/submit")
+                .build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+            let (witness, expr_path) = extract_witness_expr_path(&response);
+
+            assert_eq!(expr_path, vec!["http_expr", "submit", "<$>"]);
+            assert_matches!(
+                witness.lookup_subtree(&expr_path),
+                SubtreeLookupResult::Found(_)
+            );
+
+            response
+        };
+        let allow_header = |response: &HttpResponse| {
+            response
+                .headers()
+                .iter()
+                .find(|(name, _)| name == "allow")
+                .map(|(_, value)| value.clone())
+        };
+
+        let response = serve(&asset_router, Method::POST);
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), b"Submitted");
+
+        let response = serve(&asset_router, Method::GET);
+        assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(allow_header(&response).as_deref(), Some("POST"));
+
+        asset_router
+            .certify_method_response(
+                "/submit",
+                Method::GET,
+                HttpResponse::ok(b"<form></form>".as_slice(), vec![]).build(),
+            )
+            .unwrap();
+
+        let response = serve(&asset_router, Method::GET);
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), b"<form></form>");
+
+        let response = serve(&asset_router, Method::DELETE);
+        assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(allow_header(&response).as_deref(), Some("GET, POST"));
+
+        let mut expected_router = AssetRouter::default();
+        expected_router
+            .certify_assets(
+                vec![Asset::new("submit", b"Submit form".as_slice())],
+                vec![],
+            )
+            .unwrap();
+
+        asset_router
+            .delete_method_response("/submit", &Method::POST)
+            .unwrap();
+        asset_router
+            .delete_method_response("/submit", &Method::GET)
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), expected_router.root_hash());
+
+        let response = serve(&asset_router, Method::POST);
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), b"Submit form");
     }
 
     #[test]
-    fn test_one_chunk_long_asset_served_in_full() {
-        let asset_name = ONE_CHUNK_ASSET_NAME;
-        let long_asset_router =
-            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
-        let req_url = format!("/{asset_name}");
-        let asset_body = long_asset_body(asset_name);
-        // Request the entire "one-chunk"-asset, should obtain it in full.
-        let request = HttpRequest::get(&req_url).build();
-        let mut expected_response = build_200_response(
-            asset_body,
-            asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-            ],
-        );
+    fn test_allowed_methods() {
+        let assets = vec![Asset::new("index.html", index_html_body())];
+        let asset_configs = vec![AssetConfig::File {
+            path: "index.html".to_string(),
+            content_type: Some("text/html".to_string()),
+            headers: vec![],
+            fallback_for: vec![],
+            aliased_by: vec!["/".to_string()],
+            encodings: vec![],
+            options: Default::default(),
+        }];
 
-        let response = long_asset_router
-            .serve_asset(&data_certificate(), &request)
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
 
-        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
-        );
-        assert_eq!(response, expected_response);
-    }
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
 
-    #[rstest]
-    #[case(TWO_CHUNKS_ASSET_NAME)]
-    #[case(SIX_CHUNKS_ASSET_NAME)]
-    #[case(TEN_CHUNKS_ASSET_NAME)]
-    fn test_long_asset_served_in_chunks(#[case] asset_name: &str) {
-        let long_asset_router =
-            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
-        let req_url = format!("/{asset_name}");
-        let asset_body = long_asset_body(asset_name);
-        let asset_len = asset_body.len();
-        // Request the entire asset, should obtain the first chunk.
-        let request = HttpRequest::get(&req_url).build();
-        let mut expected_response = build_206_response(
-            asset_body[0..ASSET_CHUNK_SIZE].to_vec(),
-            asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-range".to_string(),
-                    format!("bytes 0-{}/{}", ASSET_CHUNK_SIZE - 1, asset_len),
-                ),
-            ],
-        );
+        let serve = |asset_router: &AssetRouter, method: Method, req_path: &str| {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url(req_path)
+                .build();
+            let response = fixture.serve(&asset_router, &request);
 
-        let response = long_asset_router
-            .serve_asset(&data_certificate(), &request)
+            (request, response)
+        };
+
+        for req_path in ["/index.html", "/"] {
+            for method in [Method::GET, Method::HEAD] {
+                let (request, response) = serve(&asset_router, method, req_path);
+                assert_eq!(response.status_code(), StatusCode::OK);
+                assert_eq!(response.body(), index_html_body());
+                assert!(fixture
+                    .verify(&request, response)
+                    .unwrap()
+                    .is_fully_certified());
+            }
+
+            for method in [Method::POST, Method::PUT, Method::DELETE] {
+                let (request, response) = serve(&asset_router, method, req_path);
+                assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+                assert_eq!(get_header(&response, "allow").as_deref(), Some("GET, HEAD"));
+
+                let verified_response = fixture
+                    .verify(&request, response.clone())
+                    .unwrap()
+                    .response
+                    .unwrap();
+                assert_eq!(verified_response.status_code, Some(405));
+                assert!(verified_response
+                    .headers
+                    .contains(&("allow".to_string(), "GET, HEAD".to_string())));
+
+                let (status_code, headers, body, upgrade) = response.into_parts();
+                let tampered_headers = headers
+                    .into_iter()
+                    .map(|(name, value)| match name.as_str() {
+                        "allow" => (name, "GET, HEAD, POST".to_string()),
+                        _ => (name, value),
+                    })
+                    .collect();
+                assert_matches!(
+                    fixture.verify(
+                        &request,
+                        HttpResponse::from_parts(status_code, tampered_headers, body, upgrade)
+                    ),
+                    Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+                );
+            }
+        }
+
+        asset_router
+            .certify_method_response(
+                "/",
+                Method::POST,
+                HttpResponse::ok(b"Submitted".as_slice(), vec![]).build(),
+            )
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
 
-        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+        let (_, response) = serve(&asset_router, Method::POST, "/");
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), b"Submitted");
+
+        let (_, response) = serve(&asset_router, Method::GET, "/");
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), index_html_body());
+
+        let (_, response) = serve(&asset_router, Method::DELETE, "/");
+        assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(
+            get_header(&response, "allow").as_deref(),
+            Some("GET, HEAD, POST")
         );
-        assert_eq!(response, expected_response);
 
-        // Request the subsequent chunks, should obtain them.
-        let expected_number_of_chunks =
-            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
-        let mut asset_len_so_far = response.body().len();
-        let mut number_of_chunks_so_far = 1;
-        while asset_len_so_far < asset_len {
-            let chunk_request = HttpRequest::get(&req_url)
-                .with_headers(vec![(
-                    "range".to_string(),
-                    format!("bytes={}-", asset_len_so_far),
-                )])
+        asset_router
+            .delete_method_response("/", &Method::POST)
+            .unwrap();
+        asset_router.delete_assets(assets, asset_configs).unwrap();
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    }
+
+    #[test]
+    fn test_empty_allowed_methods_serve_any_method() {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![AssetConfig::file("index.html")
+                    .with_content_type("text/html")
+                    .with_allowed_methods(vec![])
+                    .build()],
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+        for method in [Method::GET, Method::POST, Method::PUT] {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url("/index.html")
                 .build();
-            let expected_range_end = cmp::min(asset_len_so_far + ASSET_CHUNK_SIZE, asset_len) - 1;
-            let mut expected_response = build_206_response(
-                asset_body[asset_len_so_far..=expected_range_end].to_vec(),
-                asset_range_chunk_cel_expr(),
-                vec![
-                    (
-                        "cache-control".to_string(),
-                        "public, no-cache, no-store".to_string(),
-                    ),
-                    ("content-type".to_string(), "text/html".to_string()),
-                    (
-                        "content-range".to_string(),
-                        format!(
-                            "bytes {}-{}/{}",
-                            asset_len_so_far, expected_range_end, asset_len
-                        ),
-                    ),
-                ],
-            );
-            let response = long_asset_router
-                .serve_asset(&data_certificate(), &chunk_request)
-                .unwrap();
-            let (witness, expr_path) = extract_witness_expr_path(&response);
-            assert_matches!(
-                witness.lookup_subtree(&expr_path),
-                SubtreeLookupResult::Found(_)
-            );
-            add_v2_certificate_header(
-                &data_certificate(),
-                &mut expected_response,
-                &witness,
-                &expr_path,
-            );
-            assert_eq!(response, expected_response);
-            asset_len_so_far += response.body().len();
-            number_of_chunks_so_far += 1;
+            let response = fixture.serve(&asset_router, &request);
+
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), index_html_body());
+            assert!(get_header(&response, "allow").is_none());
         }
-        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks)
     }
 
-    #[rstest]
-    #[case(TWO_CHUNKS_ASSET_NAME)]
-    #[case(SIX_CHUNKS_ASSET_NAME)]
-    #[case(TEN_CHUNKS_ASSET_NAME)]
-    fn test_long_asset_deletion_removes_chunks(#[case] asset_name: &str) {
-        let mut long_asset_router =
-            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity]);
-        let req_url = format!("/{asset_name}");
-        let asset_body = long_asset_body(asset_name);
-        let asset_len = asset_body.len();
-        let mut all_requests = vec![];
-        // Request the entire asset and the chunks, all should succeed.
-        // First the asset...
-        let request = HttpRequest::get(&req_url).build();
-        let response = long_asset_router
-            .serve_asset(&data_certificate(), &request)
+    #[test]
+    fn test_cors() {
+        let cors = AssetCorsConfig::new("https://app.example.com")
+            .with_allowed_methods(vec![Method::GET, Method::HEAD, Method::POST])
+            .with_allowed_headers(vec!["Content-Type".to_string(), "X-Api-Key".to_string()])
+            .with_max_age(600);
+        let assets = vec![Asset::new("index.html", index_html_body())];
+        let asset_configs = vec![
+            AssetConfig::File {
+                path: "index.html".to_string(),
+                content_type: Some("text/html".to_string()),
+                headers: vec![],
+                fallback_for: vec![],
+                aliased_by: vec!["/".to_string()],
+                encodings: vec![],
+                options: Default::default(),
+            },
+            AssetConfig::Redirect {
+                from: "/home".to_string(),
+                to: "/".to_string(),
+                kind: AssetRedirectKind::Permanent,
+                headers: vec![],
+            },
+        ];
+
+        let mut asset_router = AssetRouter::default().with_cors(cors);
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
 
-        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
-        );
-        assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
-        all_requests.push(request);
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
 
-        // ... then the subsequent chunks.
-        let expected_number_of_chunks =
-            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
-        let mut asset_len_so_far = response.body().len();
-        let mut number_of_chunks_so_far = 1;
-        while asset_len_so_far < asset_len {
-            let chunk_request = HttpRequest::get(&req_url)
+        let serve = |method: Method, req_path: &str| {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url(req_path)
                 .with_headers(vec![(
-                    "range".to_string(),
-                    format!("bytes={}-", asset_len_so_far),
+                    "origin".to_string(),
+                    "https://app.example.com".to_string(),
                 )])
                 .build();
-            let response = long_asset_router
-                .serve_asset(&data_certificate(), &chunk_request)
+            let response = fixture.serve(&asset_router, &request);
+
+            (request, response)
+        };
+        let tamper = |response: HttpResponse| {
+            let (status_code, headers, body, upgrade) = response.into_parts();
+            let headers = headers
+                .into_iter()
+                .map(|(name, value)| match name.as_str() {
+                    "access-control-allow-origin" => (name, "*".to_string()),
+                    _ => (name, value),
+                })
+                .collect();
+
+            HttpResponse::from_parts(status_code, headers, body, upgrade)
+        };
+        let certified_header = |response: HttpResponse, request: HttpRequest, name: &str| {
+            let verified_response = fixture
+                .verify(&request, response)
+                .unwrap()
+                .response
                 .unwrap();
-            let (witness, expr_path) = extract_witness_expr_path(&response);
+
+            verified_response
+                .headers
+                .into_iter()
+                .find(|(header_name, _)| header_name == name)
+                .map(|(_, value)| value)
+        };
+
+        for (method, req_path, expected_status_code) in [
+            (Method::GET, "/index.html", StatusCode::OK),
+            (Method::GET, "/", StatusCode::OK),
+            (Method::GET, "/home", StatusCode::MOVED_PERMANENTLY),
+        ] {
+            let (request, response) = serve(method, req_path);
+            assert_eq!(response.status_code(), expected_status_code);
+            assert_eq!(
+                certified_header(
+                    response.clone(),
+                    request.clone(),
+                    "access-control-allow-origin"
+                )
+                .as_deref(),
+                Some("https://app.example.com")
+            );
+            assert!(get_header(&response, "access-control-allow-methods").is_none());
             assert_matches!(
-                witness.lookup_subtree(&expr_path),
-                SubtreeLookupResult::Found(_)
+                fixture.verify(&request, tamper(response)),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
             );
-            assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
-            asset_len_so_far += response.body().len();
-            number_of_chunks_so_far += 1;
-            all_requests.push(chunk_request);
         }
-        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks);
-        assert_eq!(all_requests.len(), expected_number_of_chunks);
 
-        // Delete the asset.
-        long_asset_router
-            .delete_assets(
-                vec![Asset::new(&req_url, asset_body)],
-                vec![long_asset_config(asset_name)],
-            )
-            .expect("Asset deletion failed");
+        for req_path in ["/index.html", "/"] {
+            let (request, response) = serve(Method::OPTIONS, req_path);
+            assert_eq!(response.status_code(), StatusCode::NO_CONTENT);
+            assert!(response.body().is_empty());
+            for (name, expected_value) in [
+                ("access-control-allow-origin", "https://app.example.com"),
+                ("access-control-allow-methods", "GET, HEAD, POST"),
+                ("access-control-allow-headers", "Content-Type, X-Api-Key"),
+                ("access-control-max-age", "600"),
+            ] {
+                assert_eq!(
+                    certified_header(response.clone(), request.clone(), name).as_deref(),
+                    Some(expected_value)
+                );
+            }
+            assert_matches!(
+                fixture.verify(&request, tamper(response.clone())),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+            );
 
-        // Re-request the asset and the chunks, all should fail.
-        for request in all_requests {
-            let result = long_asset_router.serve_asset(&data_certificate(), &request);
+            let (get_request, _) = serve(Method::GET, req_path);
             assert_matches!(
-                result,
-                Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                    request_url,
-                 }) if request_url == request.get_path().unwrap()
+                fixture.verify(&get_request, response),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
             );
         }
+
+        asset_router
+            .certify_method_response(
+                "/",
+                Method::OPTIONS,
+                HttpResponse::ok(b"Custom preflight".as_slice(), vec![]).build(),
+            )
+            .unwrap();
+        let (_, response) = serve(Method::OPTIONS, "/");
+        assert_eq!(response.body(), b"Custom preflight");
+        asset_router
+            .delete_method_response("/", &Method::OPTIONS)
+            .unwrap();
+
+        asset_router.delete_assets(assets, asset_configs).unwrap();
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
     }
 
-    #[rstest]
-    #[case(SIX_CHUNKS_ASSET_NAME, "deflate", AssetEncoding::Deflate)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "deflate, identity", AssetEncoding::Deflate)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "gzip", AssetEncoding::Gzip)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, identity", AssetEncoding::Gzip)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, deflate", AssetEncoding::Gzip)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "gzip, deflate, identity", AssetEncoding::Gzip)]
-    #[case(SIX_CHUNKS_ASSET_NAME, "br", AssetEncoding::Brotli)]
-    #[case(
-        SIX_CHUNKS_ASSET_NAME,
-        "br, gzip, deflate, identity",
-        AssetEncoding::Brotli
-    )]
-    #[case(
-        SIX_CHUNKS_ASSET_NAME,
-        "gzip, deflate, identity, br",
-        AssetEncoding::Brotli
-    )]
-    fn test_encoded_long_asset_served_in_encoded_chunks(
-        #[case] asset_name: &str,
-        #[case] accept_encoding: &str,
-        #[case] expected_encoding: AssetEncoding,
-    ) {
-        let (_, expected_encoding_suffix) = expected_encoding.default_config();
-        let long_asset_router = long_asset_router_with_params(
-            &[asset_name],
-            &[AssetEncoding::Identity, expected_encoding],
+    #[test]
+    fn test_variants() {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_variants(
+                "/home",
+                "X-Locale",
+                HttpResponse::ok(b"Hello".as_slice(), vec![]).build(),
+                vec![
+                    (
+                        "fr".to_string(),
+                        HttpResponse::ok(b"Bonjour".as_slice(), vec![]).build(),
+                    ),
+                    (
+                        "de".to_string(),
+                        HttpResponse::ok(b"Hallo".as_slice(), vec![]).build(),
+                    ),
+                ],
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request_with_locale = |locale: &str| {
+            HttpRequest::get("/home")
+                .with_headers(vec![("x-locale".to_string(), locale.to_string())])
+                .build()
+        };
+
+        for (request, expected_body) in [
+            (request_with_locale("fr"), b"Bonjour".as_slice()),
+            (request_with_locale("de"), b"Hallo".as_slice()),
+            (HttpRequest::get("/home").build(), b"Hello".as_slice()),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), expected_body);
+            assert_eq!(get_header(&response, "vary").as_deref(), Some("X-Locale"));
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+            assert_eq!(verification_info.response.unwrap().body, expected_body);
+        }
+
+        assert_matches!(
+            asset_router.serve_asset(&data_certificate(), &request_with_locale("es")),
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
         );
-        let req_url = format!("/{asset_name}");
-        let encoded_asset_name = format!("{asset_name}{expected_encoding_suffix}");
-        let asset_body = long_asset_body(&encoded_asset_name);
-        let asset_len = asset_body.len();
 
-        let request = HttpRequest::get(&req_url)
-            .with_headers(vec![(
-                "accept-encoding".to_string(),
-                accept_encoding.to_string(),
-            )])
-            .build();
-        let mut expected_response = build_206_response(
-            asset_body[0..ASSET_CHUNK_SIZE].to_vec(),
-            asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-encoding".to_string(),
-                    expected_encoding.to_string(),
-                ),
-                (
-                    "content-range".to_string(),
-                    format!("bytes 0-{}/{}", ASSET_CHUNK_SIZE - 1, asset_len),
-                ),
-            ],
+        let fr_response = fixture.serve(&asset_router, &request_with_locale("fr"));
+        assert_matches!(
+            fixture.verify(&request_with_locale("de"), fr_response.clone()),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
-        let response = long_asset_router
-            .serve_asset(&data_certificate(), &request)
-            .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
+        assert_matches!(
+            fixture.verify(&HttpRequest::get("/home").build(), fr_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
 
-        assert_eq!(
-            expr_path,
-            HttpCertificationPath::exact(req_url.clone()).to_expr_path()
+        let default_response = fixture.serve(&asset_router, &HttpRequest::get("/home").build());
+        for locale in ["fr", "es"] {
+            assert_matches!(
+                fixture.verify(&request_with_locale(locale), default_response.clone()),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+            );
+        }
+
+        asset_router
+            .certify_variants(
+                "/home",
+                "X-Locale",
+                HttpResponse::ok(b"Hi".as_slice(), vec![]).build(),
+                vec![(
+                    "it".to_string(),
+                    HttpResponse::ok(b"Ciao".as_slice(), vec![]).build(),
+                )],
+            )
+            .unwrap();
+        assert_matches!(
+            asset_router.serve_asset(&data_certificate(), &request_with_locale("fr")),
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
         );
+        let response = fixture.serve(&asset_router, &request_with_locale("it"));
+        assert_eq!(response.body(), b"Ciao");
+        let response = fixture.serve(&asset_router, &HttpRequest::get("/home").build());
+        assert_eq!(response.body(), b"Hi");
+
+        asset_router.delete_variants("/home");
         assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+            asset_router.serve_asset(&data_certificate(), &HttpRequest::get("/home").build()),
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
         );
-        assert_eq!(response, expected_response);
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    }
 
-        // Request the subsequent chunks, should obtain them.
-        let expected_number_of_chunks =
-            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
-        let mut asset_len_so_far = response.body().len();
-        let mut number_of_chunks_so_far = 1;
-        while asset_len_so_far < asset_len {
-            let chunk_request = HttpRequest::get(&req_url)
-                .with_headers(vec![
-                    ("range".to_string(), format!("bytes={}-", asset_len_so_far)),
-                    ("accept-encoding".to_string(), accept_encoding.to_string()),
-                ])
-                .build();
-            let expected_range_end = cmp::min(asset_len_so_far + ASSET_CHUNK_SIZE, asset_len) - 1;
-            let mut expected_response = build_206_response(
-                asset_body[asset_len_so_far..=expected_range_end].to_vec(),
-                encoded_range_chunk_asset_cel_expr(),
+    #[test]
+    fn test_variants_with_selector() {
+        let mut asset_router = AssetRouter::default()
+            .with_build_id("build-1")
+            .with_cors(AssetCorsConfig::new("https://example.com"));
+        asset_router
+            .certify_variants_with_selector(
+                "/home",
+                "Cookie",
+                vec![
+                    "group=a".to_string(),
+                    "group=b".to_string(),
+                    "consent=yes; group=b".to_string(),
+                    "consent=yes".to_string(),
+                    "group=c".to_string(),
+                ],
+                |cookies| {
+                    cookies
+                        .split("; ")
+                        .find_map(|cookie| cookie.strip_prefix("group="))
+                },
+                HttpResponse::ok(b"Hello".as_slice(), vec![]).build(),
                 vec![
                     (
-                        "cache-control".to_string(),
-                        "public, no-cache, no-store".to_string(),
-                    ),
-                    ("content-type".to_string(), "text/html".to_string()),
-                    (
-                        "content-encoding".to_string(),
-                        expected_encoding.to_string(),
+                        "a".to_string(),
+                        HttpResponse::ok(b"Hello A".as_slice(), vec![]).build(),
                     ),
                     (
-                        "content-range".to_string(),
-                        format!(
-                            "bytes {}-{}/{}",
-                            asset_len_so_far, expected_range_end, asset_len
-                        ),
+                        "b".to_string(),
+                        HttpResponse::ok(b"Hello B".as_slice(), vec![]).build(),
                     ),
                 ],
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request_with_cookie = |cookie: &str| {
+            HttpRequest::get("/home")
+                .with_headers(vec![("cookie".to_string(), cookie.to_string())])
+                .build()
+        };
+
+        for (request, expected_body) in [
+            (request_with_cookie("group=a"), b"Hello A".as_slice()),
+            (request_with_cookie("group=b"), b"Hello B".as_slice()),
+            (
+                request_with_cookie("consent=yes; group=b"),
+                b"Hello B".as_slice(),
+            ),
+            (request_with_cookie("consent=yes"), b"Hello".as_slice()),
+            (HttpRequest::get("/home").build(), b"Hello".as_slice()),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), expected_body);
+            assert_eq!(get_header(&response, "vary").as_deref(), Some("Cookie"));
+            assert_eq!(
+                get_header(&response, BUILD_ID_HEADER_NAME).as_deref(),
+                Some("build-1")
             );
-            let response = long_asset_router
-                .serve_asset(&data_certificate(), &chunk_request)
-                .unwrap();
-            let (witness, expr_path) = extract_witness_expr_path(&response);
-            assert_matches!(
-                witness.lookup_subtree(&expr_path),
-                SubtreeLookupResult::Found(_)
+            assert_eq!(
+                get_header(&response, "access-control-allow-origin").as_deref(),
+                Some("https://example.com")
             );
-            add_v2_certificate_header(
-                &data_certificate(),
-                &mut expected_response,
-                &witness,
-                &expr_path,
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+            assert_eq!(verification_info.response.unwrap().body, expected_body);
+        }
+
+        for cookie in ["group=c", "group=b; consent=yes"] {
+            assert_matches!(
+                asset_router.serve_asset(&data_certificate(), &request_with_cookie(cookie)),
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
             );
-            assert_eq!(response, expected_response);
-            asset_len_so_far += response.body().len();
-            number_of_chunks_so_far += 1;
         }
-        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks)
+
+        let b_response = fixture.serve(&asset_router, &request_with_cookie("group=b"));
+        assert_matches!(
+            fixture.verify(&request_with_cookie("consent=yes; group=b"), b_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+
+        let consent_response = fixture.serve(&asset_router, &request_with_cookie("consent=yes"));
+        assert_matches!(
+            fixture.verify(&request_with_cookie("group=b"), consent_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+
+        asset_router.delete_variants("/home");
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
     }
 
     #[rstest]
-    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Brotli)]
-    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Gzip)]
-    #[case(TWO_CHUNKS_ASSET_NAME, AssetEncoding::Deflate)]
-    fn test_encoded_long_asset_deletion_removes_encoded_chunks(
-        #[case] asset_name: &str,
-        #[case] encoding: AssetEncoding,
-    ) {
-        let (_, encoding_suffix) = encoding.default_config();
-        let mut long_asset_router =
-            long_asset_router_with_params(&[asset_name], &[AssetEncoding::Identity, encoding]);
-        let req_url = format!("/{asset_name}");
-        let encoded_asset_name = format!("{asset_name}{encoding_suffix}");
-        let encoded_asset_body = long_asset_body(&encoded_asset_name);
-        let asset_len = encoded_asset_body.len();
-        let mut all_requests = vec![];
-        // Request the entire asset and the chunks, all should succeed.
-        // First the asset...
-        let request = HttpRequest::get(&req_url)
-            .with_headers(vec![("accept-encoding".to_string(), encoding.to_string())])
-            .build();
-        let response = long_asset_router
-            .serve_asset(&data_certificate(), &request)
+    fn test_media_type_fallbacks(index_html_config: AssetConfig) {
+        let not_found_html_body = b"<h1>Not found</h1>".as_slice();
+        let not_found_json_body = br#"{"error":"not found"}"#.as_slice();
+        let not_found_html_config = AssetConfig::File {
+            path: "404.html".to_string(),
+            content_type: Some("text/html".to_string()),
+            headers: vec![],
+            fallback_for: vec![AssetFallbackConfig {
+                scope: "/api".to_string(),
+                status_code: Some(StatusCode::NOT_FOUND),
+            }],
+            aliased_by: vec![],
+            encodings: vec![],
+            options: AssetFileOptions {
+                addressable: false,
+                ..Default::default()
+            },
+        };
+
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("404.html", not_found_html_body),
+                ],
+                vec![index_html_config.clone(), not_found_html_config.clone()],
+            )
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
+        let expected_root_hash = asset_router.root_hash();
+        for scope in ["/api", "/data"] {
+            asset_router
+                .certify_media_type_fallback(
+                    scope,
+                    "application/json",
+                    HttpResponse::not_found(not_found_json_body, vec![]).build(),
+                )
+                .unwrap();
+        }
 
-        assert_eq!(expr_path, vec!["http_expr", &req_url[1..], "<$>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request_with_accept = |path: &str, accept: Option<&str>| {
+            HttpRequest::get(path)
+                .with_headers(
+                    accept
+                        .map(|accept| ("accept".to_string(), accept.to_string()))
+                        .into_iter()
+                        .collect(),
+                )
+                .build()
+        };
+        let index_html_body = index_html_body();
+
+        for (request, expected_status_code, expected_body) in [
+            (
+                request_with_accept("/api/missing", Some("application/json")),
+                StatusCode::NOT_FOUND,
+                not_found_json_body,
+            ),
+            (
+                request_with_accept("/api/missing", Some("application/json, text/html;q=0.5")),
+                StatusCode::NOT_FOUND,
+                not_found_json_body,
+            ),
+            (
+                request_with_accept("/api/missing", Some("text/html")),
+                StatusCode::NOT_FOUND,
+                not_found_html_body,
+            ),
+            (
+                request_with_accept("/api/missing", Some("*/*")),
+                StatusCode::NOT_FOUND,
+                not_found_html_body,
+            ),
+            (
+                request_with_accept("/api/missing", Some("image/png")),
+                StatusCode::NOT_FOUND,
+                not_found_html_body,
+            ),
+            (
+                request_with_accept("/api/missing", None),
+                StatusCode::NOT_FOUND,
+                not_found_html_body,
+            ),
+            (
+                request_with_accept("/data/missing", Some("application/json")),
+                StatusCode::NOT_FOUND,
+                not_found_json_body,
+            ),
+            (
+                request_with_accept("/data/missing", Some("text/html")),
+                StatusCode::NOT_FOUND,
+                not_found_json_body,
+            ),
+            (
+                request_with_accept("/missing", Some("application/json")),
+                StatusCode::OK,
+                index_html_body.as_slice(),
+            ),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), expected_status_code);
+            assert_eq!(response.body(), expected_body);
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert_eq!(verification_info.response.unwrap().body, expected_body);
+        }
+
+        let response = fixture.serve(
+            &asset_router,
+            &request_with_accept("/api/missing", Some("application/json")),
+        );
+        assert_eq!(
+            get_header(&response, "content-type").as_deref(),
+            Some("application/json")
         );
-        assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
-        all_requests.push(request);
 
-        // ... then the subsequent chunks.
-        let expected_number_of_chunks =
-            (asset_len as f32 / ASSET_CHUNK_SIZE as f32).ceil() as usize;
-        let mut asset_len_so_far = response.body().len();
-        let mut number_of_chunks_so_far = 1;
-        while asset_len_so_far < asset_len {
-            let chunk_request = HttpRequest::get(&req_url)
-                .with_headers(vec![
-                    ("range".to_string(), format!("bytes={asset_len_so_far}-")),
-                    ("accept-encoding".to_string(), encoding.to_string()),
-                ])
-                .build();
-            let response = long_asset_router
-                .serve_asset(&data_certificate(), &chunk_request)
-                .unwrap();
-            let (witness, expr_path) = extract_witness_expr_path(&response);
-            assert_matches!(
-                witness.lookup_subtree(&expr_path),
-                SubtreeLookupResult::Found(_)
-            );
-            assert_eq!(response.status_code(), StatusCode::PARTIAL_CONTENT);
-            asset_len_so_far += response.body().len();
-            number_of_chunks_so_far += 1;
-            all_requests.push(chunk_request);
+        for (request, expected_vary) in [
+            (
+                request_with_accept("/api/missing", Some("application/json")),
+                Some("Accept"),
+            ),
+            (
+                request_with_accept("/api/missing", Some("text/html")),
+                Some("Accept"),
+            ),
+            (request_with_accept("/api/missing", None), Some("Accept")),
+            (
+                request_with_accept("/data/missing", Some("text/html")),
+                Some("Accept"),
+            ),
+            (
+                request_with_accept("/missing", Some("application/json")),
+                None,
+            ),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(get_header(&response, "vary").as_deref(), expected_vary);
         }
-        assert_eq!(number_of_chunks_so_far, expected_number_of_chunks);
-        assert_eq!(all_requests.len(), expected_number_of_chunks);
 
-        // Delete the asset.
-        long_asset_router
-            .delete_assets(
+        let mut reordered_asset_router = AssetRouter::default();
+        for scope in ["/api", "/data"] {
+            reordered_asset_router
+                .certify_media_type_fallback(
+                    scope,
+                    "application/json",
+                    HttpResponse::not_found(not_found_json_body, vec![]).build(),
+                )
+                .unwrap();
+        }
+        reordered_asset_router
+            .certify_assets(
                 vec![
-                    Asset::new(&req_url, long_asset_body(asset_name)),
-                    Asset::new(&format!("/{encoded_asset_name}"), encoded_asset_body),
+                    Asset::new("index.html", index_html_body.clone()),
+                    Asset::new("404.html", not_found_html_body),
                 ],
-                vec![long_asset_config(&req_url)],
+                vec![index_html_config.clone(), not_found_html_config.clone()],
             )
-            .expect("Asset deletion failed");
+            .unwrap();
+        assert_eq!(reordered_asset_router.root_hash(), asset_router.root_hash());
 
-        // Re-request the asset and the chunks, all should fail.
-        for request in all_requests {
-            let result = long_asset_router.serve_asset(&data_certificate(), &request);
-            assert_matches!(
-                result,
-                Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                    request_url,
-                 }) if request_url == request.get_path().unwrap()
-            );
-        }
+        asset_router
+            .delete_media_type_fallback("/api", "application/json")
+            .unwrap();
+        let response = fixture.serve(
+            &asset_router,
+            &request_with_accept("/api/missing", Some("application/json")),
+        );
+        assert_eq!(response.body(), not_found_html_body);
+        assert_eq!(get_header(&response, "vary"), None);
+
+        reordered_asset_router
+            .delete_assets(
+                vec![Asset::new("404.html", not_found_html_body)],
+                vec![not_found_html_config.clone()],
+            )
+            .unwrap();
+        reordered_asset_router
+            .delete_media_type_fallback("/api", "application/json")
+            .unwrap();
+        reordered_asset_router
+            .certify_assets(
+                vec![Asset::new("404.html", not_found_html_body)],
+                vec![not_found_html_config.clone()],
+            )
+            .unwrap();
+        assert_eq!(reordered_asset_router.root_hash(), asset_router.root_hash());
+
+        asset_router
+            .delete_media_type_fallback("/data", "application/json")
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), expected_root_hash);
     }
 
     #[rstest]
-    #[case(index_html_zz_body(), "/", "deflate", "deflate")]
-    #[case(index_html_zz_body(), "/", "deflate, identity", "deflate")]
-    #[case(index_html_gz_body(), "/", "gzip", "gzip")]
-    #[case(index_html_gz_body(), "/", "gzip, identity", "gzip")]
-    #[case(index_html_gz_body(), "/", "gzip, deflate", "gzip")]
-    #[case(index_html_gz_body(), "/", "gzip, deflate, identity", "gzip")]
-    #[case(index_html_br_body(), "/", "br", "br")]
-    #[case(index_html_br_body(), "/", "br, gzip, deflate, identity", "br")]
-    #[case(index_html_br_body(), "/", "gzip, deflate, identity, br", "br")]
-    #[case(index_html_zz_body(), "/index.html", "deflate", "deflate")]
-    #[case(index_html_zz_body(), "/index.html", "deflate, identity", "deflate")]
-    #[case(index_html_gz_body(), "/index.html", "gzip", "gzip")]
-    #[case(index_html_gz_body(), "/index.html", "gzip, identity", "gzip")]
-    #[case(index_html_gz_body(), "/index.html", "gzip, deflate", "gzip")]
-    #[case(index_html_gz_body(), "/index.html", "gzip, deflate, identity", "gzip")]
-    #[case(index_html_br_body(), "/index.html", "br", "br")]
-    #[case(
-        index_html_br_body(),
-        "/index.html",
-        "br, gzip, deflate, identity",
-        "br"
-    )]
-    #[case(
-        index_html_br_body(),
-        "/index.html",
-        "gzip, deflate, identity, br",
-        "br"
-    )]
-    fn test_encoded_index_html(
-        #[case] expected_body: Vec<u8>,
-        #[case] req_url: &str,
-        #[case] accept_encoding: &str,
-        #[case] expected_encoding: &str,
-        mut asset_router: AssetRouter,
-    ) {
-        let request = HttpRequest::get(req_url)
-            .with_headers(vec![(
-                "accept-encoding".to_string(),
-                accept_encoding.to_string(),
-            )])
-            .build();
-        let mut expected_response = build_200_response(
-            expected_body,
-            encoded_asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-encoding".to_string(),
-                    expected_encoding.to_string(),
-                ),
-            ],
+    fn test_maintenance(index_html_config: AssetConfig) {
+        let mut asset_router = AssetRouter::default().with_build_id("1.2.3");
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![index_html_config.clone()],
+            )
+            .unwrap();
+        assert!(!asset_router.is_in_maintenance());
+
+        let maintenance_root_hash = asset_router.enter_maintenance(120).unwrap();
+        assert!(asset_router.is_in_maintenance());
+        assert_eq!(maintenance_root_hash, asset_router.root_hash());
+        assert_ne!(
+            maintenance_root_hash,
+            asset_router.tree.borrow().root_hash()
+        );
+
+        asset_router
+            .certify_assets(
+                vec![Asset::new(
+                    "app.js",
+                    b"console.log('Hello World!');".as_slice(),
+                )],
+                vec![],
+            )
+            .unwrap();
+
+        assert_eq!(maintenance_root_hash, asset_router.root_hash());
+        let fixture = VerificationFixture::new(&maintenance_root_hash);
+
+        for request in [
+            HttpRequest::get("/").build(),
+            HttpRequest::get("/index.html").build(),
+            HttpRequest::get("/app.js").build(),
+            HttpRequest::get("/not-found").build(),
+            HttpRequest::post("/index.html").build(),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(
+                get_header(&response, "retry-after"),
+                Some("120".to_string())
+            );
+            assert_eq!(
+                get_header(&response, BUILD_ID_HEADER_NAME),
+                Some("1.2.3".to_string())
+            );
+            assert!(response.body().is_empty());
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+            assert_eq!(verification_info.response.unwrap().status_code, Some(503));
+        }
+
+        let root_hash = asset_router.exit_maintenance();
+        assert!(!asset_router.is_in_maintenance());
+        assert_eq!(root_hash, asset_router.root_hash());
+
+        let fixture = VerificationFixture::new(&root_hash);
+
+        for (req_url, expected_body) in [
+            ("/index.html", index_html_body()),
+            ("/app.js", b"console.log('Hello World!');".as_slice()),
+        ] {
+            let request = HttpRequest::get(req_url).build();
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), expected_body);
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+        }
+    }
+
+    #[rstest]
+    fn test_maintenance_with_shared_tree(index_html_config: AssetConfig) {
+        let http_certification_tree: Rc<RefCell<HttpCertificationTree>> = Default::default();
+        let mut asset_router = AssetRouter::with_tree(http_certification_tree.clone());
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![index_html_config],
+            )
+            .unwrap();
+        let root_hash = asset_router.root_hash();
+
+        assert_matches!(
+            asset_router.enter_maintenance(120),
+            Err(AssetCertificationError::MaintenanceWithSharedTree)
         );
+        assert!(!asset_router.is_in_maintenance());
+        assert_eq!(asset_router.root_hash(), root_hash);
+        assert_eq!(http_certification_tree.borrow().root_hash(), root_hash);
+
         let response = asset_router
-            .serve_asset(&data_certificate(), &request)
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/index.html").build(),
+            )
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        drop(http_certification_tree);
+        asset_router.enter_maintenance(120).unwrap();
+        assert!(asset_router.is_in_maintenance());
+    }
+
+    #[test]
+    fn test_refresh_redirect_response_passes_verification() {
+        let body = br#"<!DOCTYPE html><meta http-equiv="refresh" content="0; url=/new-page">"#;
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(vec![Asset::new("new-page", b"New page".as_slice())], vec![])
+            .unwrap();
+        asset_router
+            .certify_method_response(
+                "/old-page",
+                Method::GET,
+                HttpResponse::ok(
+                    body.as_slice(),
+                    vec![
+                        ("content-type".to_string(), "text/html".to_string()),
+                        ("refresh".to_string(), "0; url=/new-page".to_string()),
+                    ],
+                )
+                .build(),
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request = HttpRequest::get("/old-page").build();
+        let response = fixture.serve(&asset_router, &request);
+        assert_eq!(response.status_code(), StatusCode::OK);
+
+        let verification_info = fixture.verify(&request, response.clone()).unwrap();
+        assert!(verification_info.is_fully_certified());
+        let verified_response = verification_info.response.unwrap();
+        assert_eq!(verified_response.status_code, Some(200));
+        assert_eq!(verified_response.body, body.to_vec());
+        assert!(verified_response
+            .headers
+            .contains(&("refresh".to_string(), "0; url=/new-page".to_string())));
+
+        let (status_code, headers, body, upgrade) = response.into_parts();
+        let tampered_headers = headers
+            .iter()
+            .map(|(name, value)| match name.as_str() {
+                "refresh" => (name.clone(), "0; url=https://phishing.example".to_string()),
+                _ => (name.clone(), value.clone()),
+            })
+            .collect();
+        let tampered_refresh_response =
+            HttpResponse::from_parts(status_code, tampered_headers, body, upgrade);
+        assert_matches!(
+            fixture.verify(&request, tampered_refresh_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
 
-        assert_eq!(
-            expr_path,
-            HttpCertificationPath::exact(req_url).to_expr_path()
+        let tampered_body_response = HttpResponse::from_parts(
+            status_code,
+            headers,
+            br#"<meta http-equiv="refresh" content="0; url=https://phishing.example">"#.as_slice(),
+            upgrade,
         );
         assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+            fixture.verify(&request, tampered_body_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
-        assert_eq!(response, expected_response);
+    }
 
+    #[rstest]
+    #[case(AssetETagKind::Strong)]
+    #[case(AssetETagKind::Weak)]
+    fn test_etags(#[case] etag_kind: AssetETagKind) {
+        let mut asset_router = AssetRouter::default().with_etags(etag_kind);
         asset_router
-            .delete_assets(
+            .certify_assets(
                 vec![
                     Asset::new("index.html", index_html_body()),
                     Asset::new("index.html.gz", index_html_gz_body()),
-                    Asset::new("index.html.zz", index_html_zz_body()),
-                    Asset::new("index.html.br", index_html_br_body()),
                 ],
                 vec![index_html_config()],
             )
             .unwrap();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
-        assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == req_url
-        );
+        let identity_request = HttpRequest::get("/index.html")
+            .with_headers(vec![(
+                "Accept-Encoding".to_string(),
+                "identity".to_string(),
+            )])
+            .build();
+        let identity_response = asset_router
+            .serve_asset(&data_certificate(), &identity_request)
+            .unwrap();
+        let identity_etag = get_header(&identity_response, "etag").unwrap();
+
+        let gzip_request = HttpRequest::get("/index.html")
+            .with_headers(vec![("Accept-Encoding".to_string(), "gzip".to_string())])
+            .build();
+        let gzip_response = asset_router
+            .serve_asset(&data_certificate(), &gzip_request)
+            .unwrap();
+        let gzip_etag = get_header(&gzip_response, "etag").unwrap();
+
+        assert_eq!(identity_response.status_code(), StatusCode::OK);
+        assert_eq!(gzip_response.status_code(), StatusCode::OK);
+        match etag_kind {
+            AssetETagKind::Strong => {
+                assert!(identity_etag.starts_with('"'));
+                assert!(gzip_etag.starts_with('"'));
+                assert_ne!(identity_etag, gzip_etag);
+            }
+            AssetETagKind::Weak => {
+                assert!(identity_etag.starts_with("W/\""));
+                assert_eq!(identity_etag, gzip_etag);
+            }
+        }
     }
 
     #[rstest]
-    #[case(index_html_zz_body(), "/something", "deflate", "deflate")]
-    #[case(index_html_zz_body(), "/something", "deflate, identity", "deflate")]
-    #[case(index_html_gz_body(), "/something", "gzip", "gzip")]
-    #[case(index_html_gz_body(), "/something", "gzip, identity", "gzip")]
-    #[case(index_html_gz_body(), "/something", "gzip, deflate", "gzip")]
-    #[case(index_html_gz_body(), "/something", "gzip, deflate, identity", "gzip")]
-    #[case(index_html_br_body(), "/something", "br", "br")]
-    #[case(
-        index_html_br_body(),
-        "/something",
-        "br, gzip, deflate, identity",
-        "br"
-    )]
-    #[case(
-        index_html_br_body(),
-        "/something",
-        "gzip, deflate, identity, br",
-        "br"
-    )]
-    #[case(index_html_zz_body(), "/assets/css/app.css", "deflate", "deflate")]
-    #[case(
-        index_html_zz_body(),
-        "/assets/css/app.css",
-        "deflate, identity",
-        "deflate"
-    )]
-    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip", "gzip")]
-    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip, identity", "gzip")]
-    #[case(index_html_gz_body(), "/assets/css/app.css", "gzip, deflate", "gzip")]
-    #[case(
-        index_html_gz_body(),
-        "/assets/css/app.css",
-        "gzip, deflate, identity",
-        "gzip"
-    )]
-    #[case(index_html_br_body(), "/assets/css/app.css", "br", "br")]
-    #[case(
-        index_html_br_body(),
-        "/assets/css/app.css",
-        "br, gzip, deflate, identity",
-        "br"
-    )]
-    #[case(
-        index_html_br_body(),
-        "/assets/css/app.css",
-        "gzip, deflate, identity, br",
-        "br"
-    )]
-    fn test_encoded_index_html_fallback(
-        #[case] expected_body: Vec<u8>,
-        #[case] req_url: &str,
-        #[case] accept_encoding: &str,
-        #[case] expected_encoding: &str,
-        mut asset_router: AssetRouter,
+    #[case(AssetETagKind::Strong, "/index.html", vec!["http_expr", "index.html", "<$>"])]
+    #[case(AssetETagKind::Weak, "/index.html", vec!["http_expr", "index.html", "<$>"])]
+    #[case(AssetETagKind::Strong, "/something", vec!["http_expr", "", "<*>"])]
+    #[case(AssetETagKind::Weak, "/something", vec!["http_expr", "", "<*>"])]
+    fn test_if_none_match(
+        #[case] etag_kind: AssetETagKind,
+        #[case] req_path: &str,
+        #[case] expected_expr_path: Vec<&str>,
     ) {
-        let request = HttpRequest::get(req_url)
-            .with_headers(vec![(
-                "accept-encoding".to_string(),
-                accept_encoding.to_string(),
-            )])
-            .build();
-        let mut expected_response = build_200_response(
-            expected_body,
-            encoded_asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-encoding".to_string(),
-                    expected_encoding.to_string(),
-                ),
-            ],
-        );
+        let mut asset_router = AssetRouter::default().with_etags(etag_kind);
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let request = HttpRequest::get(req_path).build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let etag = get_header(&response, "etag").unwrap();
+        let opaque_tag = etag.trim_start_matches("W/").to_string();
 
+        let request = HttpRequest::get(req_path)
+            .with_headers(vec![("If-None-Match".to_string(), etag.clone())])
+            .build();
         let response = asset_router
             .serve_asset(&data_certificate(), &request)
             .unwrap();
         let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
 
-        let requested_expr_path = HttpCertificationPath::exact(req_url).to_expr_path();
-        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_eq!(response.status_code(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.body(), b"");
+        assert_eq!(get_header(&response, "etag"), Some(etag.clone()));
+        assert_eq!(get_header(&response, "content-length"), None);
+        assert_eq!(expr_path, expected_expr_path);
         assert_matches!(
             witness.lookup_subtree(&expr_path),
             SubtreeLookupResult::Found(_)
         );
-        assert_matches!(
-            witness.lookup_subtree(&requested_expr_path),
-            SubtreeLookupResult::Absent
-        );
-        assert_eq!(response, expected_response);
 
+        for if_none_match in [
+            format!("W/{opaque_tag}"),
+            format!(r#""other", {etag}"#),
+            "*".to_string(),
+            r#""other""#.to_string(),
+        ] {
+            let request = HttpRequest::get(req_path)
+                .with_headers(vec![("If-None-Match".to_string(), if_none_match)])
+                .build();
+            let response = asset_router
+                .serve_asset(&data_certificate(), &request)
+                .unwrap();
+
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), index_html_body());
+        }
+    }
+
+    #[test]
+    fn test_serve_not_modified() {
+        let mut asset_router = AssetRouter::default()
+            .with_etags(AssetETagKind::Strong)
+            .with_build_id("build-1");
         asset_router
-            .delete_assets(
-                vec![
-                    Asset::new("index.html", index_html_body()),
-                    Asset::new("index.html.gz", index_html_gz_body()),
-                    Asset::new("index.html.zz", index_html_zz_body()),
-                    Asset::new("index.html.br", index_html_br_body()),
-                ],
-                vec![index_html_config()],
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![AssetConfig::File {
+                    path: "index.html".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![
+                        ("Cache-Control".to_string(), "no-cache".to_string()),
+                        ("Vary".to_string(), "Accept-Encoding".to_string()),
+                        ("X-Custom".to_string(), "custom".to_string()),
+                    ],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![],
+                    options: Default::default(),
+                }],
             )
             .unwrap();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request = HttpRequest::get("/index.html").build();
+        let ok_response = fixture.serve(&asset_router, &request);
+        let etag = get_header(&ok_response, "etag").unwrap();
+
+        let not_modified_response = asset_router
+            .serve_not_modified(&fixture.certificate_cbor, &request)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            not_modified_response.status_code(),
+            StatusCode::NOT_MODIFIED
+        );
+        assert_eq!(not_modified_response.body(), b"");
+        assert_eq!(
+            get_header(&not_modified_response, "etag"),
+            Some(etag.clone())
+        );
+        assert_eq!(
+            get_header(&not_modified_response, "cache-control").as_deref(),
+            Some("no-cache")
+        );
+        assert_eq!(
+            get_header(&not_modified_response, "vary").as_deref(),
+            Some("Accept-Encoding")
+        );
+        assert_eq!(get_header(&not_modified_response, "x-custom"), None);
+        assert_eq!(get_header(&not_modified_response, "x-build-id"), None);
+        assert_eq!(get_header(&not_modified_response, "content-type"), None);
+        assert_eq!(get_header(&not_modified_response, "content-length"), None);
+
         assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == req_url
+            fixture.verify(&request, not_modified_response.clone()),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+
+        let conditional_request = HttpRequest::get("/index.html")
+            .with_headers(vec![("If-None-Match".to_string(), etag)])
+            .build();
+        let conditional_response = fixture.serve(&asset_router, &conditional_request);
+        assert_eq!(conditional_response, not_modified_response);
+
+        let result = fixture
+            .verify(&conditional_request, not_modified_response)
+            .unwrap();
+        assert_eq!(result.response.unwrap().status_code, Some(304));
+
+        let asset_router = AssetRouter::default();
+        assert_eq!(
+            asset_router
+                .serve_not_modified(
+                    &fixture.certificate_cbor,
+                    &HttpRequest::get("/index.html").build()
+                )
+                .unwrap(),
+            None
         );
     }
 
     #[rstest]
-    #[case("/something", "/something")]
-    #[case("https://internetcomputer.org/something", "/something")]
-    fn test_index_html_root_fallback(
-        mut asset_router: AssetRouter,
-        #[case] req_url: &str,
-        #[case] req_path: &str,
-    ) {
-        let mut expected_response = expected_index_html_response();
+    fn test_if_none_match_without_etags(asset_router: AssetRouter) {
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![("If-None-Match".to_string(), "*".to_string())])
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
 
-        let request = HttpRequest::get(req_url).build();
-        let requested_expr_path = HttpCertificationPath::exact(req_path).to_expr_path();
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(get_header(&response, "etag"), None);
+    }
+
+    #[rstest]
+    #[case("identity")]
+    #[case("identity;q=1, br;q=0")]
+    #[case("br;q=0")]
+    #[case("br;q=0.0, gzip")]
+    fn test_identity_encoding_preference(#[case] accept_encoding: &str) {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![AssetConfig::File {
+                    path: "index.html".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![AssetEncoding::Brotli.default_config()],
+                    options: Default::default(),
+                }],
+            )
+            .unwrap();
 
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![(
+                "Accept-Encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
         let response = asset_router
             .serve_asset(&data_certificate(), &request)
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
 
-        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
-        );
-        assert_matches!(
-            witness.lookup_subtree(&requested_expr_path),
-            SubtreeLookupResult::Absent
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), index_html_body().as_slice());
+        assert_eq!(get_header(&response, "content-encoding"), None);
+
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![("Accept-Encoding".to_string(), "br".to_string())])
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+
+        assert_eq!(response.body(), index_html_br_body().as_slice());
+        assert_eq!(
+            get_header(&response, "content-encoding"),
+            Some("br".to_string())
         );
-        assert_eq!(response, expected_response);
+    }
 
+    #[rstest]
+    #[case("*", Some("br"))]
+    #[case("*;q=0", None)]
+    #[case("identity;q=0, *", Some("br"))]
+    #[case("br;q=0, *", Some("gzip"))]
+    #[case("gzip, *;q=0", Some("gzip"))]
+    #[case("deflate, *", Some("br"))]
+    #[case("br;q=0, gzip;q=0, *", None)]
+    fn test_wildcard_encoding_preference(
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: Option<&str>,
+    ) {
+        let mut asset_router = AssetRouter::default();
         asset_router
-            .delete_assets(
+            .certify_assets(
                 vec![
                     Asset::new("index.html", index_html_body()),
-                    Asset::new("index.html.gz", index_html_gz_body()),
-                    Asset::new("index.html.zz", index_html_zz_body()),
                     Asset::new("index.html.br", index_html_br_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
                 ],
-                vec![index_html_config()],
+                vec![AssetConfig::File {
+                    path: "index.html".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![
+                        AssetEncoding::Brotli.default_config(),
+                        AssetEncoding::Gzip.default_config(),
+                    ],
+                    options: Default::default(),
+                }],
             )
             .unwrap();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
-        assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == req_path
-        );
-    }
-
-    #[rstest]
-    #[case("/assets/css/app.css", "/assets/css/app.css")]
-    #[case(
-        "https://internetcomputer.org/assets/css/app.css",
-        "/assets/css/app.css"
-    )]
-    fn test_index_html_nested_fallback(
-        mut asset_router: AssetRouter,
-        #[case] req_url: &str,
-        #[case] req_path: &str,
-    ) {
-        let mut expected_response = expected_index_html_response();
-
-        let request = HttpRequest::get(req_url).build();
-        let requested_expr_path = HttpCertificationPath::exact(req_path).to_expr_path();
-
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![(
+                "Accept-Encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
         let response = asset_router
             .serve_asset(&data_certificate(), &request)
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
 
-        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
-        assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
-        );
-        assert_matches!(
-            witness.lookup_subtree(&requested_expr_path),
-            SubtreeLookupResult::Absent
+        let expected_body = match expected_encoding {
+            Some("br") => index_html_br_body(),
+            Some("gzip") => index_html_gz_body(),
+            _ => index_html_body(),
+        };
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), expected_body.as_slice());
+        assert_eq!(
+            get_header(&response, "content-encoding"),
+            expected_encoding.map(str::to_string)
         );
-        assert_eq!(response, expected_response);
+    }
 
+    #[rstest]
+    #[case("br;q=0.5, gzip", Some("gzip"))]
+    #[case("gzip;q=0.8, br;q=0.9", Some("br"))]
+    #[case("gzip;q=0.9, *;q=0.5", Some("gzip"))]
+    #[case("br;Q=0.1, gzip;q=0.2", Some("gzip"))]
+    #[case("br;q=0.5, gzip;q=0.5", Some("br"))]
+    #[case("identity, br;q=0.5", None)]
+    #[case("identity;q=0.5, br;q=0.5", Some("br"))]
+    #[case("br;q=2, gzip", Some("br"))]
+    #[case("br;q=invalid, gzip", Some("br"))]
+    fn test_accept_encoding_quality_values(
+        #[case] accept_encoding: &str,
+        #[case] expected_encoding: Option<&str>,
+    ) {
+        let mut asset_router = AssetRouter::default();
         asset_router
-            .delete_assets(
+            .certify_assets(
                 vec![
                     Asset::new("index.html", index_html_body()),
-                    Asset::new("index.html.gz", index_html_gz_body()),
-                    Asset::new("index.html.zz", index_html_zz_body()),
                     Asset::new("index.html.br", index_html_br_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
                 ],
-                vec![index_html_config()],
+                vec![AssetConfig::File {
+                    path: "index.html".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![
+                        AssetEncoding::Brotli.default_config(),
+                        AssetEncoding::Gzip.default_config(),
+                    ],
+                    options: Default::default(),
+                }],
             )
             .unwrap();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
-        assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == req_path
+        let request = HttpRequest::get("/index.html")
+            .with_headers(vec![(
+                "Accept-Encoding".to_string(),
+                accept_encoding.to_string(),
+            )])
+            .build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+
+        let expected_body = match expected_encoding {
+            Some("br") => index_html_br_body(),
+            Some("gzip") => index_html_gz_body(),
+            _ => index_html_body(),
+        };
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), expected_body.as_slice());
+        assert_eq!(
+            get_header(&response, "content-encoding"),
+            expected_encoding.map(str::to_string)
         );
     }
 
@@ -2732,104 +8912,368 @@ mod tests {
             &expr_path,
         );
 
-        assert_eq!(
-            expr_path,
-            HttpCertificationPath::exact(request.get_path().unwrap()).to_expr_path()
+        assert_eq!(
+            expr_path,
+            HttpCertificationPath::exact(request.get_path().unwrap()).to_expr_path()
+        );
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("not-found.html", not_found_html_body()),
+                    Asset::new("not-found.html.gz", not_found_html_gz_body()),
+                    Asset::new("not-found.html.zz", not_found_html_zz_body()),
+                    Asset::new("not-found.html.br", not_found_html_br_body()),
+                ],
+                vec![not_found_html_config()],
+            )
+            .unwrap();
+
+        let mut expected_response = expected_index_html_response();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+        assert_eq!(response, expected_response);
+
+        asset_router
+            .delete_assets(
+                vec![
+                    Asset::new("index.html", index_html_body()),
+                    Asset::new("index.html.gz", index_html_gz_body()),
+                    Asset::new("index.html.zz", index_html_zz_body()),
+                    Asset::new("index.html.br", index_html_br_body()),
+                ],
+                vec![index_html_config()],
+            )
+            .unwrap();
+
+        let result = asset_router.serve_asset(&data_certificate(), &request);
+        assert_matches!(
+            result,
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+             }) if request_url == request.get_path().unwrap()
+        );
+    }
+
+    #[rstest]
+    fn test_delete_all_assets() {
+        let mut asset_router = asset_router();
+
+        let request = HttpRequest::get("/index.html").build();
+
+        let mut expected_response = expected_index_html_response();
+
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+        add_v2_certificate_header(
+            &data_certificate(),
+            &mut expected_response,
+            &witness,
+            &expr_path,
+        );
+
+        assert_eq!(response, expected_response);
+
+        asset_router.delete_all_assets();
+
+        assert_matches!(
+            asset_router.serve_asset(
+                &data_certificate(),
+                &request,
+            ),
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+            }) if request_url == "/index.html"
+        );
+
+        let assets: Vec<_> = asset_router.get_assets().iter().collect();
+        assert!(assets.is_empty());
+    }
+
+    #[rstest]
+    fn test_clear() {
+        let mut asset_router = asset_router();
+        asset_router
+            .certify_method_response(
+                "/submit",
+                Method::POST,
+                HttpResponse::ok(b"Submitted".as_slice(), vec![]).build(),
+            )
+            .unwrap();
+
+        asset_router.clear();
+
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+        for path in ["/index.html", "/", "/non-existent"] {
+            assert_matches!(
+                asset_router.serve_asset(
+                    &data_certificate(),
+                    &HttpRequest::get(path).build(),
+                ),
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                    request_url,
+                }) if request_url == path
+            );
+        }
+        assert_matches!(
+            asset_router.serve_asset(
+                &data_certificate(),
+                &HttpRequest::post("/submit").build(),
+            ),
+            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
+                request_url,
+            }) if request_url == "/submit"
+        );
+        assert!(asset_router.get_assets().iter().next().is_none());
+        assert!(asset_router.get_fallback_assets().iter().next().is_none());
+
+        let mut expected_router = AssetRouter::default();
+        for router in [&mut asset_router, &mut expected_router] {
+            router
+                .certify_assets(
+                    vec![Asset::new("index.html", b"<h1>Redeployed</h1>".as_slice())],
+                    vec![],
+                )
+                .unwrap();
+        }
+
+        assert_eq!(asset_router.root_hash(), expected_router.root_hash());
+        let response = asset_router
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/index.html").build(),
+            )
+            .unwrap();
+        assert_eq!(response.body(), b"<h1>Redeployed</h1>");
+    }
+
+    #[test]
+    fn test_clear_resets_certification_state() {
+        let mut asset_router = asset_router();
+        asset_router.begin_certification(vec![]).unwrap();
+        asset_router.enter_maintenance(60).unwrap();
+
+        asset_router.clear();
+
+        assert!(!asset_router.is_in_maintenance());
+        assert_matches!(
+            asset_router.finalize(),
+            Err(AssetCertificationError::NoCertificationInProgress)
+        );
+        asset_router.begin_certification(vec![]).unwrap();
+        asset_router
+            .certify_chunk(vec![Asset::new(
+                "index.html",
+                b"<h1>Redeployed</h1>".as_slice(),
+            )])
+            .unwrap();
+        asset_router.finalize().unwrap();
+
+        let response = asset_router
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/index.html").build(),
+            )
+            .unwrap();
+        assert_eq!(response.status_code(), StatusCode::OK);
+        assert_eq!(response.body(), b"<h1>Redeployed</h1>");
+    }
+
+    #[rstest]
+    fn test_chunked_certification(asset_router: AssetRouter) {
+        let assets = vec![
+            Asset::new("index.html", index_html_body()),
+            Asset::new("index.html.gz", index_html_gz_body()),
+            Asset::new("index.html.zz", index_html_zz_body()),
+            Asset::new("index.html.br", index_html_br_body()),
+            Asset::new("js/app-488df671.js", app_js_body()),
+            Asset::new("js/app-488df671.js.gz", app_js_gz_body()),
+            Asset::new("js/app-488df671.js.zz", app_js_zz_body()),
+            Asset::new("js/app-488df671.js.br", app_js_br_body()),
+            Asset::new("css/app-ba74b708.css", app_css_body()),
+            Asset::new("not-found.html", not_found_html_body()),
+            Asset::new("not-found.html.gz", not_found_html_gz_body()),
+            Asset::new("not-found.html.zz", not_found_html_zz_body()),
+            Asset::new("not-found.html.br", not_found_html_br_body()),
+        ];
+        let asset_configs = vec![
+            index_html_config(),
+            js_config(),
+            css_config(),
+            not_found_html_config(),
+            old_url_redirect_config(),
+            css_redirect_config(),
+        ];
+
+        let mut chunked_asset_router = AssetRouter::default();
+        assert_matches!(
+            chunked_asset_router.certify_chunk(assets[..4].to_vec()),
+            Err(AssetCertificationError::NoCertificationInProgress)
         );
         assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+            chunked_asset_router.finalize(),
+            Err(AssetCertificationError::NoCertificationInProgress)
         );
-        assert_eq!(response, expected_response);
 
-        asset_router
-            .delete_assets(
-                vec![
-                    Asset::new("not-found.html", not_found_html_body()),
-                    Asset::new("not-found.html.gz", not_found_html_gz_body()),
-                    Asset::new("not-found.html.zz", not_found_html_zz_body()),
-                    Asset::new("not-found.html.br", not_found_html_br_body()),
-                ],
-                vec![not_found_html_config()],
-            )
+        chunked_asset_router
+            .begin_certification(asset_configs.clone())
             .unwrap();
+        assert_matches!(
+            chunked_asset_router.begin_certification(asset_configs),
+            Err(AssetCertificationError::CertificationAlreadyInProgress)
+        );
 
-        let mut expected_response = expected_index_html_response();
+        for chunk in [&assets[..4], &assets[4..8], &assets[8..]] {
+            chunked_asset_router.certify_chunk(chunk.to_vec()).unwrap();
+        }
+        let root_hash = chunked_asset_router.finalize().unwrap();
 
-        let response = asset_router
-            .serve_asset(&data_certificate(), &request)
-            .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
+        assert_eq!(root_hash, asset_router.root_hash());
+        for req_path in ["/", "/js/app-488df671.js", "/old-url", "/non-existent"] {
+            let request = HttpRequest::get(req_path)
+                .with_headers(vec![("Accept-Encoding".to_string(), "gzip".to_string())])
+                .build();
+
+            assert_eq!(
+                chunked_asset_router
+                    .serve_asset(&data_certificate(), &request)
+                    .unwrap(),
+                asset_router
+                    .serve_asset(&data_certificate(), &request)
+                    .unwrap()
+            );
+        }
 
-        assert_eq!(expr_path, vec!["http_expr", "", "<*>"]);
         assert_matches!(
-            witness.lookup_subtree(&expr_path),
-            SubtreeLookupResult::Found(_)
+            chunked_asset_router.finalize(),
+            Err(AssetCertificationError::NoCertificationInProgress)
         );
-        assert_eq!(response, expected_response);
+    }
 
+    #[rstest]
+    fn test_chunked_certification_keeps_serving_until_finalized() {
+        let mut asset_router = AssetRouter::default();
         asset_router
-            .delete_assets(
-                vec![
-                    Asset::new("index.html", index_html_body()),
-                    Asset::new("index.html.gz", index_html_gz_body()),
-                    Asset::new("index.html.zz", index_html_zz_body()),
-                    Asset::new("index.html.br", index_html_br_body()),
-                ],
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
                 vec![index_html_config()],
             )
             .unwrap();
+        let root_hash = asset_router.root_hash();
 
-        let result = asset_router.serve_asset(&data_certificate(), &request);
+        let index_request = HttpRequest::get("/index.html").build();
+        let app_js_request = HttpRequest::get("/js/app-488df671.js").build();
+
+        asset_router
+            .begin_certification(vec![index_html_config(), js_config()])
+            .unwrap();
+        asset_router
+            .certify_chunk(vec![Asset::new("js/app-488df671.js", app_js_body())])
+            .unwrap();
+
+        assert_eq!(asset_router.root_hash(), root_hash);
+        verify_served(&asset_router, &index_request);
         assert_matches!(
-            result,
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-             }) if request_url == request.get_path().unwrap()
+            asset_router.serve_asset(&data_certificate(), &app_js_request),
+            Err(_)
         );
-    }
-
-    #[rstest]
-    fn test_delete_all_assets() {
-        let mut asset_router = asset_router();
 
-        let request = HttpRequest::get("/index.html").build();
+        let root_hash = asset_router.finalize().unwrap();
 
-        let mut expected_response = expected_index_html_response();
+        assert_eq!(root_hash, asset_router.root_hash());
+        verify_served(&asset_router, &index_request);
+        verify_served(&asset_router, &app_js_request);
+    }
 
-        let response = asset_router
-            .serve_asset(&data_certificate(), &request)
+    #[rstest]
+    fn test_chunked_certification_replaces_recertified_assets() {
+        let new_index_html_body = b"<html><body><h1>Hello again!</h1></body></html>".to_vec();
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", index_html_body())],
+                vec![index_html_config()],
+            )
             .unwrap();
-        let (witness, expr_path) = extract_witness_expr_path(&response);
-        add_v2_certificate_header(
-            &data_certificate(),
-            &mut expected_response,
-            &witness,
-            &expr_path,
-        );
-
-        assert_eq!(response, expected_response);
-
-        asset_router.delete_all_assets();
 
-        assert_matches!(
-            asset_router.serve_asset(
-                &data_certificate(),
-                &request,
+        let old_responses = [
+            (
+                "/index.html",
+                asset_router.responses[&RequestKey::new("/index.html", None, None)].clone(),
             ),
-            Err(AssetCertificationError::NoAssetMatchingRequestUrl {
-                request_url,
-            }) if request_url == "/index.html"
-        );
+            (
+                "/",
+                asset_router.responses[&RequestKey::new("/", None, None)].clone(),
+            ),
+            (
+                "/non-existent",
+                asset_router.fallback_responses[&RequestKey::new("/", None, None)].clone(),
+            ),
+        ];
 
-        let assets: Vec<_> = asset_router.get_assets().iter().collect();
-        assert!(assets.is_empty());
+        asset_router
+            .begin_certification(vec![index_html_config()])
+            .unwrap();
+        asset_router
+            .certify_chunk(vec![Asset::new("index.html", new_index_html_body.clone())])
+            .unwrap();
+        let root_hash = asset_router.finalize().unwrap();
+
+        let mut expected_router = AssetRouter::default();
+        expected_router
+            .certify_assets(
+                vec![Asset::new("index.html", new_index_html_body.clone())],
+                vec![index_html_config()],
+            )
+            .unwrap();
+        assert_eq!(root_hash, expected_router.root_hash());
+
+        let fixture = VerificationFixture::new(&root_hash);
+        for (req_path, old_response) in old_responses {
+            let request = &HttpRequest::get(req_path).build();
+
+            // a replica serving the previously certified response with a witness from the
+            // current tree
+            let old_response = asset_router
+                .with_certificate_header(
+                    &fixture.certificate_cbor,
+                    req_path,
+                    &asset_router.tree.borrow(),
+                    old_response,
+                )
+                .unwrap();
+            assert_eq!(old_response.body(), index_html_body().as_slice());
+            assert!(fixture.verify(request, old_response).is_err());
+
+            let response = fixture.serve(&asset_router, request);
+            assert_eq!(response.body(), new_index_html_body.as_slice());
+            assert!(fixture
+                .verify(request, response)
+                .unwrap()
+                .is_fully_certified());
+        }
     }
 
     #[rstest]
@@ -3123,28 +9567,159 @@ mod tests {
                     "public, no-cache, no-store".to_string(),
                 ),
                 ("content-type".to_string(), "text/html".to_string()),
-                ("content-encoding".to_string(), "gzip".to_string()),
+                ("content-encoding".to_string(), "gzip".to_string()),
+                (
+                    "content-range".to_string(),
+                    format!(
+                        "bytes 0-{}/{}",
+                        first_chunk_gzip_body.len() - 1,
+                        full_gz_body.len()
+                    ),
+                ),
+            ],
+        );
+        assert_matches!(
+            first_chunk_gzip_response,
+            Some(first_chunk_gzip_response) if first_chunk_gzip_response == &expected_first_chunk_gzip_response
+        );
+
+        let second_chunk_body = &full_body[ASSET_CHUNK_SIZE..full_body.len()];
+        let second_chunk_response = asset_router.get_assets().get(
+            format!("/{}", TWO_CHUNKS_ASSET_NAME),
+            None,
+            Some(ASSET_CHUNK_SIZE),
+        );
+        let expected_second_chunk_response = build_206_response(
+            second_chunk_body.to_vec(),
+            asset_range_chunk_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                (
+                    "content-range".to_string(),
+                    format!(
+                        "bytes {}-{}/{}",
+                        first_chunk_body.len(),
+                        first_chunk_body.len() + second_chunk_body.len() - 1,
+                        full_body.len()
+                    ),
+                ),
+            ],
+        );
+        assert_matches!(
+            second_chunk_response,
+            Some(second_chunk_response) if second_chunk_response == &expected_second_chunk_response
+        );
+
+        let second_chunk_gzip_body = &full_gz_body[ASSET_CHUNK_SIZE..full_gz_body.len()];
+        let second_chunk_gzip_response = asset_router.get_assets().get(
+            format!("/{}", TWO_CHUNKS_ASSET_NAME),
+            Some(AssetEncoding::Gzip),
+            Some(ASSET_CHUNK_SIZE),
+        );
+        let expected_second_chunk_gzip_response = build_206_response(
+            second_chunk_gzip_body.to_vec(),
+            asset_range_chunk_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
+                ("content-encoding".to_string(), "gzip".to_string()),
+                (
+                    "content-range".to_string(),
+                    format!(
+                        "bytes {}-{}/{}",
+                        first_chunk_gzip_body.len(),
+                        first_chunk_gzip_body.len() + second_chunk_gzip_body.len() - 1,
+                        full_gz_body.len()
+                    ),
+                ),
+            ],
+        );
+
+        assert_matches!(
+            second_chunk_gzip_response,
+            Some(second_chunk_gzip_response) if second_chunk_gzip_response == &expected_second_chunk_gzip_response
+        );
+
+        asset_router
+            .delete_assets(
+                vec![
+                    long_asset(TWO_CHUNKS_ASSET_NAME.to_string()),
+                    long_asset(format!("{}{}", TWO_CHUNKS_ASSET_NAME, gzip_suffix)),
+                ],
+                vec![
+                    long_asset_config(TWO_CHUNKS_ASSET_NAME),
+                    long_asset_config(&format!("{}{}", TWO_CHUNKS_ASSET_NAME, gzip_suffix)),
+                ],
+            )
+            .unwrap();
+
+        let first_chunk_response =
+            asset_router
+                .get_assets()
+                .get(format!("/{}", TWO_CHUNKS_ASSET_NAME), None, Some(0));
+        assert_matches!(first_chunk_response, None);
+
+        let first_chunk_gzip_response = asset_router.get_assets().get(
+            format!("/{}", TWO_CHUNKS_ASSET_NAME),
+            Some(AssetEncoding::Gzip),
+            Some(0),
+        );
+        assert_matches!(first_chunk_gzip_response, None);
+
+        let second_chunk_response = asset_router.get_assets().get(
+            format!("/{}", TWO_CHUNKS_ASSET_NAME),
+            None,
+            Some(ASSET_CHUNK_SIZE),
+        );
+        assert_matches!(second_chunk_response, None);
+
+        let second_chunk_gzip_response = asset_router.get_assets().get(
+            format!("/{}", TWO_CHUNKS_ASSET_NAME),
+            Some(AssetEncoding::Gzip),
+            Some(ASSET_CHUNK_SIZE),
+        );
+        assert_matches!(second_chunk_gzip_response, None);
+    }
+
+    #[rstest]
+    fn test_asset_map_iter() {
+        let asset_router =
+            long_asset_router_with_params(&[TWO_CHUNKS_ASSET_NAME], &[AssetEncoding::Identity]);
+        let full_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+
+        let assets: Vec<_> = asset_router.get_assets().iter().collect();
+        assert!(assets.len() == 3);
+
+        let first_chunk_body = &full_body[0..ASSET_CHUNK_SIZE];
+        let expected_first_chunk_response = build_206_response(
+            first_chunk_body.to_vec(),
+            asset_cel_expr(),
+            vec![
+                (
+                    "cache-control".to_string(),
+                    "public, no-cache, no-store".to_string(),
+                ),
+                ("content-type".to_string(), "text/html".to_string()),
                 (
                     "content-range".to_string(),
-                    format!(
-                        "bytes 0-{}/{}",
-                        first_chunk_gzip_body.len() - 1,
-                        full_gz_body.len()
-                    ),
+                    format!("bytes 0-{}/{}", first_chunk_body.len() - 1, full_body.len()),
                 ),
             ],
         );
-        assert_matches!(
-            first_chunk_gzip_response,
-            Some(first_chunk_gzip_response) if first_chunk_gzip_response == &expected_first_chunk_gzip_response
-        );
+
+        assert!(assets.contains(&(
+            (&format!("/{}", TWO_CHUNKS_ASSET_NAME), None, Some(0)),
+            &expected_first_chunk_response
+        )));
 
         let second_chunk_body = &full_body[ASSET_CHUNK_SIZE..full_body.len()];
-        let second_chunk_response = asset_router.get_assets().get(
-            format!("/{}", TWO_CHUNKS_ASSET_NAME),
-            None,
-            Some(ASSET_CHUNK_SIZE),
-        );
         let expected_second_chunk_response = build_206_response(
             second_chunk_body.to_vec(),
             asset_range_chunk_cel_expr(),
@@ -3165,161 +9740,619 @@ mod tests {
                 ),
             ],
         );
-        assert_matches!(
-            second_chunk_response,
-            Some(second_chunk_response) if second_chunk_response == &expected_second_chunk_response
-        );
+        assert!(assets.contains(&(
+            (
+                &format!("/{}", TWO_CHUNKS_ASSET_NAME),
+                None,
+                Some(ASSET_CHUNK_SIZE)
+            ),
+            &expected_second_chunk_response
+        )));
 
-        let second_chunk_gzip_body = &full_gz_body[ASSET_CHUNK_SIZE..full_gz_body.len()];
-        let second_chunk_gzip_response = asset_router.get_assets().get(
-            format!("/{}", TWO_CHUNKS_ASSET_NAME),
-            Some(AssetEncoding::Gzip),
-            Some(ASSET_CHUNK_SIZE),
-        );
-        let expected_second_chunk_gzip_response = build_206_response(
-            second_chunk_gzip_body.to_vec(),
-            asset_range_chunk_cel_expr(),
+        let expected_full_response = build_200_response(
+            full_body,
+            asset_cel_expr(),
             vec![
                 (
                     "cache-control".to_string(),
                     "public, no-cache, no-store".to_string(),
                 ),
                 ("content-type".to_string(), "text/html".to_string()),
-                ("content-encoding".to_string(), "gzip".to_string()),
-                (
-                    "content-range".to_string(),
-                    format!(
-                        "bytes {}-{}/{}",
-                        first_chunk_gzip_body.len(),
-                        first_chunk_gzip_body.len() + second_chunk_gzip_body.len() - 1,
-                        full_gz_body.len()
-                    ),
-                ),
             ],
         );
+        assert!(assets.contains(&(
+            (&format!("/{}", TWO_CHUNKS_ASSET_NAME), None, None),
+            &expected_full_response
+        )));
+    }
+
+    #[rstest]
+    #[case(
+        old_url_redirect_config(),
+        "/old-url",
+        StatusCode::MOVED_PERMANENTLY,
+        "/"
+    )]
+    #[case(
+        css_redirect_config(),
+        "/css/app.css",
+        StatusCode::TEMPORARY_REDIRECT,
+        "/css/app-ba74b708.css"
+    )]
+    fn test_redirects_pass_verification(
+        #[case] redirect_config: AssetConfig,
+        #[case] req_path: &str,
+        #[case] expected_status_code: StatusCode,
+        #[case] expected_location: &str,
+    ) {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(vec![], vec![redirect_config])
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request = HttpRequest::get(req_path).build();
+        let response = fixture.serve(&asset_router, &request);
+
+        let verification_info = fixture.verify(&request, response.clone()).unwrap();
+        assert!(verification_info.is_fully_certified());
+        let verified_response = verification_info.response.unwrap();
+        assert_eq!(
+            verified_response.status_code,
+            Some(expected_status_code.as_u16())
+        );
+        assert!(verified_response
+            .headers
+            .contains(&("location".to_string(), expected_location.to_string())));
+
+        let (status_code, headers, body, upgrade) = response.clone().into_parts();
+        let tampered_headers = headers
+            .iter()
+            .map(|(name, value)| match name.as_str() {
+                "location" => (name.clone(), "https://phishing.example".to_string()),
+                _ => (name.clone(), value.clone()),
+            })
+            .collect();
+        let tampered_location_response =
+            HttpResponse::from_parts(status_code, tampered_headers, body.clone(), upgrade);
+        assert_matches!(
+            fixture.verify(&request, tampered_location_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
 
+        let tampered_status_response =
+            HttpResponse::from_parts(StatusCode::FOUND, headers, body, upgrade);
         assert_matches!(
-            second_chunk_gzip_response,
-            Some(second_chunk_gzip_response) if second_chunk_gzip_response == &expected_second_chunk_gzip_response
+            fixture.verify(&request, tampered_status_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
+    }
+
+    #[rstest]
+    #[case("/img?w=100", b"w=100".as_slice())]
+    #[case("/img?v=2&w=200", b"w=200".as_slice())]
+    #[case("/img?w=300", b"default".as_slice())]
+    #[case("/img", b"default".as_slice())]
+    fn test_certified_query_parameters(#[case] req_url: &str, #[case] expected_body: &[u8]) {
+        let img_config =
+            |path: &str, certified_query_parameters: Vec<(String, String)>| AssetConfig::File {
+                path: path.to_string(),
+                content_type: Some("image/png".to_string()),
+                headers: vec![],
+                fallback_for: vec![],
+                aliased_by: vec!["/img".to_string()],
+                encodings: vec![],
+                options: AssetFileOptions {
+                    addressable: false,
+                    certified_query_parameters,
+                    ..Default::default()
+                },
+            };
 
+        let mut asset_router = AssetRouter::default();
         asset_router
-            .delete_assets(
+            .certify_assets(
                 vec![
-                    long_asset(TWO_CHUNKS_ASSET_NAME.to_string()),
-                    long_asset(format!("{}{}", TWO_CHUNKS_ASSET_NAME, gzip_suffix)),
+                    Asset::new("img", b"default".as_slice()),
+                    Asset::new("img-100.png", b"w=100".as_slice()),
+                    Asset::new("img-200.png", b"w=200".as_slice()),
                 ],
                 vec![
-                    long_asset_config(TWO_CHUNKS_ASSET_NAME),
-                    long_asset_config(&format!("{}{}", TWO_CHUNKS_ASSET_NAME, gzip_suffix)),
+                    img_config("img-100.png", vec![("w".to_string(), "100".to_string())]),
+                    img_config("img-200.png", vec![("w".to_string(), "200".to_string())]),
                 ],
             )
             .unwrap();
 
-        let first_chunk_response =
-            asset_router
-                .get_assets()
-                .get(format!("/{}", TWO_CHUNKS_ASSET_NAME), None, Some(0));
-        assert_matches!(first_chunk_response, None);
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
 
-        let first_chunk_gzip_response = asset_router.get_assets().get(
-            format!("/{}", TWO_CHUNKS_ASSET_NAME),
-            Some(AssetEncoding::Gzip),
-            Some(0),
+        let request = HttpRequest::get(req_url).build();
+        let response = fixture.serve(&asset_router, &request);
+        assert_eq!(response.body(), expected_body);
+
+        let verification_info = fixture.verify(&request, response.clone()).unwrap();
+        assert!(verification_info.is_fully_certified());
+        assert_eq!(verification_info.response.unwrap().body, expected_body);
+
+        if expected_body != b"default" {
+            let other_request = HttpRequest::get("/img?w=400").build();
+            assert_matches!(
+                fixture.verify(&other_request, response),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+            );
+        }
+    }
+
+    #[rstest]
+    fn test_delete_assets_prunes_certified_query_parameter_names() {
+        let img_config =
+            |path: &str, certified_query_parameters: Vec<(String, String)>| AssetConfig::File {
+                path: path.to_string(),
+                content_type: Some("image/png".to_string()),
+                headers: vec![],
+                fallback_for: vec![],
+                aliased_by: vec!["/img".to_string()],
+                encodings: vec![],
+                options: AssetFileOptions {
+                    addressable: false,
+                    certified_query_parameters,
+                    ..Default::default()
+                },
+            };
+        let img_100_config = img_config("img-100.png", vec![("w".to_string(), "100".to_string())]);
+        let img_200_config = img_config("img-200.png", vec![("w".to_string(), "200".to_string())]);
+        let img_100 = Asset::new("img-100.png", b"w=100".as_slice());
+        let img_200 = Asset::new("img-200.png", b"w=200".as_slice());
+
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![
+                    Asset::new("img", b"default".as_slice()),
+                    img_100.clone(),
+                    img_200.clone(),
+                ],
+                vec![img_100_config.clone(), img_200_config.clone()],
+            )
+            .unwrap();
+
+        asset_router
+            .delete_assets(vec![img_100], vec![img_100_config])
+            .unwrap();
+        assert_eq!(
+            asset_router.certified_query_parameter_names.get("/img"),
+            Some(&vec![vec!["w".to_string()]])
         );
-        assert_matches!(first_chunk_gzip_response, None);
 
-        let second_chunk_response = asset_router.get_assets().get(
-            format!("/{}", TWO_CHUNKS_ASSET_NAME),
-            None,
-            Some(ASSET_CHUNK_SIZE),
+        asset_router
+            .delete_assets(vec![img_200], vec![img_200_config])
+            .unwrap();
+        assert!(asset_router.certified_query_parameter_names.is_empty());
+
+        let request = HttpRequest::get("/img?w=200").build();
+        let response = verify_served(&asset_router, &request).response.unwrap();
+        assert_eq!(response.body, b"default");
+    }
+
+    #[rstest]
+    fn test_certificate_header_name(asset_router: AssetRouter) {
+        const RENAMED_CERTIFICATE_HEADER_NAME: &str = "X-IC-Certificate";
+
+        let asset_router =
+            asset_router.with_certificate_header_name(RENAMED_CERTIFICATE_HEADER_NAME);
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request = HttpRequest::get("/index.html").build();
+        let response = fixture.serve(&asset_router, &request);
+
+        assert!(get_header(&response, RENAMED_CERTIFICATE_HEADER_NAME).is_some());
+        assert_eq!(get_header(&response, CERTIFICATE_HEADER_NAME), None);
+
+        let verifier = ResponseVerifier::new(
+            &fixture.canister_id,
+            fixture.current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &fixture.root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+        assert_matches!(
+            verifier.verify_borrowed(&request, &response),
+            Err(ResponseVerificationError::HeaderMissingCertification)
         );
-        assert_matches!(second_chunk_response, None);
 
-        let second_chunk_gzip_response = asset_router.get_assets().get(
-            format!("/{}", TWO_CHUNKS_ASSET_NAME),
-            Some(AssetEncoding::Gzip),
-            Some(ASSET_CHUNK_SIZE),
+        let verification_info = verifier
+            .with_certificate_header_name(RENAMED_CERTIFICATE_HEADER_NAME)
+            .verify_borrowed(&request, &response)
+            .unwrap();
+        assert!(verification_info.is_fully_certified());
+        assert_eq!(verification_info.response.unwrap().body, index_html_body());
+    }
+
+    #[rstest]
+    fn test_build_id(index_html_config: AssetConfig) {
+        let assets = vec![Asset::new("index.html", index_html_body())];
+        let asset_configs = vec![
+            index_html_config,
+            AssetConfig::Redirect {
+                from: "/old-url".to_string(),
+                to: "/".to_string(),
+                kind: AssetRedirectKind::Permanent,
+                headers: vec![],
+            },
+        ];
+
+        let mut asset_router = AssetRouter::default().with_build_id("1.2.3");
+        asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        for req_url in ["/index.html", "/not-found", "/old-url"] {
+            let request = HttpRequest::get(req_url).build();
+            let response = fixture.serve(&asset_router, &request);
+
+            assert_eq!(
+                get_header(&response, BUILD_ID_HEADER_NAME),
+                Some("1.2.3".to_string())
+            );
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+            assert!(verification_info
+                .response
+                .unwrap()
+                .headers
+                .iter()
+                .any(
+                    |(name, value)| name.eq_ignore_ascii_case(BUILD_ID_HEADER_NAME)
+                        && value == "1.2.3"
+                ));
+        }
+
+        let mut other_asset_router = AssetRouter::default().with_build_id("1.2.4");
+        other_asset_router
+            .certify_assets(assets.clone(), asset_configs.clone())
+            .unwrap();
+        assert_ne!(asset_router.root_hash(), other_asset_router.root_hash());
+
+        asset_router.delete_assets(assets, asset_configs).unwrap();
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    }
+
+    #[rstest]
+    #[case("/../index.html")]
+    #[case("/css/../../index.html")]
+    #[case("/%2e%2e/index.html")]
+    #[case("/css/%2E%2E%2Findex.html")]
+    #[case("/css/..%5Cindex.html")]
+    #[case("/%252e%252e%252findex.html")]
+    #[case("/css/%25252e%25252e/index.html")]
+    fn test_path_traversal_is_rejected(asset_router: AssetRouter, #[case] req_url: &str) {
+        let request = HttpRequest::get(req_url).build();
+
+        assert_matches!(
+            asset_router.serve_asset(&data_certificate(), &request),
+            Err(AssetCertificationError::HttpCertificationError(
+                HttpCertificationError::PathTraversal { .. }
+            ))
+        );
+    }
+
+    #[rstest]
+    fn test_validate_assets(index_html_config: AssetConfig) {
+        let assets = vec![
+            Asset::new("index.html", index_html_body()),
+            Asset::new("index.html.gz", index_html_gz_body()),
+            Asset::new("index.html.zz", index_html_zz_body()),
+            Asset::new("index.html.br", index_html_br_body()),
+        ];
+
+        assert_matches!(
+            AssetRouter::validate_assets(&assets, &[index_html_config.clone()]),
+            Ok(())
+        );
+
+        assert_matches!(
+            AssetRouter::validate_assets(&assets[..3], &[index_html_config.clone()]),
+            Err(AssetCertificationError::MissingEncodingAsset {
+                path,
+                encoding: AssetEncoding::Brotli,
+                encoded_path,
+            }) if path == "index.html" && encoded_path == "index.html.br"
+        );
+
+        assert_matches!(
+            AssetRouter::validate_assets(&assets[1..], &[index_html_config.clone()]),
+            Err(AssetCertificationError::ConfigAssetMismatch { config_path })
+                if config_path == "index.html"
+        );
+
+        assert_matches!(
+            AssetRouter::validate_assets(
+                &assets,
+                &[index_html_config.clone(), index_html_config.clone()]
+            ),
+            Err(AssetCertificationError::DuplicatePath { path }) if path == "index.html"
+        );
+
+        let mut duplicate_assets = assets.clone();
+        duplicate_assets.push(Asset::new("index.html.gz", index_html_gz_body()));
+        assert_matches!(
+            AssetRouter::validate_assets(&duplicate_assets, &[index_html_config.clone()]),
+            Err(AssetCertificationError::DuplicatePath { path }) if path == "index.html.gz"
+        );
+
+        let invalid_glob_config = AssetConfig::Pattern {
+            pattern: "**/*.{js,css".to_string(),
+            content_type: None,
+            headers: vec![],
+            encodings: vec![],
+            options: Default::default(),
+        };
+        let result = AssetRouter::validate_assets(&assets, &[invalid_glob_config.clone()]);
+        assert_matches!(
+            &result,
+            Err(AssetCertificationError::InvalidGlob { pattern, .. }) if pattern == "**/*.{js,css"
+        );
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with(r#"Invalid glob pattern "**/*.{js,css" in asset config: "#));
+
+        let mut asset_router = AssetRouter::default();
+        assert_matches!(
+            asset_router.certify_assets(vec![], vec![invalid_glob_config]),
+            Err(AssetCertificationError::InvalidGlob { .. })
+        );
+        assert_matches!(
+            asset_router.certify_assets(duplicate_assets, vec![index_html_config]),
+            Ok(())
         );
-        assert_matches!(second_chunk_gzip_response, None);
     }
 
-    #[rstest]
-    fn test_asset_map_iter() {
-        let asset_router =
-            long_asset_router_with_params(&[TWO_CHUNKS_ASSET_NAME], &[AssetEncoding::Identity]);
-        let full_body = long_asset_body(TWO_CHUNKS_ASSET_NAME);
+    #[test]
+    fn test_conflicting_routes() {
+        let config =
+            |path: &str, aliased_by: Vec<&str>, fallback_for: Vec<&str>| AssetConfig::File {
+                path: path.to_string(),
+                content_type: Some("text/html".to_string()),
+                headers: vec![],
+                fallback_for: fallback_for
+                    .into_iter()
+                    .map(|scope| AssetFallbackConfig {
+                        scope: scope.to_string(),
+                        status_code: Some(StatusCode::NOT_FOUND),
+                    })
+                    .collect(),
+                aliased_by: aliased_by.into_iter().map(ToString::to_string).collect(),
+                encodings: vec![],
+                options: Default::default(),
+            };
+        let assets = || {
+            vec![
+                Asset::new("a.html", b"a".as_slice()),
+                Asset::new("b.html", b"b".as_slice()),
+            ]
+        };
+        let data_certificate = data_certificate();
+        let body = |asset_router: &AssetRouter, path: &str| {
+            asset_router
+                .serve_asset(&data_certificate, &HttpRequest::get(path).build())
+                .unwrap()
+                .body()
+                .to_vec()
+        };
+
+        let mut asset_router = AssetRouter::default();
+        assert_matches!(
+            asset_router.certify_assets(
+                assets(),
+                vec![
+                    config("a.html", vec!["/a", "/home"], vec![]),
+                    config("b.html", vec!["/home"], vec![]),
+                ],
+            ),
+            Err(AssetCertificationError::DuplicateAlias { alias, path, other_path })
+                if alias == "/home" && path == "a.html" && other_path == "b.html"
+        );
+        let result = asset_router.certify_assets(
+            assets(),
+            vec![
+                config("a.html", vec![], vec!["/"]),
+                config("b.html", vec![], vec!["/docs", "/"]),
+            ],
+        );
+        assert_matches!(
+            &result,
+            Err(AssetCertificationError::ConflictingFallbackScope { scope, path, other_path })
+                if scope == "/" && path == "a.html" && other_path == "b.html"
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            r#"The fallback scope "/" is listed for both "a.html" and "b.html", each fallback scope must belong to a single file config"#
+        );
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+
+        assert_matches!(
+            AssetRouter::validate_assets(
+                &assets(),
+                &[
+                    config("a.html", vec!["/home"], vec![]),
+                    config("b.html", vec!["/home"], vec![]),
+                ],
+            ),
+            Err(AssetCertificationError::DuplicateAlias { .. })
+        );
+
+        let asset_configs = vec![
+            config("a.html", vec!["/a", "/home", "/home"], vec!["/"]),
+            config("b.html", vec!["/home"], vec!["/"]),
+        ];
+        let mut asset_router = AssetRouter::default().with_conflicting_routes_allowed(true);
+        asset_router
+            .certify_assets(assets(), asset_configs.clone())
+            .unwrap();
+        assert_eq!(body(&asset_router, "/a"), b"a");
+        assert_eq!(body(&asset_router, "/home"), b"b");
+        assert_eq!(body(&asset_router, "/not-found"), b"b");
+
+        asset_router.delete_assets(assets(), asset_configs).unwrap();
+        for path in ["/a", "/home", "/not-found"] {
+            assert_matches!(
+                asset_router.serve_asset(&data_certificate, &HttpRequest::get(path).build()),
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
+            );
+        }
+
+        let a_asset = || vec![Asset::new("a.html", b"a".as_slice())];
+        let b_asset = || vec![Asset::new("b.html", b"b".as_slice())];
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(a_asset(), vec![config("a.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+        let root_hash = asset_router.root_hash();
+        assert_matches!(
+            asset_router.certify_assets(b_asset(), vec![config("b.html", vec!["/home"], vec![])]),
+            Err(AssetCertificationError::DuplicateAlias { alias, path, other_path })
+                if alias == "/home" && path == "a.html" && other_path == "b.html"
+        );
+        assert_matches!(
+            asset_router.begin_certification(vec![config("b.html", vec![], vec!["/"])]),
+            Err(AssetCertificationError::ConflictingFallbackScope { scope, path, other_path })
+                if scope == "/" && path == "a.html" && other_path == "b.html"
+        );
+        assert_eq!(asset_router.root_hash(), root_hash);
+        asset_router
+            .certify_assets(a_asset(), vec![config("a.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+
+        asset_router
+            .delete_assets(a_asset(), vec![config("a.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+        asset_router
+            .certify_assets(b_asset(), vec![config("b.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+        assert_eq!(body(&asset_router, "/home"), b"b");
+        assert_eq!(body(&asset_router, "/not-found"), b"b");
 
-        let assets: Vec<_> = asset_router.get_assets().iter().collect();
-        assert!(assets.len() == 3);
+        let mut asset_router = AssetRouter::default().with_conflicting_routes_allowed(true);
+        asset_router
+            .certify_assets(a_asset(), vec![config("a.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+        asset_router
+            .certify_assets(b_asset(), vec![config("b.html", vec!["/home"], vec!["/"])])
+            .unwrap();
+        assert_eq!(body(&asset_router, "/home"), b"b");
+        assert_eq!(body(&asset_router, "/not-found"), b"b");
+    }
 
-        let first_chunk_body = &full_body[0..ASSET_CHUNK_SIZE];
-        let expected_first_chunk_response = build_206_response(
-            first_chunk_body.to_vec(),
-            asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
-                ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-range".to_string(),
-                    format!("bytes 0-{}/{}", first_chunk_body.len() - 1, full_body.len()),
-                ),
-            ],
-        );
+    #[test]
+    fn test_forwarded_redirects() {
+        let absolute_redirect_config = AssetConfig::Redirect {
+            from: "/docs".to_string(),
+            to: "https://docs.example.org/".to_string(),
+            kind: AssetRedirectKind::Temporary,
+            headers: vec![],
+        };
+        let mut asset_router =
+            AssetRouter::default().with_trusted_forwarded_hosts(vec!["example.com".to_string()]);
+        asset_router
+            .certify_assets(
+                vec![],
+                vec![old_url_redirect_config(), absolute_redirect_config.clone()],
+            )
+            .unwrap();
 
-        assert!(assets.contains(&(
-            (&format!("/{}", TWO_CHUNKS_ASSET_NAME), None, Some(0)),
-            &expected_first_chunk_response
-        )));
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let forwarded_request = |path: &str, headers: &[(&str, &str)]| {
+            HttpRequest::get(path)
+                .with_headers(
+                    headers
+                        .iter()
+                        .map(|(name, value)| (name.to_string(), value.to_string()))
+                        .collect(),
+                )
+                .build()
+        };
+        let https_example_com = [
+            ("x-forwarded-host", "example.com"),
+            ("x-forwarded-proto", "https"),
+        ];
 
-        let second_chunk_body = &full_body[ASSET_CHUNK_SIZE..full_body.len()];
-        let expected_second_chunk_response = build_206_response(
-            second_chunk_body.to_vec(),
-            asset_range_chunk_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
+        for (request, expected_location) in [
+            (
+                forwarded_request("/old-url", &https_example_com),
+                "https://example.com/",
+            ),
+            (
+                forwarded_request(
+                    "/old-url",
+                    &[
+                        ("X-Forwarded-Host", "example.com"),
+                        ("X-Forwarded-Proto", "http"),
+                    ],
                 ),
-                ("content-type".to_string(), "text/html".to_string()),
-                (
-                    "content-range".to_string(),
-                    format!(
-                        "bytes {}-{}/{}",
-                        first_chunk_body.len(),
-                        first_chunk_body.len() + second_chunk_body.len() - 1,
-                        full_body.len()
-                    ),
+                "http://example.com/",
+            ),
+            (
+                forwarded_request(
+                    "/old-url",
+                    &[
+                        ("X-Forwarded-Host", "attacker.com"),
+                        ("X-Forwarded-Proto", "https"),
+                    ],
                 ),
-            ],
-        );
-        assert!(assets.contains(&(
+                "/",
+            ),
             (
-                &format!("/{}", TWO_CHUNKS_ASSET_NAME),
-                None,
-                Some(ASSET_CHUNK_SIZE)
+                forwarded_request("/old-url", &[("X-Forwarded-Host", "example.com")]),
+                "/",
             ),
-            &expected_second_chunk_response
-        )));
+            (forwarded_request("/old-url", &[]), "/"),
+            (
+                forwarded_request("/docs", &https_example_com),
+                "https://docs.example.org/",
+            ),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(
+                get_header(&response, "location").as_deref(),
+                Some(expected_location)
+            );
 
-        let expected_full_response = build_200_response(
-            full_body,
-            asset_cel_expr(),
-            vec![
-                (
-                    "cache-control".to_string(),
-                    "public, no-cache, no-store".to_string(),
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+        }
+
+        let forwarded_response = fixture.serve(
+            &asset_router,
+            &forwarded_request("/old-url", &https_example_com),
+        );
+        assert_matches!(
+            fixture.verify(
+                &forwarded_request(
+                    "/old-url",
+                    &[
+                        ("X-Forwarded-Host", "attacker.com"),
+                        ("X-Forwarded-Proto", "https"),
+                    ],
                 ),
-                ("content-type".to_string(), "text/html".to_string()),
-            ],
+                forwarded_response,
+            ),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
         );
-        assert!(assets.contains(&(
-            (&format!("/{}", TWO_CHUNKS_ASSET_NAME), None, None),
-            &expected_full_response
-        )));
+
+        asset_router
+            .delete_assets(
+                vec![],
+                vec![old_url_redirect_config(), absolute_redirect_config],
+            )
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
     }
 
     #[rstest]
@@ -3549,6 +10582,7 @@ mod tests {
             }],
             aliased_by: vec!["/".to_string()],
             encodings: vec![],
+            options: Default::default(),
         };
 
         asset_router
@@ -3855,6 +10889,7 @@ mod tests {
                 AssetEncoding::Deflate.default_config(),
                 AssetEncoding::Brotli.default_config(),
             ],
+            options: Default::default(),
         }
     }
 
@@ -3872,6 +10907,7 @@ mod tests {
                 AssetEncoding::Deflate.default_config(),
                 AssetEncoding::Brotli.default_config(),
             ],
+            options: Default::default(),
         }
     }
 
@@ -3889,6 +10925,7 @@ mod tests {
                 AssetEncoding::Deflate.default_config(),
                 AssetEncoding::Brotli.default_config(),
             ],
+            options: Default::default(),
         }
     }
 
@@ -3924,6 +10961,7 @@ mod tests {
                 AssetEncoding::Deflate.default_config(),
                 AssetEncoding::Brotli.default_config(),
             ],
+            options: Default::default(),
         }
     }
 
@@ -3968,12 +11006,16 @@ mod tests {
                 AssetEncoding::Deflate.default_config(),
                 AssetEncoding::Gzip.default_config(),
             ],
+            options: Default::default(),
         }
     }
 
     #[fixture]
     fn asset_router<'a>() -> AssetRouter<'a> {
-        let mut asset_router = AssetRouter::default();
+        // requests without an `Accept-Encoding` header are served the identity
+        // variant, so that the expected responses don't depend on the encodings
+        let mut asset_router =
+            AssetRouter::default().with_any_encoding_when_accept_encoding_missing(false);
 
         let assets = vec![
             Asset::new("index.html", index_html_body()),
@@ -4075,6 +11117,77 @@ mod tests {
             .build()
     }
 
+    /// A certificate for a root hash, used to serve responses that can be verified with
+    /// [verify_request_response_pair].
+    struct VerificationFixture {
+        root_key: Vec<u8>,
+        certificate_cbor: Vec<u8>,
+        canister_id: Vec<u8>,
+        current_time: u128,
+    }
+
+    impl VerificationFixture {
+        fn new(root_hash: &Hash) -> Self {
+            let current_time = get_current_timestamp();
+            let V2CertificateFixture {
+                root_key,
+                certificate_cbor,
+                canister_id,
+            } = create_v2_certificate_fixture(root_hash, &current_time);
+            let canister_id: &[u8] = canister_id.as_ref();
+
+            Self {
+                root_key,
+                certificate_cbor,
+                canister_id: canister_id.to_vec(),
+                current_time,
+            }
+        }
+
+        fn serve<'a>(
+            &self,
+            asset_router: &AssetRouter<'a>,
+            request: &HttpRequest,
+        ) -> HttpResponse<'a> {
+            asset_router
+                .serve_asset(&self.certificate_cbor, request)
+                .unwrap()
+        }
+
+        fn verify(
+            &self,
+            request: &HttpRequest,
+            response: HttpResponse,
+        ) -> ResponseVerificationResult<VerificationInfo> {
+            verify_request_response_pair(
+                request.clone(),
+                response,
+                &self.canister_id,
+                self.current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &self.root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            )
+        }
+    }
+
+    /// Serves the request with a certificate for the router's root hash and verifies the
+    /// served response, panicking if verification fails.
+    fn verify_served(asset_router: &AssetRouter, request: &HttpRequest) -> VerificationInfo {
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+        let response = fixture.serve(asset_router, request);
+
+        fixture.verify(request, response).unwrap()
+    }
+
+    fn get_header(response: &HttpResponse, name: &str) -> Option<String> {
+        response
+            .headers()
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    }
+
     fn extract_witness_expr_path(response: &HttpResponse) -> (HashTree, Vec<String>) {
         let (_, certificate_header_str) = response
             .headers()
@@ -4088,4 +11201,347 @@ mod tests {
             certificate_header.expr_path.unwrap(),
         )
     }
+
+    #[rstest]
+    #[case(
+        "public, max-age=31536000, immutable",
+        Some("public, max-age=300, immutable")
+    )]
+    #[case("max-age=3600, s-maxage=86400", Some("max-age=300, s-maxage=300"))]
+    #[case("public,MAX-AGE=600", Some("public, MAX-AGE=300"))]
+    #[case("public, max-age=60", None)]
+    #[case("public, max-age=300", None)]
+    #[case("public, no-cache, no-store", None)]
+    #[case("max-age=invalid", None)]
+    fn test_bounded_cache_control(#[case] cache_control: &str, #[case] expected: Option<&str>) {
+        let asset_router = AssetRouter::default().with_max_cert_time_offset(300_000_000_000);
+
+        assert_eq!(
+            asset_router.bounded_cache_control(cache_control).as_deref(),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(31536000, 300)]
+    #[case(300, 300)]
+    #[case(60, 60)]
+    #[case(0, 0)]
+    fn test_bounded_max_age(#[case] max_age: u64, #[case] expected: u64) {
+        let asset_router = AssetRouter::default().with_max_cert_time_offset(300_000_000_000);
+
+        assert_eq!(asset_router.bounded_max_age(max_age), expected);
+        assert_eq!(AssetRouter::default().bounded_max_age(max_age), max_age);
+    }
+
+    #[rstest]
+    fn test_max_cert_time_offset(app_js_body: Vec<u8>) {
+        let mut asset_router =
+            AssetRouter::default().with_max_cert_time_offset(MAX_CERT_TIME_OFFSET_NS);
+        asset_router
+            .certify_assets(
+                vec![Asset::new("app.js", app_js_body)],
+                vec![AssetConfig::file("app.js")
+                    .with_content_type("text/javascript")
+                    .with_header("cache-control", "public, max-age=31536000, immutable")
+                    .build()],
+            )
+            .unwrap();
+
+        let verification_info = verify_served(&asset_router, &HttpRequest::get("/app.js").build());
+
+        assert!(verification_info.is_fully_certified());
+        assert!(verification_info.response.unwrap().headers.contains(&(
+            "cache-control".to_string(),
+            "public, max-age=300, immutable".to_string()
+        )));
+    }
+
+    #[rstest]
+    fn test_filename_hash_validation(app_js_body: Vec<u8>) {
+        let content_hash = hash(&app_js_body)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        let hashed_path = format!("assets/app.{}.js", &content_hash[..8]);
+        let filename_hash_router = || {
+            AssetRouter::default()
+                .with_filename_hash_validation(AssetFilenameHashConfig::new("assets/*.js"))
+        };
+
+        for path in [
+            hashed_path.clone(),
+            format!("assets/app.{}.js", content_hash),
+            format!("assets/app.{}.js", content_hash[..8].to_uppercase()),
+            "app.js".to_string(),
+            "assets/app.css".to_string(),
+        ] {
+            let mut asset_router = filename_hash_router();
+            asset_router
+                .certify_assets(vec![Asset::new(path.clone(), app_js_body.clone())], vec![])
+                .unwrap_or_else(|err| panic!("{path}: {err}"));
+
+            let request = HttpRequest::get(format!("/{path}")).build();
+            let response = asset_router.serve_asset(&data_certificate(), &request);
+            assert!(response.is_ok(), "{path}");
+        }
+
+        let mut asset_router = filename_hash_router();
+        let result = asset_router.certify_assets(
+            vec![
+                Asset::new(hashed_path.clone(), app_js_body.clone()),
+                Asset::new("assets/vendor.00000000.js", app_js_body.clone()),
+            ],
+            vec![],
+        );
+        assert!(matches!(
+            result,
+            Err(AssetCertificationError::FilenameHashMismatch {
+                path,
+                filename_hash,
+                content_hash: mismatched_content_hash,
+            }) if path == "assets/vendor.00000000.js"
+                && filename_hash == "00000000"
+                && mismatched_content_hash == content_hash
+        ));
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+
+        let mut asset_router = filename_hash_router();
+        let result = asset_router.certify_assets(
+            vec![Asset::new("assets/app.js", app_js_body.clone())],
+            vec![],
+        );
+        assert!(matches!(
+            result,
+            Err(AssetCertificationError::MissingFilenameHash { path })
+                if path == "assets/app.js"
+        ));
+
+        let mut asset_router = AssetRouter::default()
+            .with_filename_hash_validation(AssetFilenameHashConfig::new("assets/[.js"));
+        let result =
+            asset_router.certify_assets(vec![Asset::new(hashed_path, app_js_body)], vec![]);
+        assert!(matches!(
+            result,
+            Err(AssetCertificationError::InvalidGlob { pattern, .. }) if pattern == "assets/[.js"
+        ));
+    }
+
+    #[test]
+    fn test_client_hints() {
+        const COLOR_SCHEME_HINT: &str = "Sec-CH-Prefers-Color-Scheme";
+
+        let mut asset_router = AssetRouter::default().with_client_hints(
+            AssetClientHintsConfig::new(vec![
+                COLOR_SCHEME_HINT.to_string(),
+                "Sec-CH-DPR".to_string(),
+            ])
+            .with_critical_hints(vec![COLOR_SCHEME_HINT.to_string()]),
+        );
+        asset_router
+            .certify_variants_with_selector(
+                "/logo.svg",
+                COLOR_SCHEME_HINT,
+                vec![r#""light""#.to_string(), r#""dark""#.to_string()],
+                |color_scheme| (color_scheme == r#""dark""#).then_some("dark"),
+                HttpResponse::ok(b"light".as_slice(), vec![]).build(),
+                vec![(
+                    "dark".to_string(),
+                    HttpResponse::ok(b"dark".as_slice(), vec![]).build(),
+                )],
+            )
+            .unwrap();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("index.html", b"index".as_slice())],
+                vec![AssetConfig::File {
+                    path: "index.html".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![],
+                    options: Default::default(),
+                }],
+            )
+            .unwrap();
+
+        let fixture = VerificationFixture::new(&asset_router.root_hash());
+
+        let request_with_color_scheme = |color_scheme: &str| {
+            HttpRequest::get("/logo.svg")
+                .with_headers(vec![(
+                    COLOR_SCHEME_HINT.to_lowercase(),
+                    color_scheme.to_string(),
+                )])
+                .build()
+        };
+
+        for (request, expected_body) in [
+            (request_with_color_scheme(r#""dark""#), b"dark".as_slice()),
+            (request_with_color_scheme(r#""light""#), b"light".as_slice()),
+            (HttpRequest::get("/logo.svg").build(), b"light".as_slice()),
+        ] {
+            let response = fixture.serve(&asset_router, &request);
+            assert_eq!(response.status_code(), StatusCode::OK);
+            assert_eq!(response.body(), expected_body);
+            assert_eq!(
+                get_header(&response, "accept-ch"),
+                Some("Sec-CH-Prefers-Color-Scheme, Sec-CH-DPR".to_string())
+            );
+            assert_eq!(
+                get_header(&response, "vary"),
+                Some(COLOR_SCHEME_HINT.to_string())
+            );
+            assert_eq!(
+                get_header(&response, "critical-ch"),
+                Some(COLOR_SCHEME_HINT.to_string())
+            );
+
+            let verification_info = fixture.verify(&request, response).unwrap();
+            assert!(verification_info.is_fully_certified());
+            assert_eq!(verification_info.response.unwrap().body, expected_body);
+        }
+
+        let index_response = fixture.serve(&asset_router, &HttpRequest::get("/index.html").build());
+        assert_eq!(
+            get_header(&index_response, "accept-ch"),
+            Some("Sec-CH-Prefers-Color-Scheme, Sec-CH-DPR".to_string())
+        );
+        assert_eq!(get_header(&index_response, "vary"), None);
+
+        for color_scheme in [r#""no-preference""#, "dark"] {
+            assert_matches!(
+                asset_router.serve_asset(
+                    &data_certificate(),
+                    &request_with_color_scheme(color_scheme)
+                ),
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
+            );
+        }
+
+        for light_request in [
+            HttpRequest::get("/logo.svg").build(),
+            request_with_color_scheme(r#""light""#),
+        ] {
+            let light_response = fixture.serve(&asset_router, &light_request);
+            assert_matches!(
+                fixture.verify(&request_with_color_scheme(r#""dark""#), light_response),
+                Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+            );
+        }
+
+        let dark_response = fixture.serve(&asset_router, &request_with_color_scheme(r#""dark""#));
+        assert_matches!(
+            fixture.verify(
+                &request_with_color_scheme(r#""light""#),
+                dark_response.clone()
+            ),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+        assert_matches!(
+            fixture.verify(&HttpRequest::get("/logo.svg").build(), dark_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+
+        asset_router
+            .certify_variants(
+                "/logo.svg",
+                "X-Theme",
+                HttpResponse::ok(b"light".as_slice(), vec![]).build(),
+                vec![(
+                    "dark".to_string(),
+                    HttpResponse::ok(
+                        b"dark".as_slice(),
+                        vec![("Vary".to_string(), "Accept-Encoding".to_string())],
+                    )
+                    .build(),
+                )],
+            )
+            .unwrap();
+        let response = asset_router
+            .serve_asset(
+                &data_certificate(),
+                &HttpRequest::get("/logo.svg")
+                    .with_headers(vec![("x-theme".to_string(), "dark".to_string())])
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(response.body(), b"dark");
+        assert_eq!(
+            get_header(&response, "vary"),
+            Some("Accept-Encoding, X-Theme".to_string())
+        );
+    }
+
+    #[test]
+    fn test_delete_assets_by_path_deletes_method_responses() {
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_assets(
+                vec![Asset::new("submit", b"Submit form".as_slice())],
+                vec![AssetConfig::File {
+                    path: "submit".to_string(),
+                    content_type: Some("text/html".to_string()),
+                    headers: vec![],
+                    fallback_for: vec![],
+                    aliased_by: vec![],
+                    encodings: vec![],
+                    options: AssetFileOptions {
+                        allowed_methods: vec![Method::GET],
+                        ..Default::default()
+                    },
+                }],
+            )
+            .unwrap();
+        asset_router
+            .certify_method_response(
+                "/submit",
+                Method::POST,
+                HttpResponse::ok(b"Submitted".as_slice(), vec![]).build(),
+            )
+            .unwrap();
+
+        asset_router.delete_assets_by_path(vec!["/submit"]);
+
+        for method in [Method::GET, Method::POST, Method::PUT] {
+            let request = HttpRequest::builder()
+                .with_method(method)
+                .with_url("/submit")
+                .build();
+            assert_matches!(
+                asset_router.serve_asset(&data_certificate(), &request),
+                Err(AssetCertificationError::NoAssetMatchingRequestUrl { .. })
+            );
+        }
+        assert_eq!(asset_router.root_hash(), AssetRouter::default().root_hash());
+    }
+
+    #[test]
+    fn test_recertify_same_method_response() {
+        let response = HttpResponse::ok(b"Submitted".as_slice(), vec![]).build();
+        let mut asset_router = AssetRouter::default();
+        asset_router
+            .certify_method_response("/submit", Method::POST, response.clone())
+            .unwrap();
+        let root_hash = asset_router.root_hash();
+
+        asset_router
+            .certify_method_response("/submit", Method::POST, response)
+            .unwrap();
+        assert_eq!(asset_router.root_hash(), root_hash);
+
+        let request = HttpRequest::post("/submit").build();
+        let response = asset_router
+            .serve_asset(&data_certificate(), &request)
+            .unwrap();
+        let (witness, expr_path) = extract_witness_expr_path(&response);
+
+        assert_eq!(response.body(), b"Submitted");
+        assert_eq!(expr_path, vec!["http_expr", "submit", "<$>"]);
+        assert_matches!(
+            witness.lookup_subtree(&expr_path),
+            SubtreeLookupResult::Found(_)
+        );
+    }
 }
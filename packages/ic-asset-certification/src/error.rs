@@ -1,3 +1,5 @@
+use crate::AssetEncoding;
+
 /// Asset certification result type.
 pub type AssetCertificationResult<T = ()> = Result<T, AssetCertificationError>;
 
@@ -11,15 +13,150 @@ pub enum AssetCertificationError {
         request_url: String,
     },
 
+    /// Thrown when no assets have been certified for the host of a given request,
+    /// and no default host has been configured.
+    #[error(r#"No assets were found matching the current request host: {host:?}"#)]
+    NoAssetsMatchingRequestHost {
+        /// The request host that was not matched to any assets, if any.
+        host: Option<String>,
+    },
+
     /// Thrown when the asset certification process fails.
     #[error(r#"HTTP Certification Error: "{0}""#)]
     HttpCertificationError(#[from] ic_http_certification::HttpCertificationError),
 
-    /// Thrown when glob pattern parsing fails.
-    #[error(r#"Glob error: {0}"#)]
-    GlobsetError(#[from] globset::Error),
+    /// Thrown when the glob pattern of a [Pattern](crate::AssetConfig::Pattern) config is invalid.
+    #[error(r#"Invalid glob pattern "{pattern}" in asset config: {source}"#)]
+    InvalidGlob {
+        /// The glob pattern that could not be parsed.
+        pattern: String,
+
+        /// The error returned when parsing the glob pattern.
+        #[source]
+        source: globset::Error,
+    },
+
+    /// Thrown when multiple assets, or multiple [File](crate::AssetConfig::File) configs,
+    /// are provided for the same path.
+    #[error(r#"The path "{path}" was provided more than once, each asset and file config must have a unique path"#)]
+    DuplicatePath {
+        /// The path that was provided more than once.
+        path: String,
+    },
+
+    /// Thrown when a [File](crate::AssetConfig::File) config lists an encoding, but the
+    /// encoded asset for that encoding was not provided.
+    #[error(r#"The {encoding} encoded asset "{encoded_path}" for the configured asset "{path}" was not provided"#)]
+    MissingEncodingAsset {
+        /// The path of the asset that the encoding was configured for.
+        path: String,
+
+        /// The encoding that was configured, but not provided.
+        encoding: AssetEncoding,
+
+        /// The path that the encoded asset was expected at.
+        encoded_path: String,
+    },
+
+    /// Thrown when an [encoding priority](crate::AssetRouter::with_encoding_priority) lists
+    /// the [Identity](AssetEncoding::Identity) encoding, or lists an encoding more than once.
+    #[error(r#"The {encoding} encoding cannot be prioritized, the encoding priority must list each encoding other than identity at most once"#)]
+    InvalidEncodingPriority {
+        /// The encoding that cannot be prioritized.
+        encoding: AssetEncoding,
+    },
+
+    /// Thrown when a [path rewrite](crate::AssetRouter::with_path_rewrites) has a prefix that
+    /// does not start and end with a `/`.
+    #[error(r#"The path rewrite prefix "{prefix}" is invalid, path rewrite prefixes must start and end with a "/""#)]
+    InvalidPathRewrite {
+        /// The prefix that is invalid.
+        prefix: String,
+    },
+
+    /// Thrown when the same alias is listed in the
+    /// [aliased_by](crate::AssetConfig::File::aliased_by) of multiple
+    /// [File](crate::AssetConfig::File) configs, or was already certified for another file,
+    /// unless conflicting routes are
+    /// [allowed](crate::AssetRouter::with_conflicting_routes_allowed).
+    #[error(r#"The alias "{alias}" is listed for both "{path}" and "{other_path}", each alias must belong to a single file config"#)]
+    DuplicateAlias {
+        /// The alias that was listed more than once.
+        alias: String,
+
+        /// The path of the first file config that listed the alias.
+        path: String,
+
+        /// The path of the other file config that listed the alias.
+        other_path: String,
+    },
+
+    /// Thrown when the same fallback scope is listed in the
+    /// [fallback_for](crate::AssetConfig::File::fallback_for) of multiple
+    /// [File](crate::AssetConfig::File) configs, or was already certified for another file,
+    /// unless conflicting routes are
+    /// [allowed](crate::AssetRouter::with_conflicting_routes_allowed).
+    #[error(r#"The fallback scope "{scope}" is listed for both "{path}" and "{other_path}", each fallback scope must belong to a single file config"#)]
+    ConflictingFallbackScope {
+        /// The fallback scope that was listed more than once.
+        scope: String,
+
+        /// The path of the first file config that listed the fallback scope.
+        path: String,
+
+        /// The path of the other file config that listed the fallback scope.
+        other_path: String,
+    },
+
+    /// Thrown when an asset matches the pattern of the
+    /// [filename hash validation](crate::AssetRouter::with_filename_hash_validation), but its
+    /// filename does not contain a hash.
+    #[error(r#"The filename of the asset "{path}" does not contain a content hash"#)]
+    MissingFilenameHash {
+        /// The path of the asset.
+        path: String,
+    },
+
+    /// Thrown when the hash in the filename of an asset does not match the hash of its content,
+    /// see [with_filename_hash_validation](crate::AssetRouter::with_filename_hash_validation).
+    #[error(r#"The hash "{filename_hash}" in the filename of the asset "{path}" does not match the hash of its content "{content_hash}""#)]
+    FilenameHashMismatch {
+        /// The path of the asset.
+        path: String,
+
+        /// The hash in the filename of the asset.
+        filename_hash: String,
+
+        /// The hex encoded SHA-256 hash of the content of the asset.
+        content_hash: String,
+    },
+
+    /// Thrown when a [File](crate::AssetConfig::File) config does not match any provided asset.
+    #[error(r#"No asset was provided for the file config with path "{config_path}""#)]
+    ConfigAssetMismatch {
+        /// The path of the file config that did not match any asset.
+        config_path: String,
+    },
 
     /// Request
     #[error(r#"Request error: {0}"#)]
     RequestError(String),
+
+    /// Thrown when a chunked certification is started while another one is still in progress.
+    #[error(r#"A chunked certification is already in progress, it must be finalized before starting another one"#)]
+    CertificationAlreadyInProgress,
+
+    /// Thrown when a chunk is certified or a certification is finalized without first starting
+    /// a chunked certification.
+    #[error(
+        r#"No chunked certification is in progress, it must be started with begin_certification"#
+    )]
+    NoCertificationInProgress,
+
+    /// Thrown when maintenance mode is entered on a router that shares its tree with other
+    /// routers, for example through [with_tree](crate::AssetRouter::with_tree).
+    #[error(
+        r#"Maintenance mode is not supported for routers that share their certification tree"#
+    )]
+    MaintenanceWithSharedTree,
 }
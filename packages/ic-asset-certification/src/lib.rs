@@ -120,8 +120,33 @@
 //!         - Gzip
 //!         - Deflate
 //!         - Identity
-//!     - The asset router will return the highest priority encoding that has been
-//!       certified and is supported by the client.
+//!     - This order can be changed with
+//!       [with_encoding_priority](AssetRouter::with_encoding_priority), for example
+//!       to prefer Gzip, which is cheaper to decode on low-end clients.
+//!     - The asset router will return the encoding with the highest quality value
+//!       in the `Accept-Encoding` header that has been certified, and the highest
+//!       priority encoding among those with equal quality values, such as `br` for
+//!       `gzip, br` and `gzip` for `gzip, br;q=0.5`.
+//!     - Encodings that the client marks with a quality of zero, such as `br;q=0`,
+//!       are never served.
+//!     - The uncompressed (identity) file is always certified and is served when the
+//!       `Accept-Encoding` header is empty, only contains `identity`, prefers
+//!       `identity` over the certified encodings, or none of the client's
+//!       acceptable encodings have been certified.
+//!     - A missing `Accept-Encoding` header is treated as accepting any encoding,
+//!       like `*`. Since some clients that omit it cannot decode compressed bodies,
+//!       the identity file can be served to them instead with
+//!       [with_any_encoding_when_accept_encoding_missing](AssetRouter::with_any_encoding_when_accept_encoding_missing).
+//! - [options](AssetConfig::File::options)
+//!     - Additional options, such as whether an individual file is
+//!       [addressable](AssetFileOptions::addressable) by its own path, whether its
+//!       certification is skipped, or which query parameters and methods are certified
+//!       for it, see [AssetFileOptions] and [AssetPatternOptions].
+//!     - Each option has a default, so most configs can use `Default::default()`,
+//!       or set only the options that differ from the defaults with
+//!       `AssetFileOptions { addressable: false, ..Default::default() }`.
+//!     - Options are added with a default, so configs written this way keep
+//!       compiling as new options are added.
 //!
 //! ### Configuring individual files
 //!
@@ -185,6 +210,7 @@
 //!         AssetEncoding::Brotli.default_config(),
 //!         AssetEncoding::Gzip.default_config(),
 //!     ],
+//!     options: Default::default(),
 //! };
 //! ```
 //!
@@ -238,9 +264,18 @@
 //!         AssetEncoding::Brotli.default_config(),
 //!         AssetEncoding::Gzip.default_config(),
 //!     ],
+//!     options: Default::default(),
 //! };
 //! ```
 //!
+//! A fallback always serves the body of the asset it is configured on. To serve
+//! different bodies for different scopes, such as a JSON `404` response for `/api` and
+//! an HTML `404` response for `/`, configure the `/api` scope as a fallback on a
+//! `404.json` asset and the `/` scope as a fallback on a `404.html` asset. Each asset
+//! is certified separately for its own scopes, with its own content type, headers and
+//! status code, so requests within `/api` will only pass verification with the JSON
+//! response and all other requests with the HTML response.
+//!
 //! ### Configuring file patterns
 //!
 //! When configuring file patterns, the `pattern` property is provided. This
@@ -286,6 +321,7 @@
 //!         AssetEncoding::Brotli.default_config(),
 //!         AssetEncoding::Gzip.default_config(),
 //!     ],
+//!     options: Default::default(),
 //! };
 //! ```
 //!
@@ -402,6 +438,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.js".to_string(),
@@ -414,6 +451,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.css".to_string(),
@@ -426,6 +464,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Redirect {
 //!         from: "/old".to_string(),
@@ -495,6 +534,7 @@
 //!     }],
 //!     aliased_by: vec!["/".to_string()],
 //!     encodings: vec![],
+//!     options: Default::default(),
 //! };
 //!
 //! let http_request = HttpRequest::get("/").build();
@@ -596,6 +636,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.js".to_string(),
@@ -608,6 +649,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.css".to_string(),
@@ -620,6 +662,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Redirect {
 //!         from: "/old".to_string(),
@@ -669,6 +712,7 @@
 //!                 AssetEncoding::Brotli.default_config(),
 //!                 AssetEncoding::Gzip.default_config(),
 //!             ],
+//!             options: Default::default(),
 //!         }],
 //!     )
 //!     .unwrap();
@@ -700,6 +744,7 @@
 //!                 AssetEncoding::Brotli.default_config(),
 //!                 AssetEncoding::Gzip.default_config(),
 //!             ],
+//!             options: Default::default(),
 //!         }],
 //!     )
 //!     .unwrap();
@@ -740,6 +785,7 @@
 //!                 AssetEncoding::Brotli.default_config(),
 //!                 AssetEncoding::Gzip.default_config(),
 //!             ],
+//!             options: Default::default(),
 //!         },
 //!     ]
 //! ).unwrap();
@@ -856,6 +902,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.js".to_string(),
@@ -868,6 +915,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Pattern {
 //!         pattern: "**/*.css".to_string(),
@@ -880,6 +928,7 @@
 //!             AssetEncoding::Brotli.default_config(),
 //!             AssetEncoding::Gzip.default_config(),
 //!         ],
+//!         options: Default::default(),
 //!     },
 //!     AssetConfig::Redirect {
 //!         from: "/old".to_string(),
@@ -1004,6 +1053,7 @@
 
 mod asset;
 mod asset_config;
+mod asset_host_router;
 mod asset_map;
 mod asset_router;
 mod error;
@@ -1011,6 +1061,7 @@ mod types;
 
 pub use asset::*;
 pub use asset_config::*;
+pub use asset_host_router::*;
 pub use asset_map::*;
 pub use asset_router::*;
 pub use error::*;
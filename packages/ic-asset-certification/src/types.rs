@@ -4,6 +4,9 @@ use ic_http_certification::{HttpCertificationTreeEntry, HttpResponse};
 pub(crate) struct CertifiedAssetResponse<'a> {
     pub(crate) response: HttpResponse<'a>,
     pub(crate) tree_entry: HttpCertificationTreeEntry<'a>,
+    /// The `304 Not Modified` response that is served instead of this response
+    /// when the request's `If-None-Match` header matches this response's `ETag`.
+    pub(crate) not_modified: Option<Box<CertifiedAssetResponse<'a>>>,
 }
 
 /// A key created from request data, to retrieve the corresponding response.
@@ -15,6 +18,8 @@ pub(crate) struct RequestKey {
     pub(crate) encoding: Option<String>,
     /// The beginning of the requested range (if any), counting from 0.
     pub(crate) range_begin: Option<usize>,
+    /// The certified query parameters of the request (if any), as they appear in the URL.
+    pub(crate) query: Option<String>,
 }
 
 impl RequestKey {
@@ -27,6 +32,13 @@ impl RequestKey {
             path: path.into(),
             encoding,
             range_begin,
+            query: None,
         }
     }
+
+    pub(crate) fn with_query(mut self, query: Option<String>) -> Self {
+        self.query = query;
+
+        self
+    }
 }
@@ -182,6 +182,7 @@ fn asset_config(path: String, encodings: Vec<(AssetEncoding, String)>) -> AssetC
         fallback_for: vec![],
         aliased_by: vec![],
         encodings,
+        options: Default::default(),
     }
 }
 
@@ -1,15 +1,17 @@
 mod tests {
     use assert_matches::assert_matches;
+    use candid::Principal;
     use ic_certificate_verification::CertificateVerificationError;
     use ic_certification_testing::{CertificateBuilder, CertificateData};
     use ic_http_certification::{HttpRequest, HttpResponse, CERTIFICATE_HEADER_NAME};
     use ic_response_verification::types::{VerificationInfo, VerifiedResponse};
     use ic_response_verification::verify_request_response_pair;
-    use ic_response_verification::ResponseVerificationError;
+    use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
     use ic_response_verification_test_utils::{
-        create_canister_id, create_certificate_header, create_certified_data,
-        get_current_timestamp, get_timestamp, AssetTree,
+        create_canister_id, create_certificate_header, create_certified_data, deflate_encode,
+        get_current_timestamp, get_timestamp, gzip_encode, AssetTree,
     };
+    use ic_types::{CanisterId, PrincipalId};
     use std::ops::{Add, Sub};
     use std::time::{Duration, SystemTime};
 
@@ -64,11 +66,18 @@ mod tests {
         )
         .unwrap();
 
+        assert!(result.is_fully_certified());
         assert_matches!(
             result,
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
             } if verification_version == 1 && response == Some(expected_response)
         );
     }
@@ -127,6 +136,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
             } if verification_version == 1 && response == Some(expected_response)
         );
     }
@@ -184,6 +199,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
             } if verification_version == 1 && response == Some(expected_response)
         );
     }
@@ -595,4 +616,516 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn undecodable_content_encoding_fails_strict_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        let response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+                ("Content-Encoding".into(), "br".into()),
+            ],
+        )
+        .build();
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let lenient_result = verifier.verify(HttpRequest::get(path).build(), response.clone());
+
+        assert_matches!(
+            lenient_result,
+            Ok(VerificationInfo {
+                verification_version,
+                response: Some(_),
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
+            }) if verification_version == 1
+        );
+
+        let strict_result = verifier
+            .with_strict_content_encoding(true)
+            .verify(HttpRequest::get(path).build(), response)
+            .unwrap_err();
+
+        assert_matches!(
+            strict_result,
+            ResponseVerificationError::ContentEncodingMismatch { encoding } if encoding == "br"
+        );
+    }
+
+    #[test]
+    fn invalid_content_encoding_fails_strict_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        let response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+                ("Content-Encoding".into(), "gzip".into()),
+            ],
+        )
+        .build();
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let lenient_result = verifier.verify(HttpRequest::get(path).build(), response.clone());
+
+        assert_matches!(lenient_result, Err(ResponseVerificationError::IoError(_)));
+
+        let strict_result = verifier
+            .with_strict_content_encoding(true)
+            .verify(HttpRequest::get(path).build(), response)
+            .unwrap_err();
+
+        assert_matches!(
+            strict_result,
+            ResponseVerificationError::ContentEncodingMismatch { encoding } if encoding == "gzip"
+        );
+    }
+
+    #[test]
+    fn gzip_body_certified_decoded_passes_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let encoded_body = gzip_encode(body.as_bytes());
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        let response = HttpResponse::ok(
+            encoded_body.clone(),
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+                ("Content-Encoding".into(), "gzip".into()),
+            ],
+        )
+        .build();
+        let expected_response = VerifiedResponse {
+            status_code: None,
+            body: encoded_body,
+            headers: vec![],
+        };
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let lenient_result = verifier
+            .verify(HttpRequest::get(path).build(), response.clone())
+            .unwrap();
+
+        assert_matches!(
+            lenient_result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
+            } if verification_version == 1 && response.as_ref() == Some(&expected_response)
+        );
+
+        let strict_result = verifier
+            .with_strict_content_encoding(true)
+            .verify(HttpRequest::get(path).build(), response);
+
+        assert_matches!(
+            strict_result,
+            Ok(VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
+            }) if verification_version == 1 && response == Some(expected_response)
+        );
+    }
+
+    #[test]
+    fn gzip_body_certified_raw_passes_lenient_verification_only() {
+        let path = "/";
+        let body = "Hello World!";
+        let encoded_body = gzip_encode(body.as_bytes());
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, &encoded_body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        let response = HttpResponse::ok(
+            encoded_body.clone(),
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+                ("Content-Encoding".into(), "gzip".into()),
+            ],
+        )
+        .build();
+        let expected_response = VerifiedResponse {
+            status_code: None,
+            body: encoded_body,
+            headers: vec![],
+        };
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let lenient_result = verifier
+            .verify(HttpRequest::get(path).build(), response.clone())
+            .unwrap();
+
+        assert_matches!(
+            lenient_result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: None,
+                certificate_expression: None,
+                certificate_expression_hash: None,
+                ..
+            } if verification_version == 1 && response.as_ref() == Some(&expected_response)
+        );
+
+        let strict_result = verifier
+            .with_strict_content_encoding(true)
+            .verify(HttpRequest::get(path).build(), response);
+
+        assert_matches!(
+            strict_result,
+            Err(ResponseVerificationError::InvalidResponseBody)
+        );
+    }
+
+    #[test]
+    fn multiple_content_encodings_pass_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let encoded_body = gzip_encode(&deflate_encode(body.as_bytes()));
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        let response = HttpResponse::ok(
+            encoded_body.clone(),
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header.clone()),
+                ("Content-Encoding".into(), "deflate, gzip".into()),
+            ],
+        )
+        .build();
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .with_strict_content_encoding(true);
+
+        let result = verifier
+            .verify(HttpRequest::get(path).build(), response)
+            .unwrap();
+
+        assert_eq!(result.verification_version, 1);
+        assert_eq!(
+            result.response.map(|response| response.body),
+            Some(encoded_body.clone())
+        );
+
+        let reversed_response = HttpResponse::ok(
+            encoded_body,
+            vec![
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+                ("Content-Encoding".into(), "gzip, deflate".into()),
+            ],
+        )
+        .build();
+
+        let reversed_result = verifier
+            .verify(HttpRequest::get(path).build(), reversed_response)
+            .unwrap_err();
+
+        assert_matches!(
+            reversed_result,
+            ResponseVerificationError::ContentEncodingMismatch { encoding } if encoding == "gzip, deflate"
+        );
+    }
+
+    #[test]
+    fn multiple_content_encodings_with_brotli_pass_lenient_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        for content_encoding in ["gzip, br", "br, gzip"] {
+            let response = HttpResponse::ok(
+                body.as_bytes(),
+                vec![
+                    (CERTIFICATE_HEADER_NAME.into(), certificate_header.clone()),
+                    ("Content-Encoding".into(), content_encoding.into()),
+                ],
+            )
+            .build();
+            let verifier = ResponseVerifier::new(
+                canister_id.as_ref(),
+                current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            );
+
+            let result = verifier
+                .verify(HttpRequest::get(path).build(), response)
+                .unwrap();
+
+            assert_eq!(result.verification_version, 1);
+            assert_eq!(
+                result.response.map(|response| response.body),
+                Some(body.as_bytes().to_vec())
+            );
+        }
+    }
+
+    #[test]
+    fn multiple_content_encodings_with_brotli_fail_strict_verification() {
+        let path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let canister_id = create_canister_id("rdmx6-jaaaa-aaaaa-aaadq-cai");
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        let CertificateData {
+            cbor_encoded_certificate,
+            certificate: _,
+            root_key,
+        } = CertificateBuilder::new(&canister_id.to_string(), &certified_data)
+            .unwrap()
+            .with_time(current_time)
+            .build()
+            .unwrap();
+
+        let certificate_header = create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+
+        for content_encoding in ["gzip, br", "br, gzip"] {
+            let response = HttpResponse::ok(
+                body.as_bytes(),
+                vec![
+                    (CERTIFICATE_HEADER_NAME.into(), certificate_header.clone()),
+                    ("Content-Encoding".into(), content_encoding.into()),
+                ],
+            )
+            .build();
+            let verifier = ResponseVerifier::new(
+                canister_id.as_ref(),
+                current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            )
+            .with_strict_content_encoding(true);
+
+            let result = verifier
+                .verify(HttpRequest::get(path).build(), response)
+                .unwrap_err();
+
+            assert_matches!(
+                result,
+                ResponseVerificationError::UnsupportedContentEncoding { encoding } if encoding == content_encoding
+            );
+        }
+    }
+
+    #[test]
+    fn subnet_id_is_reported_for_delegated_certificates_only() {
+        let path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let canister_id = CanisterId::from_u64(5);
+
+        let mut asset_tree = AssetTree::new();
+        asset_tree.insert(path, body);
+        let certified_data = asset_tree.get_certified_data();
+        let tree_cbor = asset_tree.serialize_to_cbor(Some(path));
+
+        for (delegation_subnet_id, expected_subnet_id) in [
+            (None, None),
+            (
+                Some(123),
+                Some(Principal::from_slice(
+                    PrincipalId::new_subnet_test_id(123).as_slice(),
+                )),
+            ),
+        ] {
+            let mut certificate_builder =
+                CertificateBuilder::new(&canister_id.to_string(), &certified_data).unwrap();
+            certificate_builder.with_time(current_time);
+            if let Some(delegation_subnet_id) = delegation_subnet_id {
+                certificate_builder.with_delegation(delegation_subnet_id, vec![(0, 10)]);
+            }
+            let CertificateData {
+                cbor_encoded_certificate,
+                certificate: _,
+                root_key,
+            } = certificate_builder.build().unwrap();
+
+            let certificate_header =
+                create_certificate_header(&cbor_encoded_certificate, &tree_cbor);
+            let response = HttpResponse::ok(
+                body.as_bytes(),
+                vec![(CERTIFICATE_HEADER_NAME.into(), certificate_header)],
+            )
+            .build();
+
+            let result = verify_request_response_pair(
+                HttpRequest::get(path).build(),
+                response,
+                canister_id.as_ref(),
+                current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            )
+            .unwrap();
+
+            assert_eq!(result.subnet_id, expected_subnet_id);
+        }
+    }
 }
@@ -113,6 +113,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response == Some(expected_certified_response)
         );
     }
@@ -428,6 +434,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response == Some(expected_certified_response)
         );
     }
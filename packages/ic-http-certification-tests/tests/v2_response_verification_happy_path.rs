@@ -1,17 +1,24 @@
 mod tests {
     use assert_matches::assert_matches;
+    use candid::Principal;
+    use ic_certificate_verification::CertificateVerificationError;
     use ic_http_certification::{
-        DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpCertificationPath,
-        HttpCertificationTreeEntry, HttpRequest, HttpResponse, CERTIFICATE_EXPRESSION_HEADER_NAME,
-        CERTIFICATE_HEADER_NAME,
+        filter_response_headers, response_headers_hash, DefaultCelBuilder,
+        DefaultResponseCertification, HttpCertification, HttpCertificationPath,
+        HttpCertificationTree, HttpCertificationTreeEntry, HttpRequest, HttpResponse,
+        HttpUpdateResponse, CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
     };
+    use ic_representation_independent_hash::hash;
     use ic_response_verification::{
-        types::{VerificationInfo, VerifiedResponse},
-        verify_request_response_pair,
+        types::{VerificationInfo, VerificationOutcome, VerifiedResponse},
+        verify_request_response_pair, CertificateHeader, ResponseVerificationError,
+        ResponseVerifier,
     };
     use ic_response_verification_test_utils::{
         create_v2_fixture, get_current_timestamp, V2Fixture,
     };
+    use ic_types::PrincipalId;
+    use rstest::*;
 
     const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
     const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
@@ -60,11 +67,19 @@ mod tests {
         )
         .unwrap();
 
+        assert_eq!(result.outcome(), VerificationOutcome::CertifiedSkip);
+        assert!(!result.is_fully_certified());
         assert_matches!(
             result,
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response.is_none()
         );
     }
@@ -139,6 +154,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response == Some(expected_response)
         );
     }
@@ -220,6 +241,12 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response == Some(expected_response)
         );
     }
@@ -297,7 +324,1331 @@ mod tests {
             VerificationInfo {
                 verification_version,
                 response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
             } if verification_version == 2 && response == Some(expected_response)
         );
     }
+
+    #[test]
+    fn response_headers_hash_matches_verified_response_hash() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let expr_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "max-age=604800".into()),
+                ("Server".into(), "Apache/2.4.1 (Unix)".into()),
+            ],
+        )
+        .build();
+
+        // the response hash, as a canister would compute it manually
+        let response_headers = filter_response_headers(&response, &cel_expr.response);
+        let headers_hash =
+            response_headers_hash(&response.status_code().as_u16().into(), &response_headers);
+        let response_hash = hash([headers_hash, hash(response.body())].concat().as_slice());
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry = HttpCertificationTreeEntry::new(&expr_path, certification);
+
+        let mut tree = HttpCertificationTree::default();
+        tree.insert(&certification_tree_entry);
+        let witness = tree.witness(&certification_tree_entry, req_path).unwrap();
+        let mut expected_tree_path = certification_tree_entry
+            .path
+            .to_expr_path()
+            .into_iter()
+            .map(String::into_bytes)
+            .collect::<Vec<_>>();
+        expected_tree_path.extend([
+            hash(cel_expr.to_string().as_bytes()).to_vec(),
+            vec![],
+            response_hash.to_vec(),
+        ]);
+        assert!(witness.list_paths().iter().any(|tree_path| tree_path
+            .iter()
+            .map(|label| label.as_bytes())
+            .eq(expected_tree_path.iter().map(Vec::as_slice))));
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert!(result.is_fully_certified());
+    }
+
+    #[test]
+    fn verified_response_into_http_response_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((
+            CERTIFICATE_HEADER_NAME.to_string(),
+            certificate_header.clone(),
+        ));
+
+        let verified_response = verify_request_response_pair(
+            HttpRequest::get(req_path).build(),
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap()
+        .response
+        .unwrap();
+
+        let reemitted_response = verified_response
+            .into_http_response(&certificate_header)
+            .unwrap();
+
+        let result = verify_request_response_pair(
+            HttpRequest::get(req_path).build(),
+            reemitted_response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase(),
+                    cel_expr.to_string(),
+                ),
+                ("cache-control".into(), "max-age=604800".into()),
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+            ],
+        };
+
+        assert!(result.is_fully_certified());
+        assert_eq!(result.outcome(), VerificationOutcome::Certified);
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response == Some(expected_response)
+        );
+    }
+
+    #[test]
+    fn expected_upgrade_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+        let cel_expr = DefaultCelBuilder::skip_certification();
+
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .with_upgrade(true)
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .with_expected_upgrade(true)
+        .verify(HttpRequest::get(req_path).build(), response)
+        .unwrap();
+
+        assert!(!result.is_fully_certified());
+        assert_eq!(result.outcome(), VerificationOutcome::CertifiedSkip);
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response.is_none()
+        );
+    }
+
+    #[test]
+    fn split_certificate_header_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+        let cel_expr = DefaultCelBuilder::skip_certification();
+
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        let (first_certificate_header, second_certificate_header) =
+            certificate_header.split_once(',').unwrap();
+        response.add_header((
+            CERTIFICATE_HEADER_NAME.to_string(),
+            first_certificate_header.to_string(),
+        ));
+        response.add_header((
+            CERTIFICATE_HEADER_NAME.to_lowercase(),
+            second_certificate_header.to_string(),
+        ));
+
+        let result = verify_request_response_pair(
+            HttpRequest::get(req_path).build(),
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response.is_none()
+        );
+    }
+
+    #[test]
+    fn preserved_header_casing_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                ("Cache-Control".into(), "max-age=604800".into()),
+                ("X-Uncertified".into(), "uncertified".into()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((
+            CERTIFICATE_HEADER_NAME.to_uppercase(),
+            certificate_header.clone(),
+        ));
+
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier
+            .clone()
+            .with_preserved_header_casing(true)
+            .verify(HttpRequest::get(req_path).build(), response.clone())
+            .unwrap();
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("Cache-Control".into(), "max-age=604800".into()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                (
+                    CERTIFICATE_HEADER_NAME.to_uppercase(),
+                    certificate_header.clone(),
+                ),
+            ],
+        };
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response == Some(expected_response)
+        );
+
+        let result = verifier
+            .verify(HttpRequest::get(req_path).build(), response)
+            .unwrap();
+        let expected_response = VerifiedResponse {
+            status_code: Some(200),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("cache-control".into(), "max-age=604800".into()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase(),
+                    cel_expr.to_string(),
+                ),
+                (CERTIFICATE_HEADER_NAME.into(), certificate_header),
+            ],
+        };
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response == Some(expected_response)
+        );
+    }
+
+    #[test]
+    fn borrowed_request_and_response_pass_verification() {
+        let req_path = "/";
+        let body = vec![b'a'; 64 * 1024];
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+        let cel_expr = DefaultCelBuilder::full_certification().build();
+
+        let request = HttpRequest::post(req_path)
+            .with_body(body.as_slice())
+            .build();
+        let mut response = HttpResponse::ok(
+            body.as_slice(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .verify_borrowed(&request, &response)
+        .unwrap();
+
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response: Some(VerifiedResponse { body: verified_body, .. }),
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && verified_body == body
+        );
+        assert_eq!(request.body(), body.as_slice());
+        assert_eq!(response.body(), body.as_slice());
+    }
+
+    #[test]
+    fn owned_response_body_is_moved_into_verified_response() {
+        let req_path = "/";
+        let body = vec![b'a'; 64 * 1024];
+        let body_ptr = body.as_ptr();
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body,
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .verify(request, response)
+        .unwrap();
+
+        let verified_body = result.response.unwrap().body;
+        assert_eq!(verified_body, vec![b'a'; 64 * 1024]);
+        assert_eq!(verified_body.as_ptr(), body_ptr);
+    }
+
+    #[test]
+    fn update_response_passes_verification() {
+        let req_path = "/api/todos";
+        let body = r#"{"id":1}"#;
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Content-Type"],
+            ))
+            .build();
+
+        let request = HttpRequest::post(req_path).build();
+        let response = HttpResponse::created(
+            body.as_bytes(),
+            vec![
+                ("Content-Type".into(), "application/json".into()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        let mut update_response = HttpUpdateResponse::from(response);
+        update_response.add_header((
+            CERTIFICATE_HEADER_NAME.to_string(),
+            certificate_header.clone(),
+        ));
+
+        let result = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .with_expected_upgrade(true)
+        .verify_update(request, update_response)
+        .unwrap();
+
+        let expected_response = VerifiedResponse {
+            status_code: Some(201),
+            body: body.as_bytes().to_vec(),
+            headers: vec![
+                ("content-type".into(), "application/json".into()),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase(),
+                    cel_expr.to_string(),
+                ),
+                (CERTIFICATE_HEADER_NAME.to_string(), certificate_header),
+            ],
+        };
+        assert_matches!(
+            result,
+            VerificationInfo {
+                verification_version,
+                response,
+                uncertified_headers: None,
+                root_key_index: None,
+                subnet_id: Some(_),
+                certificate_expression: Some(_),
+                certificate_expression_hash: Some(_),
+                ..
+            } if verification_version == 2 && response == Some(expected_response)
+        );
+    }
+
+    #[test]
+    fn uncertified_headers_are_reported() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Content-Type"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                ("Content-Type".into(), "text/plain".into()),
+                (
+                    "Content-Security-Policy".into(),
+                    "default-src 'self'".into(),
+                ),
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "no-cache".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier.verify_borrowed(&request, &response).unwrap();
+        assert_eq!(result.uncertified_headers, None);
+
+        let result = verifier
+            .with_uncertified_headers_report(true)
+            .verify_borrowed(&request, &response)
+            .unwrap();
+        assert!(result.is_fully_certified());
+        assert_eq!(
+            result.uncertified_headers,
+            Some(vec![
+                (
+                    "Content-Security-Policy".to_string(),
+                    "default-src 'self'".to_string()
+                ),
+                ("Cache-Control".to_string(), "no-cache".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn additional_root_keys_are_accepted() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+        let V2Fixture {
+            root_key: other_root_key,
+            ..
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+        let other_verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &other_root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier.verify_borrowed(&request, &response).unwrap();
+        assert_eq!(result.root_key_index, None);
+        assert_eq!(result.certificate_time_ns, current_time);
+
+        assert_matches!(
+            other_verifier.verify_borrowed(&request, &response),
+            Err(ResponseVerificationError::CertificateVerificationFailed(
+                CertificateVerificationError::SignatureVerificationFailed
+            ))
+        );
+
+        let additional_root_keys = [other_root_key.as_slice(), root_key.as_slice()];
+        let result = other_verifier
+            .clone()
+            .with_additional_root_keys(&additional_root_keys)
+            .verify_borrowed(&request, &response)
+            .unwrap();
+        assert!(result.is_fully_certified());
+        assert_eq!(result.root_key_index, Some(2));
+
+        let additional_root_keys = [other_root_key.as_slice()];
+        let result = verifier
+            .clone()
+            .with_additional_root_keys(&additional_root_keys)
+            .verify_borrowed(&request, &response)
+            .unwrap();
+        assert_eq!(result.root_key_index, Some(0));
+
+        let additional_root_keys = [other_root_key.as_slice(), [0; 96].as_slice()];
+        assert_matches!(
+            other_verifier
+                .clone()
+                .with_additional_root_keys(&additional_root_keys)
+                .verify_borrowed(&request, &response),
+            Err(ResponseVerificationError::CertificateVerificationFailed(
+                CertificateVerificationError::DerKeyLengthMismatch { .. }
+            ))
+        );
+
+        let additional_root_keys = [root_key.as_slice()];
+        assert_matches!(
+            other_verifier
+                .clone()
+                .with_additional_root_keys(&additional_root_keys)
+                .verify_borrowed(&HttpRequest::get("/other").build(), &response),
+            Err(ResponseVerificationError::ExactExpressionPathMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn expected_body_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier
+            .verify_expected_body(&request, &response, body.as_bytes())
+            .unwrap();
+        assert!(result.is_fully_certified());
+        assert_eq!(result.response.unwrap().body, body.as_bytes());
+
+        let tampered_response = response.clone().map_body(|_| b"Hello Mars?".to_vec());
+        assert_matches!(
+            verifier.verify_borrowed(&request, &tampered_response),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+        let result = verifier
+            .verify_expected_body(&request, &tampered_response, body.as_bytes())
+            .unwrap();
+        assert_eq!(result.response.unwrap().body, body.as_bytes());
+
+        assert_matches!(
+            verifier.verify_expected_body(&request, &response, b"Hello Mars?"),
+            Err(ResponseVerificationError::ExpectedBodyNotCertified)
+        );
+        assert_matches!(
+            verifier.verify_expected_body(
+                &HttpRequest::get("/other").build(),
+                &response,
+                body.as_bytes()
+            ),
+            Err(ResponseVerificationError::ExactExpressionPathMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn expected_body_fails_verification_with_uncertified_request() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_query_parameters(vec!["q"])
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get("/?q=hello").build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        assert!(verifier
+            .verify_expected_body(&request, &response, body.as_bytes())
+            .unwrap()
+            .is_fully_certified());
+        assert_matches!(
+            verifier.verify_expected_body(&request, &response, b"Hello Mars?"),
+            Err(ResponseVerificationError::ExpectedBodyNotCertified)
+        );
+        assert_matches!(
+            verifier.verify_expected_body(
+                &HttpRequest::get("/?q=goodbye").build(),
+                &response,
+                body.as_bytes()
+            ),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+    }
+
+    #[test]
+    fn expected_body_fails_verification_without_certification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+        let cel_expr = DefaultCelBuilder::skip_certification();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        assert!(!verifier
+            .verify_borrowed(&request, &response)
+            .unwrap()
+            .is_fully_certified());
+        assert_matches!(
+            verifier.verify_expected_body(&request, &response, body.as_bytes()),
+            Err(ResponseVerificationError::ExpectedBodyNotCertified)
+        );
+    }
+
+    #[test]
+    fn body_hash_passes_verification() {
+        let req_path = "/video.mp4";
+        let body = vec![7; 3 * 1024 * 1024];
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Content-Type"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_slice(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Content-Type".into(), "video/mp4".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let (status_code, headers, _body, upgrade) = response.clone().into_parts();
+        let streamed_response =
+            HttpResponse::from_parts(status_code, headers, Vec::<u8>::new(), upgrade);
+
+        let result = verifier
+            .verify_body_hash(&request, &streamed_response, hash(&body))
+            .unwrap();
+        assert!(result.is_fully_certified());
+        let verified_response = result.response.unwrap();
+        assert_eq!(verified_response.status_code, Some(200));
+        assert!(verified_response.body.is_empty());
+        assert!(verified_response
+            .headers
+            .contains(&("content-type".to_string(), "video/mp4".to_string())));
+
+        let full_result = verifier.verify_borrowed(&request, &response).unwrap();
+        assert_eq!(
+            full_result.response.unwrap().headers,
+            verified_response.headers
+        );
+
+        let mut tampered_body = body.clone();
+        tampered_body[1024] = 8;
+        assert_matches!(
+            verifier.verify_body_hash(&request, &streamed_response, hash(&tampered_body)),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+
+        let (status_code, headers, body, upgrade) = streamed_response.into_parts();
+        let tampered_headers = headers
+            .into_iter()
+            .map(|(name, value)| match name.as_str() {
+                "Content-Type" => (name, "text/html".to_string()),
+                _ => (name, value),
+            })
+            .collect();
+        assert_matches!(
+            verifier.verify_body_hash(
+                &request,
+                &HttpResponse::from_parts(status_code, tampered_headers, body, upgrade),
+                hash(b"Hello World!"),
+            ),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+    }
+
+    #[test]
+    fn certificate_expression_is_reported() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "no-cache".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert_eq!(result.certificate_expression, Some(cel_expr.to_string()));
+        assert_eq!(
+            result.certificate_expression_hash,
+            Some(hash(cel_expr.to_string().as_bytes()))
+        );
+    }
+
+    #[test]
+    fn pre_parsed_certificate_header_passes_verification() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        let parsed_certificate_header = CertificateHeader::from(&certificate_header).unwrap();
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier
+            .verify_certificate_header(&request, &response, &parsed_certificate_header)
+            .unwrap();
+        let expected_result = verifier.verify_borrowed(&request, &response).unwrap();
+        assert!(result.is_fully_certified());
+        assert_eq!(result.verification_version, 2);
+        assert_eq!(
+            result.certificate_time_ns,
+            expected_result.certificate_time_ns
+        );
+        assert_eq!(result.response, expected_result.response);
+
+        let mut tampered_response = response.clone();
+        tampered_response.headers_mut()[1].1 = "no-cache".to_string();
+        assert_matches!(
+            verifier.verify_certificate_header(
+                &request,
+                &tampered_response,
+                &parsed_certificate_header
+            ),
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        );
+    }
+
+    #[test]
+    fn delegated_certificate_subnet_id_is_reported() {
+        let req_path = "/";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path).build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        // the v2 fixture's certificate is signed by subnet 123 with a delegation
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.subnet_id,
+            Some(Principal::from_slice(
+                PrincipalId::new_subnet_test_id(123).as_slice()
+            ))
+        );
+    }
+
+    #[rstest]
+    #[case("/?q=greeting", Some("rdmx6-jaaaa-aaaaa-aaadq-cai.icp0.io"))]
+    #[case("/?q=greeting", Some("www.example.com"))]
+    #[case("/?q=greeting", Some("www.example.com:8443"))]
+    #[case("https://www.example.com/?q=greeting", Some("www.example.com"))]
+    #[case("https://www.example.com/?q=greeting", None)]
+    fn custom_domain_host_passes_verification(#[case] req_url: &str, #[case] host: Option<&str>) {
+        let req_path = "/?q=greeting";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(vec!["Cache-Control"])
+            .with_request_query_parameters(vec!["q"])
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let request_headers = vec![("Cache-Control".to_string(), "no-cache".to_string())];
+        let request = HttpRequest::get(req_path)
+            .with_headers(request_headers.clone())
+            .build();
+        let custom_domain_request = HttpRequest::get(req_url)
+            .with_headers(
+                request_headers
+                    .into_iter()
+                    .chain(host.map(|host| ("Host".to_string(), host.to_string())))
+                    .collect(),
+            )
+            .build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let verify = |request: HttpRequest| {
+            verify_request_response_pair(
+                request,
+                response.clone(),
+                canister_id.as_ref(),
+                current_time,
+                MAX_CERT_TIME_OFFSET_NS,
+                &root_key,
+                MIN_REQUESTED_VERIFICATION_VERSION,
+            )
+            .unwrap()
+        };
+        let result = verify(request);
+        let custom_domain_result = verify(custom_domain_request);
+
+        assert_eq!(result.outcome(), VerificationOutcome::Certified);
+        assert_eq!(
+            custom_domain_result.outcome(),
+            VerificationOutcome::Certified
+        );
+        assert_eq!(
+            custom_domain_result.verification_version,
+            result.verification_version
+        );
+        assert_eq!(custom_domain_result.response, result.response);
+        assert_eq!(
+            custom_domain_result.certificate_expression,
+            result.certificate_expression
+        );
+        assert_eq!(
+            custom_domain_result.certificate_expression_hash,
+            result.certificate_expression_hash
+        );
+        assert_eq!(
+            custom_domain_result.certificate_time_ns,
+            result.certificate_time_ns
+        );
+        assert_eq!(custom_domain_result.subnet_id, result.subnet_id);
+    }
 }
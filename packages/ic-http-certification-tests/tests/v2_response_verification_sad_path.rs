@@ -8,11 +8,13 @@ mod tests {
     use candid::Principal;
     use ic_certificate_verification::CertificateVerificationError;
     use ic_http_certification::{
-        CelExpression, DefaultFullCelExpression, HttpCertification, HttpCertificationPath,
-        HttpCertificationTreeEntry, HttpRequest, HttpResponse, CERTIFICATE_EXPRESSION_HEADER_NAME,
-        CERTIFICATE_HEADER_NAME,
+        CelExpression, DefaultCelBuilder, DefaultFullCelExpression, DefaultResponseCertification,
+        HttpCertification, HttpCertificationPath, HttpCertificationTreeEntry, HttpRequest,
+        HttpResponse, CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
+    };
+    use ic_response_verification::{
+        verify_request_response_pair, ResponseVerificationError, ResponseVerifier,
     };
-    use ic_response_verification::{verify_request_response_pair, ResponseVerificationError};
     use ic_response_verification_test_utils::{
         create_v2_certificate_fixture, create_v2_fixture, create_v2_header, create_v2_tree_fixture,
         get_current_timestamp, V2CertificateFixture, V2Fixture, V2TreeFixture,
@@ -223,6 +225,114 @@ mod tests {
         );
     }
 
+    #[rstest]
+    fn tampered_response_only_cel_expr_fails_verification() {
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+        let tampered_cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+                vec!["Cache-Control"],
+            ))
+            .build();
+        let request = HttpRequest::get("/").build();
+        let response = cache_control_response(&cel_expr.to_string());
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+
+        let result = verify_with_tampered_cel_expr(
+            request,
+            response,
+            certification,
+            &tampered_cel_expr.to_string(),
+        );
+
+        assert_matches!(
+            result,
+            ResponseVerificationError::InvalidExpressionHash {
+                provided_expr_path
+            } if provided_expr_path == HttpCertificationPath::exact("/").to_expr_path()
+        );
+    }
+
+    #[rstest]
+    fn tampered_full_cel_expr_fails_verification(
+        #[from(full_certification_cel)] cel_expr: DefaultFullCelExpression<'static>,
+    ) {
+        let tampered_cel_expr = DefaultCelBuilder::full_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+        let request = HttpRequest::get("/?q=greeting")
+            .with_headers(vec![("Cache-Control".into(), "no-cache".into())])
+            .build();
+        let response = cache_control_response(&cel_expr.to_string());
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+
+        let result = verify_with_tampered_cel_expr(
+            request,
+            response,
+            certification,
+            &tampered_cel_expr.to_string(),
+        );
+
+        assert_matches!(
+            result,
+            ResponseVerificationError::InvalidExpressionHash {
+                provided_expr_path
+            } if provided_expr_path == HttpCertificationPath::exact("/").to_expr_path()
+        );
+    }
+
+    fn cache_control_response(cel_expr: &str) -> HttpResponse<'static> {
+        HttpResponse::ok(
+            b"Hello World!",
+            vec![
+                (CERTIFICATE_EXPRESSION_HEADER_NAME.into(), cel_expr.into()),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+        )
+        .build()
+    }
+
+    /// Certifies the response, then replaces its expression header with `tampered_cel_expr`
+    /// and returns the resulting verification error.
+    fn verify_with_tampered_cel_expr(
+        request: HttpRequest,
+        mut response: HttpResponse,
+        certification: HttpCertification,
+        tampered_cel_expr: &str,
+    ) -> ResponseVerificationError {
+        let current_time = get_current_timestamp();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(HttpCertificationPath::exact("/"), certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(request.url(), &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        response.headers_mut()[0] = (
+            CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+            tampered_cel_expr.into(),
+        );
+
+        verify_request_response_pair(
+            request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap_err()
+    }
+
     #[rstest]
     #[case::invalid_root_key_certificate(
         invalid_root_key_certificate(),
@@ -319,6 +429,300 @@ mod tests {
             )
         )
     }
+
+    #[rstest]
+    #[case(true, None)]
+    #[case(true, Some(false))]
+    #[case(false, Some(true))]
+    fn upgrade_mismatch_fails_verification(
+        #[from(skip_certification_cel)] cel_expr: CelExpression<'static>,
+        #[case] expected_upgrade: bool,
+        #[case] upgrade: Option<bool>,
+    ) {
+        let req_path = "/";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let mut response_builder = HttpResponse::ok(
+            "Hello World!".as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        );
+        if let Some(upgrade) = upgrade {
+            response_builder = response_builder.with_upgrade(upgrade);
+        }
+        let mut response = response_builder.build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .with_expected_upgrade(expected_upgrade)
+        .verify(HttpRequest::get(req_path).build(), response)
+        .unwrap_err();
+
+        assert_matches!(
+            result,
+            ResponseVerificationError::UpgradeMismatch {
+                expected_upgrade: actual_expected_upgrade,
+                upgrade: actual_upgrade,
+            } if actual_expected_upgrade == expected_upgrade
+                && actual_upgrade == upgrade.unwrap_or(false)
+        );
+    }
+
+    #[rstest]
+    fn header_limits_exceeded_fails_verification(
+        #[from(skip_certification_cel)] cel_expr: CelExpression<'static>,
+    ) {
+        let req_path = "/";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let mut response = HttpResponse::ok(
+            "Hello World!".as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let verifier = ResponseVerifier::new(
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let result = verifier
+            .clone()
+            .with_max_header_count(1)
+            .verify(HttpRequest::get(req_path).build(), response.clone())
+            .unwrap_err();
+        assert_matches!(
+            result,
+            ResponseVerificationError::TooManyHeaders {
+                header_count: 2,
+                max_header_count: 1,
+            }
+        );
+
+        let result = verifier
+            .clone()
+            .with_max_header_bytes(64)
+            .verify(HttpRequest::get(req_path).build(), response.clone())
+            .unwrap_err();
+        assert_matches!(
+            result,
+            ResponseVerificationError::HeadersTooLarge {
+                header_bytes,
+                max_header_bytes: 64,
+            } if header_bytes == response.total_header_bytes()
+        );
+
+        let result = verifier
+            .clone()
+            .with_max_header_bytes(response.total_header_bytes() - 1)
+            .verify(HttpRequest::get(req_path).build(), response.clone())
+            .unwrap_err();
+        assert_matches!(result, ResponseVerificationError::HeadersTooLarge { .. });
+
+        let result = verifier
+            .with_max_header_count(2)
+            .verify(HttpRequest::get(req_path).build(), response);
+        assert!(result.is_ok());
+    }
+
+    #[rstest]
+    #[case("chunked")]
+    #[case("gzip, chunked")]
+    #[case("Chunked")]
+    #[case("identity, chunked")]
+    fn transfer_encoding_fails_verification(
+        #[from(skip_certification_cel)] cel_expr: CelExpression<'static>,
+        #[case] transfer_encoding: &str,
+    ) {
+        let req_path = "/";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let mut response = HttpResponse::ok(
+            "c\r\nHello World!\r\n0\r\n\r\n".as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Transfer-Encoding".into(), transfer_encoding.into()),
+            ],
+        )
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            HttpRequest::get(req_path).build(),
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap_err();
+
+        assert_matches!(
+            result,
+            ResponseVerificationError::UnsupportedTransferEncoding {
+                transfer_encoding: actual_transfer_encoding,
+            } if actual_transfer_encoding == transfer_encoding
+        );
+    }
+
+    #[rstest]
+    fn identity_transfer_encoding_passes_verification(
+        #[from(skip_certification_cel)] cel_expr: CelExpression<'static>,
+    ) {
+        let req_path = "/";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact(req_path);
+
+        let mut response = HttpResponse::ok(
+            "Hello World!".as_bytes(),
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Transfer-Encoding".into(), "identity".into()),
+            ],
+        )
+        .build();
+
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, HttpCertification::skip());
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            HttpRequest::get(req_path).build(),
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn certified_host_mismatch_fails_verification() {
+        let req_path = "/?q=greeting";
+        let body = "Hello World!";
+        let current_time = get_current_timestamp();
+        let certification_path = HttpCertificationPath::exact("/");
+
+        let cel_expr = DefaultCelBuilder::full_certification()
+            .with_request_headers(vec!["Host"])
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec![],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path)
+            .with_headers(vec![("Host".into(), "www.example.com".into())])
+            .build();
+        let wrong_request = HttpRequest::get(req_path)
+            .with_headers(vec![(
+                "Host".into(),
+                "rdmx6-jaaaa-aaaaa-aaadq-cai.icp0.io".into(),
+            )])
+            .build();
+        let mut response = HttpResponse::ok(
+            body.as_bytes(),
+            vec![(
+                CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                cel_expr.to_string(),
+            )],
+        )
+        .build();
+
+        let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+        let result = verify_request_response_pair(
+            wrong_request,
+            response,
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap_err();
+
+        assert_matches!(
+            result,
+            ResponseVerificationError::InvalidRequestAndResponseHashes {
+                provided_expr_path
+            } if provided_expr_path == certification_path.to_expr_path()
+        );
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
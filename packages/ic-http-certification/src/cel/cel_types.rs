@@ -2,6 +2,7 @@ use super::{
     create_cel_expr, create_default_cel_expr, create_default_full_cel_expr,
     create_default_response_only_cel_expr,
 };
+use crate::{certified_response_header_names, HttpResponse};
 use std::{
     borrow::Cow,
     fmt::{Display, Formatter},
@@ -73,6 +74,16 @@ pub struct DefaultResponseOnlyCelExpression<'a> {
     pub response: DefaultResponseCertification<'a>,
 }
 
+impl DefaultResponseOnlyCelExpression<'_> {
+    /// Returns the names of the headers of the given [HttpResponse](crate::HttpResponse) that will
+    /// be included in certification by this CEL expression.
+    ///
+    /// See [certified_response_header_names](crate::certified_response_header_names) for more details.
+    pub fn certified_response_header_names(&self, response: &HttpResponse) -> Vec<String> {
+        certified_response_header_names(response, &self.response)
+    }
+}
+
 impl Display for DefaultResponseOnlyCelExpression<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", create_default_response_only_cel_expr(self))
@@ -92,6 +103,16 @@ pub struct DefaultFullCelExpression<'a> {
     pub response: DefaultResponseCertification<'a>,
 }
 
+impl DefaultFullCelExpression<'_> {
+    /// Returns the names of the headers of the given [HttpResponse](crate::HttpResponse) that will
+    /// be included in certification by this CEL expression.
+    ///
+    /// See [certified_response_header_names](crate::certified_response_header_names) for more details.
+    pub fn certified_response_header_names(&self, response: &HttpResponse) -> Vec<String> {
+        certified_response_header_names(response, &self.response)
+    }
+}
+
 impl Display for DefaultFullCelExpression<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", create_default_full_cel_expr(self))
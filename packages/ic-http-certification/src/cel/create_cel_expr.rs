@@ -8,6 +8,14 @@ use super::{
 ///
 /// [CelExpression::to_string](ToString::to_string()) is an alias of this function and can be used
 /// for ergonomics.
+///
+/// The returned string is canonical: it contains no whitespace, and lists headers and query
+/// parameters in the order that they were provided, so the same [CelExpression] always produces
+/// the same bytes. This string must be used, unmodified, as the value of the
+/// [IC-CertificateExpression](crate::CERTIFICATE_EXPRESSION_HEADER_NAME) header. Verifiers hash
+/// the exact bytes of that header value to compute the expression hash that is looked up in the
+/// certification tree, so the header value must match the string that was hashed when the
+/// response was [certified](crate::HttpCertification), byte for byte.
 pub fn create_cel_expr(certification: &CelExpression) -> String {
     match certification {
         CelExpression::Default(certification) => create_default_cel_expr(certification),
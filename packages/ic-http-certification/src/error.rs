@@ -55,4 +55,77 @@ pub enum HttpCertificationError {
         /// The HTTP status code that was not recognized.
         status_code: u16,
     },
+
+    /// A header name was not a valid HTTP token.
+    #[error(r#"Invalid header name: "{header_name}""#)]
+    InvalidHeaderName {
+        /// The header name that was not valid.
+        header_name: String,
+    },
+
+    /// A header value contained forbidden characters.
+    #[error(r#"Invalid value for header "{header_name}": "{header_value}""#)]
+    InvalidHeaderValue {
+        /// The name of the header with the invalid value.
+        header_name: String,
+
+        /// The header value that was not valid.
+        header_value: String,
+    },
+
+    /// The body of a request could not be deserialized from JSON.
+    #[error(r#"Failed to deserialize JSON body: "{0}""#)]
+    JsonDeserializationError(String),
+
+    /// Error converting a string into an HTTP method.
+    #[error(r#"Error converting string into HTTP method: "{method}""#)]
+    InvalidHttpMethod {
+        /// The HTTP method that was not recognized.
+        method: String,
+    },
+
+    /// The Candid encoded bytes could not be decoded.
+    #[error(r#"Failed to decode Candid: "{0}""#)]
+    MalformedCandid(String),
+
+    /// The path of a request URL contained a `..` segment, either literally or percent-encoded.
+    #[error(r#"Request path contains a path traversal segment: "{path}""#)]
+    PathTraversal {
+        /// The path of the request URL, as it was received.
+        path: String,
+    },
+
+    /// The path of a request URL was not valid UTF-8 after percent-decoding.
+    #[error(r#"Request path is not valid UTF-8 after percent-decoding: "{path}""#)]
+    NonUtf8Path {
+        /// The path of the request URL, as it was received.
+        path: String,
+    },
+}
+
+/// Error type for parsing the `Range` header of a request with
+/// [HttpRequest::range](crate::HttpRequest::range).
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RangeError {
+    /// The `Range` header was not a syntactically valid byte range set, or the request
+    /// contained multiple `Range` headers. Such a header must be ignored, and the full
+    /// representation served instead.
+    #[error(r#"Malformed Range header: "{range}""#)]
+    MalformedRange {
+        /// The value of the `Range` header, as it was received.
+        range: String,
+    },
+
+    /// None of the byte ranges in the `Range` header overlap the representation. This should
+    /// be answered with a `416 Range Not Satisfiable` response.
+    #[error(
+        r#"Range header "{range}" is not satisfiable for a representation of {total_len} bytes"#
+    )]
+    UnsatisfiableRange {
+        /// The value of the `Range` header, as it was received.
+        range: String,
+
+        /// The length of the representation that the ranges were resolved against.
+        total_len: u64,
+    },
 }
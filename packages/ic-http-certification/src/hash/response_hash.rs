@@ -83,9 +83,84 @@ pub fn filter_response_headers(
     response_headers
 }
 
+/// Returns the names of the headers of an [HttpResponse] that will be included in certification
+/// according to a CEL expression defined by [DefaultResponseCertification].
+///
+/// This is the name-only form of [filter_response_headers]. The returned names are lowercased and
+/// deduplicated, and are listed in the order that they first appear in the response. The
+/// `IC-CertificateExpression` header is always certified, so it will be included if it is present.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::{DefaultResponseCertification, HttpResponse, certified_response_header_names};
+///
+/// let response_certification = DefaultResponseCertification::certified_response_headers(vec![
+///     "Content-Type",
+///     "Cache-Control",
+/// ]);
+/// let response = HttpResponse::ok(
+///     b"Hello, World!",
+///     vec![
+///         ("Content-Type".into(), "text/plain".into()),
+///         ("Content-Length".into(), "13".into()),
+///     ],
+/// )
+/// .build();
+///
+/// let header_names = certified_response_header_names(&response, &response_certification);
+///
+/// assert_eq!(header_names, vec!["content-type".to_string()]);
+/// ```
+pub fn certified_response_header_names(
+    response: &HttpResponse,
+    response_certification: &DefaultResponseCertification<'_>,
+) -> Vec<String> {
+    let mut header_names: Vec<String> = vec![];
+
+    for (header_name, _) in filter_response_headers(response, response_certification).headers {
+        if !header_names.contains(&header_name) {
+            header_names.push(header_name);
+        }
+    }
+
+    header_names
+}
+
 /// Calculates the
 /// [Representation Independent Hash](https://internetcomputer.org/docs/current/references/ic-interface-spec/#hash-of-map)
 /// of [ResponseHeaders] that have been filtered with [filter_response_headers].
+///
+/// This is the same computation that is used by [response_hash] when certifying a response and by
+/// response verification when verifying it, so a canister that computes response hashes manually
+/// should combine it with the hash of the response body in the same way.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::{
+///     filter_response_headers, response_hash, response_headers_hash, DefaultResponseCertification,
+///     HttpResponse,
+/// };
+/// use ic_representation_independent_hash::hash;
+///
+/// let response_certification =
+///     DefaultResponseCertification::certified_response_headers(vec!["Content-Type"]);
+/// let response = HttpResponse::ok(
+///     b"Hello, World!",
+///     vec![("Content-Type".into(), "text/plain".into())],
+/// )
+/// .build();
+///
+/// let response_headers = filter_response_headers(&response, &response_certification);
+/// let headers_hash =
+///     response_headers_hash(&response.status_code().as_u16().into(), &response_headers);
+///
+/// assert_eq!(
+///     hash(&[headers_hash, hash(response.body())].concat()),
+///     response_hash(&response, &response_certification, None),
+/// );
+/// ```
 pub fn response_headers_hash(status_code: &u64, response_headers: &ResponseHeaders) -> Hash {
     let mut headers_to_verify: Vec<(String, Value)> = response_headers
         .headers
@@ -207,6 +282,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn certified_response_header_names_with_certified_headers() {
+        let response_certification =
+            DefaultResponseCertification::certified_response_headers(vec![
+                "Cache-Control",
+                "Content-Type",
+            ]);
+        let response = create_response(CERTIFIED_HEADERS_CEL_EXPRESSION);
+
+        let result = certified_response_header_names(&response, &response_certification);
+
+        assert_eq!(
+            result,
+            vec![
+                CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase(),
+                "cache-control".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn certified_response_header_names_with_header_exclusions() {
+        let response_certification =
+            DefaultResponseCertification::response_header_exclusions(vec![
+                "Content-Security-Policy",
+            ]);
+        let response = create_response(HEADER_EXCLUSIONS_CEL_EXPRESSION);
+
+        let result = certified_response_header_names(&response, &response_certification);
+
+        assert_eq!(
+            result,
+            vec![
+                CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase(),
+                "accept-encoding".to_string(),
+                "cache-control".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn response_hash_with_certified_headers() {
         let response_certification =
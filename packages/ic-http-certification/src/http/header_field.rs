@@ -1,2 +1,73 @@
+use crate::{HttpCertificationError, HttpCertificationResult};
+use http::header::{HeaderName, HeaderValue};
+
 /// An HTTP header field, represented as a tuple of (name, value).
 pub type HeaderField = (String, String);
+
+/// Validates that each header name is a valid HTTP token and that each header value
+/// does not contain any forbidden control characters. Non-ASCII characters are allowed
+/// in header values.
+pub(crate) fn validate_header_fields(headers: &[HeaderField]) -> HttpCertificationResult {
+    for (name, value) in headers {
+        if HeaderName::from_bytes(name.as_bytes()).is_err() {
+            return Err(HttpCertificationError::InvalidHeaderName {
+                header_name: name.clone(),
+            });
+        }
+
+        if HeaderValue::from_bytes(value.as_bytes()).is_err() {
+            return Err(HttpCertificationError::InvalidHeaderValue {
+                header_name: name.clone(),
+                header_value: value.clone(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use rstest::*;
+
+    #[rstest]
+    #[case("Content-Type", "text/plain")]
+    #[case("x-custom-header", "")]
+    #[case("X-Custom-Header", "value with spaces\tand tabs")]
+    #[case("Content-Disposition", "attachment; filename=\"résumé.pdf\"")]
+    fn validate_header_fields_valid(#[case] name: &str, #[case] value: &str) {
+        let headers = vec![(name.to_string(), value.to_string())];
+
+        assert_matches!(validate_header_fields(&headers), Ok(()));
+    }
+
+    #[rstest]
+    #[case("X Custom")]
+    #[case("")]
+    #[case("X-Custom:")]
+    #[case("X-Custom\n")]
+    fn validate_header_fields_invalid_name(#[case] name: &str) {
+        let headers = vec![(name.to_string(), "value".to_string())];
+
+        assert_matches!(
+            validate_header_fields(&headers),
+            Err(HttpCertificationError::InvalidHeaderName { header_name }) if header_name == name
+        );
+    }
+
+    #[rstest]
+    #[case("value\r\nX-Injected: true")]
+    #[case("value\n")]
+    #[case("value\0")]
+    fn validate_header_fields_invalid_value(#[case] value: &str) {
+        let headers = vec![("X-Custom".to_string(), value.to_string())];
+
+        assert_matches!(
+            validate_header_fields(&headers),
+            Err(HttpCertificationError::InvalidHeaderValue { header_name, header_value })
+                if header_name == "X-Custom" && header_value == value
+        );
+    }
+}
@@ -1,11 +1,14 @@
-use crate::HeaderField;
+use crate::{
+    validate_header_fields, HeaderField, HttpCertificationError, HttpCertificationResult, Method,
+};
 use candid::{
     types::{Serializer, Type, TypeInner},
     CandidType, Deserialize,
 };
 pub use http::StatusCode;
+use ic_representation_independent_hash::hash;
 use serde::Deserializer;
-use std::{borrow::Cow, fmt::Debug};
+use std::{borrow::Cow, collections::HashMap, fmt::Debug};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct StatusCodeWrapper(StatusCode);
@@ -42,6 +45,23 @@ impl From<StatusCode> for StatusCodeWrapper {
     }
 }
 
+/// Returns the total size of the given headers when serialized as `name: value\r\n` lines.
+fn total_header_bytes(headers: &[HeaderField]) -> usize {
+    headers
+        .iter()
+        .map(|(name, value)| name.len() + ": ".len() + value.len() + "\r\n".len())
+        .sum()
+}
+
+/// The Candid representation of an [HttpResponse], without any validation of the status code.
+#[derive(CandidType, Deserialize)]
+struct CandidHttpResponse {
+    status_code: u16,
+    headers: Vec<HeaderField>,
+    body: Vec<u8>,
+    upgrade: Option<bool>,
+}
+
 /// A Candid-encodable representation of an HTTP response. This struct is used
 /// by the `http_request` method of the HTTP Gateway Protocol's Candid interface.
 ///
@@ -73,6 +93,7 @@ impl From<StatusCode> for StatusCodeWrapper {
 /// - [NO_CONTENT](HttpResponse::no_content)
 /// - [MOVED_PERMANENTLY](HttpResponse::moved_permanently)
 /// - [TEMPORARY_REDIRECT](HttpResponse::temporary_redirect)
+/// - [Any redirect](HttpResponse::redirect)
 /// - [BAD_REQUEST](HttpResponse::bad_request)
 /// - [UNAUTHORIZED](HttpResponse::unauthorized)
 /// - [FORBIDDEN](HttpResponse::forbidden)
@@ -203,14 +224,7 @@ impl<'a> HttpResponse<'a> {
         location: impl Into<String>,
         headers: Vec<(String, String)>,
     ) -> HttpResponseBuilder<'a> {
-        let headers = headers
-            .into_iter()
-            .chain(std::iter::once(("Location".into(), location.into())))
-            .collect();
-
-        Self::builder()
-            .with_status_code(StatusCode::MOVED_PERMANENTLY)
-            .with_headers(headers)
+        Self::redirect(StatusCode::MOVED_PERMANENTLY, location, headers)
     }
 
     /// Creates a new [HttpResponseBuilder] initialized with a NOT_MODIFIED status code and
@@ -255,13 +269,51 @@ impl<'a> HttpResponse<'a> {
         location: impl Into<String>,
         headers: Vec<(String, String)>,
     ) -> HttpResponseBuilder<'a> {
+        Self::redirect(StatusCode::TEMPORARY_REDIRECT, location, headers)
+    }
+
+    /// Creates a new [HttpResponseBuilder] initialized with the given redirect status code,
+    /// location and headers.
+    ///
+    /// This is useful when the kind of redirect is only known at runtime, e.g. `301 Moved
+    /// Permanently`, `302 Found`, `303 See Other`, `307 Temporary Redirect` or
+    /// `308 Permanent Redirect`. The `Location` header is added after the given headers.
+    ///
+    /// The status code must be a redirection (`3xx`) status code other than `304 Not Modified`,
+    /// which is not a redirect and should be created with
+    /// [not_modified](HttpResponse::not_modified) instead. This is checked with a debug
+    /// assertion, so using any other status code panics in debug builds.
+    ///
+    /// This method returns an instance of [HttpResponseBuilder] that can be used to create an
+    /// [HttpResponse].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpResponse, StatusCode};
+    ///
+    /// let response = HttpResponse::redirect(StatusCode::SEE_OTHER, "/orders/1", vec![("Content-Type".into(), "text/plain".into())]).build();
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::SEE_OTHER);
+    /// assert_eq!(response.headers(), &[("Content-Type".into(), "text/plain".into()), ("Location".into(), "/orders/1".into())]);
+    /// ```
+    pub fn redirect(
+        status_code: StatusCode,
+        location: impl Into<String>,
+        headers: Vec<(String, String)>,
+    ) -> HttpResponseBuilder<'a> {
+        debug_assert!(
+            status_code.is_redirection() && status_code != StatusCode::NOT_MODIFIED,
+            "redirect status code must be 3xx, other than 304, got {status_code}"
+        );
+
         let headers = headers
             .into_iter()
             .chain(std::iter::once(("Location".into(), location.into())))
             .collect();
 
         Self::builder()
-            .with_status_code(StatusCode::TEMPORARY_REDIRECT)
+            .with_status_code(status_code)
             .with_headers(headers)
     }
 
@@ -400,6 +452,62 @@ impl<'a> HttpResponse<'a> {
             .with_headers(headers)
     }
 
+    /// Creates a new [HttpResponseBuilder] initialized with a METHOD_NOT_ALLOWED status code,
+    /// the given body and headers, and an `Allow` header listing the given methods.
+    ///
+    /// [RFC 7231](https://www.rfc-editor.org/rfc/rfc7231#section-6.5.5) requires a
+    /// `405 Method Not Allowed` response to include an `Allow` header. The methods are joined
+    /// with `, ` in the given order, skipping duplicates, and any `Allow` header in the given
+    /// headers is replaced. The `Allow` header is a regular response header, so it is
+    /// certified along with the rest of the response, unless it is excluded from the
+    /// response certification.
+    ///
+    /// This method returns an instance of [HttpResponseBuilder] that can be used to
+    /// to create an [HttpResponse].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpResponse, Method, StatusCode};
+    ///
+    /// let response = HttpResponse::method_not_allowed_with_allow(
+    ///     &[Method::GET, Method::HEAD],
+    ///     b"Method Not Allowed",
+    ///     vec![("Content-Type".into(), "text/plain".into())],
+    /// )
+    /// .build();
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("allow".into(), "GET, HEAD".into()),
+    ///     ]
+    /// );
+    /// assert_eq!(response.body(), b"Method Not Allowed");
+    /// ```
+    pub fn method_not_allowed_with_allow(
+        allowed: &[Method],
+        body: impl Into<Cow<'a, [u8]>>,
+        headers: Vec<(String, String)>,
+    ) -> HttpResponseBuilder<'a> {
+        let mut allowed_methods: Vec<&str> = Vec::with_capacity(allowed.len());
+        for method in allowed {
+            if !allowed_methods.contains(&method.as_str()) {
+                allowed_methods.push(method.as_str());
+            }
+        }
+
+        let mut headers = headers
+            .into_iter()
+            .filter(|(name, _)| !name.eq_ignore_ascii_case(http::header::ALLOW.as_str()))
+            .collect::<Vec<_>>();
+        headers.push((http::header::ALLOW.to_string(), allowed_methods.join(", ")));
+
+        Self::method_not_allowed(body, headers)
+    }
+
     /// Creates a new [HttpResponseBuilder] initialized with a CONFLICT status code and
     /// the given body and headers.
     ///
@@ -479,6 +587,48 @@ impl<'a> HttpResponse<'a> {
         HttpResponseBuilder::new()
     }
 
+    /// Decodes an [HttpResponse] from Candid encoded bytes, such as the response of an
+    /// inter-canister call, validating the status code once the bytes have been decoded.
+    ///
+    /// Returns [MalformedCandid](HttpCertificationError::MalformedCandid) if the bytes are
+    /// not a valid Candid encoding of an [HttpResponse], or
+    /// [InvalidHttpStatusCode](HttpCertificationError::InvalidHttpStatusCode) if the status
+    /// code is not between `100` and `999`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpCertificationError, HttpResponse};
+    ///
+    /// let response = HttpResponse::ok(b"Hello, World!", vec![]).build();
+    /// let bytes = candid::encode_one(&response).unwrap();
+    ///
+    /// let decoded_response = HttpResponse::try_from_candid(&bytes).unwrap();
+    /// assert_eq!(decoded_response, response);
+    ///
+    /// let result = HttpResponse::try_from_candid(b"not candid");
+    /// assert!(matches!(result, Err(HttpCertificationError::MalformedCandid(_))));
+    /// ```
+    pub fn try_from_candid(bytes: &[u8]) -> HttpCertificationResult<Self> {
+        let CandidHttpResponse {
+            status_code,
+            headers,
+            body,
+            upgrade,
+        } = candid::decode_one(bytes)
+            .map_err(|err| HttpCertificationError::MalformedCandid(err.to_string()))?;
+
+        let status_code = StatusCode::from_u16(status_code)
+            .map_err(|_| HttpCertificationError::InvalidHttpStatusCode { status_code })?;
+
+        Ok(Self {
+            status_code: status_code.into(),
+            headers,
+            body: body.into(),
+            upgrade,
+        })
+    }
+
     /// Returns the HTTP status code of the response.
     ///
     /// # Examples
@@ -535,6 +685,50 @@ impl<'a> HttpResponse<'a> {
         &mut self.headers
     }
 
+    /// Returns the number of HTTP headers of the response, counting repeated headers
+    /// separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("Vary".into(), "Origin".into()),
+    ///         ("Vary".into(), "Accept-Encoding".into()),
+    ///     ])
+    ///     .build();
+    ///
+    /// assert_eq!(response.header_count(), 3);
+    /// ```
+    #[inline]
+    pub fn header_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns the total size of the HTTP headers of the response in bytes, as they would be
+    /// serialized in an HTTP/1.1 response, including the `: ` separator between each name and
+    /// value, and the `\r\n` line ending after each header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![("Content-Type".into(), "text/plain".into())])
+    ///     .build();
+    ///
+    /// // "Content-Type: text/plain\r\n"
+    /// assert_eq!(response.total_header_bytes(), 26);
+    /// ```
+    #[inline]
+    pub fn total_header_bytes(&self) -> usize {
+        total_header_bytes(&self.headers)
+    }
+
     /// Adds an additional header to the HTTP response.
     ///
     /// # Examples
@@ -555,6 +749,47 @@ impl<'a> HttpResponse<'a> {
         self.headers.push(header);
     }
 
+    /// Appends a value to a header that holds a comma-separated list, such as `Cache-Control`
+    /// or `Vary`. The header name is matched case-insensitively and, if the header appears
+    /// multiple times, the value is appended to the last instance. If the header is not present
+    /// or is empty, it is set to the given value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let mut response = HttpResponse::builder()
+    ///     .with_headers(vec![("Cache-Control".into(), "public".into())])
+    ///     .build();
+    ///
+    /// response.append_to_header("cache-control", "max-age=3600");
+    /// response.append_to_header("Vary", "Accept-Encoding");
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[
+    ///         ("Cache-Control".into(), "public, max-age=3600".into()),
+    ///         ("Vary".into(), "Accept-Encoding".into()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn append_to_header(&mut self, name: &str, value: &str) {
+        match self
+            .headers
+            .iter_mut()
+            .rev()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+        {
+            Some((_, header_value)) if !header_value.trim().is_empty() => {
+                header_value.push_str(", ");
+                header_value.push_str(value);
+            }
+            Some((_, header_value)) => *header_value = value.to_string(),
+            None => self.headers.push((name.to_string(), value.to_string())),
+        }
+    }
+
     /// Returns the HTTP body of the response.
     ///
     /// # Examples
@@ -573,6 +808,33 @@ impl<'a> HttpResponse<'a> {
         &self.body
     }
 
+    /// Computes a strong entity tag for the given body, formatted as the quoted, hex encoded
+    /// SHA-256 hash of the body, for use in the `ETag` header.
+    ///
+    /// The entity tag changes whenever the body changes, so it can be used to validate
+    /// conditional requests such as `If-None-Match`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let etag = HttpResponse::strong_etag(b"Hello, World!");
+    ///
+    /// assert_eq!(
+    ///     etag,
+    ///     r#""dffd6021bb2bd5b0af676290809ec3a53191dd81c7f70a4b28688a362182986f""#
+    /// );
+    /// ```
+    pub fn strong_etag(body: &[u8]) -> String {
+        let hex = hash(body)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+
+        format!(r#""{hex}""#)
+    }
+
     /// Returns the upgrade flag of the response. This will determine if the HTTP Gateway will
     /// upgrade the request to an update call.
     ///
@@ -591,6 +853,171 @@ impl<'a> HttpResponse<'a> {
     pub fn upgrade(&self) -> Option<bool> {
         self.upgrade
     }
+
+    /// Transforms the HTTP body of the response using the provided function, keeping the
+    /// status code, headers and upgrade flag unchanged.
+    ///
+    /// Note that changing the body invalidates any certification that was previously
+    /// calculated for this response, so this should be done before the response is certified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::ok(b"<html><body></body></html>", vec![("Content-Type".into(), "text/html".into())])
+    ///     .build()
+    ///     .map_body(|body| {
+    ///         String::from_utf8_lossy(body)
+    ///             .replace("</body>", "<script src=\"/app.js\"></script></body>")
+    ///             .into_bytes()
+    ///     });
+    ///
+    /// assert_eq!(response.body(), b"<html><body><script src=\"/app.js\"></script></body></html>");
+    /// assert_eq!(response.headers(), &[("Content-Type".into(), "text/html".into())]);
+    /// ```
+    pub fn map_body(self, f: impl FnOnce(&[u8]) -> Vec<u8>) -> Self {
+        let body = f(&self.body);
+
+        Self {
+            body: Cow::Owned(body),
+            ..self
+        }
+    }
+
+    /// Creates a new [HttpResponse] with the status code, headers and upgrade flag of this
+    /// response, and the provided body. This is useful for serving many responses that share
+    /// their headers, such as pages of an API result, without specifying the headers for
+    /// each of them.
+    ///
+    /// Note that headers describing the body, such as `Content-Length` or `ETag`, are cloned
+    /// unchanged, and that the new response is not covered by any certification that was
+    /// previously calculated for this response, so it must be certified separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::ok(b"[1, 2]", vec![("Content-Type".into(), "application/json".into())])
+    ///     .build();
+    ///
+    /// let next_page = response.clone_with_body(b"[3, 4]");
+    ///
+    /// assert_eq!(next_page.status_code(), response.status_code());
+    /// assert_eq!(next_page.headers(), &[("Content-Type".into(), "application/json".into())]);
+    /// assert_eq!(next_page.body(), b"[3, 4]");
+    /// assert_eq!(response.body(), b"[1, 2]");
+    /// ```
+    pub fn clone_with_body(&self, body: impl Into<Cow<'a, [u8]>>) -> HttpResponse<'a> {
+        Self {
+            status_code: self.status_code,
+            headers: self.headers.clone(),
+            body: body.into(),
+            upgrade: self.upgrade,
+        }
+    }
+
+    /// Consumes the response, returning its status code, headers, body and upgrade flag.
+    ///
+    /// The response can be reconstructed from these parts with [from_parts](HttpResponse::from_parts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpResponse, StatusCode};
+    ///
+    /// let response = HttpResponse::ok(b"Hello, World!", vec![("Content-Type".into(), "text/plain".into())])
+    ///     .with_upgrade(true)
+    ///     .build();
+    ///
+    /// let (status_code, headers, body, upgrade) = response.into_parts();
+    ///
+    /// assert_eq!(status_code, StatusCode::OK);
+    /// assert_eq!(headers, vec![("Content-Type".into(), "text/plain".into())]);
+    /// assert_eq!(body.as_ref(), b"Hello, World!");
+    /// assert_eq!(upgrade, Some(true));
+    /// ```
+    #[inline]
+    pub fn into_parts(self) -> (StatusCode, Vec<HeaderField>, Cow<'a, [u8]>, Option<bool>) {
+        (self.status_code.0, self.headers, self.body, self.upgrade)
+    }
+
+    /// Creates a new [HttpResponse] from its status code, headers, body and upgrade flag,
+    /// as returned by [into_parts](HttpResponse::into_parts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpResponse, StatusCode};
+    ///
+    /// let response = HttpResponse::ok(b"Hello, World!", vec![("Content-Type".into(), "text/plain".into())])
+    ///     .build();
+    ///
+    /// let (_, mut headers, body, upgrade) = response.into_parts();
+    /// headers.push(("Cache-Control".into(), "no-cache".into()));
+    /// let response = HttpResponse::from_parts(StatusCode::NOT_FOUND, headers, body, upgrade);
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::NOT_FOUND);
+    /// assert_eq!(response.headers(), &[("Content-Type".into(), "text/plain".into()), ("Cache-Control".into(), "no-cache".into())]);
+    /// assert_eq!(response.body(), b"Hello, World!");
+    /// assert_eq!(response.upgrade(), None);
+    /// ```
+    #[inline]
+    pub fn from_parts(
+        status_code: StatusCode,
+        headers: Vec<HeaderField>,
+        body: impl Into<Cow<'a, [u8]>>,
+        upgrade: Option<bool>,
+    ) -> Self {
+        Self {
+            status_code: status_code.into(),
+            headers,
+            body: body.into(),
+            upgrade,
+        }
+    }
+
+    /// Compares this response with another response, including the exact order of their
+    /// headers.
+    ///
+    /// The [PartialEq] implementation for [HttpResponse] ignores the order of headers, since
+    /// it does not affect the meaning of a response in most cases. This method can be used
+    /// instead when the order matters, for example to assert that headers are produced in a
+    /// deterministic order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::ok(
+    ///     b"Hello, World!",
+    ///     vec![
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("Cache-Control".into(), "no-cache".into()),
+    ///     ],
+    /// )
+    /// .build();
+    /// let reordered_response = HttpResponse::ok(
+    ///     b"Hello, World!",
+    ///     vec![
+    ///         ("Cache-Control".into(), "no-cache".into()),
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///     ],
+    /// )
+    /// .build();
+    ///
+    /// assert_eq!(response, reordered_response);
+    /// assert!(!response.eq_ordered(&reordered_response));
+    /// assert!(response.eq_ordered(&response.clone()));
+    /// ```
+    pub fn eq_ordered(&self, other: &Self) -> bool {
+        self.status_code == other.status_code
+            && self.headers == other.headers
+            && self.body == other.body
+            && self.upgrade == other.upgrade
+    }
 }
 
 /// An HTTP response builder.
@@ -623,6 +1050,20 @@ pub struct HttpResponseBuilder<'a> {
     upgrade: Option<bool>,
 }
 
+fn sniff_content_type(body: &[u8]) -> Option<&'static str> {
+    match body {
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'%', b'P', b'D', b'F', b'-', ..] => Some("application/pdf"),
+        [0x1F, 0x8B, 0x08, ..] => Some("application/gzip"),
+        [b'P', b'K', 0x03, 0x04, ..] => Some("application/zip"),
+        [0x00, b'a', b's', b'm', ..] => Some("application/wasm"),
+        _ => None,
+    }
+}
+
 impl<'a> HttpResponseBuilder<'a> {
     /// Creates a new instance of the [HttpResponseBuilder] that can be used to
     /// constract an [HttpResponse].
@@ -690,6 +1131,79 @@ impl<'a> HttpResponseBuilder<'a> {
         self
     }
 
+    /// Sets the headers of the HTTP response from a map of header names to values,
+    /// replacing any headers that were previously set.
+    ///
+    /// This is convenient when headers are collected in a [HashMap] to deduplicate them.
+    /// Since the iteration order of a [HashMap] is not deterministic, the headers are
+    /// sorted by name, comparing the bytes of each name, so the same map always produces
+    /// the same response. The certification of a response does not depend on the order of
+    /// its headers, but a stable order keeps the served responses identical across calls.
+    /// Header names that only differ in casing are distinct keys of the map, so they are
+    /// kept as separate headers, with uppercase names sorted first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    /// use std::collections::HashMap;
+    ///
+    /// let headers = HashMap::from([
+    ///     ("X-Frame-Options".to_string(), "DENY".to_string()),
+    ///     ("Content-Type".to_string(), "text/plain".to_string()),
+    /// ]);
+    /// let response = HttpResponse::builder().with_headers_map(headers).build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("X-Frame-Options".into(), "DENY".into()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn with_headers_map(mut self, headers: HashMap<String, String>) -> Self {
+        let mut headers = headers.into_iter().collect::<Vec<_>>();
+        headers.sort_unstable_by(|(name, _), (other_name, _)| name.cmp(other_name));
+        self.headers = headers;
+
+        self
+    }
+
+    /// Appends the given headers to the headers of the HTTP response,
+    /// keeping any headers that were previously set.
+    ///
+    /// Unlike [with_headers](HttpResponseBuilder::with_headers), this does not
+    /// replace the existing headers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![("Content-Type".into(), "text/plain".into())])
+    ///     .extend_headers(vec![
+    ///         ("X-Content-Type-Options".into(), "nosniff".into()),
+    ///         ("X-Frame-Options".into(), "DENY".into()),
+    ///     ])
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("X-Content-Type-Options".into(), "nosniff".into()),
+    ///         ("X-Frame-Options".into(), "DENY".into()),
+    ///     ]
+    /// );
+    /// ```
+    pub fn extend_headers(mut self, headers: impl IntoIterator<Item = HeaderField>) -> Self {
+        self.headers.extend(headers);
+
+        self
+    }
+
     /// Sets the body of the HTTP response.
     ///
     /// This function will accept both owned and borrowed values. By default,
@@ -734,6 +1248,274 @@ impl<'a> HttpResponseBuilder<'a> {
         self
     }
 
+    /// Clears the upgrade flag of the HTTP response, so that it is set to `None`, for example
+    /// after creating the builder from an existing [HttpResponse].
+    ///
+    /// `None` is treated the same as `Some(false)` by the HTTP Gateway, but it is encoded
+    /// differently in Candid, where the `upgrade` field is `null` instead of `opt false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpResponse, HttpResponseBuilder};
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_upgrade(true)
+    ///     .build();
+    ///
+    /// let response = HttpResponseBuilder::from(response)
+    ///     .without_upgrade()
+    ///     .build();
+    ///
+    /// assert_eq!(response.upgrade(), None);
+    /// ```
+    pub fn without_upgrade(mut self) -> Self {
+        self.upgrade = None;
+
+        self
+    }
+
+    /// Sets the `ETag` header of the HTTP response, replacing any previously set `ETag` header.
+    ///
+    /// The tag is formatted as a quoted strong entity tag, unless it is already quoted or a
+    /// weak entity tag (`W/"..."`), in which case it is used as is. See
+    /// [with_strong_etag](HttpResponseBuilder::with_strong_etag) to derive the tag from the
+    /// body of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_etag("v1")
+    ///     .build();
+    ///
+    /// assert_eq!(response.headers(), &[("etag".into(), r#""v1""#.into())]);
+    /// ```
+    pub fn with_etag(self, tag: impl Into<String>) -> Self {
+        let tag = tag.into();
+        let etag = if tag.starts_with('"') || tag.starts_with(r#"W/""#) {
+            tag
+        } else {
+            format!(r#""{tag}""#)
+        };
+
+        self.with_replaced_header(http::header::ETAG.as_str(), etag)
+    }
+
+    /// Sets the `ETag` header of the HTTP response to a strong entity tag computed from
+    /// the hash of the body, see [strong_etag](HttpResponse::strong_etag).
+    ///
+    /// The body must be set before calling this method, since the entity tag is computed
+    /// from the current body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_body(b"Hello, World!")
+    ///     .with_strong_etag()
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[("etag".into(), HttpResponse::strong_etag(b"Hello, World!"))]
+    /// );
+    /// ```
+    pub fn with_strong_etag(self) -> Self {
+        let etag = HttpResponse::strong_etag(&self.body);
+
+        self.with_etag(etag)
+    }
+
+    /// Sets the `Last-Modified` header of the HTTP response, replacing any previously set
+    /// `Last-Modified` header.
+    ///
+    /// The value is expected to be an HTTP date, such as `Wed, 21 Oct 2015 07:28:00 GMT`,
+    /// and is set as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_last_modified("Wed, 21 Oct 2015 07:28:00 GMT")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[("last-modified".into(), "Wed, 21 Oct 2015 07:28:00 GMT".into())]
+    /// );
+    /// ```
+    pub fn with_last_modified(self, http_date: impl Into<String>) -> Self {
+        self.with_replaced_header(http::header::LAST_MODIFIED.as_str(), http_date.into())
+    }
+
+    /// Sets the `Content-Disposition` header of the HTTP response to `attachment` with the
+    /// given filename, so that browsers download the body as a file instead of displaying it,
+    /// replacing any previously set `Content-Disposition` header.
+    ///
+    /// Quotes and backslashes in the filename are escaped. If the filename contains
+    /// characters other than printable ASCII, it is also included as a UTF-8, percent-encoded
+    /// `filename*` parameter, as described in [RFC 6266](https://www.rfc-editor.org/rfc/rfc6266)
+    /// and [RFC 5987](https://www.rfc-editor.org/rfc/rfc5987). The `filename` parameter is kept
+    /// as a fallback for older clients, with each of those characters replaced by `_`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_attachment("report.pdf")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[(
+    ///         "content-disposition".into(),
+    ///         r#"attachment; filename="report.pdf""#.into()
+    ///     )]
+    /// );
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_attachment("résumé.pdf")
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[(
+    ///         "content-disposition".into(),
+    ///         r#"attachment; filename="r_sum_.pdf"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"#.into()
+    ///     )]
+    /// );
+    /// ```
+    pub fn with_attachment(self, filename: &str) -> Self {
+        let is_printable_ascii = |c: char| c.is_ascii() && !c.is_ascii_control();
+
+        let fallback_filename = filename
+            .chars()
+            .map(|c| match c {
+                '"' | '\\' => format!("\\{c}"),
+                c if is_printable_ascii(c) => c.to_string(),
+                _ => "_".to_string(),
+            })
+            .collect::<String>();
+        let mut content_disposition = format!(r#"attachment; filename="{fallback_filename}""#);
+
+        if !filename.chars().all(is_printable_ascii) {
+            content_disposition.push_str("; filename*=UTF-8''");
+            content_disposition.push_str(&urlencoding::encode(filename));
+        }
+
+        self.with_replaced_header(
+            http::header::CONTENT_DISPOSITION.as_str(),
+            content_disposition,
+        )
+    }
+
+    /// Sets the `Content-Type` header of the HTTP response to the type detected from the
+    /// leading bytes of the body, if the response does not already have a `Content-Type`
+    /// header.
+    ///
+    /// Browsers may guess the type of a response without a `Content-Type` header by sniffing
+    /// its body, and the guess is not covered by certification. Setting the type on the
+    /// canister side means that the type is certified along with the rest of the response.
+    ///
+    /// The following types are detected by their magic numbers:
+    /// - `image/png`
+    /// - `image/jpeg`
+    /// - `image/gif`
+    /// - `image/webp`
+    /// - `application/pdf`
+    /// - `application/gzip`
+    /// - `application/zip`
+    /// - `application/wasm`
+    ///
+    /// Text formats, such as HTML or JSON, have no magic number and are never detected. If
+    /// the type of the body cannot be detected, the `Content-Type` header is left unset.
+    /// The body must be set before calling this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_body(b"%PDF-1.7")
+    ///     .with_sniffed_content_type()
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[("content-type".into(), "application/pdf".into())]
+    /// );
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_body(b"Hello, World!")
+    ///     .with_sniffed_content_type()
+    ///     .build();
+    ///
+    /// assert!(response.headers().is_empty());
+    /// ```
+    pub fn with_sniffed_content_type(mut self) -> Self {
+        let content_type = http::header::CONTENT_TYPE.as_str();
+        let has_content_type = self
+            .headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(content_type));
+
+        if !has_content_type {
+            if let Some(sniffed_content_type) = sniff_content_type(&self.body) {
+                self.headers
+                    .push((content_type.to_string(), sniffed_content_type.to_string()));
+            }
+        }
+
+        self
+    }
+
+    fn with_replaced_header(mut self, name: &str, value: String) -> Self {
+        self.headers
+            .retain(|(header_name, _)| !header_name.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value));
+
+        self
+    }
+
+    /// Runs the given closure with the final body of the HTTP response and a mutable
+    /// reference to its headers, so that headers that can only be computed once the body
+    /// is complete, such as `Content-Length` or a checksum of the body, can be added
+    /// right before building the response.
+    ///
+    /// The body must be set before calling this method. Any headers added or changed by
+    /// the closure are part of the response like any other header, so they are included
+    /// in the certification of the response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_body(b"Hello, World!")
+    ///     .finalize_with(|body, headers| {
+    ///         headers.push(("content-length".into(), body.len().to_string()));
+    ///     })
+    ///     .build();
+    ///
+    /// assert_eq!(response.headers(), &[("content-length".into(), "13".into())]);
+    /// ```
+    pub fn finalize_with(mut self, f: impl FnOnce(&[u8], &mut Vec<HeaderField>)) -> Self {
+        f(&self.body, &mut self.headers);
+
+        self
+    }
+
     /// Build an [HttpResponse] from the builder.
     ///
     /// If the status code is not set, it will default to `200`.
@@ -766,6 +1548,38 @@ impl<'a> HttpResponseBuilder<'a> {
         }
     }
 
+    /// Build an [HttpResponse] from the builder, validating that each header name is a
+    /// valid HTTP token and that each header value does not contain forbidden control
+    /// characters, such as `\r` or `\n`.
+    ///
+    /// Returns [InvalidHeaderName](crate::HttpCertificationError::InvalidHeaderName) or
+    /// [InvalidHeaderValue](crate::HttpCertificationError::InvalidHeaderValue) for the first
+    /// invalid header. Defaults are the same as for [build](HttpResponseBuilder::build).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpCertificationError, HttpResponse};
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![("Content-Type".into(), "text/plain".into())])
+    ///     .try_build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(response.headers(), &[("Content-Type".into(), "text/plain".into())]);
+    ///
+    /// let result = HttpResponse::builder()
+    ///     .with_headers(vec![("X Custom".into(), "Bar".into())])
+    ///     .try_build();
+    ///
+    /// assert!(matches!(result, Err(HttpCertificationError::InvalidHeaderName { .. })));
+    /// ```
+    pub fn try_build(self) -> HttpCertificationResult<HttpResponse<'a>> {
+        validate_header_fields(&self.headers)?;
+
+        Ok(self.build())
+    }
+
     /// Build an [HttpUpdateResponse] from the builder.
     ///
     /// If the status code is not set, it will default to `200`.
@@ -846,7 +1660,9 @@ impl Debug for HttpResponse<'_> {
 /// by the `http_update_request` method of the HTTP Gateway Protocol.
 ///
 /// This is the same as [HttpResponse], excluding the
-/// [upgrade](HttpResponse::upgrade) field.
+/// [upgrade](HttpResponse::upgrade) field. An [HttpUpdateResponse] can be converted
+/// back into an [HttpResponse] without an upgrade flag, for example to hash or verify it
+/// in the same way as a response to the `http_request` method.
 ///
 /// # Examples
 ///
@@ -864,6 +1680,11 @@ impl Debug for HttpResponse<'_> {
 /// assert_eq!(update_response.status_code(), StatusCode::OK);
 /// assert_eq!(update_response.headers(), &[("Content-Type".into(), "text/plain".into())]);
 /// assert_eq!(update_response.body(), b"Hello, World!");
+///
+/// let response = HttpResponse::from(update_response);
+///
+/// assert_eq!(response.status_code(), StatusCode::OK);
+/// assert_eq!(response.upgrade(), None);
 /// ```
 #[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
 pub struct HttpUpdateResponse<'a> {
@@ -934,6 +1755,50 @@ impl<'a> HttpUpdateResponse<'a> {
         &mut self.headers
     }
 
+    /// Returns the number of HTTP headers of the response, counting repeated headers
+    /// separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![
+    ///         ("Content-Type".into(), "text/plain".into()),
+    ///         ("Vary".into(), "Origin".into()),
+    ///         ("Vary".into(), "Accept-Encoding".into()),
+    ///     ])
+    ///     .build_update();
+    ///
+    /// assert_eq!(response.header_count(), 3);
+    /// ```
+    #[inline]
+    pub fn header_count(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Returns the total size of the HTTP headers of the response in bytes, as they would be
+    /// serialized in an HTTP/1.1 response, including the `: ` separator between each name and
+    /// value, and the `\r\n` line ending after each header.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::HttpResponse;
+    ///
+    /// let response = HttpResponse::builder()
+    ///     .with_headers(vec![("Content-Type".into(), "text/plain".into())])
+    ///     .build_update();
+    ///
+    /// // "Content-Type: text/plain\r\n"
+    /// assert_eq!(response.total_header_bytes(), 26);
+    /// ```
+    #[inline]
+    pub fn total_header_bytes(&self) -> usize {
+        total_header_bytes(&self.headers)
+    }
+
     /// Adds an additional header to the HTTP response.
     ///
     /// # Examples
@@ -982,3 +1847,482 @@ impl<'a> From<HttpResponse<'a>> for HttpUpdateResponse<'a> {
         }
     }
 }
+
+impl<'a> From<HttpUpdateResponse<'a>> for HttpResponse<'a> {
+    fn from(response: HttpUpdateResponse<'a>) -> Self {
+        Self {
+            status_code: response.status_code,
+            headers: response.headers,
+            body: response.body,
+            upgrade: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(StatusCode::MOVED_PERMANENTLY)]
+    #[case(StatusCode::FOUND)]
+    #[case(StatusCode::SEE_OTHER)]
+    #[case(StatusCode::TEMPORARY_REDIRECT)]
+    #[case(StatusCode::PERMANENT_REDIRECT)]
+    fn redirect(#[case] status_code: StatusCode) {
+        let response = HttpResponse::redirect(
+            status_code,
+            "https://www.example.com",
+            vec![("Cache-Control".into(), "no-cache".into())],
+        )
+        .build();
+
+        assert_eq!(response.status_code(), status_code);
+        assert_eq!(
+            response.headers(),
+            &[
+                ("Cache-Control".into(), "no-cache".into()),
+                ("Location".into(), "https://www.example.com".into())
+            ]
+        );
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    fn redirect_helpers() {
+        let headers = vec![("Content-Type".to_string(), "text/plain".to_string())];
+
+        assert_eq!(
+            HttpResponse::moved_permanently("/new", headers.clone()).build(),
+            HttpResponse::redirect(StatusCode::MOVED_PERMANENTLY, "/new", headers.clone()).build()
+        );
+        assert_eq!(
+            HttpResponse::temporary_redirect("/new", headers.clone()).build(),
+            HttpResponse::redirect(StatusCode::TEMPORARY_REDIRECT, "/new", headers).build()
+        );
+    }
+
+    #[rstest]
+    #[case(StatusCode::OK)]
+    #[case(StatusCode::NOT_MODIFIED)]
+    #[case(StatusCode::NOT_FOUND)]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "redirect status code must be 3xx")]
+    fn redirect_non_redirection_status_code(#[case] status_code: StatusCode) {
+        HttpResponse::redirect(status_code, "https://www.example.com", vec![]);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![("Vary", "Accept-Encoding")])]
+    #[case(vec![("Vary", "")], vec![("Vary", "Accept-Encoding")])]
+    #[case(vec![("Vary", "Origin")], vec![("Vary", "Origin, Accept-Encoding")])]
+    #[case(vec![("vary", "Origin")], vec![("vary", "Origin, Accept-Encoding")])]
+    #[case(
+        vec![("Vary", "Origin"), ("Content-Type", "text/html"), ("Vary", "Accept")],
+        vec![("Vary", "Origin"), ("Content-Type", "text/html"), ("Vary", "Accept, Accept-Encoding")],
+    )]
+    #[case(
+        vec![("Content-Type", "text/html")],
+        vec![("Content-Type", "text/html"), ("Vary", "Accept-Encoding")],
+    )]
+    fn append_to_header(
+        #[case] headers: Vec<(&str, &str)>,
+        #[case] expected_headers: Vec<(&str, &str)>,
+    ) {
+        let into_header_fields = |headers: Vec<(&str, &str)>| {
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let mut response = HttpResponse::ok(b"", into_header_fields(headers)).build();
+
+        response.append_to_header("Vary", "Accept-Encoding");
+
+        assert_eq!(response.headers(), into_header_fields(expected_headers));
+    }
+
+    #[rstest]
+    #[case(200, Some(StatusCode::OK))]
+    #[case(999, Some(StatusCode::from_u16(999).unwrap()))]
+    #[case(42, None)]
+    #[case(1000, None)]
+    #[case(u16::MAX, None)]
+    fn response_try_from_candid(
+        #[case] status_code: u16,
+        #[case] expected_status_code: Option<StatusCode>,
+    ) {
+        let bytes = candid::encode_one(CandidHttpResponse {
+            status_code,
+            headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+            body: b"Hello, World!".to_vec(),
+            upgrade: Some(true),
+        })
+        .unwrap();
+
+        let result = HttpResponse::try_from_candid(&bytes);
+
+        match expected_status_code {
+            Some(expected_status_code) => {
+                let response = result.unwrap();
+                assert_eq!(response.status_code(), expected_status_code);
+                assert_eq!(
+                    response.headers(),
+                    &[("Content-Type".to_string(), "text/plain".to_string())]
+                );
+                assert_eq!(response.body(), b"Hello, World!");
+                assert_eq!(response.upgrade(), Some(true));
+            }
+            None => assert!(matches!(
+                result,
+                Err(HttpCertificationError::InvalidHttpStatusCode { status_code: actual })
+                    if actual == status_code
+            )),
+        }
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(false))]
+    #[case(Some(true))]
+    fn response_upgrade_candid_round_trip(#[case] upgrade: Option<bool>) {
+        let builder = HttpResponse::ok(b"Hello, World!", vec![]).with_upgrade(true);
+        let builder = match upgrade {
+            Some(upgrade) => builder.with_upgrade(upgrade),
+            None => builder.without_upgrade(),
+        };
+        let response = builder.build();
+        assert_eq!(response.upgrade(), upgrade);
+
+        let bytes = candid::encode_one(&response).unwrap();
+
+        let candid_response = candid::decode_one::<CandidHttpResponse>(&bytes).unwrap();
+        assert_eq!(candid_response.upgrade, upgrade);
+
+        let decoded_response = HttpResponse::try_from_candid(&bytes).unwrap();
+        assert_eq!(decoded_response.upgrade(), upgrade);
+        assert_eq!(decoded_response, response);
+    }
+
+    #[rstest]
+    #[case(vec![], vec![])]
+    #[case(vec![("B", "2"), ("A", "1"), ("C", "3")], vec![("A", "1"), ("B", "2"), ("C", "3")])]
+    #[case(vec![("b", "2"), ("B", "1"), ("a", "3")], vec![("B", "1"), ("a", "3"), ("b", "2")])]
+    fn response_with_headers_map(
+        #[case] headers: Vec<(&str, &str)>,
+        #[case] expected_headers: Vec<(&str, &str)>,
+    ) {
+        let headers = headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        let expected_headers = expected_headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+
+        let response = HttpResponse::builder()
+            .with_headers(vec![("X-Replaced".to_string(), "true".to_string())])
+            .with_headers_map(headers)
+            .build();
+
+        assert_eq!(response.headers(), expected_headers.as_slice());
+    }
+
+    #[rstest]
+    #[case(vec![], 0, 0)]
+    #[case(vec![("Content-Type", "text/plain")], 1, 26)]
+    #[case(vec![("Vary", "Origin"), ("Vary", "Accept-Encoding")], 2, 37)]
+    #[case(vec![("x-empty", "")], 1, 11)]
+    #[case(vec![("x-utf8", "café")], 1, 15)]
+    fn response_header_sizes(
+        #[case] headers: Vec<(&str, &str)>,
+        #[case] expected_count: usize,
+        #[case] expected_bytes: usize,
+    ) {
+        let headers = headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect::<Vec<_>>();
+        let response = HttpResponse::ok(b"Hello, World!", headers).build();
+
+        assert_eq!(response.header_count(), expected_count);
+        assert_eq!(response.total_header_bytes(), expected_bytes);
+
+        let update_response = HttpUpdateResponse::from(response);
+        assert_eq!(update_response.header_count(), expected_count);
+        assert_eq!(update_response.total_header_bytes(), expected_bytes);
+    }
+
+    #[rstest]
+    fn response_try_from_malformed_candid() {
+        let request_bytes = candid::encode_one(crate::HttpRequest::get("/").build()).unwrap();
+
+        for bytes in [b"not candid".as_slice(), request_bytes.as_slice()] {
+            assert!(matches!(
+                HttpResponse::try_from_candid(bytes),
+                Err(HttpCertificationError::MalformedCandid(_))
+            ));
+        }
+    }
+
+    #[rstest]
+    #[case(vec![("A", "1"), ("B", "2")], vec![("A", "1"), ("B", "2")], true, true)]
+    #[case(vec![("A", "1"), ("B", "2")], vec![("B", "2"), ("A", "1")], true, false)]
+    #[case(vec![("A", "1"), ("A", "2")], vec![("A", "2"), ("A", "1")], true, false)]
+    #[case(vec![("A", "1"), ("B", "2")], vec![("A", "1"), ("B", "3")], false, false)]
+    #[case(vec![("A", "1")], vec![("A", "1"), ("A", "1")], false, false)]
+    fn eq_ordered(
+        #[case] headers: Vec<(&str, &str)>,
+        #[case] other_headers: Vec<(&str, &str)>,
+        #[case] expected_eq: bool,
+        #[case] expected_eq_ordered: bool,
+    ) {
+        let response_with_headers = |headers: Vec<(&str, &str)>| {
+            let headers = headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+
+            HttpResponse::ok(b"Hello, World!", headers).build()
+        };
+        let response = response_with_headers(headers);
+        let other_response = response_with_headers(other_headers);
+
+        assert_eq!(response == other_response, expected_eq);
+        assert_eq!(response.eq_ordered(&other_response), expected_eq_ordered);
+        assert_eq!(other_response.eq_ordered(&response), expected_eq_ordered);
+    }
+
+    #[rstest]
+    #[case("v1", r#""v1""#)]
+    #[case(r#""v1""#, r#""v1""#)]
+    #[case(r#"W/"v1""#, r#"W/"v1""#)]
+    fn with_etag(#[case] tag: &str, #[case] expected_etag: &str) {
+        let response = HttpResponse::builder()
+            .with_headers(vec![
+                ("ETag".to_string(), r#""v0""#.to_string()),
+                ("Content-Type".to_string(), "text/plain".to_string()),
+            ])
+            .with_etag(tag)
+            .with_last_modified("Wed, 21 Oct 2015 07:28:00 GMT")
+            .build();
+
+        assert_eq!(
+            response.headers(),
+            &[
+                ("Content-Type".to_string(), "text/plain".to_string()),
+                ("etag".to_string(), expected_etag.to_string()),
+                (
+                    "last-modified".to_string(),
+                    "Wed, 21 Oct 2015 07:28:00 GMT".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn with_strong_etag() {
+        let response = HttpResponse::builder()
+            .with_body(b"Hello, World!")
+            .with_strong_etag()
+            .build();
+        let other_response = HttpResponse::builder()
+            .with_body(b"Hello, Internet Computer!")
+            .with_strong_etag()
+            .build();
+
+        assert_eq!(
+            response.headers(),
+            &[(
+                "etag".to_string(),
+                HttpResponse::strong_etag(b"Hello, World!")
+            )]
+        );
+        assert_ne!(response.headers(), other_response.headers());
+    }
+
+    #[rstest]
+    #[case(vec![Method::GET], vec![], vec![("allow", "GET")])]
+    #[case(
+        vec![Method::GET, Method::HEAD, Method::GET],
+        vec![("content-length", "0")],
+        vec![("content-length", "0"), ("allow", "GET, HEAD")],
+    )]
+    #[case(
+        vec![Method::POST],
+        vec![("Allow", "GET"), ("content-type", "text/plain")],
+        vec![("content-type", "text/plain"), ("allow", "POST")],
+    )]
+    #[case(vec![], vec![], vec![("allow", "")])]
+    fn method_not_allowed_with_allow(
+        #[case] allowed: Vec<Method>,
+        #[case] headers: Vec<(&str, &str)>,
+        #[case] expected_headers: Vec<(&str, &str)>,
+    ) {
+        let into_header_fields = |headers: Vec<(&str, &str)>| {
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let response =
+            HttpResponse::method_not_allowed_with_allow(&allowed, b"", into_header_fields(headers))
+                .build();
+
+        assert_eq!(response.status_code(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers(), into_header_fields(expected_headers));
+    }
+
+    #[rstest]
+    fn finalize_with() {
+        let response = HttpResponse::builder()
+            .with_headers(vec![
+                ("content-type".to_string(), "text/plain".to_string()),
+                ("content-length".to_string(), "0".to_string()),
+            ])
+            .with_body(b"Hello, World!")
+            .finalize_with(|body, headers| {
+                headers.retain(|(name, _)| !name.eq_ignore_ascii_case("content-length"));
+                headers.push(("content-length".to_string(), body.len().to_string()));
+                headers.push(("etag".to_string(), HttpResponse::strong_etag(body)));
+            })
+            .build();
+
+        assert_eq!(response.body(), b"Hello, World!");
+        assert_eq!(
+            response.headers(),
+            &[
+                ("content-type".to_string(), "text/plain".to_string()),
+                ("content-length".to_string(), "13".to_string()),
+                (
+                    "etag".to_string(),
+                    HttpResponse::strong_etag(b"Hello, World!")
+                ),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("report.pdf", r#"attachment; filename="report.pdf""#)]
+    #[case("my report (1).pdf", r#"attachment; filename="my report (1).pdf""#)]
+    #[case(r#"say "hi"\.txt"#, r#"attachment; filename="say \"hi\"\\.txt""#)]
+    #[case(
+        "résumé.pdf",
+        r#"attachment; filename="r_sum_.pdf"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"#
+    )]
+    #[case(
+        "报告.txt",
+        r#"attachment; filename="__.txt"; filename*=UTF-8''%E6%8A%A5%E5%91%8A.txt"#
+    )]
+    #[case(
+        "😀 \"smile\".png",
+        r#"attachment; filename="_ \"smile\".png"; filename*=UTF-8''%F0%9F%98%80%20%22smile%22.png"#
+    )]
+    #[case(
+        "line\nbreak.txt",
+        r#"attachment; filename="line_break.txt"; filename*=UTF-8''line%0Abreak.txt"#
+    )]
+    fn with_attachment(#[case] filename: &str, #[case] expected_content_disposition: &str) {
+        let response = HttpResponse::builder()
+            .with_headers(vec![(
+                "Content-Disposition".to_string(),
+                "inline".to_string(),
+            )])
+            .with_attachment(filename)
+            .try_build()
+            .unwrap();
+
+        assert_eq!(
+            response.headers(),
+            &[(
+                "content-disposition".to_string(),
+                expected_content_disposition.to_string()
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", Some("image/png"))]
+    #[case(b"\xff\xd8\xff\xe0\0\x10JFIF", Some("image/jpeg"))]
+    #[case(b"GIF87a", Some("image/gif"))]
+    #[case(b"GIF89a\x01\0\x01\0", Some("image/gif"))]
+    #[case(b"RIFF\x24\0\0\0WEBPVP8 ", Some("image/webp"))]
+    #[case(b"%PDF-1.7", Some("application/pdf"))]
+    #[case(b"\x1f\x8b\x08\0\0\0\0\0", Some("application/gzip"))]
+    #[case(b"PK\x03\x04\x14\0", Some("application/zip"))]
+    #[case(b"\0asm\x01\0\0\0", Some("application/wasm"))]
+    #[case(b"", None)]
+    #[case(b"\x89PNG", None)]
+    #[case(b"GIF88a", None)]
+    #[case(b"RIFF\x24\0\0\0WAVEfmt ", None)]
+    #[case(b"<!DOCTYPE html>", None)]
+    #[case(b"{\"hello\": \"world\"}", None)]
+    fn with_sniffed_content_type(#[case] body: &[u8], #[case] expected_content_type: Option<&str>) {
+        let response = HttpResponse::builder()
+            .with_body(body)
+            .with_sniffed_content_type()
+            .build();
+
+        let expected_headers = expected_content_type
+            .map(|content_type| ("content-type".to_string(), content_type.to_string()))
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(response.headers(), expected_headers.as_slice());
+    }
+
+    #[rstest]
+    fn with_sniffed_content_type_keeps_existing_content_type() {
+        let response = HttpResponse::builder()
+            .with_headers(vec![(
+                "Content-Type".to_string(),
+                "application/octet-stream".to_string(),
+            )])
+            .with_body(b"%PDF-1.7")
+            .with_sniffed_content_type()
+            .build();
+
+        assert_eq!(
+            response.headers(),
+            &[(
+                "Content-Type".to_string(),
+                "application/octet-stream".to_string()
+            )]
+        );
+    }
+
+    #[rstest]
+    #[case(None)]
+    #[case(Some(true))]
+    fn clone_with_body(#[case] upgrade: Option<bool>) {
+        let mut builder = HttpResponse::not_found(
+            b"first page".as_slice(),
+            vec![("Content-Type".into(), "text/plain".into())],
+        );
+        if let Some(upgrade) = upgrade {
+            builder = builder.with_upgrade(upgrade);
+        }
+        let response = builder.build();
+
+        let pages = [b"second page".as_slice(), b"third page".as_slice()]
+            .into_iter()
+            .map(|body| response.clone_with_body(body))
+            .collect::<Vec<_>>();
+
+        for (page, expected_body) in pages
+            .iter()
+            .zip([b"second page".as_slice(), b"third page".as_slice()])
+        {
+            assert_eq!(page.status_code(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                page.headers(),
+                &[("Content-Type".into(), "text/plain".into())]
+            );
+            assert_eq!(page.body(), expected_body);
+            assert_eq!(page.upgrade(), upgrade);
+            assert!(matches!(page.body, Cow::Borrowed(_)));
+        }
+        assert_eq!(response.body(), b"first page");
+    }
+}
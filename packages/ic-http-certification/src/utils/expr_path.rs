@@ -0,0 +1,148 @@
+use super::{more_specific_wildcards_for, EXACT_PATH_TERMINATOR, PATH_DIR_SEPARATOR, PATH_PREFIX};
+
+fn request_path_segments(request_path: &str) -> Vec<String> {
+    let mut segments = request_path
+        .split('/')
+        .filter(|e| !e.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+
+    // make sure to treat a request for a directory and a file as different paths
+    // i.e. /app is not the same as /app/
+    // we do this by inserting an empty segment for directory paths
+    if request_path.ends_with('/') {
+        segments.push(PATH_DIR_SEPARATOR.to_string());
+    }
+
+    segments
+}
+
+/// Returns the expression path that is expected in the `IC-Certificate` header
+/// for a response that is certified with an exact path matching the given
+/// request path.
+///
+/// The request path is expected to be already URL decoded, as returned by
+/// [HttpRequest::get_path](crate::HttpRequest::get_path).
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::utils::exact_expr_path;
+///
+/// assert_eq!(exact_expr_path("/"), vec!["http_expr", "", "<$>"]);
+/// assert_eq!(exact_expr_path("/app/index.html"), vec!["http_expr", "app", "index.html", "<$>"]);
+/// assert_eq!(exact_expr_path("/app/"), vec!["http_expr", "app", "", "<$>"]);
+/// ```
+pub fn exact_expr_path(request_path: &str) -> Vec<String> {
+    let mut expr_path = vec![PATH_PREFIX.to_string()];
+    expr_path.extend(request_path_segments(request_path));
+    expr_path.push(EXACT_PATH_TERMINATOR.to_string());
+
+    expr_path
+}
+
+/// Returns all wildcard expression paths that are valid in the `IC-Certificate`
+/// header for a response to the given request path, when no exact path has been
+/// certified. The paths are ordered from the most specific to the least specific.
+///
+/// A verifier will only accept a wildcard expression path if none of the more specific
+/// paths in this list exist in the certification tree, so a fallback response must be
+/// certified with the first path from this list that is present in the tree.
+///
+/// The request path is expected to be already URL decoded, as returned by
+/// [HttpRequest::get_path](crate::HttpRequest::get_path).
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::utils::fallback_expr_paths;
+///
+/// assert_eq!(
+///     fallback_expr_paths("/app/index.html"),
+///     vec![
+///         vec!["http_expr", "app", "index.html", "<*>"],
+///         vec!["http_expr", "app", "", "<*>"],
+///         vec!["http_expr", "app", "<*>"],
+///         vec!["http_expr", "", "<*>"],
+///     ]
+/// );
+/// ```
+pub fn fallback_expr_paths(request_path: &str) -> Vec<Vec<String>> {
+    let segments = request_path_segments(request_path)
+        .into_iter()
+        .map(String::into_bytes)
+        .collect::<Vec<_>>();
+
+    more_specific_wildcards_for(&segments, &[])
+        .into_iter()
+        .map(|wildcard_path| {
+            let mut expr_path = vec![PATH_PREFIX.to_string()];
+            expr_path.extend(
+                wildcard_path
+                    .iter()
+                    .map(|segment| String::from_utf8_lossy(segment).to_string()),
+            );
+
+            expr_path
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HttpCertificationPath;
+    use rstest::*;
+
+    #[rstest]
+    #[case("/", vec!["http_expr", "", "<$>"])]
+    #[case("/index.html", vec!["http_expr", "index.html", "<$>"])]
+    #[case("/app/index.html", vec!["http_expr", "app", "index.html", "<$>"])]
+    #[case("/app/", vec!["http_expr", "app", "", "<$>"])]
+    #[case("/app", vec!["http_expr", "app", "<$>"])]
+    fn test_exact_expr_path(#[case] request_path: &str, #[case] expected: Vec<&str>) {
+        let expr_path = exact_expr_path(request_path);
+
+        assert_eq!(expr_path, expected);
+        assert_eq!(
+            expr_path,
+            HttpCertificationPath::exact(request_path).to_expr_path()
+        );
+    }
+
+    #[rstest]
+    #[case("/", vec![vec!["http_expr", "", "<*>"]])]
+    #[case("/index.html", vec![
+        vec!["http_expr", "index.html", "<*>"],
+        vec!["http_expr", "", "<*>"],
+    ])]
+    #[case("/app/index.html", vec![
+        vec!["http_expr", "app", "index.html", "<*>"],
+        vec!["http_expr", "app", "", "<*>"],
+        vec!["http_expr", "app", "<*>"],
+        vec!["http_expr", "", "<*>"],
+    ])]
+    #[case("/app/", vec![
+        vec!["http_expr", "app", "", "<*>"],
+        vec!["http_expr", "app", "<*>"],
+        vec!["http_expr", "", "<*>"],
+    ])]
+    fn test_fallback_expr_paths(#[case] request_path: &str, #[case] expected: Vec<Vec<&str>>) {
+        let expr_paths = fallback_expr_paths(request_path);
+
+        assert_eq!(expr_paths, expected);
+    }
+
+    #[rstest]
+    #[case("/", "/")]
+    #[case("/app/index.html", "/app/")]
+    #[case("/app/index.html", "/app")]
+    fn test_fallback_expr_paths_match_wildcard_paths(
+        #[case] request_path: &str,
+        #[case] scope: &str,
+    ) {
+        let expr_paths = fallback_expr_paths(request_path);
+
+        assert!(expr_paths.contains(&HttpCertificationPath::wildcard(scope).to_expr_path()));
+    }
+}
@@ -10,3 +10,6 @@ pub use wildcard_paths::*;
 
 mod skip_certification;
 pub use skip_certification::*;
+
+mod expr_path;
+pub use expr_path::*;
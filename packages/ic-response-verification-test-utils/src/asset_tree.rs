@@ -28,7 +28,7 @@ impl AssetTree {
         }
     }
 
-    pub fn insert(&mut self, path: &'static str, body: &str) {
+    pub fn insert(&mut self, path: &'static str, body: impl AsRef<[u8]>) {
         let body_hash = hash(body);
 
         self.tree.insert(path, body_hash);
@@ -70,7 +70,11 @@ async fn v1_test(canister_id: &str, agent: &Agent) -> Result<()> {
         result,
         VerificationInfo {
             verification_version,
-            response: _,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_expression: None,
+            certificate_expression_hash: None,
+            ..
         } if verification_version == 1
     ));
 
@@ -80,7 +84,11 @@ async fn v1_test(canister_id: &str, agent: &Agent) -> Result<()> {
         result,
         VerificationInfo {
             verification_version,
-            response: _,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_expression: None,
+            certificate_expression_hash: None,
+            ..
         } if verification_version == 1
     ));
 
@@ -151,7 +159,11 @@ async fn v2_load_asset(
         result,
         VerificationInfo {
             verification_version,
-            response: _,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_expression: Some(_),
+            certificate_expression_hash: Some(_),
+            ..
         } if verification_version == 2
     ));
     assert_eq!(asset, response.body().to_vec());
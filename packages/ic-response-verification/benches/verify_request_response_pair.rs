@@ -0,0 +1,150 @@
+//! Benchmarks for [verify_request_response_pair] with small, medium and large responses.
+//!
+//! Run with `cargo bench -p ic-response-verification`. To compare the performance of a change,
+//! save a baseline before making the change and compare against it afterwards:
+//!
+//! ```sh
+//! cargo bench -p ic-response-verification -- --save-baseline before
+//! cargo bench -p ic-response-verification -- --baseline before
+//! ```
+//!
+//! Note that the verification of the certificate's BLS signature is included in each iteration,
+//! so the difference between response sizes reflects the cost of hashing and header processing.
+//!
+//! To measure the header lookup fast path against the previous implementation, which copied
+//! every response header into a `HashMap`, check out the commit that introduced the fast path,
+//! save a baseline with the previous version of `src/verification/verify_request_response_pair.rs`
+//! and compare the fast path against it:
+//!
+//! ```sh
+//! git checkout <commit>
+//! git checkout <commit>~1 -- packages/ic-response-verification/src/verification/verify_request_response_pair.rs
+//! cargo bench -p ic-response-verification -- --save-baseline before
+//! git checkout <commit> -- packages/ic-response-verification/src/verification/verify_request_response_pair.rs
+//! cargo bench -p ic-response-verification -- --baseline before
+//! ```
+//!
+//! Criterion reports the change in time per iteration and throughput for each scenario, and
+//! these numbers should be included when reporting on changes to the verification hot path.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ic_http_certification::{
+    DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpCertificationPath,
+    HttpCertificationTreeEntry, HttpRequest, HttpResponse, CERTIFICATE_EXPRESSION_HEADER_NAME,
+    CERTIFICATE_HEADER_NAME,
+};
+use ic_response_verification::verify_request_response_pair;
+use ic_response_verification_test_utils::{create_v2_fixture, get_current_timestamp, V2Fixture};
+
+const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
+const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
+const REQ_PATH: &str = "/api/todos";
+
+struct Scenario {
+    name: &'static str,
+    body_len: usize,
+    header_count: usize,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "small",
+        body_len: 256,
+        header_count: 3,
+    },
+    Scenario {
+        name: "medium",
+        body_len: 16 * 1024,
+        header_count: 10,
+    },
+    Scenario {
+        name: "large",
+        body_len: 1024 * 1024,
+        header_count: 30,
+    },
+];
+
+struct Fixture {
+    request: HttpRequest<'static>,
+    response: HttpResponse<'static>,
+    root_key: Vec<u8>,
+    canister_id: Vec<u8>,
+    current_time: u128,
+}
+
+fn create_fixture(scenario: &Scenario) -> Fixture {
+    let current_time = get_current_timestamp();
+    let header_names = (0..scenario.header_count)
+        .map(|i| format!("X-Header-{i}"))
+        .collect::<Vec<_>>();
+
+    let cel_expr = DefaultCelBuilder::full_certification()
+        .with_response_certification(DefaultResponseCertification::certified_response_headers(
+            header_names.iter().map(String::as_str).collect::<Vec<_>>(),
+        ))
+        .build();
+
+    let request = HttpRequest::get(REQ_PATH).build();
+    let mut headers = header_names
+        .iter()
+        .map(|name| (name.clone(), "value".to_string()))
+        .collect::<Vec<_>>();
+    headers.push((
+        CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+        cel_expr.to_string(),
+    ));
+    let mut response = HttpResponse::ok(vec![b'a'; scenario.body_len], headers).build();
+
+    let certification = HttpCertification::full(&cel_expr, &request, &response, None).unwrap();
+    let certification_path = HttpCertificationPath::exact(REQ_PATH);
+    let certification_tree_entry =
+        HttpCertificationTreeEntry::new(&certification_path, certification);
+
+    let V2Fixture {
+        root_key,
+        certificate_header,
+        canister_id,
+    } = create_v2_fixture(REQ_PATH, &certification_tree_entry, &current_time);
+    response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+
+    Fixture {
+        request,
+        response,
+        root_key,
+        canister_id: canister_id.as_ref().to_vec(),
+        current_time,
+    }
+}
+
+fn bench_verify_request_response_pair(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_request_response_pair");
+
+    for scenario in SCENARIOS {
+        let fixture = create_fixture(scenario);
+
+        group.throughput(Throughput::Bytes(scenario.body_len as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(scenario.name),
+            &fixture,
+            |b, fixture| {
+                b.iter(|| {
+                    verify_request_response_pair(
+                        black_box(fixture.request.clone()),
+                        black_box(fixture.response.clone()),
+                        &fixture.canister_id,
+                        fixture.current_time,
+                        MAX_CERT_TIME_OFFSET_NS,
+                        &fixture.root_key,
+                        MIN_REQUESTED_VERIFICATION_VERSION,
+                    )
+                    .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_request_response_pair);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes to the CEL parser, which receives untrusted `IC-CertificateExpression`
+//! headers, to check that it fails with an error instead of panicking.
+//!
+//! Requires a nightly toolchain and [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz). Run
+//! from the `packages/ic-response-verification` directory with:
+//!
+//! ```sh
+//! cargo +nightly fuzz run parse_cel_expression
+//! ```
+
+#![no_main]
+
+use ic_response_verification::cel::parse_and_map_cel_expression;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_and_map_cel_expression(&String::from_utf8_lossy(data));
+});
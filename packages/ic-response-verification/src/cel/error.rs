@@ -90,6 +90,13 @@ pub enum CelParserError {
     #[error(r#"The ValidationArgs parameter must specify at least one of the no_certification or certification properties"#)]
     MissingValidationArgsProperty,
 
+    /// The CEL expression nests functions, objects or arrays more deeply than the parser allows.
+    #[error(r#"The CEL expression exceeds the maximum nesting depth of {max_nesting_depth:?}"#)]
+    MaxNestingDepthExceeded {
+        /// The maximum nesting depth allowed by the parser.
+        max_nesting_depth: usize,
+    },
+
     /// The CEL parser encountered a syntax error while parsing the CEL expression. Using the "debug" feature flag can help to debug these syntax errors.
     #[error(r#"Cel Syntax Expception: {0}"#)]
     CelSyntaxException(String),
@@ -9,5 +9,16 @@ mod parser;
 pub(crate) use ast_mapping::map_cel_ast;
 pub(crate) use parser::parse_cel_expression;
 
+/// Parses the given CEL expression and maps it onto a [CelExpression](ic_http_certification::cel::CelExpression),
+/// discarding the result. This is only exposed for the fuzz targets in the `fuzz` directory.
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub fn parse_and_map_cel_expression(cel: &str) -> Result<(), CelParserError> {
+    let cel_value = parse_cel_expression(cel)?;
+    map_cel_ast(&cel_value)?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests;
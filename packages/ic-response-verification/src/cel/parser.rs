@@ -161,7 +161,43 @@ fn cel_value<'a, E: ParseError<&'a str> + ContextError<&'a str>>(
     )(i)
 }
 
+/// The maximum depth of nested functions, objects and arrays in a CEL expression. The parser is
+/// recursive, so this bounds its stack usage for untrusted expressions. The default certification
+/// expressions are nested at most 5 levels deep.
+const MAX_NESTING_DEPTH: usize = 32;
+
+fn exceeds_max_nesting_depth(i: &str) -> bool {
+    let mut depth = 0usize;
+    let mut is_in_string = false;
+    let mut is_escaped = false;
+
+    for c in i.chars() {
+        match c {
+            _ if is_escaped => is_escaped = false,
+            '\\' if is_in_string => is_escaped = true,
+            '"' => is_in_string = !is_in_string,
+            _ if is_in_string => {}
+            '(' | '{' | '[' => {
+                depth += 1;
+                if depth > MAX_NESTING_DEPTH {
+                    return true;
+                }
+            }
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    false
+}
+
 pub(crate) fn parse_cel_expression(i: &str) -> CelParserResult<CelValue> {
+    if exceeds_max_nesting_depth(i) {
+        return Err(CelParserError::MaxNestingDepthExceeded {
+            max_nesting_depth: MAX_NESTING_DEPTH,
+        });
+    }
+
     let result = cel_value::<nom::error::Error<&str>>(i);
 
     match result {
@@ -1,4 +1,4 @@
-use crate::cel::{map_cel_ast, parse_cel_expression};
+use crate::cel::{map_cel_ast, parse_cel_expression, CelParserError};
 use ic_http_certification::{
     cel::{
         CelExpression, DefaultCelExpression, DefaultFullCelExpression, DefaultRequestCertification,
@@ -6,7 +6,13 @@ use ic_http_certification::{
     },
     DefaultResponseCertification,
 };
+use ic_representation_independent_hash::hash;
 use ic_response_verification_test_utils::remove_whitespace;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use rstest::*;
+
+const PROPERTY_TEST_ITERATIONS: usize = 1_000;
 
 #[test]
 fn parses_no_certification_expression() {
@@ -111,3 +117,248 @@ fn parses_full_certification_expression() {
     assert_eq!(&result, &expected_result);
     assert_eq!(&minified_result, &expected_result);
 }
+
+#[rstest]
+#[case(CelExpression::Default(DefaultCelExpression::Skip))]
+#[case(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+    DefaultResponseOnlyCelExpression {
+        response: DefaultResponseCertification::certified_response_headers(vec![]),
+    }
+)))]
+#[case(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+    DefaultResponseOnlyCelExpression {
+        response: DefaultResponseCertification::certified_response_headers(vec![
+            "Cache-Control",
+            "ETag",
+            "Content-Type",
+        ]),
+    }
+)))]
+#[case(CelExpression::Default(DefaultCelExpression::ResponseOnly(
+    DefaultResponseOnlyCelExpression {
+        response: DefaultResponseCertification::response_header_exclusions(vec!["Date"]),
+    }
+)))]
+#[case(CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+    request: DefaultRequestCertification::new(vec![], vec![]),
+    response: DefaultResponseCertification::response_header_exclusions(vec![]),
+})))]
+#[case(CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+    request: DefaultRequestCertification::new(vec!["host", "accept"], vec!["filter", "page"]),
+    response: DefaultResponseCertification::certified_response_headers(vec![
+        "Content-Type",
+        "Content-Encoding",
+    ]),
+})))]
+fn serialized_expression_round_trips(#[case] cel_expr: CelExpression) {
+    let certificate_expression_header = cel_expr.to_string();
+    let expected_expr_hash = hash(certificate_expression_header.as_bytes());
+
+    let parsed_cel_expr = parse_cel_expression(&certificate_expression_header).unwrap();
+    let result = map_cel_ast(&parsed_cel_expr).unwrap();
+    assert_eq!(result, cel_expr);
+
+    let reserialized_expression = result.to_string();
+    assert_eq!(reserialized_expression, certificate_expression_header);
+    assert_eq!(hash(reserialized_expression.as_bytes()), expected_expr_hash);
+    assert_eq!(
+        remove_whitespace(&certificate_expression_header),
+        certificate_expression_header
+    );
+}
+
+fn random_index(rng: &mut ChaCha20Rng, len: usize) -> usize {
+    rng.next_u32() as usize % len
+}
+
+fn random_names(rng: &mut ChaCha20Rng) -> Vec<String> {
+    const NAME_CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-";
+
+    (0..random_index(rng, 6))
+        .map(|_| {
+            (0..=random_index(rng, 16))
+                .map(|_| NAME_CHARS[random_index(rng, NAME_CHARS.len())] as char)
+                .collect()
+        })
+        .collect()
+}
+
+fn random_response_certification<'a>(
+    rng: &mut ChaCha20Rng,
+    headers: &'a [&'a str],
+) -> DefaultResponseCertification<'a> {
+    if rng.next_u32() % 2 == 0 {
+        DefaultResponseCertification::certified_response_headers(headers)
+    } else {
+        DefaultResponseCertification::response_header_exclusions(headers)
+    }
+}
+
+fn random_cel_expression<'a>(
+    rng: &mut ChaCha20Rng,
+    request_headers: &'a [&'a str],
+    query_parameters: &'a [&'a str],
+    response_headers: &'a [&'a str],
+) -> CelExpression<'a> {
+    match random_index(rng, 3) {
+        0 => CelExpression::Default(DefaultCelExpression::Skip),
+        1 => CelExpression::Default(DefaultCelExpression::ResponseOnly(
+            DefaultResponseOnlyCelExpression {
+                response: random_response_certification(rng, response_headers),
+            },
+        )),
+        _ => CelExpression::Default(DefaultCelExpression::Full(DefaultFullCelExpression {
+            request: DefaultRequestCertification::new(request_headers, query_parameters),
+            response: random_response_certification(rng, response_headers),
+        })),
+    }
+}
+
+fn parse_and_map_cel_expression(cel_expression: &str) {
+    if let Ok(parsed_cel_expr) = parse_cel_expression(cel_expression) {
+        let _ = map_cel_ast(&parsed_cel_expr);
+    }
+}
+
+#[test]
+fn random_serialized_expressions_round_trip() {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+
+    for _ in 0..PROPERTY_TEST_ITERATIONS {
+        let request_headers = random_names(&mut rng);
+        let request_headers = request_headers
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let query_parameters = random_names(&mut rng);
+        let query_parameters = query_parameters
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let response_headers = random_names(&mut rng);
+        let response_headers = response_headers
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let cel_expr = random_cel_expression(
+            &mut rng,
+            &request_headers,
+            &query_parameters,
+            &response_headers,
+        );
+        let certificate_expression_header = cel_expr.to_string();
+
+        let parsed_cel_expr = parse_cel_expression(&certificate_expression_header)
+            .unwrap_or_else(|e| panic!("failed parsing {certificate_expression_header}: {e}"));
+        let result = map_cel_ast(&parsed_cel_expr)
+            .unwrap_or_else(|e| panic!("failed mapping {certificate_expression_header}: {e}"));
+        assert_eq!(result, cel_expr, "{certificate_expression_header}");
+        assert_eq!(result.to_string(), certificate_expression_header);
+    }
+}
+
+#[test]
+fn random_mutated_expressions_do_not_panic() {
+    const MUTATION_CHARS: &[char] = &[
+        '(',
+        ')',
+        '{',
+        '}',
+        '[',
+        ']',
+        ':',
+        ',',
+        '"',
+        '\\',
+        ' ',
+        '\n',
+        'a',
+        'Z',
+        '0',
+        '-',
+        '_',
+        'é',
+        '\u{0}',
+        '\u{1F600}',
+    ];
+
+    let mut rng = ChaCha20Rng::seed_from_u64(1);
+
+    for _ in 0..PROPERTY_TEST_ITERATIONS {
+        let request_headers = random_names(&mut rng);
+        let request_headers = request_headers
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let response_headers = random_names(&mut rng);
+        let response_headers = response_headers
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let cel_expr = random_cel_expression(&mut rng, &request_headers, &[], &response_headers);
+        let mut cel_expression = cel_expr.to_string().chars().collect::<Vec<_>>();
+
+        for _ in 0..=random_index(&mut rng, 4) {
+            let position = random_index(&mut rng, cel_expression.len() + 1);
+            let mutation_char = MUTATION_CHARS[random_index(&mut rng, MUTATION_CHARS.len())];
+
+            match random_index(&mut rng, 4) {
+                0 => cel_expression.truncate(position),
+                1 => cel_expression.insert(position, mutation_char),
+                2 if position < cel_expression.len() => {
+                    cel_expression.remove(position);
+                }
+                _ if position < cel_expression.len() => cel_expression[position] = mutation_char,
+                _ => cel_expression.push(mutation_char),
+            }
+        }
+
+        parse_and_map_cel_expression(&cel_expression.into_iter().collect::<String>());
+    }
+}
+
+#[test]
+fn random_bytes_do_not_panic() {
+    let mut rng = ChaCha20Rng::seed_from_u64(2);
+
+    for _ in 0..PROPERTY_TEST_ITERATIONS {
+        let mut bytes = vec![0; random_index(&mut rng, 512)];
+        rng.fill_bytes(&mut bytes);
+
+        parse_and_map_cel_expression(&String::from_utf8_lossy(&bytes));
+    }
+}
+
+#[rstest]
+#[case("[".repeat(100_000))]
+#[case("a(".repeat(100_000))]
+#[case("a{b:".repeat(100_000))]
+#[case(format!("{}\"]\"{}", "[".repeat(50_000), "]".repeat(50_000)))]
+fn deeply_nested_expressions_are_rejected(#[case] cel_expression: String) {
+    assert!(matches!(
+        parse_cel_expression(&cel_expression),
+        Err(CelParserError::MaxNestingDepthExceeded {
+            max_nesting_depth: 32
+        })
+    ));
+}
+
+#[test]
+fn brackets_in_strings_do_not_count_towards_nesting_depth() {
+    let cel_expression = format!(r#"a(["{}", "\"{}"])"#, "[".repeat(1_000), "(".repeat(1_000));
+
+    assert!(!matches!(
+        parse_cel_expression(&cel_expression),
+        Err(CelParserError::MaxNestingDepthExceeded { .. })
+    ));
+}
+
+#[test]
+fn expressions_at_max_nesting_depth_are_not_rejected() {
+    let cel_expression = format!("{}{}", "[".repeat(32), "]".repeat(32));
+
+    assert!(!matches!(
+        parse_cel_expression(&cel_expression),
+        Err(CelParserError::MaxNestingDepthExceeded { .. })
+    ));
+}
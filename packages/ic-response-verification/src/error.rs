@@ -77,18 +77,22 @@ pub enum ResponseVerificationError {
 
     /// The exact expression path provided by the "IC-Certificate" response header
     /// was not found in the tree
-    #[error(r#"The exact expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) was not found in the tree"#)]
+    #[error(r#"The exact expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) is valid for the request path ({request_path:?}), but was not found in the tree"#)]
     ExactExpressionPathNotFoundInTree {
         /// The missing expression path
         provided_expr_path: Vec<String>,
+        /// The request path
+        request_path: String,
     },
 
     /// The exact expression path provided by the "IC-Certificate" response header
     /// is not valid for the request path
-    #[error(r#"The exact expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) is not valid for the request path ({request_path:?})"#)]
+    #[error(r#"The exact expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) is not valid for the request path ({request_path:?}), the expected exact expression path is {expected_expr_path:?}"#)]
     ExactExpressionPathMismatch {
         /// The expression path provided by the "IC-Certificate" response header
         provided_expr_path: Vec<String>,
+        /// The exact expression path derived from the request path
+        expected_expr_path: Vec<String>,
         /// The request path
         request_path: String,
     },
@@ -118,10 +122,13 @@ pub enum ResponseVerificationError {
 
     /// The wildcard expression path provided by the "IC-Certificate" response
     /// header is not valid for the request path
-    #[error(r#"The wildcard expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) is not valid for the request path ({request_path:?})"#)]
+    #[error(r#"The wildcard expression path provided by the "IC-Certificate" response header ({provided_expr_path:?}) is not valid for the request path ({request_path:?}), the valid wildcard expression paths are {expected_expr_paths:?}"#)]
     WildcardExpressionPathMismatch {
         /// The expression path provided by the "IC-Certificate" response header
         provided_expr_path: Vec<String>,
+        /// The wildcard expression paths derived from the request path that would be valid,
+        /// ordered from the most specific to the least specific
+        expected_expr_paths: Vec<Vec<String>>,
         /// The request path
         request_path: String,
     },
@@ -201,6 +208,124 @@ pub enum ResponseVerificationError {
     /// HTTP Certification error
     #[error(r#"HTTP Certification error: "{0}""#)]
     HttpCertificationError(#[from] ic_http_certification::HttpCertificationError),
+
+    /// The upgrade flag of the response did not match the expected value
+    #[error(r#"The upgrade flag of the response ({upgrade:?}) does not match the expected value ({expected_upgrade:?})"#)]
+    UpgradeMismatch {
+        /// The expected value of the upgrade flag
+        expected_upgrade: bool,
+        /// The actual value of the upgrade flag
+        upgrade: bool,
+    },
+
+    /// The response body could not be decoded using the encoding declared by the
+    /// "Content-Encoding" response header
+    #[error(r#"The response body could not be decoded using the encoding declared by the "Content-Encoding" response header ({encoding:?})"#)]
+    ContentEncodingMismatch {
+        /// The encoding declared by the "Content-Encoding" response header
+        encoding: String,
+    },
+
+    /// The "Content-Encoding" response header lists multiple encodings, and at least one of them
+    /// cannot be decoded
+    #[error(r#"The "Content-Encoding" response header ({encoding:?}) lists multiple encodings, and at least one of them cannot be decoded"#)]
+    UnsupportedContentEncoding {
+        /// The encoding declared by the "Content-Encoding" response header
+        encoding: String,
+    },
+
+    /// The "Content-Encoding" response header lists more encodings than the verifier decodes
+    #[error(r#"The "Content-Encoding" response header ({encoding:?}) lists more than the maximum of {max_content_encodings} encodings"#)]
+    TooManyContentEncodings {
+        /// The encoding declared by the "Content-Encoding" response header
+        encoding: String,
+        /// The maximum number of encodings that the verifier decodes
+        max_content_encodings: usize,
+    },
+
+    /// Decoding the response body produced more bytes than the verifier allows
+    #[error(r#"Decoding the response body produced more than the maximum of {max_decoded_body_size} bytes"#)]
+    DecodedBodyTooLarge {
+        /// The maximum number of bytes that decoding the body may produce, across all encodings
+        max_decoded_body_size: usize,
+    },
+
+    /// The response has more headers than the verifier allows
+    #[error(r#"The response has {header_count} headers, which exceeds the maximum of {max_header_count} headers"#)]
+    TooManyHeaders {
+        /// The number of headers in the response
+        header_count: usize,
+        /// The maximum number of headers allowed by the verifier
+        max_header_count: usize,
+    },
+
+    /// The total size of the response headers is larger than the verifier allows
+    #[error(r#"The response headers have a total size of {header_bytes} bytes, which exceeds the maximum of {max_header_bytes} bytes"#)]
+    HeadersTooLarge {
+        /// The total size of the response headers, in bytes, as counted by
+        /// [HttpResponse::total_header_bytes](ic_http_certification::HttpResponse::total_header_bytes)
+        header_bytes: usize,
+        /// The maximum total size of response headers allowed by the verifier, in bytes
+        max_header_bytes: usize,
+    },
+
+    /// The response declares a transfer coding other than "identity" in the
+    /// "Transfer-Encoding" response header
+    #[error(r#"The response declares an unsupported transfer coding in the "Transfer-Encoding" response header ({transfer_encoding:?}), the body must be decoded before verification"#)]
+    UnsupportedTransferEncoding {
+        /// The value of the "Transfer-Encoding" response header
+        transfer_encoding: String,
+    },
+
+    /// The expected response body, together with the status code and headers of the response,
+    /// is not covered by the certification
+    #[error("The expected response body is not certified")]
+    ExpectedBodyNotCertified,
+}
+
+impl ResponseVerificationError {
+    /// Returns `true` if the verification failure might be transient, so retrying the request,
+    /// possibly against a different replica, could succeed.
+    ///
+    /// The following errors are classified as retryable:
+    /// - [CertificateVerificationFailed](ResponseVerificationError::CertificateVerificationFailed)
+    ///   with [TimeTooFarInThePast](ic_certificate_verification::CertificateVerificationError::TimeTooFarInThePast)
+    ///   or [TimeTooFarInTheFuture](ic_certificate_verification::CertificateVerificationError::TimeTooFarInTheFuture),
+    ///   which are caused by a stale certificate or by clock skew between the client and the replica.
+    /// - [HeaderMissingCertification](ResponseVerificationError::HeaderMissingCertification),
+    ///   [HeaderMissingCertificate](ResponseVerificationError::HeaderMissingCertificate),
+    ///   [HeaderMissingTree](ResponseVerificationError::HeaderMissingTree),
+    ///   [HeaderMissingCertificateExpression](ResponseVerificationError::HeaderMissingCertificateExpression) and
+    ///   [HeaderMissingCertificateExpressionPath](ResponseVerificationError::HeaderMissingCertificateExpressionPath),
+    ///   which may be caused by an intermediary that stripped response headers.
+    ///
+    /// All other errors, including invalid signatures, hash mismatches, body mismatches and
+    /// malformed headers, are classified as fatal. Clients with a different retry policy can
+    /// match on the error variants directly instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerificationError;
+    ///
+    /// assert!(ResponseVerificationError::HeaderMissingCertification.is_retryable());
+    /// assert!(!ResponseVerificationError::InvalidResponseBody.is_retryable());
+    /// ```
+    pub fn is_retryable(&self) -> bool {
+        use ic_certificate_verification::CertificateVerificationError;
+
+        matches!(
+            self,
+            ResponseVerificationError::CertificateVerificationFailed(
+                CertificateVerificationError::TimeTooFarInThePast { .. }
+                    | CertificateVerificationError::TimeTooFarInTheFuture { .. }
+            ) | ResponseVerificationError::HeaderMissingCertification
+                | ResponseVerificationError::HeaderMissingCertificate
+                | ResponseVerificationError::HeaderMissingTree
+                | ResponseVerificationError::HeaderMissingCertificateExpression
+                | ResponseVerificationError::HeaderMissingCertificateExpressionPath
+        )
+    }
 }
 
 impl From<std::io::Error> for ResponseVerificationError {
@@ -285,6 +410,28 @@ pub enum ResponseVerificationJsErrorCode {
     CertificateVerificationFailed,
     /// HTTP Certification error
     HttpCertificationError,
+    /// The upgrade flag of the response did not match the expected value
+    UpgradeMismatch,
+    /// The response body could not be decoded using the encoding declared by the
+    /// "Content-Encoding" response header
+    ContentEncodingMismatch,
+    /// The "Content-Encoding" response header lists multiple encodings, and at least one of them
+    /// cannot be decoded
+    UnsupportedContentEncoding,
+    /// The "Content-Encoding" response header lists more encodings than the verifier decodes
+    TooManyContentEncodings,
+    /// Decoding the response body produced more bytes than the verifier allows
+    DecodedBodyTooLarge,
+    /// The response has more headers than the verifier allows
+    TooManyHeaders,
+    /// The total size of the response headers is larger than the verifier allows
+    HeadersTooLarge,
+    /// The response declares a transfer coding other than "identity" in the
+    /// "Transfer-Encoding" response header
+    UnsupportedTransferEncoding,
+    /// The expected response body, together with the status code and headers of the response,
+    /// is not covered by the certification
+    ExpectedBodyNotCertified,
 }
 
 /// JS Representation of the ResponseVerificationError
@@ -385,6 +532,33 @@ impl From<ResponseVerificationError> for ResponseVerificationJsError {
             ResponseVerificationError::HttpCertificationError(_) => {
                 ResponseVerificationJsErrorCode::HttpCertificationError
             }
+            ResponseVerificationError::UpgradeMismatch { .. } => {
+                ResponseVerificationJsErrorCode::UpgradeMismatch
+            }
+            ResponseVerificationError::ContentEncodingMismatch { .. } => {
+                ResponseVerificationJsErrorCode::ContentEncodingMismatch
+            }
+            ResponseVerificationError::UnsupportedContentEncoding { .. } => {
+                ResponseVerificationJsErrorCode::UnsupportedContentEncoding
+            }
+            ResponseVerificationError::TooManyContentEncodings { .. } => {
+                ResponseVerificationJsErrorCode::TooManyContentEncodings
+            }
+            ResponseVerificationError::DecodedBodyTooLarge { .. } => {
+                ResponseVerificationJsErrorCode::DecodedBodyTooLarge
+            }
+            ResponseVerificationError::TooManyHeaders { .. } => {
+                ResponseVerificationJsErrorCode::TooManyHeaders
+            }
+            ResponseVerificationError::HeadersTooLarge { .. } => {
+                ResponseVerificationJsErrorCode::HeadersTooLarge
+            }
+            ResponseVerificationError::UnsupportedTransferEncoding { .. } => {
+                ResponseVerificationJsErrorCode::UnsupportedTransferEncoding
+            }
+            ResponseVerificationError::ExpectedBodyNotCertified => {
+                ResponseVerificationJsErrorCode::ExpectedBodyNotCertified
+            }
         };
         let message = error.to_string();
 
@@ -736,6 +910,20 @@ mod tests {
         )
     }
 
+    #[wasm_bindgen_test]
+    fn error_into_expected_body_not_certified_error() {
+        let error = ResponseVerificationError::ExpectedBodyNotCertified;
+        let result = ResponseVerificationJsError::from(error);
+
+        assert_eq!(
+            result,
+            ResponseVerificationJsError {
+                code: ResponseVerificationJsErrorCode::ExpectedBodyNotCertified,
+                message: r#"The expected response body is not certified"#.into(),
+            }
+        )
+    }
+
     #[wasm_bindgen_test]
     fn error_into_invalid_missing_certificate_error() {
         let error = ResponseVerificationError::HeaderMissingCertificate;
@@ -1,4 +1,6 @@
 use crate::types::VerifiedResponse;
+use candid::Principal;
+use ic_certification::hash_tree::Hash;
 
 #[cfg(all(target_arch = "wasm32", feature = "js"))]
 use wasm_bindgen::prelude::*;
@@ -6,79 +8,417 @@ use wasm_bindgen::prelude::*;
 #[cfg(all(target_arch = "wasm32", feature = "js"))]
 #[wasm_bindgen(typescript_custom_section)]
 const VERIFICATION_RESULT: &'static str = r#"
+/**
+ * The outcome of successfully verifying a request and response pair. `certified` means that
+ * `response` was covered by the certification and can be trusted. `certifiedSkip` means that
+ * the canister skipped certification, so nothing about the response is certified and its
+ * payload must be treated as untrusted.
+ */
+type VerificationOutcome = 'certified' | 'certifiedSkip';
+
+/**
+ * The result of successfully verifying a request and response pair.
+ */
 type VerificationInfo = {
+  /**
+   * The status code, headers and body that were certified. This is missing when certification
+   * was skipped, in which case the response's payload must be treated as untrusted.
+   */
   response?: VerifiedResponse;
+  /**
+   * The version of verification that was used to verify the response.
+   */
   verificationVersion: number;
+  /**
+   * Whether the response's body was covered by the certification, meaning that `response` can
+   * be trusted.
+   */
+  isFullyCertified: boolean;
+  /**
+   * Whether the response was certified, or the canister skipped its certification.
+   */
+  outcome: VerificationOutcome;
+  /**
+   * The time of the certificate that the response was verified with, in nanoseconds since the
+   * UNIX epoch.
+   *
+   * This is a `bigint`, since the time doesn't fit in a `number`, and `JSON.stringify` throws a
+   * `TypeError` for `bigint` values. To serialize the result, for example to log it, convert the
+   * time to a string first:
+   *
+   * ```ts
+   * JSON.stringify(result, (_key, value) =>
+   *   typeof value === 'bigint' ? value.toString() : value,
+   * );
+   * ```
+   */
+  certificateTimeNs: bigint;
+  /**
+   * The ID of the subnet that signed the certificate, in the textual representation of its
+   * principal, such as `tdb26-jop6k-aogll-7ltgs-eruif-6kk7m-qpktf-gdiqx-mxtrf-vb5e6-eqe`. This
+   * is only present when the certificate was signed by a subnet with a delegation from the root
+   * key.
+   */
+  subnetId?: string;
+  /**
+   * The response headers that were not covered by the certification. This is only present when
+   * the uncertified headers report is enabled.
+   */
+  uncertifiedHeaders?: [string, string][];
+  /**
+   * The index of the root key that the certificate was verified with. This is only present when
+   * additional root keys are configured.
+   */
+  rootKeyIndex?: number;
+  /**
+   * The exact value of the `IC-CertificateExpression` header that the response was verified
+   * with. This is only present for version 2 verification.
+   */
+  certificateExpression?: string;
+  /**
+   * The SHA-256 hash of `certificateExpression`, as it is found in the certification tree. This
+   * is only present for version 2 verification.
+   */
+  certificateExpressionHash?: Uint8Array;
 }
 "#;
 
+/// The outcome of successfully verifying a request/response pair's certification, see
+/// [VerificationInfo::outcome].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationOutcome {
+    /// The response was certified, and the parts of it that were covered by the certification
+    /// are returned in the [response](VerificationInfo::response).
+    Certified,
+    /// The certificate is valid, but the canister skipped certification for the request, so
+    /// nothing about the response is certified. No [response](VerificationInfo::response) is
+    /// returned, and the status code, headers and body of the response must be treated as
+    /// untrusted.
+    CertifiedSkip,
+}
+
 /// Result of verifying the provided request/response pair's certification.
+///
+/// New fields may be added to this struct in future releases, so it can't be constructed
+/// outside of this crate, and patterns matching it must use `..`.
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct VerificationInfo {
     /// Response object including the status code, body and headers that were included in the
-    /// certification and passed verification. If verification failed then this object will be
-    /// empty.
+    /// certification and passed verification. This is `None` when the canister skipped
+    /// certification, in which case the response's payload is untrusted, see
+    /// [VerificationOutcome::CertifiedSkip].
     pub response: Option<VerifiedResponse>,
     /// The version of verification that was used to verify the response
     pub verification_version: u16,
+    /// The response headers that were present in the response, but not covered by the
+    /// certification, in the order they appear in the response. This is only populated when
+    /// enabled with
+    /// [with_uncertified_headers_report](crate::ResponseVerifier::with_uncertified_headers_report),
+    /// and is `None` otherwise.
+    pub uncertified_headers: Option<Vec<(String, String)>>,
+    /// The index of the root key that the certificate was verified with, where `0` is the key
+    /// passed to [ResponseVerifier::new](crate::ResponseVerifier::new) and `n` is the `n`th key
+    /// passed to
+    /// [with_additional_root_keys](crate::ResponseVerifier::with_additional_root_keys). This is
+    /// only populated when additional root keys are configured, and is `None` otherwise.
+    pub root_key_index: Option<usize>,
+    /// The time of the certificate that the response was verified with, in nanoseconds since
+    /// the UNIX epoch.
+    pub certificate_time_ns: u128,
+    /// The ID of the subnet that signed the certificate that the response was verified with.
+    /// This is taken from the certificate's delegation, and is `None` when the certificate was
+    /// signed with the root key directly, without a delegation.
+    pub subnet_id: Option<Principal>,
+    /// The exact value of the `IC-CertificateExpression` header that the response was verified
+    /// with, which can be logged or compared against the expected certification policy of the
+    /// canister. This is only populated for version 2 verification, and is `None` otherwise.
+    pub certificate_expression: Option<String>,
+    /// The SHA-256 hash of the [certificate_expression](VerificationInfo::certificate_expression),
+    /// as it is found in the certification tree. This is only populated for version 2
+    /// verification, and is `None` otherwise.
+    pub certificate_expression_hash: Option<Hash>,
+}
+
+impl VerificationInfo {
+    /// Returns `true` if the response's body was covered by the certification that passed
+    /// verification, meaning that the [response](VerificationInfo::response) can be trusted.
+    ///
+    /// What is covered depends on the verification version:
+    /// - Version 1 certifies the response body only, so this always returns `true`.
+    ///   The status code and headers are not certified and are not included in the
+    ///   [response](VerificationInfo::response).
+    /// - Version 2 certifies the response body, the status code and the headers selected by
+    ///   the certification's CEL expression when the response is certified with a
+    ///   `ResponseOnly` or `Full` certification, so this returns `true`. When
+    ///   certification is skipped, nothing about the response is certified, no
+    ///   [response](VerificationInfo::response) is returned, and this returns `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::types::VerificationInfo;
+    ///
+    /// fn trusted_body(verification_info: &VerificationInfo) -> Option<&[u8]> {
+    ///     if !verification_info.is_fully_certified() {
+    ///         return None;
+    ///     }
+    ///
+    ///     verification_info
+    ///         .response
+    ///         .as_ref()
+    ///         .map(|response| response.body.as_slice())
+    /// }
+    /// ```
+    pub fn is_fully_certified(&self) -> bool {
+        self.outcome() == VerificationOutcome::Certified
+    }
+
+    /// Returns whether the response was [certified](VerificationOutcome::Certified), or the
+    /// canister [skipped certification](VerificationOutcome::CertifiedSkip) for the request.
+    ///
+    /// A skipped certification passes verification, since the canister certified that the
+    /// request path is not certified, but it does not certify anything about the response.
+    /// Callers that log or report verification results should report
+    /// [CertifiedSkip](VerificationOutcome::CertifiedSkip) separately from a verified response.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::types::{VerificationInfo, VerificationOutcome};
+    ///
+    /// fn log_outcome(verification_info: &VerificationInfo) {
+    ///     match verification_info.outcome() {
+    ///         VerificationOutcome::Certified => println!("verified response"),
+    ///         VerificationOutcome::CertifiedSkip => println!("uncertified response"),
+    ///     }
+    /// }
+    /// ```
+    pub fn outcome(&self) -> VerificationOutcome {
+        match (self.verification_version, &self.response) {
+            (1, _) | (_, Some(_)) => VerificationOutcome::Certified,
+            (_, None) => VerificationOutcome::CertifiedSkip,
+        }
+    }
 }
 
 #[cfg(all(target_arch = "wasm32", feature = "js"))]
 impl From<VerificationInfo> for JsValue {
     fn from(verification_result: VerificationInfo) -> Self {
-        use js_sys::{Array, Number, Object};
+        use js_sys::{Array, BigInt, Boolean, Number, Object, Uint8Array};
+
+        let entries = Array::new();
+
+        let is_fully_certified = Boolean::from(verification_result.is_fully_certified());
+        let is_fully_certified_entry =
+            Array::of2(&JsValue::from("isFullyCertified"), &is_fully_certified);
+
+        let outcome = JsValue::from(match verification_result.outcome() {
+            VerificationOutcome::Certified => "certified",
+            VerificationOutcome::CertifiedSkip => "certifiedSkip",
+        });
+        let outcome_entry = Array::of2(&JsValue::from("outcome"), &outcome);
+
+        let certificate_time_ns = BigInt::from(verification_result.certificate_time_ns);
+        let certificate_time_ns_entry =
+            Array::of2(&JsValue::from("certificateTimeNs"), &certificate_time_ns);
 
         let verification_version = Number::from(verification_result.verification_version);
         let verification_version_entry =
             Array::of2(&JsValue::from("verificationVersion"), &verification_version);
 
         let response = JsValue::from(verification_result.response);
-        let response_entry = Array::of2(&JsValue::from("response"), &response.into());
+        let response_entry = Array::of2(&JsValue::from("response"), &response);
 
-        let result =
-            Object::from_entries(&Array::of2(&response_entry, &verification_version_entry))
-                .unwrap();
+        entries.push(&response_entry);
+        entries.push(&verification_version_entry);
+        entries.push(&is_fully_certified_entry);
+        entries.push(&outcome_entry);
+        entries.push(&certificate_time_ns_entry);
 
-        JsValue::from(result)
+        if let Some(uncertified_headers) = verification_result.uncertified_headers {
+            let uncertified_headers = uncertified_headers
+                .into_iter()
+                .map(|(name, value)| Array::of2(&JsValue::from(name), &JsValue::from(value)))
+                .collect::<Array>();
+            entries.push(&Array::of2(
+                &JsValue::from("uncertifiedHeaders"),
+                &uncertified_headers,
+            ));
+        }
+
+        if let Some(subnet_id) = verification_result.subnet_id {
+            let subnet_id = JsValue::from(subnet_id.to_text());
+            entries.push(&Array::of2(&JsValue::from("subnetId"), &subnet_id));
+        }
+
+        if let Some(root_key_index) = verification_result.root_key_index {
+            let root_key_index = Number::from(root_key_index as u32);
+            entries.push(&Array::of2(&JsValue::from("rootKeyIndex"), &root_key_index));
+        }
+
+        if let Some(certificate_expression) = verification_result.certificate_expression {
+            entries.push(&Array::of2(
+                &JsValue::from("certificateExpression"),
+                &JsValue::from(certificate_expression),
+            ));
+        }
+
+        if let Some(certificate_expression_hash) = verification_result.certificate_expression_hash {
+            let certificate_expression_hash =
+                Uint8Array::from(certificate_expression_hash.as_slice());
+            entries.push(&Array::of2(
+                &JsValue::from("certificateExpressionHash"),
+                &certificate_expression_hash,
+            ));
+        }
+
+        JsValue::from(Object::from_entries(&entries).unwrap())
     }
 }
 
 #[cfg(all(target_arch = "wasm32", feature = "js", test))]
 mod tests {
     use super::*;
-    use js_sys::JSON;
+    use js_sys::{BigInt, Object, Reflect, Uint8Array, JSON};
     use wasm_bindgen::JsValue;
     use wasm_bindgen_test::wasm_bindgen_test;
 
+    const CERTIFICATE_TIME_NS: u128 = 1_700_000_000_000_000_000;
+
+    fn get(value: &JsValue, key: &str) -> JsValue {
+        Reflect::get(value, &JsValue::from(key)).unwrap()
+    }
+
+    fn keys(value: &JsValue) -> Vec<String> {
+        Object::keys(value.unchecked_ref())
+            .iter()
+            .map(|key| key.as_string().unwrap())
+            .collect()
+    }
+
     #[wasm_bindgen_test]
     fn serialize_verification_result_with_no_response() {
-        let expected = r#"{"verificationVersion":1}"#;
+        let result = JsValue::from(VerificationInfo {
+            response: None,
+            verification_version: 2,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_time_ns: CERTIFICATE_TIME_NS,
+            subnet_id: None,
+            certificate_expression: None,
+            certificate_expression_hash: None,
+        });
 
         assert_eq!(
-            JSON::stringify(&JsValue::from(VerificationInfo {
-                response: None,
-                verification_version: 1,
-            }))
-            .unwrap(),
-            expected
+            keys(&result),
+            [
+                "response",
+                "verificationVersion",
+                "isFullyCertified",
+                "outcome",
+                "certificateTimeNs"
+            ]
+        );
+        assert!(get(&result, "response").is_undefined());
+        assert_eq!(get(&result, "verificationVersion").as_f64(), Some(2.0));
+        assert_eq!(get(&result, "isFullyCertified").as_bool(), Some(false));
+        assert_eq!(
+            get(&result, "outcome").as_string().as_deref(),
+            Some("certifiedSkip")
+        );
+        assert_eq!(
+            get(&result, "certificateTimeNs"),
+            JsValue::from(BigInt::from(CERTIFICATE_TIME_NS))
         );
     }
 
     #[wasm_bindgen_test]
     fn serialize_verification_result_with_response() {
-        let expected = r#"{"response":{"statusCode":200,"body":{"0":0,"1":1,"2":2},"headers":[]},"verificationVersion":2}"#;
+        let expected_response = r#"{"statusCode":200,"body":{"0":0,"1":1,"2":2},"headers":[]}"#;
+
+        let result = JsValue::from(VerificationInfo {
+            response: Some(VerifiedResponse {
+                status_code: Some(200),
+                body: vec![0, 1, 2],
+                headers: vec![],
+            }),
+            verification_version: 2,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_time_ns: CERTIFICATE_TIME_NS,
+            subnet_id: None,
+            certificate_expression: None,
+            certificate_expression_hash: None,
+        });
+
+        assert_eq!(
+            JSON::stringify(&get(&result, "response")).unwrap(),
+            expected_response
+        );
+        assert_eq!(get(&result, "verificationVersion").as_f64(), Some(2.0));
+        assert_eq!(get(&result, "isFullyCertified").as_bool(), Some(true));
+        assert_eq!(
+            get(&result, "outcome").as_string().as_deref(),
+            Some("certified")
+        );
+        assert_eq!(
+            get(&result, "certificateTimeNs"),
+            JsValue::from(BigInt::from(CERTIFICATE_TIME_NS))
+        );
+        assert!(get(&result, "uncertifiedHeaders").is_undefined());
+        assert!(get(&result, "rootKeyIndex").is_undefined());
+        assert!(get(&result, "subnetId").is_undefined());
+        assert!(get(&result, "certificateExpression").is_undefined());
+        assert!(get(&result, "certificateExpressionHash").is_undefined());
+    }
+
+    #[wasm_bindgen_test]
+    fn serialize_verification_result_with_certificate_expression() {
+        let certificate_expression =
+            "default_certification(ValidationArgs{no_certification:Empty{}})";
+
+        let result = JsValue::from(VerificationInfo {
+            response: None,
+            verification_version: 2,
+            uncertified_headers: None,
+            root_key_index: None,
+            certificate_time_ns: CERTIFICATE_TIME_NS,
+            subnet_id: None,
+            certificate_expression: Some(certificate_expression.to_string()),
+            certificate_expression_hash: Some([1; 32]),
+        });
+
+        assert_eq!(
+            get(&result, "certificateExpression").as_string().as_deref(),
+            Some(certificate_expression)
+        );
+        assert_eq!(
+            Uint8Array::new(&get(&result, "certificateExpressionHash")).to_vec(),
+            vec![1; 32]
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn serialize_verification_result_with_reports() {
+        let expected_uncertified_headers = r#"[["x-uncertified","value"]]"#;
+
+        let result = JsValue::from(VerificationInfo {
+            response: None,
+            verification_version: 1,
+            uncertified_headers: Some(vec![("x-uncertified".into(), "value".into())]),
+            root_key_index: Some(1),
+            certificate_time_ns: CERTIFICATE_TIME_NS,
+            subnet_id: None,
+            certificate_expression: None,
+            certificate_expression_hash: None,
+        });
 
+        assert_eq!(get(&result, "isFullyCertified").as_bool(), Some(true));
         assert_eq!(
-            JSON::stringify(&JsValue::from(VerificationInfo {
-                response: Some(VerifiedResponse {
-                    status_code: Some(200),
-                    body: vec![0, 1, 2],
-                    headers: vec![],
-                }),
-                verification_version: 2,
-            }))
-            .unwrap(),
-            expected
+            JSON::stringify(&get(&result, "uncertifiedHeaders")).unwrap(),
+            expected_uncertified_headers
         );
+        assert_eq!(get(&result, "rootKeyIndex").as_f64(), Some(1.0));
     }
 }
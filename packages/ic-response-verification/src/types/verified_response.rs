@@ -1,3 +1,7 @@
+use crate::ResponseVerificationResult;
+use ic_http_certification::{
+    HttpCertificationError, HttpResponse, StatusCode, CERTIFICATE_HEADER_NAME,
+};
 #[cfg(all(target_arch = "wasm32", feature = "js"))]
 use wasm_bindgen::prelude::*;
 
@@ -17,11 +21,83 @@ pub struct VerifiedResponse {
     /// The HTTP status code of the response, i.e. 200.
     pub status_code: Option<u16>,
     /// The HTTP headers of the request, i.e. \[\["Ic-Certificate", "certificate=:2dn3o2R0cmVlgw=:, tree=:2dn3gwGDA:"\]\]
+    ///
+    /// By default, the names of certified headers are lowercased and the `IC-Certificate`
+    /// header is appended last, using the casing of
+    /// [CERTIFICATE_HEADER_NAME](ic_http_certification::CERTIFICATE_HEADER_NAME). The original
+    /// casing can be preserved with
+    /// [with_preserved_header_casing](crate::ResponseVerifier::with_preserved_header_casing).
     pub headers: Vec<(String, String)>,
     /// The body of the request as a candid decoded blob, i.e.  \[60, 33, 100, 111, 99\]
     pub body: Vec<u8>,
 }
 
+impl VerifiedResponse {
+    /// Converts this verified response into an [HttpResponse] that includes the
+    /// provided `IC-Certificate` header, allowing the response to be re-emitted
+    /// with its certification intact, e.g. by a caching proxy.
+    ///
+    /// Any `IC-Certificate` header that is already present in the verified
+    /// response's headers is replaced with the provided `certificate_header`.
+    ///
+    /// Response verification v1 does not certify the status code of a
+    /// response, so if the [status_code](VerifiedResponse::status_code) is not
+    /// present, the response will be created with a `200 OK` status code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{StatusCode, CERTIFICATE_HEADER_NAME};
+    /// use ic_response_verification::types::VerifiedResponse;
+    ///
+    /// let verified_response = VerifiedResponse {
+    ///     status_code: Some(200),
+    ///     headers: vec![("Content-Type".to_string(), "text/plain".to_string())],
+    ///     body: b"Hello, World!".to_vec(),
+    /// };
+    ///
+    /// let response = verified_response
+    ///     .into_http_response("certificate=:AQID:, tree=:AQID:")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(response.status_code(), StatusCode::OK);
+    /// assert_eq!(
+    ///     response.headers(),
+    ///     &[
+    ///         ("Content-Type".to_string(), "text/plain".to_string()),
+    ///         (CERTIFICATE_HEADER_NAME.to_string(), "certificate=:AQID:, tree=:AQID:".to_string()),
+    ///     ]
+    /// );
+    /// assert_eq!(response.body(), b"Hello, World!");
+    /// ```
+    pub fn into_http_response(
+        self,
+        certificate_header: &str,
+    ) -> ResponseVerificationResult<HttpResponse<'static>> {
+        let status_code = match self.status_code {
+            Some(status_code) => StatusCode::from_u16(status_code)
+                .map_err(|_| HttpCertificationError::InvalidHttpStatusCode { status_code })?,
+            None => StatusCode::OK,
+        };
+
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .into_iter()
+            .filter(|(name, _)| !name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME))
+            .collect();
+        headers.push((
+            CERTIFICATE_HEADER_NAME.to_string(),
+            certificate_header.to_string(),
+        ));
+
+        Ok(HttpResponse::builder()
+            .with_status_code(status_code)
+            .with_headers(headers)
+            .with_body(self.body)
+            .build())
+    }
+}
+
 #[cfg(all(target_arch = "wasm32", feature = "js"))]
 impl From<VerifiedResponse> for JsValue {
     fn from(response: VerifiedResponse) -> Self {
@@ -3,8 +3,8 @@ use ic_certification::hash_tree::HashTreeNode;
 use ic_certification::{hash_tree::Hash, HashTree, Label, SubtreeLookupResult};
 use ic_http_certification::cel::DefaultCelExpression;
 use ic_http_certification::utils::{
-    is_wildcard_path_valid_for_request_path, more_specific_wildcards_for,
-    EXACT_PATH_TERMINATOR_BYTES, PATH_PREFIX_BYTES,
+    exact_expr_path, fallback_expr_paths, is_wildcard_path_valid_for_request_path,
+    more_specific_wildcards_for, EXACT_PATH_TERMINATOR_BYTES, PATH_PREFIX_BYTES,
 };
 use ic_http_certification::CelExpression;
 
@@ -48,21 +48,10 @@ pub fn validate_expr_path(
         });
     }
 
-    let mut request_url_parts = vec!["http_expr"];
-    request_url_parts.extend(request_path.split('/').filter(|e| !e.is_empty()));
-
-    // make sure to treat a request for a directory and a file as different paths
-    // i.e. /app is not the same as /app/
-    // we do this by inserting an empty space for directory paths
-    if request_path.ends_with('/') {
-        request_url_parts.push("");
-    }
-
     let original_path = path_from_parts(expr_path);
-    let mut request_url_path = path_from_parts(&request_url_parts);
+    let mut request_url_path = path_from_parts(&exact_expr_path(request_path));
 
     // if the expr_path matches the full URL, there can't be a more precise path in the tree
-    request_url_path.push("<$>".into());
     if original_path.eq(&request_url_path) {
         return if path_exists_in_tree(&original_path, tree) {
             Ok(())
@@ -70,6 +59,7 @@ pub fn validate_expr_path(
             Err(
                 ResponseVerificationError::ExactExpressionPathNotFoundInTree {
                     provided_expr_path: expr_path.to_vec(),
+                    request_path: request_path.to_string(),
                 },
             )
         };
@@ -79,11 +69,9 @@ pub fn validate_expr_path(
     // so validation fails if the certified_path ends with an exact path suffix,
     if original_path.ends_with(&[EXACT_PATH_TERMINATOR_BYTES.to_vec()]) {
         return Err(ResponseVerificationError::ExactExpressionPathMismatch {
-            request_path: request_url_path
-                .iter()
-                .map(|e| String::from_utf8_lossy(e))
-                .collect(),
             provided_expr_path: expr_path.to_vec(),
+            expected_expr_path: exact_expr_path(request_path),
+            request_path: request_path.to_string(),
         });
     }
 
@@ -107,10 +95,8 @@ pub fn validate_expr_path(
 
     if !is_wildcard_path_valid_for_request_path(&potential_path, &request_url_path) {
         return Err(ResponseVerificationError::WildcardExpressionPathMismatch {
-            provided_expr_path: potential_path
-                .iter()
-                .map(|e| String::from_utf8_lossy(e).to_string())
-                .collect(),
+            provided_expr_path: expr_path.to_vec(),
+            expected_expr_paths: fallback_expr_paths(request_path),
             request_path: request_path.to_string(),
         });
     }
@@ -163,6 +149,16 @@ pub fn validate_expr_hash(
     }
 }
 
+/// Validates that the request and response hashes are certified in the tree under the
+/// given expression path and expression hash.
+///
+/// The expression hash is validated first with [validate_expr_hash], and the request and
+/// response hashes are only looked up within the subtree of that expression hash. A
+/// response whose `IC-CertificateExpression` header was swapped for a different
+/// expression therefore fails with
+/// [InvalidExpressionHash](ResponseVerificationError::InvalidExpressionHash), even if the
+/// request and response hashes under the swapped expression happen to be certified for
+/// another expression.
 pub fn validate_hashes(
     expr_hash: &Hash,
     request_hash: &Option<Hash>,
@@ -173,15 +169,10 @@ pub fn validate_hashes(
 ) -> ResponseVerificationResult {
     let expr_tree = validate_expr_hash(expr_path, expr_hash, tree)?;
 
-    let mut expr_tree_path: Vec<Label> = vec![];
-    if let (CelExpression::Default(DefaultCelExpression::Full(_)), Some(request_hash)) =
-        (&certification, request_hash)
-    {
-        expr_tree_path.push(request_hash.into());
-    } else {
-        expr_tree_path.push("".into());
-    }
-    expr_tree_path.push(response_hash.into());
+    let expr_tree_path: Vec<Label> = vec![
+        request_hash_label(request_hash, certification),
+        response_hash.into(),
+    ];
 
     match expr_tree.lookup_subtree(&expr_tree_path) {
         SubtreeLookupResult::Found(res_tree) => {
@@ -199,6 +190,38 @@ pub fn validate_hashes(
     }
 }
 
+/// Returns `true` if the request hash is certified in the tree under the given expression
+/// path and expression hash, for any response.
+///
+/// This tells a request that was not certified apart from a response that was not certified,
+/// when [validate_hashes] fails with
+/// [InvalidRequestAndResponseHashes](ResponseVerificationError::InvalidRequestAndResponseHashes).
+pub fn is_request_hash_certified(
+    expr_hash: &Hash,
+    request_hash: &Option<Hash>,
+    expr_path: &[String],
+    tree: &HashTree,
+    certification: &CelExpression,
+) -> bool {
+    let Ok(expr_tree) = validate_expr_hash(expr_path, expr_hash, tree) else {
+        return false;
+    };
+
+    matches!(
+        expr_tree.lookup_subtree(&[request_hash_label(request_hash, certification)]),
+        SubtreeLookupResult::Found(_)
+    )
+}
+
+fn request_hash_label(request_hash: &Option<Hash>, certification: &CelExpression) -> Label {
+    match (certification, request_hash) {
+        (CelExpression::Default(DefaultCelExpression::Full(_)), Some(request_hash)) => {
+            request_hash.into()
+        }
+        _ => "".into(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -808,7 +831,10 @@ mod tests {
 
         assert!(matches!(
             result,
-            ResponseVerificationError::ExactExpressionPathNotFoundInTree { .. }
+            ResponseVerificationError::ExactExpressionPathNotFoundInTree {
+                provided_expr_path,
+                request_path,
+            } if provided_expr_path == expr_path && request_path == "/assets/js/app.js"
         ));
     }
 
@@ -832,9 +858,19 @@ mod tests {
         let result = validate_expr_path(&expr_path, request_uri.path(), &tree).unwrap_err();
 
         assert!(matches!(
-            result,
-            ResponseVerificationError::ExactExpressionPathMismatch { .. }
+            &result,
+            ResponseVerificationError::ExactExpressionPathMismatch {
+                provided_expr_path,
+                expected_expr_path,
+                request_path,
+            } if provided_expr_path == &expr_path
+                && expected_expr_path == &vec!["http_expr", "assets", "js", "app.js", "<$>"]
+                && request_path == "/assets/js/app.js"
         ));
+        assert_eq!(
+            result.to_string(),
+            r#"The exact expression path provided by the "IC-Certificate" response header (["http_expr", "assets", "js", "<$>"]) is not valid for the request path ("/assets/js/app.js"), the expected exact expression path is ["http_expr", "assets", "js", "app.js", "<$>"]"#
+        );
     }
 
     #[test]
@@ -864,7 +900,20 @@ mod tests {
 
         assert!(matches!(
             result,
-            ResponseVerificationError::WildcardExpressionPathMismatch { .. }
+            ResponseVerificationError::WildcardExpressionPathMismatch {
+                provided_expr_path,
+                expected_expr_paths,
+                request_path,
+            } if provided_expr_path == expr_path
+                && expected_expr_paths == vec![
+                    vec!["http_expr", "assets", "js", "app.js", "<*>"],
+                    vec!["http_expr", "assets", "js", "", "<*>"],
+                    vec!["http_expr", "assets", "js", "<*>"],
+                    vec!["http_expr", "assets", "", "<*>"],
+                    vec!["http_expr", "assets", "<*>"],
+                    vec!["http_expr", "", "<*>"],
+                ]
+                && request_path == "/assets/js/app.js"
         ));
     }
 
@@ -1,18 +1,113 @@
-use crate::error::ResponseVerificationResult;
-use flate2::read::{DeflateDecoder, GzDecoder};
+use crate::error::{ResponseVerificationError, ResponseVerificationResult};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
 use std::io::Read;
 
 const MAX_CHUNK_SIZE_TO_DECOMPRESS: usize = 1_024;
 
+const MAX_CONTENT_CODINGS: usize = 3;
+
+const MAX_DECODED_BODY_SIZE: usize = 32 * 1_024 * 1_024;
+
+/// Decodes the body according to its `Content-Encoding`.
+///
+/// The header can list multiple encodings, separated by commas, in the order that they were
+/// applied to the body. They are decoded in reverse order, so a body with the
+/// `Content-Encoding: gzip, deflate` header is decoded as `deflate` data first, and the result
+/// is decoded as `gzip` data. If any stage fails to decode, an error is returned.
+///
+/// If a single encoding is listed that cannot be decoded, such as `br`, the body is returned
+/// unchanged. If multiple encodings are listed and any of them cannot be decoded, such as
+/// `gzip, br` or `br, gzip`, the body cannot be partially decoded and
+/// [UnsupportedContentEncoding](ResponseVerificationError::UnsupportedContentEncoding) is
+/// returned, which the verifier only treats as a failure in
+/// [strict mode](crate::ResponseVerifier::with_strict_content_encoding).
+///
+/// At most 3 encodings are decoded, and decoding may produce at most 32 MiB across all stages,
+/// so that small, highly compressed bodies can't exhaust the verifier's memory. Otherwise,
+/// [TooManyContentEncodings](ResponseVerificationError::TooManyContentEncodings) or
+/// [DecodedBodyTooLarge](ResponseVerificationError::DecodedBodyTooLarge) is returned.
+///
+/// The `deflate` encoding is defined by
+/// [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#name-deflate-coding) as zlib-wrapped
+/// DEFLATE data, but some encoders produce raw DEFLATE data instead. The body is decoded as
+/// zlib-wrapped data first, and as raw DEFLATE data if that fails.
 pub fn decode_body(body: &[u8], encoding: Option<&str>) -> ResponseVerificationResult<Vec<u8>> {
+    let Some(encoding) = encoding else {
+        return Ok(body.to_owned());
+    };
+
+    let content_codings_count = content_codings(encoding).count();
+    if content_codings_count > MAX_CONTENT_CODINGS {
+        return Err(ResponseVerificationError::TooManyContentEncodings {
+            encoding: encoding.to_string(),
+            max_content_encodings: MAX_CONTENT_CODINGS,
+        });
+    }
+
+    if !can_decode_body(Some(encoding)) {
+        if content_codings_count > 1 {
+            return Err(ResponseVerificationError::UnsupportedContentEncoding {
+                encoding: encoding.to_string(),
+            });
+        }
+
+        return Ok(body.to_owned());
+    }
+
+    let mut decoded_body_size = 0;
+    content_codings(encoding)
+        .rev()
+        .try_fold(body.to_owned(), |body, coding| {
+            decode_content_coding(&body, coding, &mut decoded_body_size)
+        })
+}
+
+/// Returns `true` if every encoding listed in the `Content-Encoding` header can be decoded by
+/// [decode_body].
+pub fn can_decode_body(encoding: Option<&str>) -> bool {
     match encoding {
-        Some("gzip") => body_from_decoder(GzDecoder::new(body)),
-        Some("deflate") => body_from_decoder(DeflateDecoder::new(body)),
+        None => true,
+        Some(encoding) => {
+            content_codings(encoding).next().is_some()
+                && content_codings(encoding)
+                    .all(|coding| matches!(coding, "identity" | "gzip" | "deflate"))
+        }
+    }
+}
+
+fn content_codings(encoding: &str) -> impl DoubleEndedIterator<Item = &str> {
+    encoding
+        .split(',')
+        .map(str::trim)
+        .filter(|coding| !coding.is_empty())
+}
+
+fn decode_content_coding(
+    body: &[u8],
+    coding: &str,
+    decoded_body_size: &mut usize,
+) -> ResponseVerificationResult<Vec<u8>> {
+    match coding {
+        "gzip" => body_from_decoder(GzDecoder::new(body), decoded_body_size),
+        "deflate" => {
+            let zlib_decoded_body_size = *decoded_body_size;
+            body_from_decoder(ZlibDecoder::new(body), decoded_body_size).or_else(|err| {
+                if matches!(err, ResponseVerificationError::DecodedBodyTooLarge { .. }) {
+                    return Err(err);
+                }
+
+                *decoded_body_size = zlib_decoded_body_size;
+                body_from_decoder(DeflateDecoder::new(body), decoded_body_size)
+            })
+        }
         _ => Ok(body.to_owned()),
     }
 }
 
-fn body_from_decoder<D: Read>(mut decoder: D) -> ResponseVerificationResult<Vec<u8>> {
+fn body_from_decoder<D: Read>(
+    mut decoder: D,
+    decoded_body_size: &mut usize,
+) -> ResponseVerificationResult<Vec<u8>> {
     let mut decoded = Vec::new();
     let mut buffer = [0u8; MAX_CHUNK_SIZE_TO_DECOMPRESS];
 
@@ -23,6 +118,13 @@ fn body_from_decoder<D: Read>(mut decoder: D) -> ResponseVerificationResult<Vec<
             return Ok(decoded);
         }
 
+        *decoded_body_size += bytes;
+        if *decoded_body_size > MAX_DECODED_BODY_SIZE {
+            return Err(ResponseVerificationError::DecodedBodyTooLarge {
+                max_decoded_body_size: MAX_DECODED_BODY_SIZE,
+            });
+        }
+
         decoded.extend_from_slice(&buffer[..bytes]);
     }
 }
@@ -30,7 +132,8 @@ fn body_from_decoder<D: Read>(mut decoder: D) -> ResponseVerificationResult<Vec<
 #[cfg(test)]
 mod tests {
     use super::*;
-    use flate2::write::{DeflateEncoder, GzEncoder};
+    use assert_matches::assert_matches;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
     use flate2::Compression;
     use std::io::Write;
 
@@ -54,6 +157,24 @@ mod tests {
         assert_eq!(result.as_slice(), BODY);
     }
 
+    #[test]
+    fn decode_zlib_deflate_body() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(BODY).unwrap();
+        let encoded_body = encoder.finish().unwrap();
+
+        let result = decode_body(&encoded_body, Some("deflate")).unwrap();
+
+        assert_eq!(result.as_slice(), BODY);
+    }
+
+    #[test]
+    fn decode_invalid_deflate_body() {
+        let result = decode_body(b"not deflate encoded", Some("deflate"));
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn decode_deflate_body() {
         let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
@@ -64,4 +185,122 @@ mod tests {
 
         assert_eq!(result.as_slice(), BODY);
     }
+
+    #[test]
+    fn decode_multiple_encodings_in_reverse_order() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(BODY).unwrap();
+        let deflate_body = encoder.finish().unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&deflate_body).unwrap();
+        let deflate_gzip_body = encoder.finish().unwrap();
+
+        let result = decode_body(&deflate_gzip_body, Some("deflate, gzip")).unwrap();
+        assert_eq!(result.as_slice(), BODY);
+
+        let result = decode_body(&deflate_gzip_body, Some(" deflate ,identity,gzip ")).unwrap();
+        assert_eq!(result.as_slice(), BODY);
+
+        let result = decode_body(&deflate_gzip_body, Some("gzip, deflate"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn decode_multiple_encodings_with_unsupported_encoding() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(BODY).unwrap();
+        let encoded_body = encoder.finish().unwrap();
+
+        let result = decode_body(&encoded_body, Some("br")).unwrap();
+        assert_eq!(result, encoded_body);
+
+        for encoding in ["gzip, br", "br, gzip"] {
+            let result = decode_body(&encoded_body, Some(encoding));
+            assert_matches!(
+                result,
+                Err(ResponseVerificationError::UnsupportedContentEncoding { encoding: result_encoding })
+                    if result_encoding == encoding
+            );
+        }
+    }
+
+    #[test]
+    fn decode_too_many_encodings() {
+        let mut encoded_body = BODY.to_vec();
+        for _ in 0..MAX_CONTENT_CODINGS {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&encoded_body).unwrap();
+            encoded_body = encoder.finish().unwrap();
+        }
+
+        let result = decode_body(&encoded_body, Some("gzip, gzip, gzip")).unwrap();
+        assert_eq!(result.as_slice(), BODY);
+
+        let result = decode_body(&encoded_body, Some("identity, gzip, gzip, gzip"));
+        assert_matches!(
+            result,
+            Err(ResponseVerificationError::TooManyContentEncodings {
+                max_content_encodings: MAX_CONTENT_CODINGS,
+                ..
+            })
+        );
+    }
+
+    #[test]
+    fn decode_body_too_large() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&vec![0; MAX_DECODED_BODY_SIZE + 1])
+            .unwrap();
+        let encoded_body = encoder.finish().unwrap();
+
+        for encoding in ["gzip", "identity, gzip"] {
+            let result = decode_body(&encoded_body, Some(encoding));
+            assert_matches!(
+                result,
+                Err(ResponseVerificationError::DecodedBodyTooLarge {
+                    max_decoded_body_size: MAX_DECODED_BODY_SIZE,
+                })
+            );
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder
+            .write_all(&vec![0; MAX_DECODED_BODY_SIZE + 1])
+            .unwrap();
+        let encoded_body = encoder.finish().unwrap();
+
+        let result = decode_body(&encoded_body, Some("deflate"));
+        assert_matches!(
+            result,
+            Err(ResponseVerificationError::DecodedBodyTooLarge { .. })
+        );
+    }
+
+    #[test]
+    fn decode_invalid_multiple_encodings() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(BODY).unwrap();
+        let encoded_body = encoder.finish().unwrap();
+
+        let result = decode_body(&encoded_body, Some("gzip, gzip"));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn can_decode_supported_encodings() {
+        assert!(can_decode_body(None));
+        assert!(can_decode_body(Some("identity")));
+        assert!(can_decode_body(Some("gzip")));
+        assert!(can_decode_body(Some("deflate")));
+        assert!(!can_decode_body(Some("br")));
+        assert!(!can_decode_body(Some("zstd")));
+        assert!(can_decode_body(Some("gzip, deflate")));
+        assert!(can_decode_body(Some("deflate,gzip")));
+        assert!(!can_decode_body(Some("gzip, br")));
+        assert!(!can_decode_body(Some("br, gzip")));
+        assert!(!can_decode_body(Some("")));
+        assert!(!can_decode_body(Some(" , ")));
+    }
 }
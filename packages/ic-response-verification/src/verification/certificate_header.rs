@@ -26,6 +26,23 @@ pub struct CertificateHeader {
 }
 
 impl CertificateHeader {
+    /// Parses the values of multiple `IC-Certificate` header instances and returns a new
+    /// CertificateHeader.
+    ///
+    /// When a response includes more than one `IC-Certificate` header, the values are
+    /// concatenated in the order that they appear, separated by a comma, and the result is parsed
+    /// as a single header with [from](CertificateHeader::from). This is the same as combining
+    /// repeated HTTP fields into a single comma-separated field value, so each header instance
+    /// must contain complete fields, such as `certificate=:...:` or `tree=:...:`. If the same
+    /// field appears in more than one instance, the first occurrence is used.
+    pub fn from_header_values<'a>(
+        header_values: impl IntoIterator<Item = &'a str>,
+    ) -> ResponseVerificationResult<CertificateHeader> {
+        let header_value = header_values.into_iter().collect::<Vec<_>>().join(",");
+
+        Self::from(&header_value)
+    }
+
     /// Parses the given header and returns a new CertificateHeader.
     pub fn from(header_value: &str) -> ResponseVerificationResult<CertificateHeader> {
         let mut certificate = None;
@@ -406,4 +423,31 @@ mod tests {
         assert_eq!(certificate_header.version, version);
         assert_eq!(certificate_header.expr_path.unwrap(), expr_path);
     }
+
+    #[test]
+    fn certificate_header_parses_split_header() {
+        let certificate = create_certificate(None);
+        let tree = create_tree(None);
+        let version = 2u8;
+        let expr_path = vec!["/", "assets", "img.jpg"];
+        let certificate_field =
+            create_encoded_header_field("certificate", cbor_encode(&certificate));
+        let remaining_fields = [
+            create_encoded_header_field("tree", cbor_encode(&tree)),
+            create_header_field("version", &version.to_string()),
+            create_encoded_header_field("expr_path", cbor_encode(&expr_path)),
+        ]
+        .join(",");
+
+        let certificate_header = CertificateHeader::from_header_values([
+            certificate_field.as_str(),
+            remaining_fields.as_str(),
+        ])
+        .unwrap();
+
+        assert_eq!(certificate_header.certificate, certificate);
+        assert_eq!(certificate_header.tree, tree);
+        assert_eq!(certificate_header.version, version);
+        assert_eq!(certificate_header.expr_path.unwrap(), expr_path);
+    }
 }
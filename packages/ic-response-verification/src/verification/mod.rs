@@ -8,3 +8,8 @@ pub use certificate_header::*;
 
 mod verify_request_response_pair;
 pub use verify_request_response_pair::*;
+
+#[cfg(feature = "serde")]
+mod verification_context;
+#[cfg(feature = "serde")]
+pub use verification_context::*;
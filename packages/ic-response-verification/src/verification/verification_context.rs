@@ -0,0 +1,600 @@
+use crate::{
+    error::{ResponseVerificationError, ResponseVerificationResult},
+    types::VerificationInfo,
+    ResponseVerifier, DEFAULT_MAX_HEADER_BYTES, DEFAULT_MAX_HEADER_COUNT,
+};
+use ic_http_certification::{
+    HttpCertificationError, HttpRequest, HttpResponse, Method, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+/// The inputs of a request and response pair verification, captured so that a failed
+/// verification can be replayed offline, see
+/// [verify_with_context](ResponseVerifier::verify_with_context) and
+/// [verify_request_response_pair_with_context].
+///
+/// The certificate and the tree that the response was verified with are part of the
+/// response's `IC-Certificate` header, so they are captured along with the rest of the
+/// response headers. The current time of the verification is captured too, so a replay
+/// does not fail because the certificate has expired since it was captured, and so are the
+/// [options](VerificationContextOptions) of the [ResponseVerifier] that verified the response.
+///
+/// A captured context can be serialized with any [serde] data format, such as JSON, and
+/// logged or attached to a bug report. To replay a failed verification, deserialize the
+/// context, for example in a unit test, and call [verify](VerificationContext::verify), which
+/// verifies the captured request and response again with the captured inputs.
+///
+/// The request and response are captured in full, including their bodies and headers such as
+/// `Cookie`, `Authorization` or `Set-Cookie`, which can carry credentials. A captured context
+/// must be handled as sensitive data, and such headers should be redacted before it is logged
+/// or shared. Redacting a header that is certified makes the replay fail differently, so
+/// replace its value rather than removing it and check that the replay fails as expected.
+///
+/// This type requires the `serde` feature.
+///
+/// # Examples
+///
+/// ```
+/// use ic_http_certification::{HttpRequest, HttpResponse};
+/// use ic_response_verification::{
+///     verify_request_response_pair_with_context, ResponseVerificationError,
+/// };
+///
+/// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+/// let ic_public_key = [0; 96];
+/// let request = HttpRequest::get("/").build();
+/// let response = HttpResponse::ok(b"Hello World!", vec![]).build();
+///
+/// let failure = verify_request_response_pair_with_context(
+///     request,
+///     response,
+///     &canister_id,
+///     0,
+///     300_000_000_000,
+///     &ic_public_key,
+///     2,
+/// )
+/// .unwrap_err();
+/// assert!(matches!(
+///     failure.error,
+///     ResponseVerificationError::HeaderMissingCertification
+/// ));
+///
+/// // the context would normally be serialized, stored and deserialized before it is replayed
+/// let replayed_result = failure.context.verify();
+/// assert!(matches!(
+///     replayed_result,
+///     Err(ResponseVerificationError::HeaderMissingCertification)
+/// ));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationContext {
+    /// The method of the request.
+    pub request_method: String,
+    /// The URL of the request.
+    pub request_url: String,
+    /// The headers of the request, in the order they appear in the request.
+    pub request_headers: Vec<(String, String)>,
+    /// The body of the request.
+    pub request_body: Vec<u8>,
+    /// The certificate version of the request.
+    pub request_certificate_version: Option<u16>,
+    /// The status code of the response.
+    pub response_status_code: u16,
+    /// The headers of the response, in the order they appear in the response, including the
+    /// `IC-Certificate` and `IC-CertificateExpression` headers.
+    pub response_headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub response_body: Vec<u8>,
+    /// The upgrade flag of the response.
+    pub response_upgrade: Option<bool>,
+    /// The ID of the canister that the response was expected from.
+    pub canister_id: Vec<u8>,
+    /// The current time of the verification, in nanoseconds since the UNIX epoch.
+    pub current_time_ns: u128,
+    /// The maximum offset between the time of the certificate and the current time, in
+    /// nanoseconds.
+    pub max_cert_time_offset_ns: u128,
+    /// The IC public key that the certificate was verified with.
+    pub ic_public_key: Vec<u8>,
+    /// The minimum verification version that was requested.
+    pub min_requested_verification_version: u8,
+    /// The options of the [ResponseVerifier] that the response was verified with. This
+    /// defaults to the options of [ResponseVerifier::new] when it is missing.
+    #[serde(default)]
+    pub verifier_options: VerificationContextOptions,
+}
+
+/// The options of the [ResponseVerifier] that a [VerificationContext] was captured with.
+///
+/// This type requires the `serde` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationContextOptions {
+    /// See [with_expected_upgrade](ResponseVerifier::with_expected_upgrade).
+    pub expected_upgrade: Option<bool>,
+    /// See [with_strict_content_encoding](ResponseVerifier::with_strict_content_encoding).
+    pub strict_content_encoding: bool,
+    /// See [with_preserved_header_casing](ResponseVerifier::with_preserved_header_casing).
+    pub preserve_header_casing: bool,
+    /// See [with_max_header_count](ResponseVerifier::with_max_header_count).
+    pub max_header_count: usize,
+    /// See [with_max_header_bytes](ResponseVerifier::with_max_header_bytes).
+    pub max_header_bytes: usize,
+    /// See [with_uncertified_headers_report](ResponseVerifier::with_uncertified_headers_report).
+    pub report_uncertified_headers: bool,
+    /// See [with_certificate_header_name](ResponseVerifier::with_certificate_header_name).
+    pub certificate_header_name: Option<String>,
+    /// See [with_additional_root_keys](ResponseVerifier::with_additional_root_keys).
+    pub additional_ic_public_keys: Vec<Vec<u8>>,
+}
+
+impl Default for VerificationContextOptions {
+    fn default() -> Self {
+        Self {
+            expected_upgrade: None,
+            strict_content_encoding: false,
+            preserve_header_casing: false,
+            max_header_count: DEFAULT_MAX_HEADER_COUNT,
+            max_header_bytes: DEFAULT_MAX_HEADER_BYTES,
+            report_uncertified_headers: false,
+            certificate_header_name: None,
+            additional_ic_public_keys: vec![],
+        }
+    }
+}
+
+impl VerificationContext {
+    /// Captures the inputs of a verification with the same arguments as
+    /// [verify_request_response_pair](crate::verify_request_response_pair), with the default
+    /// verifier options.
+    pub fn new(
+        request: &HttpRequest,
+        response: &HttpResponse,
+        canister_id: &[u8],
+        current_time_ns: u128,
+        max_cert_time_offset_ns: u128,
+        ic_public_key: &[u8],
+        min_requested_verification_version: u8,
+    ) -> Self {
+        Self {
+            request_method: request.method().as_str().to_string(),
+            request_url: request.url().to_string(),
+            request_headers: request.headers().to_vec(),
+            request_body: request.body().to_vec(),
+            request_certificate_version: request.certificate_version(),
+            response_status_code: response.status_code().as_u16(),
+            response_headers: response.headers().to_vec(),
+            response_body: response.body().to_vec(),
+            response_upgrade: response.upgrade(),
+            canister_id: canister_id.to_vec(),
+            current_time_ns,
+            max_cert_time_offset_ns,
+            ic_public_key: ic_public_key.to_vec(),
+            min_requested_verification_version,
+            verifier_options: VerificationContextOptions::default(),
+        }
+    }
+
+    /// Captures the inputs of a verification with the given [ResponseVerifier], including its
+    /// options.
+    pub fn with_verifier(
+        request: &HttpRequest,
+        response: &HttpResponse,
+        verifier: &ResponseVerifier,
+    ) -> Self {
+        Self {
+            verifier_options: VerificationContextOptions {
+                expected_upgrade: verifier.expected_upgrade,
+                strict_content_encoding: verifier.strict_content_encoding,
+                preserve_header_casing: verifier.preserve_header_casing,
+                max_header_count: verifier.max_header_count,
+                max_header_bytes: verifier.max_header_bytes,
+                report_uncertified_headers: verifier.report_uncertified_headers,
+                certificate_header_name: verifier.certificate_header_name.map(str::to_string),
+                additional_ic_public_keys: verifier
+                    .additional_ic_public_keys
+                    .iter()
+                    .map(|ic_public_key| ic_public_key.to_vec())
+                    .collect(),
+            },
+            ..Self::new(
+                request,
+                response,
+                verifier.canister_id,
+                verifier.current_time_ns,
+                verifier.max_cert_time_offset_ns,
+                verifier.ic_public_key,
+                verifier.min_requested_verification_version,
+            )
+        }
+    }
+
+    /// Returns the captured request.
+    ///
+    /// Returns [InvalidHttpMethod](HttpCertificationError::InvalidHttpMethod) if the captured
+    /// method is not a valid HTTP method.
+    pub fn request(&self) -> ResponseVerificationResult<HttpRequest<'_>> {
+        let method = Method::from_bytes(self.request_method.as_bytes()).map_err(|_| {
+            HttpCertificationError::InvalidHttpMethod {
+                method: self.request_method.clone(),
+            }
+        })?;
+
+        let request = HttpRequest::builder()
+            .with_method(method)
+            .with_url(self.request_url.as_str())
+            .with_headers(self.request_headers.clone())
+            .with_body(self.request_body.as_slice());
+
+        Ok(match self.request_certificate_version {
+            Some(certificate_version) => request.with_certificate_version(certificate_version),
+            None => request,
+        }
+        .build())
+    }
+
+    /// Returns the captured response.
+    ///
+    /// Returns [InvalidHttpStatusCode](HttpCertificationError::InvalidHttpStatusCode) if the
+    /// captured status code is not a valid HTTP status code.
+    pub fn response(&self) -> ResponseVerificationResult<HttpResponse<'_>> {
+        let status_code = StatusCode::from_u16(self.response_status_code).map_err(|_| {
+            HttpCertificationError::InvalidHttpStatusCode {
+                status_code: self.response_status_code,
+            }
+        })?;
+
+        Ok(HttpResponse::from_parts(
+            status_code,
+            self.response_headers.clone(),
+            self.response_body.as_slice(),
+            self.response_upgrade,
+        ))
+    }
+
+    /// Replays the captured verification, verifying the captured request and response with
+    /// the captured inputs and [options](VerificationContext::verifier_options) in the same way
+    /// as [ResponseVerifier::verify].
+    pub fn verify(&self) -> ResponseVerificationResult<VerificationInfo> {
+        let VerificationContextOptions {
+            expected_upgrade,
+            strict_content_encoding,
+            preserve_header_casing,
+            max_header_count,
+            max_header_bytes,
+            report_uncertified_headers,
+            certificate_header_name,
+            additional_ic_public_keys,
+        } = &self.verifier_options;
+        let additional_ic_public_keys = additional_ic_public_keys
+            .iter()
+            .map(Vec::as_slice)
+            .collect::<Vec<_>>();
+
+        let mut verifier = ResponseVerifier::new(
+            &self.canister_id,
+            self.current_time_ns,
+            self.max_cert_time_offset_ns,
+            &self.ic_public_key,
+            self.min_requested_verification_version,
+        )
+        .with_strict_content_encoding(*strict_content_encoding)
+        .with_preserved_header_casing(*preserve_header_casing)
+        .with_max_header_count(*max_header_count)
+        .with_max_header_bytes(*max_header_bytes)
+        .with_uncertified_headers_report(*report_uncertified_headers)
+        .with_additional_root_keys(&additional_ic_public_keys);
+        if let Some(expected_upgrade) = expected_upgrade {
+            verifier = verifier.with_expected_upgrade(*expected_upgrade);
+        }
+        if let Some(certificate_header_name) = certificate_header_name {
+            verifier = verifier.with_certificate_header_name(certificate_header_name);
+        }
+
+        verifier.verify(self.request()?, self.response()?)
+    }
+}
+
+/// A failed verification, along with the [VerificationContext] that it can be replayed with,
+/// returned by [verify_with_context](ResponseVerifier::verify_with_context) and
+/// [verify_request_response_pair_with_context].
+///
+/// This type requires the `serde` feature.
+#[derive(thiserror::Error, Debug, Clone)]
+#[error("{error}")]
+pub struct VerificationFailure {
+    /// The error that the verification failed with.
+    #[source]
+    pub error: ResponseVerificationError,
+    /// The inputs of the failed verification.
+    pub context: Box<VerificationContext>,
+}
+
+impl ResponseVerifier<'_> {
+    /// Verifies a request and response pair in the same way as
+    /// [verify](ResponseVerifier::verify), capturing a [VerificationContext] of the inputs and
+    /// of this verifier's options if verification fails, so that the failure can be reproduced
+    /// offline.
+    ///
+    /// The context is only captured for failed verifications, so successful verifications do
+    /// not copy the request or the response, apart from the body of the returned
+    /// [VerifiedResponse](crate::types::VerifiedResponse). See [VerificationContext] for how to
+    /// replay a captured context, and for how to handle the credentials that it may contain.
+    ///
+    /// This function requires the `serde` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_strict_content_encoding(true);
+    /// let request = HttpRequest::get("/").build();
+    /// let response = HttpResponse::ok(b"Hello World!", vec![]).build();
+    ///
+    /// let failure = verifier.verify_with_context(request, response).unwrap_err();
+    /// assert!(failure.context.verifier_options.strict_content_encoding);
+    /// assert!(matches!(
+    ///     failure.context.verify(),
+    ///     Err(ResponseVerificationError::HeaderMissingCertification)
+    /// ));
+    /// ```
+    pub fn verify_with_context(
+        &self,
+        request: HttpRequest,
+        response: HttpResponse,
+    ) -> Result<VerificationInfo, VerificationFailure> {
+        self.verify_borrowed(&request, &response)
+            .map_err(|error| VerificationFailure {
+                error,
+                context: Box::new(VerificationContext::with_verifier(
+                    &request, &response, self,
+                )),
+            })
+    }
+}
+
+/// Verifies a request and response pair in the same way as
+/// [verify_request_response_pair](crate::verify_request_response_pair), capturing a
+/// [VerificationContext] of the inputs if verification fails, so that the failure can be
+/// reproduced offline.
+///
+/// This is equivalent to calling [verify_with_context](ResponseVerifier::verify_with_context)
+/// on a [ResponseVerifier] with the default options.
+///
+/// This function requires the `serde` feature. See [VerificationContext] for how to replay a
+/// captured context.
+pub fn verify_request_response_pair_with_context(
+    request: HttpRequest,
+    response: HttpResponse,
+    canister_id: &[u8],
+    current_time_ns: u128,
+    max_cert_time_offset_ns: u128,
+    ic_public_key: &[u8],
+    min_requested_verification_version: u8,
+) -> Result<VerificationInfo, VerificationFailure> {
+    ResponseVerifier::new(
+        canister_id,
+        current_time_ns,
+        max_cert_time_offset_ns,
+        ic_public_key,
+        min_requested_verification_version,
+    )
+    .verify_with_context(request, response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_http_certification::{
+        DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpCertificationPath,
+        HttpCertificationTreeEntry, CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
+    };
+    use ic_response_verification_test_utils::{
+        create_v2_fixture, get_current_timestamp, V2Fixture,
+    };
+
+    const MAX_CERT_TIME_OFFSET_NS: u128 = 300_000_000_000;
+    const MIN_REQUESTED_VERIFICATION_VERSION: u8 = 2;
+
+    #[test]
+    fn failed_verification_is_replayed_from_captured_context() {
+        let req_path = "/";
+        let current_time = get_current_timestamp();
+        let cel_expr = DefaultCelBuilder::response_only_certification()
+            .with_response_certification(DefaultResponseCertification::certified_response_headers(
+                vec!["Cache-Control"],
+            ))
+            .build();
+
+        let request = HttpRequest::get(req_path)
+            .with_headers(vec![("Accept".into(), "text/html".into())])
+            .with_certificate_version(2)
+            .build();
+        let mut response = HttpResponse::ok(
+            b"Hello World!",
+            vec![
+                (
+                    CERTIFICATE_EXPRESSION_HEADER_NAME.into(),
+                    cel_expr.to_string(),
+                ),
+                ("Cache-Control".into(), "max-age=604800".into()),
+            ],
+        )
+        .build();
+
+        let certification = HttpCertification::response_only(&cel_expr, &response, None).unwrap();
+        let certification_path = HttpCertificationPath::exact(req_path);
+        let certification_tree_entry =
+            HttpCertificationTreeEntry::new(&certification_path, certification);
+
+        let V2Fixture {
+            root_key,
+            certificate_header,
+            canister_id,
+        } = create_v2_fixture(req_path, &certification_tree_entry, &current_time);
+
+        response.add_header((CERTIFICATE_HEADER_NAME.to_string(), certificate_header));
+        let tampered_response = response.clone().map_body(|_| b"Goodbye World!".to_vec());
+
+        let result = verify_request_response_pair_with_context(
+            request.clone(),
+            response.clone(),
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+        assert!(result.unwrap().is_fully_certified());
+
+        let VerificationFailure { error, context } = verify_request_response_pair_with_context(
+            request.clone(),
+            tampered_response.clone(),
+            canister_id.as_ref(),
+            current_time,
+            MAX_CERT_TIME_OFFSET_NS,
+            &root_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            ResponseVerificationError::InvalidRequestAndResponseHashes { .. }
+        ));
+        assert_eq!(context.request().unwrap(), request);
+        assert!(context.response().unwrap().eq_ordered(&tampered_response));
+
+        let serialized_context = serde_cbor::to_vec(&context).unwrap();
+        let deserialized_context: VerificationContext =
+            serde_cbor::from_slice(&serialized_context).unwrap();
+        assert_eq!(deserialized_context, *context);
+
+        let replayed_result = deserialized_context.verify();
+        assert!(matches!(
+            replayed_result,
+            Err(ResponseVerificationError::InvalidRequestAndResponseHashes { .. })
+        ));
+
+        let fixed_context = VerificationContext {
+            response_body: b"Hello World!".to_vec(),
+            ..deserialized_context
+        };
+        assert!(fixed_context.verify().unwrap().is_fully_certified());
+    }
+
+    #[test]
+    fn failed_verification_is_replayed_with_captured_verifier_options() {
+        let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+        let ic_public_key = [0; 96];
+        let additional_ic_public_key = [1; 96];
+        let additional_ic_public_keys = [additional_ic_public_key.as_slice()];
+        let verifier = ResponseVerifier::new(
+            &canister_id,
+            0,
+            MAX_CERT_TIME_OFFSET_NS,
+            &ic_public_key,
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        )
+        .with_expected_upgrade(false)
+        .with_strict_content_encoding(true)
+        .with_preserved_header_casing(true)
+        .with_max_header_count(1)
+        .with_max_header_bytes(1_024)
+        .with_uncertified_headers_report(true)
+        .with_certificate_header_name("X-IC-Certificate")
+        .with_additional_root_keys(&additional_ic_public_keys);
+        let request = HttpRequest::get("/").build();
+        let response = HttpResponse::ok(
+            b"Hello World!",
+            vec![
+                ("Cache-Control".into(), "no-cache".into()),
+                ("Content-Type".into(), "text/plain".into()),
+            ],
+        )
+        .build();
+
+        let VerificationFailure { error, context } = verifier
+            .verify_with_context(request.clone(), response.clone())
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ResponseVerificationError::TooManyHeaders {
+                header_count: 2,
+                max_header_count: 1,
+            }
+        ));
+        assert_eq!(
+            context.verifier_options,
+            VerificationContextOptions {
+                expected_upgrade: Some(false),
+                strict_content_encoding: true,
+                preserve_header_casing: true,
+                max_header_count: 1,
+                max_header_bytes: 1_024,
+                report_uncertified_headers: true,
+                certificate_header_name: Some("X-IC-Certificate".to_string()),
+                additional_ic_public_keys: vec![additional_ic_public_key.to_vec()],
+            }
+        );
+
+        let serialized_context = serde_cbor::to_vec(&context).unwrap();
+        let deserialized_context: VerificationContext =
+            serde_cbor::from_slice(&serialized_context).unwrap();
+        assert!(matches!(
+            deserialized_context.verify(),
+            Err(ResponseVerificationError::TooManyHeaders {
+                header_count: 2,
+                max_header_count: 1,
+            })
+        ));
+
+        let default_options_context = VerificationContext {
+            verifier_options: VerificationContextOptions::default(),
+            ..deserialized_context
+        };
+        assert!(matches!(
+            default_options_context.verify(),
+            Err(ResponseVerificationError::HeaderMissingCertification)
+        ));
+    }
+
+    #[test]
+    fn invalid_captured_context_fails_replay() {
+        let context = VerificationContext::new(
+            &HttpRequest::get("/").build(),
+            &HttpResponse::ok(b"Hello World!", vec![]).build(),
+            &[0, 0, 0, 0, 0, 0, 0, 1, 1, 1],
+            0,
+            MAX_CERT_TIME_OFFSET_NS,
+            &[0; 96],
+            MIN_REQUESTED_VERIFICATION_VERSION,
+        );
+
+        let invalid_method_context = VerificationContext {
+            request_method: "NOT A METHOD".to_string(),
+            ..context.clone()
+        };
+        assert!(matches!(
+            invalid_method_context.verify(),
+            Err(ResponseVerificationError::HttpCertificationError(
+                HttpCertificationError::InvalidHttpMethod { method }
+            )) if method == "NOT A METHOD"
+        ));
+
+        let invalid_status_code_context = VerificationContext {
+            response_status_code: 1_000,
+            ..context
+        };
+        assert!(matches!(
+            invalid_status_code_context.verify(),
+            Err(ResponseVerificationError::HttpCertificationError(
+                HttpCertificationError::InvalidHttpStatusCode { status_code: 1_000 }
+            ))
+        ));
+    }
+}
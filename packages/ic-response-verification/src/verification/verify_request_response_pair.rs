@@ -1,32 +1,52 @@
-use super::{body::decode_body, certificate_header::CertificateHeader};
+use super::{
+    body::{can_decode_body, decode_body},
+    certificate_header::CertificateHeader,
+};
 use crate::{
     cel::{map_cel_ast, parse_cel_expression},
     error::{ResponseVerificationError, ResponseVerificationResult},
     types::{VerificationInfo, VerifiedResponse},
     validation::{
-        validate_body, validate_expr_hash, validate_expr_path, validate_hashes, validate_tree,
+        is_request_hash_certified, validate_body, validate_expr_hash, validate_expr_path,
+        validate_hashes, validate_tree,
     },
 };
-use ic_certificate_verification::VerifyCertificate;
-use ic_certification::{hash_tree::Hash, Certificate, HashTree};
+use candid::Principal;
+use ic_certificate_verification::{CertificateVerificationError, VerifyCertificate};
+use ic_certification::{hash_tree::Hash, Certificate, HashTree, LookupResult};
 use ic_http_certification::{
     cel::{
         CelExpression, DefaultCelExpression, DefaultFullCelExpression,
         DefaultResponseOnlyCelExpression,
     },
     filter_response_headers, request_hash, response_headers_hash, HttpRequest, HttpResponse,
-    CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
+    HttpUpdateResponse, CERTIFICATE_EXPRESSION_HEADER_NAME, CERTIFICATE_HEADER_NAME,
 };
 use ic_representation_independent_hash::hash;
-use std::collections::HashMap;
 
 /// The minimum verification version supported by this package.
 pub const MIN_VERIFICATION_VERSION: u8 = 1;
 /// The maximum verification version supported by this package.
 pub const MAX_VERIFICATION_VERSION: u8 = 2;
+/// The default maximum number of response headers accepted by [ResponseVerifier].
+pub const DEFAULT_MAX_HEADER_COUNT: usize = 1_024;
+/// The default maximum total size of response headers accepted by [ResponseVerifier], in bytes,
+/// as counted by [HttpResponse::total_header_bytes]. This matches the maximum size of a
+/// response from the IC.
+pub const DEFAULT_MAX_HEADER_BYTES: usize = 2 * 1024 * 1024;
 
 /// The primary entry point for verifying a request and response pair. This will verify the response
 /// with respect to the request, according the [Response Verification Spec]().
+///
+/// This is a shorthand for [ResponseVerifier::new] followed by [ResponseVerifier::verify],
+/// using the verifier's default options. Use [ResponseVerifier] directly to configure
+/// additional verification options.
+///
+/// Responses are looked up in the certification tree by the path of the request URL only,
+/// so requests to a custom domain verify in the same way as requests to
+/// `<canister_id>.icp0.io`, regardless of the host in their URL or their `Host` header.
+/// The `Host` header is only bound to the response if the certification includes it as a
+/// certified request header.
 pub fn verify_request_response_pair(
     request: HttpRequest,
     response: HttpResponse,
@@ -36,86 +56,931 @@ pub fn verify_request_response_pair(
     ic_public_key: &[u8],
     min_requested_verification_version: u8,
 ) -> ResponseVerificationResult<VerificationInfo> {
-    let headers: HashMap<_, _> = response
-        .headers()
-        .iter()
-        .map(|(k, v)| (k.to_lowercase(), v.clone()))
-        .collect();
+    ResponseVerifier::new(
+        canister_id,
+        current_time_ns,
+        max_cert_time_offset_ns,
+        ic_public_key,
+        min_requested_verification_version,
+    )
+    .verify(request, response)
+}
 
-    let Some(certificate_header_str) = headers.get(&CERTIFICATE_HEADER_NAME.to_lowercase()) else {
-        return Err(ResponseVerificationError::HeaderMissingCertification);
-    };
+/// A configurable verifier for request and response pairs.
+///
+/// The verifier holds the parameters that are shared across verifications, such as the
+/// canister ID and the IC public key, along with any additional verification options.
+/// Without any additional options, [verify](ResponseVerifier::verify) behaves exactly the same
+/// as [verify_request_response_pair].
+///
+/// # Examples
+///
+/// ```
+/// use ic_response_verification::ResponseVerifier;
+///
+/// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+/// let ic_public_key = [0; 96];
+///
+/// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+///     .with_expected_upgrade(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResponseVerifier<'a> {
+    pub(super) canister_id: &'a [u8],
+    pub(super) current_time_ns: u128,
+    pub(super) max_cert_time_offset_ns: u128,
+    pub(super) ic_public_key: &'a [u8],
+    pub(super) min_requested_verification_version: u8,
+    pub(super) expected_upgrade: Option<bool>,
+    pub(super) strict_content_encoding: bool,
+    pub(super) preserve_header_casing: bool,
+    pub(super) max_header_count: usize,
+    pub(super) max_header_bytes: usize,
+    pub(super) report_uncertified_headers: bool,
+    pub(super) certificate_header_name: Option<&'a str>,
+    pub(super) additional_ic_public_keys: &'a [&'a [u8]],
+    verifying_expected_body: bool,
+}
 
-    let certificate_header = CertificateHeader::from(certificate_header_str)?;
+impl<'a> ResponseVerifier<'a> {
+    /// Creates a new [ResponseVerifier] with the given verification parameters and
+    /// the default verification options.
+    pub fn new(
+        canister_id: &'a [u8],
+        current_time_ns: u128,
+        max_cert_time_offset_ns: u128,
+        ic_public_key: &'a [u8],
+        min_requested_verification_version: u8,
+    ) -> Self {
+        Self {
+            canister_id,
+            current_time_ns,
+            max_cert_time_offset_ns,
+            ic_public_key,
+            min_requested_verification_version,
+            expected_upgrade: None,
+            strict_content_encoding: false,
+            preserve_header_casing: false,
+            max_header_count: DEFAULT_MAX_HEADER_COUNT,
+            max_header_bytes: DEFAULT_MAX_HEADER_BYTES,
+            report_uncertified_headers: false,
+            certificate_header_name: None,
+            additional_ic_public_keys: &[],
+            verifying_expected_body: false,
+        }
+    }
 
-    match certificate_header.version {
-        version if version < min_requested_verification_version => Err(
-            ResponseVerificationError::RequestedVerificationVersionMismatch {
-                requested_version: version,
-                min_requested_verification_version,
-            },
-        ),
-        1 => {
-            let encoding = headers
-                .get("content-encoding")
-                .map(|encoding| encoding.as_str());
-
-            v1_verification(V1VerificationOpts {
-                request,
-                response,
-                canister_id,
-                current_time_ns,
-                max_cert_time_offset_ns,
-                tree: certificate_header.tree,
-                certificate: certificate_header.certificate,
-                encoding,
-                ic_public_key,
+    /// Enforces the expected value of the response's
+    /// [upgrade](ic_http_certification::HttpResponse::upgrade) flag.
+    ///
+    /// By default, the upgrade flag is ignored. When an expected value is set, a response
+    /// whose upgrade flag does not match it is rejected with
+    /// [UpgradeMismatch](ResponseVerificationError::UpgradeMismatch). A missing upgrade flag
+    /// is treated as `false`.
+    ///
+    /// The upgrade flag is not part of the certified response, so this check guards against
+    /// unexpected upgrade behavior rather than proving that the flag is authentic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_expected_upgrade(true);
+    /// ```
+    pub fn with_expected_upgrade(mut self, expected_upgrade: bool) -> Self {
+        self.expected_upgrade = Some(expected_upgrade);
+
+        self
+    }
+
+    /// Enables or disables strict verification of the response's `Content-Encoding` header
+    /// for verification version 1.
+    ///
+    /// Version 1 certification only certifies the hash of the response body, so the verifier
+    /// needs to decide which interpretation of the body should be checked against the tree.
+    ///
+    /// By default, verification is lenient and the body is checked in the following order:
+    /// 1. The response body is decoded according to the declared `Content-Encoding`
+    ///    and the hash of the decoded body is checked against the tree. A header that lists
+    ///    multiple encodings, such as `gzip, deflate`, lists them in the order that they
+    ///    were applied, so they are decoded in reverse order. If any of the encodings cannot
+    ///    be decoded, such as `br`, the body is left unchanged.
+    /// 2. If the decoded body does not match and a `Content-Encoding` header is present,
+    ///    the hash of the raw response body is checked against the tree.
+    ///
+    /// The response is accepted if either check succeeds, so a canister that certifies the
+    /// encoded body will pass verification in lenient mode, but not in strict mode.
+    ///
+    /// In strict mode, only the first check is performed. A declared encoding that cannot
+    /// be decoded is rejected with
+    /// [ContentEncodingMismatch](ResponseVerificationError::ContentEncodingMismatch), or with
+    /// [UnsupportedContentEncoding](ResponseVerificationError::UnsupportedContentEncoding) if
+    /// the header lists it along with other encodings, such as `gzip, br`, and the decoded
+    /// body must match the certified body without falling back to the raw response body.
+    /// Only `identity`, `gzip` and `deflate` encodings are supported in strict mode, and every
+    /// encoding listed by the header must be one of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 1)
+    ///     .with_strict_content_encoding(true);
+    /// ```
+    pub fn with_strict_content_encoding(mut self, strict_content_encoding: bool) -> Self {
+        self.strict_content_encoding = strict_content_encoding;
+
+        self
+    }
+
+    /// Enables or disables preserving the original casing of header names in the
+    /// [VerifiedResponse] for verification version 2.
+    ///
+    /// By default, the names of certified headers are lowercased, and the `IC-Certificate`
+    /// header is appended after the certified headers using the casing of
+    /// [CERTIFICATE_HEADER_NAME]. When enabled, the certified headers and the `IC-Certificate`
+    /// header, including every instance of a header that is split across multiple instances,
+    /// are returned exactly as they appear in the response, in their original order.
+    ///
+    /// HTTP header names are case-insensitive, so this option does not affect verification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_preserved_header_casing(true);
+    /// ```
+    pub fn with_preserved_header_casing(mut self, preserve_header_casing: bool) -> Self {
+        self.preserve_header_casing = preserve_header_casing;
+
+        self
+    }
+
+    /// Sets the maximum number of response headers that will be accepted, defaulting to
+    /// [DEFAULT_MAX_HEADER_COUNT].
+    ///
+    /// The limit is checked before any certificate decoding or hashing takes place, and a
+    /// response with more headers is rejected with
+    /// [TooManyHeaders](ResponseVerificationError::TooManyHeaders).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_max_header_count(64);
+    /// ```
+    pub fn with_max_header_count(mut self, max_header_count: usize) -> Self {
+        self.max_header_count = max_header_count;
+
+        self
+    }
+
+    /// Sets the maximum total size of response headers that will be accepted, in bytes,
+    /// defaulting to [DEFAULT_MAX_HEADER_BYTES]. The size is counted by
+    /// [HttpResponse::total_header_bytes], so it includes the `: ` separator and `\r\n` line
+    /// ending of each header, along with its name and value.
+    ///
+    /// The limit is checked before any certificate decoding or hashing takes place, and a
+    /// response with larger headers is rejected with
+    /// [HeadersTooLarge](ResponseVerificationError::HeadersTooLarge).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_max_header_bytes(64 * 1024);
+    /// ```
+    pub fn with_max_header_bytes(mut self, max_header_bytes: usize) -> Self {
+        self.max_header_bytes = max_header_bytes;
+
+        self
+    }
+
+    /// Enables or disables reporting the response headers that are present in the response,
+    /// but not covered by the certification, in
+    /// [uncertified_headers](VerificationInfo::uncertified_headers).
+    ///
+    /// By default, uncertified headers are silently filtered out of the [VerifiedResponse] and
+    /// [uncertified_headers](VerificationInfo::uncertified_headers) is `None`. When enabled, it
+    /// lists every response header whose name is not among the certified headers, in the order
+    /// they appear in the response. The `IC-Certificate` header is never reported. For
+    /// verification version 1, and for responses that skip certification, no headers are
+    /// certified, so all other headers are reported.
+    ///
+    /// This only reports headers and does not affect whether verification succeeds. It can be
+    /// used to audit that security-relevant headers are not accidentally left out of certification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_uncertified_headers_report(true);
+    /// ```
+    pub fn with_uncertified_headers_report(mut self, report_uncertified_headers: bool) -> Self {
+        self.report_uncertified_headers = report_uncertified_headers;
+
+        self
+    }
+
+    /// Sets the name of the header that carries the certificate in responses, for
+    /// environments where a proxy renames the [CERTIFICATE_HEADER_NAME] header.
+    ///
+    /// By default, the certificate is read from the [CERTIFICATE_HEADER_NAME] header. When a
+    /// different name is set, the certificate is read from headers with that name instead and
+    /// any [CERTIFICATE_HEADER_NAME] headers are ignored. The header is matched
+    /// case-insensitively and is returned as [CERTIFICATE_HEADER_NAME] in the
+    /// [VerifiedResponse]. The `IC-CertificateExpression` header is part of the certified
+    /// response and cannot be renamed.
+    ///
+    /// **Warning:** standard HTTP Gateways only accept the [CERTIFICATE_HEADER_NAME] header, so
+    /// responses using a different header name are not compatible with them. This option is
+    /// only intended for interoperability testing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_certificate_header_name("X-IC-Certificate");
+    /// ```
+    pub fn with_certificate_header_name(mut self, certificate_header_name: &'a str) -> Self {
+        self.certificate_header_name = Some(certificate_header_name);
+
+        self
+    }
+
+    /// Sets additional root keys that certificates are accepted under, alongside the IC public
+    /// key passed to [new](ResponseVerifier::new), to support root key rotation or verifying
+    /// responses from multiple networks.
+    ///
+    /// The certificate is verified with the IC public key first, followed by each additional
+    /// root key in order, until its signature is valid under one of them. Only failures caused
+    /// by the root key, such as an invalid signature, move on to the next key. Any other
+    /// failure is returned immediately. If the signature is not valid under any key, the
+    /// failure from the last key is returned. The index of the key that the certificate was
+    /// verified with is reported in [root_key_index](VerificationInfo::root_key_index).
+    ///
+    /// **Warning:** a response is trusted if it is certified under any of the accepted keys,
+    /// so the verifier is only as secure as the weakest of them. Accepting the root key of a
+    /// test network, or of a local replica, alongside the mainnet root key allows anyone in
+    /// control of that network to forge responses that pass verification. Only accept the keys
+    /// that are needed, and remove retired keys once rotation has completed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_response_verification::ResponseVerifier;
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let rotated_ic_public_key = [1; 96];
+    /// let additional_root_keys = [rotated_ic_public_key.as_slice()];
+    ///
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_additional_root_keys(&additional_root_keys);
+    /// ```
+    pub fn with_additional_root_keys(mut self, additional_root_keys: &'a [&'a [u8]]) -> Self {
+        self.additional_ic_public_keys = additional_root_keys;
+
+        self
+    }
+
+    /// Verifies a request and response pair. This will verify the response with respect to the
+    /// request, according the [Response Verification Spec](), and apply any additional
+    /// verification options that have been configured on this verifier.
+    ///
+    /// See [verify_borrowed](ResponseVerifier::verify_borrowed) for verifying a request and
+    /// response pair that is still needed after verification.
+    ///
+    /// The body of the returned [VerifiedResponse] is moved out of the response, so a response
+    /// that owns its body is verified without copying the body. A response that borrows its
+    /// body is copied once, since the returned body is always owned.
+    ///
+    /// Responses are certified over their body without any transfer coding, so a response
+    /// with a `Transfer-Encoding` header that declares any transfer coding other than
+    /// `identity`, such as `chunked`, is rejected with
+    /// [UnsupportedTransferEncoding](ResponseVerificationError::UnsupportedTransferEncoding).
+    /// Clients that bridge responses from an HTTP connection must decode the transfer coding
+    /// and remove the `Transfer-Encoding` header before verification.
+    ///
+    /// A response that the canister skipped certification for passes verification without a
+    /// [VerifiedResponse], since nothing about it is certified. Check
+    /// [outcome](VerificationInfo::outcome) for
+    /// [CertifiedSkip](crate::types::VerificationOutcome::CertifiedSkip) before treating the
+    /// response as verified.
+    pub fn verify(
+        &self,
+        request: HttpRequest,
+        response: HttpResponse,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        let verification_info = self.verify_impl(&request, &response, None, None)?;
+
+        Ok(with_verified_body(verification_info, || {
+            let (_, _, body, _) = response.into_parts();
+
+            body.into_owned()
+        }))
+    }
+
+    /// Verifies a borrowed request and response pair, in the same way as
+    /// [verify](ResponseVerifier::verify).
+    ///
+    /// This avoids cloning a request or response that the caller still needs after verification.
+    /// Combined with a request and response whose bodies borrow the caller's buffers, large bodies
+    /// are verified without being copied. Note that the body of the returned [VerifiedResponse] is
+    /// still an owned copy of the response body, use [verify](ResponseVerifier::verify) to move
+    /// an owned body into the [VerifiedResponse] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2);
+    ///
+    /// let body = vec![0; 1024 * 1024];
+    /// let request = HttpRequest::get("/").build();
+    /// let response = HttpResponse::ok(body.as_slice(), vec![]).build();
+    ///
+    /// let result = verifier.verify_borrowed(&request, &response);
+    ///
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(ResponseVerificationError::HeaderMissingCertification)
+    /// ));
+    /// assert_eq!(response.body(), body.as_slice());
+    /// ```
+    pub fn verify_borrowed(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        self.verify_impl(request, response, None, None)
+            .map(|verification_info| {
+                with_verified_body(verification_info, || response.body().to_vec())
+            })
+    }
+
+    /// Verifies a request and response pair in the same way as
+    /// [verify_borrowed](ResponseVerifier::verify_borrowed), using a precomputed hash of the
+    /// response body instead of the response's own body.
+    ///
+    /// This allows clients that receive the body out-of-band, or as a stream of chunks, to
+    /// verify the response without buffering the whole body in a single `HttpResponse`. The
+    /// body hash is the SHA-256 hash of the complete body, as it is served, which can be
+    /// computed incrementally as each chunk is received, e.g. with
+    /// [sha2::Sha256](https://docs.rs/sha2/latest/sha2/struct.Sha256.html). The response is
+    /// only verified once the hash of the complete body is known, so clients must not trust
+    /// any of the chunks before verification has passed.
+    ///
+    /// The body of `response` is ignored, so it can be empty. The status code and headers of
+    /// the response, including the `IC-Certificate` header, are still used, and the body of
+    /// the returned [VerifiedResponse] is the ignored body of `response`.
+    ///
+    /// For version 2 verification, the body hash takes the place of the hash of the response
+    /// body in the response hash, which is computed as
+    /// `hash(response_headers_hash || body_hash)`, where `response_headers_hash` is the
+    /// representation independent hash of the status code and the certified headers. For
+    /// version 1 verification, the body hash is looked up in the tree directly. Version 1
+    /// certifies the decoded body, so the body hash must be computed after removing any
+    /// `Content-Encoding`, and
+    /// [strict content encoding](ResponseVerifier::with_strict_content_encoding) does not
+    /// apply.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
+    /// use sha2::{Digest, Sha256};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2);
+    ///
+    /// let mut hasher = Sha256::new();
+    /// for chunk in [b"Hello, ".as_slice(), b"World!".as_slice()] {
+    ///     hasher.update(chunk);
+    /// }
+    /// let body_hash = hasher.finalize().into();
+    ///
+    /// let request = HttpRequest::get("/").build();
+    /// let response = HttpResponse::ok(vec![], vec![]).build();
+    ///
+    /// let result = verifier.verify_body_hash(&request, &response, body_hash);
+    ///
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(ResponseVerificationError::HeaderMissingCertification)
+    /// ));
+    /// ```
+    pub fn verify_body_hash(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        body_hash: Hash,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        self.verify_impl(request, response, None, Some(body_hash))
+            .map(|verification_info| {
+                with_verified_body(verification_info, || response.body().to_vec())
             })
+    }
+
+    /// Verifies a request and response pair in the same way as
+    /// [verify_borrowed](ResponseVerifier::verify_borrowed), using an already parsed
+    /// [CertificateHeader] instead of parsing the `IC-Certificate` header of the response.
+    ///
+    /// This avoids decoding the certificate and tree a second time in pipelines that have
+    /// already parsed the header, for example with [CertificateHeader::from], to log the
+    /// certificate. The `IC-Certificate` header of the response is not parsed, but all other
+    /// headers are used as usual, including the `IC-CertificateExpression` header for version 2
+    /// verification. For version 2 verification, the response must still carry its
+    /// `IC-Certificate` header, since it is returned as part of the verified headers.
+    ///
+    /// The certificate header is trusted to have been parsed from the response that is being
+    /// verified, so it must satisfy the same preconditions as a parsed header:
+    ///
+    /// - The [certificate](CertificateHeader::certificate) and the
+    ///   [tree](CertificateHeader::tree) are verified as usual, so they are not trusted, but
+    ///   they must be the ones that the canister returned with this response.
+    /// - The [version](CertificateHeader::version) selects the verification version, and is
+    ///   checked against the minimum requested verification version as usual.
+    /// - For version 2 verification, the [expr_path](CertificateHeader::expr_path) must be
+    ///   present, and must point to the expression in the tree for the request's path, otherwise
+    ///   verification fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse, CERTIFICATE_HEADER_NAME};
+    /// use ic_response_verification::{CertificateHeader, ResponseVerifier};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2);
+    ///
+    /// let request = HttpRequest::get("/").build();
+    /// let response = HttpResponse::ok(b"Hello, World!", vec![]).build();
+    ///
+    /// if let Some((_, header_value)) = response
+    ///     .headers()
+    ///     .iter()
+    ///     .find(|(name, _)| name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME))
+    /// {
+    ///     let certificate_header = CertificateHeader::from(header_value).unwrap();
+    ///     // log the certificate, then verify the response without parsing the header again
+    ///     let result =
+    ///         verifier.verify_certificate_header(&request, &response, &certificate_header);
+    /// }
+    /// ```
+    pub fn verify_certificate_header(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        certificate_header: &CertificateHeader,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        self.verify_impl(request, response, Some(certificate_header), None)
+            .map(|verification_info| {
+                with_verified_body(verification_info, || response.body().to_vec())
+            })
+    }
+
+    fn verify_impl(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        certificate_header: Option<&CertificateHeader>,
+        body_hash: Option<Hash>,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        if let Some(certificate_header_name) = self
+            .certificate_header_name
+            .filter(|name| !name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME))
+        {
+            let headers = response
+                .headers()
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME))
+                .map(|(name, value)| match name {
+                    name if name.eq_ignore_ascii_case(certificate_header_name) => {
+                        (CERTIFICATE_HEADER_NAME.to_string(), value.clone())
+                    }
+                    name => (name.clone(), value.clone()),
+                })
+                .collect();
+            let response = HttpResponse::from_parts(
+                response.status_code(),
+                headers,
+                response.body(),
+                response.upgrade(),
+            );
+            let verifier = Self {
+                certificate_header_name: None,
+                ..self.clone()
+            };
+
+            return verifier.verify_impl(request, &response, certificate_header, body_hash);
         }
-        2 => match headers.get(&CERTIFICATE_EXPRESSION_HEADER_NAME.to_lowercase()) {
-            Some(certificate_expression_header) => {
-                let Some(expr_path) = certificate_header.expr_path else {
-                    return Err(ResponseVerificationError::HeaderMissingCertificateExpressionPath);
+
+        if !self.additional_ic_public_keys.is_empty() {
+            let verify_with_root_key = |root_key_index: usize, ic_public_key: &'a [u8]| {
+                let verifier = ResponseVerifier {
+                    ic_public_key,
+                    additional_ic_public_keys: &[],
+                    ..self.clone()
                 };
 
-                let cel_ast = parse_cel_expression(certificate_expression_header)?;
-                let certification = map_cel_ast(&cel_ast)?;
-                let expr_hash = hash(certificate_expression_header.as_bytes());
+                verifier
+                    .verify_impl(request, response, certificate_header, body_hash)
+                    .map(|verification_info| VerificationInfo {
+                        root_key_index: Some(root_key_index),
+                        ..verification_info
+                    })
+            };
 
-                v2_verification(V2VerificationOpts {
+            let mut result = verify_with_root_key(0, self.ic_public_key);
+            for (index, ic_public_key) in self.additional_ic_public_keys.iter().copied().enumerate()
+            {
+                if !matches!(&result, Err(err) if is_root_key_mismatch(err)) {
+                    break;
+                }
+
+                result = verify_with_root_key(index + 1, ic_public_key);
+            }
+
+            return result;
+        }
+
+        self.validate_header_limits(response)?;
+        validate_transfer_encoding(response)?;
+
+        if let Some(expected_upgrade) = self.expected_upgrade {
+            let upgrade = response.upgrade().unwrap_or(false);
+
+            if upgrade != expected_upgrade {
+                return Err(ResponseVerificationError::UpgradeMismatch {
+                    expected_upgrade,
+                    upgrade,
+                });
+            }
+        }
+
+        let parsed_certificate_header;
+        let certificate_header = match certificate_header {
+            Some(certificate_header) => certificate_header,
+            None => {
+                // the `IC-Certificate` header may be split across multiple header instances
+                let certificate_header_values = response
+                    .headers()
+                    .iter()
+                    .filter(|(name, _)| name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME))
+                    .map(|(_, value)| value.as_str())
+                    .collect::<Vec<_>>();
+                if certificate_header_values.is_empty() {
+                    return Err(ResponseVerificationError::HeaderMissingCertification);
+                }
+
+                parsed_certificate_header =
+                    CertificateHeader::from_header_values(certificate_header_values)?;
+                &parsed_certificate_header
+            }
+        };
+
+        let mut verification_info = match certificate_header.version {
+            version if version < self.min_requested_verification_version => Err(
+                ResponseVerificationError::RequestedVerificationVersionMismatch {
+                    requested_version: version,
+                    min_requested_verification_version: self.min_requested_verification_version,
+                },
+            ),
+            1 => {
+                let encoding = get_header_value(response, "content-encoding").map(str::to_string);
+
+                v1_verification(V1VerificationOpts {
                     request,
                     response,
-                    canister_id,
-                    current_time_ns,
-                    max_cert_time_offset_ns,
-                    tree: certificate_header.tree,
-                    certificate: certificate_header.certificate,
-                    expr_path,
-                    expr_hash,
-                    certification,
-                    ic_public_key,
+                    canister_id: self.canister_id,
+                    current_time_ns: self.current_time_ns,
+                    max_cert_time_offset_ns: self.max_cert_time_offset_ns,
+                    tree: &certificate_header.tree,
+                    certificate: &certificate_header.certificate,
+                    encoding: encoding.as_deref(),
+                    ic_public_key: self.ic_public_key,
+                    strict_content_encoding: self.strict_content_encoding,
+                    body_hash,
                 })
             }
-            None => Err(ResponseVerificationError::HeaderMissingCertification),
-        },
-        _ => Err(ResponseVerificationError::UnsupportedVerificationVersion {
-            min_supported_version: MIN_VERIFICATION_VERSION,
-            max_supported_version: MAX_VERIFICATION_VERSION,
-            requested_version: certificate_header.version,
-        }),
+            2 => match get_header_value(response, CERTIFICATE_EXPRESSION_HEADER_NAME)
+                .map(str::to_string)
+            {
+                Some(certificate_expression_header) => {
+                    let Some(expr_path) = certificate_header.expr_path.clone() else {
+                        return Err(
+                            ResponseVerificationError::HeaderMissingCertificateExpressionPath,
+                        );
+                    };
+
+                    let cel_ast = parse_cel_expression(&certificate_expression_header)?;
+                    let certification = map_cel_ast(&cel_ast)?;
+                    let expr_hash = hash(certificate_expression_header.as_bytes());
+
+                    v2_verification(V2VerificationOpts {
+                        request,
+                        response,
+                        canister_id: self.canister_id,
+                        current_time_ns: self.current_time_ns,
+                        max_cert_time_offset_ns: self.max_cert_time_offset_ns,
+                        tree: &certificate_header.tree,
+                        certificate: &certificate_header.certificate,
+                        expr_path,
+                        expr_hash,
+                        certificate_expression: &certificate_expression_header,
+                        certification,
+                        ic_public_key: self.ic_public_key,
+                        preserve_header_casing: self.preserve_header_casing,
+                        body_hash,
+                        verifying_expected_body: self.verifying_expected_body,
+                    })
+                }
+                None => Err(ResponseVerificationError::HeaderMissingCertification),
+            },
+            _ => Err(ResponseVerificationError::UnsupportedVerificationVersion {
+                min_supported_version: MIN_VERIFICATION_VERSION,
+                max_supported_version: MAX_VERIFICATION_VERSION,
+                requested_version: certificate_header.version,
+            }),
+        }?;
+
+        if self.report_uncertified_headers {
+            verification_info.uncertified_headers =
+                Some(uncertified_headers(response, &verification_info));
+        }
+
+        Ok(verification_info)
+    }
+
+    /// Verifies that an expected response body, such as a copy of the body from a cache, is
+    /// certified for the given request and response, instead of the response's own body.
+    ///
+    /// The response is verified in the same way as with
+    /// [verify_borrowed](ResponseVerifier::verify_borrowed), but with its body replaced by
+    /// `expected_body`, so the served body is never trusted, or even read. The status code and
+    /// headers of the response, including the `IC-Certificate` header, are still used. The
+    /// expected body must be in the same form as a served body would be, so for version 1
+    /// verification it must be encoded as declared by the `Content-Encoding` header of the
+    /// response.
+    ///
+    /// Returns an
+    /// [ExpectedBodyNotCertified](ResponseVerificationError::ExpectedBodyNotCertified)
+    /// error if the hash of the expected body, together with the status code and certified
+    /// headers of the response, is not found in the certification tree, or if certification was
+    /// skipped for the response, so that no body is certified. Since the served body is
+    /// ignored, this error is also returned if the certified headers of the response have been
+    /// tampered with. When the request itself is not certified, for example because a certified
+    /// request header or query parameter doesn't match, the
+    /// [InvalidRequestAndResponseHashes](ResponseVerificationError::InvalidRequestAndResponseHashes)
+    /// error is returned instead, since the expected body can't be checked for that request.
+    /// Other verification failures, such as an invalid certificate, are returned as they are.
+    ///
+    /// On success, the [response](VerificationInfo::response) contains the expected body.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2);
+    ///
+    /// let cached_body = b"Hello, World!";
+    /// let request = HttpRequest::get("/").build();
+    /// let response = HttpResponse::ok(b"Hello, Mars?".as_slice(), vec![]).build();
+    ///
+    /// let result = verifier.verify_expected_body(&request, &response, cached_body);
+    ///
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(ResponseVerificationError::HeaderMissingCertification)
+    /// ));
+    /// ```
+    pub fn verify_expected_body(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+        expected_body: &[u8],
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        let expected_response = HttpResponse::from_parts(
+            response.status_code(),
+            response.headers().to_vec(),
+            expected_body,
+            response.upgrade(),
+        );
+
+        let verifier = Self {
+            verifying_expected_body: true,
+            ..self.clone()
+        };
+        let verification_info = verifier
+            .verify_borrowed(request, &expected_response)
+            .map_err(|err| match err {
+                ResponseVerificationError::InvalidResponseBody => {
+                    ResponseVerificationError::ExpectedBodyNotCertified
+                }
+                err => err,
+            })?;
+        if !verification_info.is_fully_certified() {
+            return Err(ResponseVerificationError::ExpectedBodyNotCertified);
+        }
+
+        Ok(verification_info)
+    }
+
+    /// Verifies a request and the [HttpUpdateResponse] returned for it by the
+    /// `http_update_request` method, after the request has been
+    /// [upgraded](ic_http_certification::HttpResponse::upgrade) to an update call.
+    ///
+    /// The update response is converted into the equivalent [HttpResponse], without an upgrade
+    /// flag, and verified in the same way as a response to the `http_request` method, so it
+    /// needs to be certified in the same way and carry the same `IC-Certificate` and
+    /// `IC-Certificate-Expression` headers. Any [expected upgrade](ResponseVerifier::with_expected_upgrade)
+    /// is ignored, since update responses do not have an upgrade flag.
+    ///
+    /// Note that a canister cannot retrieve a data certificate (`ic_cdk::api::data_certificate()`)
+    /// during an update call, so the `IC-Certificate` header of an update response needs to carry
+    /// a certificate that was obtained elsewhere, for example from a preceding query call.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ic_http_certification::{HttpRequest, HttpResponse};
+    /// use ic_response_verification::{ResponseVerificationError, ResponseVerifier};
+    ///
+    /// let canister_id = [0, 0, 0, 0, 0, 0, 0, 1, 1, 1];
+    /// let ic_public_key = [0; 96];
+    /// let verifier = ResponseVerifier::new(&canister_id, 0, 300_000_000_000, &ic_public_key, 2)
+    ///     .with_expected_upgrade(true);
+    ///
+    /// let request = HttpRequest::post("/").build();
+    /// let response = HttpResponse::ok(b"Hello, World!", vec![]).build_update();
+    ///
+    /// let result = verifier.verify_update(request, response);
+    ///
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(ResponseVerificationError::HeaderMissingCertification)
+    /// ));
+    /// ```
+    pub fn verify_update(
+        &self,
+        request: HttpRequest,
+        response: HttpUpdateResponse,
+    ) -> ResponseVerificationResult<VerificationInfo> {
+        let verifier = Self {
+            expected_upgrade: None,
+            ..self.clone()
+        };
+
+        verifier.verify(request, response.into())
+    }
+}
+
+/// Sets the body of the verified response, if there is one. The body is left empty during
+/// verification, so that [verify](ResponseVerifier::verify) can move it out of an owned response
+/// instead of copying it.
+fn with_verified_body(
+    mut verification_info: VerificationInfo,
+    body: impl FnOnce() -> Vec<u8>,
+) -> VerificationInfo {
+    if let Some(verified_response) = verification_info.response.as_mut() {
+        verified_response.body = body();
+    }
+
+    verification_info
+}
+
+/// Returns `true` if the error is caused by the certificate not being valid under the root key
+/// that it was verified with, so it may still be valid under a different root key.
+fn is_root_key_mismatch(error: &ResponseVerificationError) -> bool {
+    matches!(
+        error,
+        ResponseVerificationError::CertificateVerificationFailed(
+            CertificateVerificationError::SignatureVerificationFailed
+                | CertificateVerificationError::DerKeyLengthMismatch { .. }
+                | CertificateVerificationError::DerPrefixMismatch { .. }
+        )
+    )
+}
+
+impl ResponseVerifier<'_> {
+    fn validate_header_limits(&self, response: &HttpResponse) -> ResponseVerificationResult {
+        let header_count = response.header_count();
+        if header_count > self.max_header_count {
+            return Err(ResponseVerificationError::TooManyHeaders {
+                header_count,
+                max_header_count: self.max_header_count,
+            });
+        }
+
+        let header_bytes = response.total_header_bytes();
+        if header_bytes > self.max_header_bytes {
+            return Err(ResponseVerificationError::HeadersTooLarge {
+                header_bytes,
+                max_header_bytes: self.max_header_bytes,
+            });
+        }
+
+        Ok(())
     }
 }
 
+/// Rejects responses with a transfer coding other than `identity`, since the certified body is
+/// the body without any transfer coding and verifying the transfer-coded body would otherwise
+/// fail with a body or hash mismatch that does not point to the cause.
+fn validate_transfer_encoding(response: &HttpResponse) -> ResponseVerificationResult {
+    let transfer_codings = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case(http::header::TRANSFER_ENCODING.as_str()))
+        .map(|(_, value)| value.as_str())
+        .collect::<Vec<_>>();
+
+    let has_unsupported_transfer_coding = transfer_codings
+        .iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|transfer_coding| {
+            !transfer_coding.is_empty() && !transfer_coding.eq_ignore_ascii_case("identity")
+        });
+
+    if has_unsupported_transfer_coding {
+        return Err(ResponseVerificationError::UnsupportedTransferEncoding {
+            transfer_encoding: transfer_codings.join(", "),
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the value of the last header with the given name, matched case-insensitively,
+/// without allocating a lookup table for all headers of the response.
+fn get_header_value<'a>(response: &'a HttpResponse, header_name: &str) -> Option<&'a str> {
+    response
+        .headers()
+        .iter()
+        .rev()
+        .find(|(name, _)| name.eq_ignore_ascii_case(header_name))
+        .map(|(_, value)| value.as_str())
+}
+
 struct V1VerificationOpts<'a> {
-    request: HttpRequest<'a>,
-    response: HttpResponse<'a>,
+    request: &'a HttpRequest<'a>,
+    response: &'a HttpResponse<'a>,
     canister_id: &'a [u8],
     current_time_ns: u128,
     max_cert_time_offset_ns: u128,
-    tree: HashTree,
-    certificate: Certificate,
+    tree: &'a HashTree,
+    certificate: &'a Certificate,
     encoding: Option<&'a str>,
     ic_public_key: &'a [u8],
+    strict_content_encoding: bool,
+    body_hash: Option<Hash>,
 }
 
 fn v1_verification(
@@ -129,6 +994,8 @@ fn v1_verification(
         certificate,
         encoding,
         ic_public_key,
+        strict_content_encoding,
+        body_hash,
     }: V1VerificationOpts<'_>,
 ) -> ResponseVerificationResult<VerificationInfo> {
     certificate.verify(
@@ -137,18 +1004,48 @@ fn v1_verification(
         &current_time_ns,
         &max_cert_time_offset_ns,
     )?;
+    let certificate_time_ns = certificate_time_ns(certificate)?;
 
     let request_path = request.get_path()?;
-    let decoded_body = decode_body(response.body(), encoding)?;
-    let decoded_body_sha = hash(decoded_body.as_slice());
+    // a precomputed body hash is already the hash of the decoded body
+    let valid_body = if let Some(body_hash) = body_hash {
+        validate_tree(canister_id, certificate, tree)?;
 
-    validate_tree(canister_id, &certificate, &tree)?;
+        validate_body(tree, &request_path, &body_hash)
+    } else {
+        let content_encoding_mismatch = || ResponseVerificationError::ContentEncodingMismatch {
+            encoding: encoding.unwrap_or_default().to_string(),
+        };
+        let decoded_body = match decode_body(response.body(), encoding) {
+            Ok(decoded_body) => decoded_body,
+            // a body that can't be decoded is checked as is in lenient mode
+            Err(ResponseVerificationError::UnsupportedContentEncoding { .. })
+                if !strict_content_encoding =>
+            {
+                response.body().to_owned()
+            }
+            Err(ResponseVerificationError::IoError(_)) if strict_content_encoding => {
+                return Err(content_encoding_mismatch())
+            }
+            Err(err) => return Err(err),
+        };
+        if strict_content_encoding && !can_decode_body(encoding) {
+            return Err(content_encoding_mismatch());
+        }
+        let decoded_body_sha = hash(decoded_body.as_slice());
 
-    let mut valid_body = validate_body(&tree, &request_path, &decoded_body_sha);
-    if encoding.is_some() && !valid_body {
-        let body_sha = hash(response.body());
-        valid_body = validate_body(&tree, &request_path, &body_sha);
-    }
+        validate_tree(canister_id, certificate, tree)?;
+
+        // the decoded body always takes precedence,
+        // the raw body is only checked as a fallback in lenient mode
+        let mut valid_body = validate_body(tree, &request_path, &decoded_body_sha);
+        if encoding.is_some() && !valid_body && !strict_content_encoding {
+            let body_sha = hash(response.body());
+            valid_body = validate_body(tree, &request_path, &body_sha);
+        }
+
+        valid_body
+    };
 
     if !valid_body {
         return Err(ResponseVerificationError::InvalidResponseBody);
@@ -158,24 +1055,89 @@ fn v1_verification(
         response: Some(VerifiedResponse {
             status_code: None,
             headers: Vec::new(),
-            body: response.body().to_vec(),
+            // set by the caller, see `with_verified_body`
+            body: Vec::new(),
         }),
         verification_version: 1,
+        uncertified_headers: None,
+        root_key_index: None,
+        certificate_time_ns,
+        subnet_id: certificate_subnet_id(certificate),
+        certificate_expression: None,
+        certificate_expression_hash: None,
     })
 }
 
+/// Returns the time of a certificate that has already been verified, in nanoseconds since the
+/// UNIX epoch.
+fn certificate_time_ns(certificate: &Certificate) -> ResponseVerificationResult<u128> {
+    let time_path = ["time".as_bytes()];
+    let LookupResult::Found(mut encoded_certificate_time) =
+        certificate.tree.lookup_path(&time_path)
+    else {
+        return Err(CertificateVerificationError::MissingTimePathInTree {
+            path: time_path.iter().map(|p| p.to_vec()).collect(),
+        }
+        .into());
+    };
+
+    let certificate_time = leb128::read::unsigned(&mut encoded_certificate_time).map_err(|_| {
+        CertificateVerificationError::TimeDecodingFailed {
+            timestamp: encoded_certificate_time.to_vec(),
+        }
+    })?;
+
+    Ok(certificate_time.into())
+}
+
+/// Returns the ID of the subnet that signed a certificate, or `None` if the certificate was
+/// signed with the root key without a delegation.
+fn certificate_subnet_id(certificate: &Certificate) -> Option<Principal> {
+    certificate
+        .delegation
+        .as_ref()
+        .and_then(|delegation| Principal::try_from_slice(&delegation.subnet_id).ok())
+}
+
+fn uncertified_headers(
+    response: &HttpResponse,
+    verification_info: &VerificationInfo,
+) -> Vec<(String, String)> {
+    let certified_headers = verification_info
+        .response
+        .as_ref()
+        .map(|verified_response| verified_response.headers.as_slice())
+        .unwrap_or_default();
+
+    response
+        .headers()
+        .iter()
+        .filter(|(name, _)| {
+            !name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME)
+                && !certified_headers
+                    .iter()
+                    .any(|(certified_name, _)| certified_name.eq_ignore_ascii_case(name))
+        })
+        .cloned()
+        .collect()
+}
+
 struct V2VerificationOpts<'a> {
-    request: HttpRequest<'a>,
-    response: HttpResponse<'a>,
+    request: &'a HttpRequest<'a>,
+    response: &'a HttpResponse<'a>,
     canister_id: &'a [u8],
     current_time_ns: u128,
     max_cert_time_offset_ns: u128,
-    tree: HashTree,
-    certificate: Certificate,
+    tree: &'a HashTree,
+    certificate: &'a Certificate,
     expr_path: Vec<String>,
     expr_hash: Hash,
+    certificate_expression: &'a str,
     certification: CelExpression<'a>,
     ic_public_key: &'a [u8],
+    preserve_header_casing: bool,
+    body_hash: Option<Hash>,
+    verifying_expected_body: bool,
 }
 
 fn v2_verification(
@@ -189,8 +1151,12 @@ fn v2_verification(
         certificate,
         expr_path,
         expr_hash,
+        certificate_expression,
         certification,
         ic_public_key,
+        preserve_header_casing,
+        body_hash,
+        verifying_expected_body,
     }: V2VerificationOpts<'_>,
 ) -> ResponseVerificationResult<VerificationInfo> {
     let request_path = request.get_path()?;
@@ -201,17 +1167,26 @@ fn v2_verification(
         &current_time_ns,
         &max_cert_time_offset_ns,
     )?;
+    let certificate_time_ns = certificate_time_ns(certificate)?;
 
-    validate_tree(canister_id, &certificate, &tree)?;
-    validate_expr_path(&expr_path, &request_path, &tree)?;
+    validate_tree(canister_id, certificate, tree)?;
+    validate_expr_path(&expr_path, &request_path, tree)?;
 
     let (request_certification, response_certification) = match &certification {
         CelExpression::Default(DefaultCelExpression::Skip) => {
-            validate_expr_hash(&expr_path, &expr_hash, &tree)?;
+            // there are no request or response hashes to validate, so only the expression
+            // hash is validated here, the other branches validate it in `validate_hashes`
+            validate_expr_hash(&expr_path, &expr_hash, tree)?;
 
             return Ok(VerificationInfo {
                 response: None,
                 verification_version: 2,
+                uncertified_headers: None,
+                root_key_index: None,
+                certificate_time_ns,
+                subnet_id: certificate_subnet_id(certificate),
+                certificate_expression: Some(certificate_expression.to_string()),
+                certificate_expression_hash: Some(expr_hash),
             });
         }
         CelExpression::Default(DefaultCelExpression::ResponseOnly(
@@ -225,11 +1200,11 @@ fn v2_verification(
 
     let request_hash = request_certification
         .as_ref()
-        .map(|request_certification| request_hash(&request, request_certification))
+        .map(|request_certification| request_hash(request, request_certification))
         .transpose()?;
 
-    let body_hash = hash(response.body());
-    let response_headers = filter_response_headers(&response, response_certification);
+    let body_hash = body_hash.unwrap_or_else(|| hash(response.body()));
+    let response_headers = filter_response_headers(response, response_certification);
     let response_headers_hash =
         response_headers_hash(&response.status_code().as_u16().into(), &response_headers);
     let response_hash = hash([response_headers_hash, body_hash].concat().as_slice());
@@ -239,23 +1214,65 @@ fn v2_verification(
         &request_hash,
         &response_hash,
         &expr_path,
-        &tree,
+        tree,
         &certification,
-    )?;
+    )
+    .map_err(|err| match err {
+        // the request is certified, so it's the expected body that is not
+        ResponseVerificationError::InvalidRequestAndResponseHashes { .. }
+            if verifying_expected_body
+                && is_request_hash_certified(
+                    &expr_hash,
+                    &request_hash,
+                    &expr_path,
+                    tree,
+                    &certification,
+                ) =>
+        {
+            ResponseVerificationError::ExpectedBodyNotCertified
+        }
+        err => err,
+    })?;
 
-    let mut all_headers = response_headers.headers;
-    // add the certificate header back to the response
     let Some(certificate_header_str) = response_headers.certificate else {
         return Err(ResponseVerificationError::HeaderMissingCertification);
     };
-    all_headers.push((CERTIFICATE_HEADER_NAME.to_string(), certificate_header_str));
+
+    let all_headers = if preserve_header_casing {
+        // return the certified headers and the certificate header as they were received
+        response
+            .headers()
+            .iter()
+            .filter(|(name, _)| {
+                name.eq_ignore_ascii_case(CERTIFICATE_HEADER_NAME)
+                    || response_headers
+                        .headers
+                        .iter()
+                        .any(|(certified_name, _)| certified_name.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+            .collect()
+    } else {
+        let mut all_headers = response_headers.headers;
+        // add the certificate header back to the response
+        all_headers.push((CERTIFICATE_HEADER_NAME.to_string(), certificate_header_str));
+
+        all_headers
+    };
 
     Ok(VerificationInfo {
         response: Some(VerifiedResponse {
             status_code: Some(response.status_code().into()),
             headers: all_headers,
-            body: response.body().to_vec(),
+            // set by the caller, see `with_verified_body`
+            body: Vec::new(),
         }),
         verification_version: 2,
+        uncertified_headers: None,
+        root_key_index: None,
+        certificate_time_ns,
+        subnet_id: certificate_subnet_id(certificate),
+        certificate_expression: Some(certificate_expression.to_string()),
+        certificate_expression_hash: Some(expr_hash),
     })
 }